@@ -2,7 +2,10 @@
 fn main() -> anyhow::Result<()> {
   use ffmpeg_sidecar::{
     command::ffmpeg_is_installed,
-    download::{check_latest_version, download_ffmpeg_package, ffmpeg_download_url, unpack_ffmpeg},
+    download::{
+      check_latest_version, download_ffmpeg_package, ffmpeg_download_url, ffprobe_download_url,
+      unpack_ffmpeg,
+    },
     paths::sidecar_dir,
     version::ffmpeg_version_with_path,
   };
@@ -46,6 +49,13 @@ fn main() -> anyhow::Result<()> {
   println!("Extracting...");
   unpack_ffmpeg(&archive_path, &destination)?;
 
+  // Some providers (MacOS x86_64) ship FFprobe as a separate archive
+  if let Some(ffprobe_url) = ffprobe_download_url() {
+    println!("Downloading FFprobe from: {:?}", ffprobe_url);
+    let archive_path = download_ffmpeg_package(ffprobe_url, &destination)?;
+    unpack_ffmpeg(&archive_path, &destination)?;
+  }
+
   // Use the freshly installed FFmpeg to check the version number
   let version = ffmpeg_version_with_path(destination.join("ffmpeg"))?;
   println!("FFmpeg version: {}", version);
@@ -3,7 +3,7 @@ use ffmpeg_sidecar::{
   command::FfmpegCommand,
   event::{FfmpegEvent, LogLevel},
 };
-use std::{cmp::max, iter::repeat};
+use std::cmp::max;
 
 /// Process microphone audio data in realtime and display a volume meter/level
 /// indicator rendered to the terminal.
@@ -21,7 +21,7 @@ pub fn main() -> Result<()> {
 
   let audio_device = FfmpegCommand::new()
     .hide_banner()
-    .args(&["-list_devices", "true"])
+    .args(["-list_devices", "true"])
     .format("dshow")
     .input("dummy")
     .spawn()?
@@ -88,7 +88,7 @@ pub fn main() -> Result<()> {
           println!(
             "{} {} {}%",
             recording_indicator,
-            repeat('█').take(volume_normalized).collect::<String>(),
+            "█".repeat(volume_normalized),
             volume_percent
           );
         }
@@ -0,0 +1,358 @@
+//! Async child process and event stream, for use with the Tokio runtime.
+
+use std::{
+  io::{self, ErrorKind},
+  pin::Pin,
+  process::ExitStatus,
+  sync::Arc,
+  task::{Context as TaskContext, Poll},
+};
+
+use anyhow::Context;
+use futures_core::Stream;
+use tokio::{
+  io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+  process::{Child, ChildStderr, ChildStdin, ChildStdout},
+  sync::mpsc::{channel, Receiver, Sender},
+};
+
+use crate::{
+  child::ProgressPipe,
+  event::{FfmpegEvent, FfmpegOutput, LogLevel, Stream as OutputStream},
+  iter::{StdoutLayout, CHUNK_SIZE},
+  log_parser::{join_crlf, try_parse_progress_block, LogLineParser},
+  metadata::FfmpegMetadata,
+  read_until_any::read_until_any_async,
+  temp_file::{AtomicOutput, TempFile},
+};
+
+/// A wrapper around [`tokio::process::Child`] for an FFmpeg process spawned
+/// with [`FfmpegCommand::spawn_async`](crate::command::FfmpegCommand::spawn_async).
+/// The async counterpart of [`FfmpegChild`](crate::child::FfmpegChild).
+pub struct AsyncFfmpegChild {
+  inner: Child,
+  progress_pipe: Option<ProgressPipe>,
+  /// Kept alive until the child is dropped, like `FfmpegChild`.
+  _temp_files: Vec<Arc<TempFile>>,
+  /// Moved into place or deleted once the exit status is known.
+  atomic_outputs: Vec<AtomicOutput>,
+}
+
+impl AsyncFfmpegChild {
+  /// Creates a stream of events emitted by FFmpeg, with the same events as
+  /// [`FfmpegChild::iter`](crate::child::FfmpegChild::iter), in the same
+  /// order.
+  ///
+  /// Parsing runs on tasks spawned onto the current Tokio runtime, so this
+  /// must be called from within one.
+  pub fn events(&mut self) -> anyhow::Result<FfmpegEventStream> {
+    FfmpegEventStream::new(self)
+  }
+
+  /// Escape hatch to manually control the process' stdout channel.
+  /// Calling this method takes ownership of the stdout channel, so
+  /// the stream will no longer include output frames.
+  pub fn take_stdout(&mut self) -> Option<ChildStdout> {
+    self.inner.stdout.take()
+  }
+
+  /// Escape hatch to manually control the process' stderr channel.
+  /// This method is mutually exclusive with `events`, which relies on
+  /// the stderr channel to parse events.
+  pub fn take_stderr(&mut self) -> Option<ChildStderr> {
+    self.inner.stderr.take()
+  }
+
+  /// Escape hatch to manually control the process' stdin channel.
+  /// This method is mutually exclusive with `send_stdin_command` and `quit`,
+  /// which use the stdin channel to send commands to ffmpeg.
+  pub fn take_stdin(&mut self) -> Option<ChildStdin> {
+    self.inner.stdin.take()
+  }
+
+  /// Send a command to ffmpeg over stdin, used during interactive mode. See
+  /// [`FfmpegChild::send_stdin_command`](crate::child::FfmpegChild::send_stdin_command).
+  pub async fn send_stdin_command(&mut self, command: &[u8]) -> anyhow::Result<()> {
+    let stdin = self
+      .inner
+      .stdin
+      .as_mut()
+      .context("Missing child stdin; it may have been taken with `take_stdin()`")?;
+    stdin.write_all(command).await?;
+    stdin.flush().await?;
+    Ok(())
+  }
+
+  /// Send a `q` command to ffmpeg over stdin,
+  /// requesting a graceful shutdown as soon as possible.
+  pub async fn quit(&mut self) -> anyhow::Result<()> {
+    self.send_stdin_command(b"q").await
+  }
+
+  /// Forcibly terminate the inner child process, and wait for it to exit.
+  ///
+  /// Identical to `kill` in [`tokio::process::Child`].
+  pub async fn kill(&mut self) -> io::Result<()> {
+    self.inner.kill().await
+  }
+
+  /// Waits for the inner child process to finish execution.
+  ///
+  /// If stderr hasn't been taken by `events()`, it's drained first so ffmpeg
+  /// can't block on a full pipe.
+  pub async fn wait(&mut self) -> io::Result<ExitStatus> {
+    if let Some(mut stderr) = self.take_stderr() {
+      tokio::io::copy(&mut stderr, &mut tokio::io::sink()).await?;
+    }
+
+    let status = self.inner.wait().await?;
+    for output in std::mem::take(&mut self.atomic_outputs) {
+      output.finish(status.success())?;
+    }
+    Ok(status)
+  }
+
+  /// Wrap a [`tokio::process::Child`] in an `AsyncFfmpegChild`. Should
+  /// typically only be called by `FfmpegCommand::spawn_async`.
+  pub(crate) fn from_inner(
+    inner: Child,
+    progress_pipe: Option<ProgressPipe>,
+    temp_files: Vec<Arc<TempFile>>,
+    atomic_outputs: Vec<AtomicOutput>,
+  ) -> Self {
+    Self {
+      inner,
+      progress_pipe,
+      _temp_files: temp_files,
+      atomic_outputs,
+    }
+  }
+
+  /// Escape hatch to access the inner `Child`.
+  pub fn as_inner(&self) -> &Child {
+    &self.inner
+  }
+
+  /// Escape hatch to mutably access the inner `Child`.
+  pub fn as_inner_mut(&mut self) -> &mut Child {
+    &mut self.inner
+  }
+}
+
+/// An async stream of events from an ffmpeg process, including parsed
+/// metadata, progress, and raw video frames. The async counterpart of
+/// [`FfmpegIterator`](crate::iter::FfmpegIterator), sharing its parsers.
+///
+/// Implements [`futures_core::Stream`], so it works with `StreamExt` from
+/// `futures` or `tokio-stream`, or can be polled directly with
+/// [`next()`](FfmpegEventStream::next).
+///
+/// ```rust,no_run
+/// use ffmpeg_sidecar::{command::FfmpegCommand, event::FfmpegEvent};
+///
+/// # async fn run() -> anyhow::Result<()> {
+/// let mut child = FfmpegCommand::new().testsrc().rawvideo().spawn_async()?;
+/// let mut events = child.events()?;
+/// while let Some(event) = events.next().await {
+///   if let FfmpegEvent::OutputFrame(frame) = event {
+///     println!("frame {}: {}x{}", frame.frame_num, frame.width, frame.height);
+///   }
+/// }
+/// child.wait().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct FfmpegEventStream {
+  rx: Receiver<FfmpegEvent>,
+}
+
+impl FfmpegEventStream {
+  pub fn new(child: &mut AsyncFfmpegChild) -> anyhow::Result<Self> {
+    let stderr = child.take_stderr().context("No stderr channel\n - Did you call `take_stderr` elsewhere?\n - Did you forget to call `.stderr(Stdio::piped)` on the `ChildProcess`?")?;
+    let stdout = child.take_stdout();
+    // A single slot, so parsing keeps pace with the consumer like the blocking
+    // iterator's rendezvous channel.
+    let (tx, rx) = channel::<FfmpegEvent>(1);
+    if child.progress_pipe == Some(ProgressPipe::Stdout) {
+      tokio::spawn(read_stderr(stderr, tx.clone(), None, child.progress_pipe));
+      if let Some(stdout) = stdout {
+        tokio::spawn(read_progress(stdout, tx));
+      }
+    } else {
+      tokio::spawn(read_stderr(stderr, tx, stdout, child.progress_pipe));
+    }
+
+    Ok(Self { rx })
+  }
+
+  /// Wait for the next event, returning `None` once ffmpeg's output has been
+  /// fully read.
+  pub async fn next(&mut self) -> Option<FfmpegEvent> {
+    self.rx.recv().await
+  }
+}
+
+impl Stream for FfmpegEventStream {
+  type Item = FfmpegEvent;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<FfmpegEvent>> {
+    self.rx.poll_recv(cx)
+  }
+}
+
+/// Read and parse lines from ffmpeg's stderr, collecting metadata along the
+/// way. Once the metadata is complete, a second task is started to read
+/// output from `stdout`, if given.
+async fn read_stderr(
+  stderr: ChildStderr,
+  tx: Sender<FfmpegEvent>,
+  mut stdout: Option<ChildStdout>,
+  progress_pipe: Option<ProgressPipe>,
+) {
+  // The final status line is still printed to stderr despite `-nostats`;
+  // demote it to a log message so progress is only reported once.
+  let progress_as_log = progress_pipe == Some(ProgressPipe::Stdout);
+  let mut reader = BufReader::new(stderr);
+  let mut parser = LogLineParser::new();
+  if progress_pipe == Some(ProgressPipe::Stderr) {
+    parser.parse_progress_blocks();
+  }
+  let mut metadata = FfmpegMetadata::new();
+  loop {
+    let event = if let Some(event) = parser.take_pending() {
+      event
+    } else {
+      let mut buf = Vec::<u8>::new();
+      match read_until_any_async(&mut reader, &[b'\r', b'\n'], &mut buf).await {
+        Ok(0) => FfmpegEvent::LogEOF,
+        Ok(_) => {
+          join_crlf(&mut buf, reader.buffer());
+          match parser.parse_line(&String::from_utf8_lossy(buf.as_slice())) {
+            Ok(FfmpegEvent::Progress(progress)) if progress_as_log => {
+              FfmpegEvent::Log(LogLevel::Info, progress.raw_log_message)
+            }
+            Ok(event) => event,
+            Err(e) => {
+              eprintln!("Error parsing ffmpeg output: {}", e);
+              break;
+            }
+          }
+        }
+        Err(e) => {
+          eprintln!("Error parsing ffmpeg output: {}", e);
+          break;
+        }
+      }
+    };
+    let is_eof = event == FfmpegEvent::LogEOF;
+
+    let mut error = None;
+    if !metadata.is_completed() {
+      let item = Some(event.clone());
+      match metadata.handle_event(&item) {
+        Err(e) => error = Some(e.to_string()),
+        Ok(()) if metadata.is_completed() => {
+          if metadata.output_streams.is_empty() || metadata.outputs.is_empty() {
+            error = Some("No output streams found".to_string());
+          } else if let Some(stdout) = stdout.take() {
+            tokio::spawn(read_stdout(
+              stdout,
+              tx.clone(),
+              metadata.output_streams.clone(),
+              metadata.outputs.clone(),
+            ));
+          }
+        }
+        _ => {}
+      }
+    }
+
+    tx.send(event).await.ok();
+    if let Some(error) = error {
+      tx.send(FfmpegEvent::Error(error)).await.ok();
+    }
+    if is_eof {
+      break;
+    }
+  }
+}
+
+/// Read raw output frames or chunks from ffmpeg's stdout.
+async fn read_stdout(
+  stdout: ChildStdout,
+  tx: Sender<FfmpegEvent>,
+  output_streams: Vec<OutputStream>,
+  outputs: Vec<FfmpegOutput>,
+) {
+  let layout = match StdoutLayout::new(&output_streams, &outputs) {
+    Ok(layout) => layout,
+    Err(e) => {
+      tx.send(FfmpegEvent::Error(e)).await.ok();
+      return;
+    }
+  };
+  if let Some(warning) = &layout.warning {
+    tx.send(FfmpegEvent::Error(warning.clone())).await.ok();
+  }
+
+  let mut reader = BufReader::new(stdout);
+  match &layout.frame_sizes {
+    None => {
+      let mut chunk_buffer = vec![0u8; CHUNK_SIZE];
+      loop {
+        match reader.read(chunk_buffer.as_mut_slice()).await {
+          Ok(0) => break,
+          Ok(bytes_read) => {
+            let data = chunk_buffer[..bytes_read].to_vec();
+            tx.send(FfmpegEvent::OutputChunk(data)).await.ok();
+          }
+          Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+          Err(e) => {
+            tx.send(FfmpegEvent::Error(e.to_string())).await.ok();
+            break;
+          }
+        }
+      }
+    }
+    Some(frame_sizes) => {
+      let mut frame_num = 0;
+      loop {
+        let mut buffer = vec![0u8; frame_sizes[frame_num % frame_sizes.len()]];
+        match reader.read_exact(buffer.as_mut_slice()).await {
+          Ok(_) => {
+            let frame = layout.frame(frame_num, buffer);
+            tx.send(FfmpegEvent::OutputFrame(frame)).await.ok();
+          }
+          Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+          Err(e) => {
+            tx.send(FfmpegEvent::Error(e.to_string())).await.ok();
+            break;
+          }
+        }
+        frame_num += 1;
+      }
+    }
+  }
+
+  tx.send(FfmpegEvent::Done).await.ok();
+}
+
+/// Read `-progress pipe:1` reports from ffmpeg's stdout, emitting one
+/// `FfmpegEvent::Progress` per block of `key=value` lines.
+async fn read_progress(stdout: ChildStdout, tx: Sender<FfmpegEvent>) {
+  let mut lines = BufReader::new(stdout).lines();
+  let mut block = String::new();
+  while let Ok(Some(line)) = lines.next_line().await {
+    block.push_str(&line);
+    block.push('\n');
+    // Every block is terminated by `progress=continue` or `progress=end`
+    if line.starts_with("progress=") {
+      if let Some(progress) = try_parse_progress_block(&block) {
+        if tx.send(FfmpegEvent::Progress(progress)).await.is_err() {
+          break;
+        }
+      }
+      block.clear();
+    }
+  }
+}
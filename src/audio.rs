@@ -0,0 +1,160 @@
+//! Typed options for common audio filters.
+//!
+//! See <https://ffmpeg.org/ffmpeg-filters.html#Audio-Filters> for the full
+//! list of filters and their options.
+
+use std::fmt;
+
+use serde::Deserialize;
+
+/// A gain for [`FfmpegCommand::volume`](crate::command::FfmpegCommand::volume).
+///
+/// ```rust
+/// use ffmpeg_sidecar::audio::Volume;
+/// assert!(Volume::Factor(0.5).to_string() == "0.5");
+/// assert!(Volume::Decibels(-3.0).to_string() == "-3dB");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Volume {
+  /// A multiplier of the amplitude, where `1.0` is unchanged
+  Factor(f32),
+  /// A change in decibels, where `0.0` is unchanged
+  Decibels(f32),
+}
+
+impl fmt::Display for Volume {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Volume::Factor(factor) => write!(f, "{factor}"),
+      Volume::Decibels(db) => write!(f, "{db}dB"),
+    }
+  }
+}
+
+/// Targets for EBU R128 loudness normalization with the `loudnorm` filter,
+/// for [`FfmpegCommand::loudnorm`](crate::command::FfmpegCommand::loudnorm).
+/// The defaults match the filter's own.
+///
+/// A single pass normalizes dynamically, as the audio plays. For a more
+/// accurate, linear normalization, run a first pass with `print_json` to
+/// measure the input, then pass the [`LoudnormMeasurement`] parsed from its
+/// log to a second pass as `measured`.
+///
+/// ```rust
+/// use ffmpeg_sidecar::audio::LoudnormOptions;
+/// let options = LoudnormOptions {
+///   integrated: -16.0,
+///   true_peak: -1.5,
+///   loudness_range: 11.0,
+///   ..Default::default()
+/// };
+/// assert!(options.to_string() == "loudnorm=I=-16:LRA=11:TP=-1.5");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoudnormOptions {
+  /// Integrated loudness target in LUFS, `I`, from -70 to -5
+  pub integrated: f32,
+  /// Loudness range target in LU, `LRA`, from 1 to 50
+  pub loudness_range: f32,
+  /// Maximum true peak in dBTP, `TP`, from -9 to 0
+  pub true_peak: f32,
+  /// Measurements of the input from a first pass, for a linear second pass
+  pub measured: Option<LoudnormMeasurement>,
+  /// `print_format=json`: log the measurements of the input once finished,
+  /// for [`LoudnormMeasurement::from_log`]
+  pub print_json: bool,
+}
+
+impl Default for LoudnormOptions {
+  fn default() -> Self {
+    Self {
+      integrated: -24.0,
+      loudness_range: 7.0,
+      true_peak: -2.0,
+      measured: None,
+      print_json: false,
+    }
+  }
+}
+
+impl fmt::Display for LoudnormOptions {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "loudnorm=I={}:LRA={}:TP={}",
+      self.integrated, self.loudness_range, self.true_peak
+    )?;
+    if let Some(measured) = &self.measured {
+      write!(
+        f,
+        ":measured_I={}:measured_LRA={}:measured_TP={}:measured_thresh={}:offset={}:linear=true",
+        measured.input_i,
+        measured.input_lra,
+        measured.input_tp,
+        measured.input_thresh,
+        measured.target_offset
+      )?;
+    }
+    if self.print_json {
+      write!(f, ":print_format=json")?;
+    }
+    Ok(())
+  }
+}
+
+/// The loudness of an input, as measured by a first pass of `loudnorm` with
+/// `print_format=json`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoudnormMeasurement {
+  pub input_i: f32,
+  pub input_lra: f32,
+  pub input_tp: f32,
+  pub input_thresh: f32,
+  pub target_offset: f32,
+}
+
+impl LoudnormMeasurement {
+  /// Parse the JSON block which `loudnorm` logs at the end of a first pass.
+  /// `log` may contain other lines, like the whole stderr of the run; the
+  /// last `{ ... }` block is used.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::audio::LoudnormMeasurement;
+  /// let log = r#"[Parsed_loudnorm_0 @ 0x600001f0c000]
+  /// {
+  ///   "input_i" : "-27.61",
+  ///   "input_tp" : "-4.47",
+  ///   "input_lra" : "18.06",
+  ///   "input_thresh" : "-39.20",
+  ///   "output_i" : "-16.58",
+  ///   "output_tp" : "-1.50",
+  ///   "output_lra" : "14.78",
+  ///   "output_thresh" : "-27.71",
+  ///   "normalization_type" : "dynamic",
+  ///   "target_offset" : "0.58"
+  /// }"#;
+  /// let measured = LoudnormMeasurement::from_log(log).unwrap();
+  /// assert!(measured.input_i == -27.61 && measured.target_offset == 0.58);
+  /// ```
+  pub fn from_log(log: &str) -> Option<Self> {
+    #[derive(Deserialize)]
+    struct Raw {
+      input_i: String,
+      input_tp: String,
+      input_lra: String,
+      input_thresh: String,
+      target_offset: String,
+    }
+
+    let start = log.rfind('{')?;
+    let end = start + log[start..].find('}')?;
+    let raw: Raw = serde_json::from_str(&log[start..=end]).ok()?;
+    Some(Self {
+      input_i: raw.input_i.parse().ok()?,
+      input_lra: raw.input_lra.parse().ok()?,
+      input_tp: raw.input_tp.parse().ok()?,
+      input_thresh: raw.input_thresh.parse().ok()?,
+      target_offset: raw.target_offset.parse().ok()?,
+    })
+  }
+}
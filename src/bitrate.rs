@@ -0,0 +1,79 @@
+//! Unit-aware bitrates for `-b:v` and `-b:a`.
+
+use std::{fmt, str::FromStr};
+
+use anyhow::Context;
+
+/// A bitrate in bits per second, for
+/// [`FfmpegCommand::video_bitrate`](crate::command::FfmpegCommand::video_bitrate)
+/// and [`audio_bitrate`](crate::command::FfmpegCommand::audio_bitrate).
+///
+/// Formats with FFmpeg's SI suffixes (`k` = 1000, `M` = 1000², `G` = 1000³), using the
+/// largest one which represents the value exactly. Parses the same syntax,
+/// including decimals like `1.5M`; a bare number is in bits per second.
+///
+/// ```rust
+/// use ffmpeg_sidecar::bitrate::Bitrate;
+/// assert!(Bitrate::mbps(5).to_string() == "5M");
+/// assert!(Bitrate::kbps(128).to_string() == "128k");
+/// assert!(Bitrate::kbps(1500).to_string() == "1500k");
+/// assert!("1.5M".parse::<Bitrate>().unwrap() == Bitrate::kbps(1500));
+/// assert!("128k".parse::<Bitrate>().unwrap().bits_per_second() == 128_000);
+/// assert!("5000".parse::<Bitrate>().unwrap() == Bitrate::bps(5000));
+/// assert!("5 Mbps".parse::<Bitrate>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bitrate {
+  bits_per_second: u64,
+}
+
+impl Bitrate {
+  pub fn bps(bits_per_second: u64) -> Self {
+    Self { bits_per_second }
+  }
+
+  pub fn kbps(kilobits_per_second: u64) -> Self {
+    Self::bps(kilobits_per_second * 1_000)
+  }
+
+  pub fn mbps(megabits_per_second: u64) -> Self {
+    Self::bps(megabits_per_second * 1_000_000)
+  }
+
+  pub fn bits_per_second(&self) -> u64 {
+    self.bits_per_second
+  }
+}
+
+impl fmt::Display for Bitrate {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let bps = self.bits_per_second;
+    match bps {
+      0 => write!(f, "0"),
+      _ if bps % 1_000_000_000 == 0 => write!(f, "{}G", bps / 1_000_000_000),
+      _ if bps % 1_000_000 == 0 => write!(f, "{}M", bps / 1_000_000),
+      _ if bps % 1_000 == 0 => write!(f, "{}k", bps / 1_000),
+      _ => write!(f, "{bps}"),
+    }
+  }
+}
+
+impl FromStr for Bitrate {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> anyhow::Result<Self> {
+    let s = s.trim();
+    let (number, multiplier) = match s.chars().last() {
+      Some('k' | 'K') => (&s[..s.len() - 1], 1e3),
+      Some('M') => (&s[..s.len() - 1], 1e6),
+      Some('G') => (&s[..s.len() - 1], 1e9),
+      _ => (s, 1.0),
+    };
+    let number: f64 = number
+      .parse()
+      .ok()
+      .filter(|n: &f64| n.is_finite() && *n >= 0.0)
+      .with_context(|| format!("Invalid bitrate: {s:?}, expected e.g. `5M` or `128k`"))?;
+    Ok(Self::bps((number * multiplier).round() as u64))
+  }
+}
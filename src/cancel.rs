@@ -0,0 +1,199 @@
+//! Cooperative cancellation of a running FFmpeg process, on request or after
+//! a timeout.
+//!
+//! Both live on [`CancellableIterator`] rather than on [`FfmpegIterator`],
+//! since stopping the process takes the [`FfmpegChild`] it borrows.
+
+use std::{
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  time::{Duration, Instant},
+};
+
+use crate::{child::FfmpegChild, event::FfmpegEvent, iter::FfmpegIterator};
+
+/// How often a [`CancellableIterator`] checks its token while no events
+/// arrive.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long [`FfmpegChild::iter_with_timeout`] waits after sending `q` on a
+/// timeout before killing ffmpeg.
+pub const TIMEOUT_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// A cheaply cloneable flag for requesting that FFmpeg stop, e.g. from another
+/// thread or a signal handler. Pass it to
+/// [`FfmpegChild::iter_cancellable`].
+///
+/// An existing `Arc<AtomicBool>` can be converted with `From`, so it keeps
+/// working as the cancellation flag.
+///
+/// ```rust
+/// use ffmpeg_sidecar::cancel::CancelToken;
+/// let token = CancelToken::new();
+/// let handle = token.clone();
+/// assert!(!token.is_cancelled());
+/// handle.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Request cancellation. Every clone of this token observes it.
+  pub fn cancel(&self) {
+    self.0.store(true, Ordering::SeqCst);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.0.load(Ordering::SeqCst)
+  }
+}
+
+impl From<Arc<AtomicBool>> for CancelToken {
+  fn from(flag: Arc<AtomicBool>) -> Self {
+    Self(flag)
+  }
+}
+
+/// Which limit of [`CancellableIterator::with_timeout`] was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutKind {
+  /// No event arrived within the idle timeout, e.g. from a stalled network
+  /// input
+  Idle,
+  /// The process ran for longer than the total timeout
+  Total,
+}
+
+/// An iterator over the events of an FFmpeg process, which shuts the process
+/// down once its [`CancelToken`] is cancelled or a timeout is exceeded.
+/// Created by [`FfmpegChild::iter_cancellable`] or
+/// [`FfmpegChild::iter_with_timeout`].
+///
+/// On cancellation, `q` is sent over stdin so FFmpeg can finish writing its
+/// output, and the process is killed if it's still running after the grace
+/// period. Events keep being yielded in the meantime, and the iterator ends
+/// once the process has exited and its output is drained.
+pub struct CancellableIterator<'a> {
+  child: &'a mut FfmpegChild,
+  iter: FfmpegIterator,
+  token: CancelToken,
+  grace_period: Duration,
+  /// When `q` was sent, or `None` before cancellation.
+  quit_at: Option<Instant>,
+  killed: bool,
+  idle_timeout: Option<Duration>,
+  total_timeout: Option<Duration>,
+  started_at: Instant,
+  last_event_at: Instant,
+  timed_out: Option<TimeoutKind>,
+}
+
+impl<'a> CancellableIterator<'a> {
+  pub(crate) fn new(
+    child: &'a mut FfmpegChild,
+    token: CancelToken,
+    grace_period: Duration,
+  ) -> anyhow::Result<Self> {
+    let iter = child.iter()?;
+    let now = Instant::now();
+    Ok(Self {
+      child,
+      iter,
+      token,
+      grace_period,
+      quit_at: None,
+      killed: false,
+      idle_timeout: None,
+      total_timeout: None,
+      started_at: now,
+      last_event_at: now,
+      timed_out: None,
+    })
+  }
+
+  /// Also stop ffmpeg, like on cancellation, if no event arrives for `idle`,
+  /// or once it has run for `total` since the iterator was created. A
+  /// [`FfmpegEvent::Error`] is yielded when a timeout is exceeded; see
+  /// [`timed_out()`](Self::timed_out).
+  ///
+  /// Any event resets the idle timeout, including log messages, so a stalled
+  /// input which keeps logging warnings isn't detected.
+  pub fn with_timeout(mut self, idle: Option<Duration>, total: Option<Duration>) -> Self {
+    self.idle_timeout = idle;
+    self.total_timeout = total;
+    self
+  }
+
+  /// Whether the process was asked to stop through the token.
+  pub fn is_cancelled(&self) -> bool {
+    self.quit_at.is_some() && self.timed_out.is_none()
+  }
+
+  /// Which timeout stopped the process, if any.
+  pub fn timed_out(&self) -> Option<TimeoutKind> {
+    self.timed_out
+  }
+
+  /// Record an exceeded timeout, returning the error event to report it.
+  fn check_timeouts(&mut self) -> Option<FfmpegEvent> {
+    if self.quit_at.is_some() {
+      return None;
+    }
+    let (kind, timeout) = match (self.idle_timeout, self.total_timeout) {
+      (Some(idle), _) if self.last_event_at.elapsed() >= idle => (TimeoutKind::Idle, idle),
+      (_, Some(total)) if self.started_at.elapsed() >= total => (TimeoutKind::Total, total),
+      _ => return None,
+    };
+    self.timed_out = Some(kind);
+    let message = match kind {
+      TimeoutKind::Idle => format!("ffmpeg timed out: no events for {timeout:?}"),
+      TimeoutKind::Total => format!("ffmpeg timed out: running for longer than {timeout:?}"),
+    };
+    Some(FfmpegEvent::Error(message))
+  }
+
+  /// Send `q` once the token is cancelled or a timeout is exceeded, then kill
+  /// after the grace period.
+  fn check_token(&mut self) {
+    match self.quit_at {
+      None if self.token.is_cancelled() || self.timed_out.is_some() => {
+        if self.child.quit().is_err() {
+          // Without stdin, the only way to stop is to kill
+          self.child.kill().ok();
+          self.killed = true;
+        }
+        self.quit_at = Some(Instant::now());
+      }
+      Some(quit_at) if !self.killed && quit_at.elapsed() >= self.grace_period => {
+        self.child.kill().ok();
+        self.killed = true;
+      }
+      _ => {}
+    }
+  }
+}
+
+impl Iterator for CancellableIterator<'_> {
+  type Item = FfmpegEvent;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      let timeout_error = self.check_timeouts();
+      self.check_token();
+      if timeout_error.is_some() {
+        return timeout_error;
+      }
+      if let Ok(event) = self.iter.next_timeout(POLL_INTERVAL) {
+        self.last_event_at = Instant::now();
+        return event;
+      }
+    }
+  }
+}
@@ -0,0 +1,224 @@
+//! Utilities for querying which features the local FFmpeg binary supports.
+
+use anyhow::Context;
+
+use crate::command::BackgroundCommand;
+use crate::paths::ffmpeg_path;
+use std::ffi::OsStr;
+use std::process::{Command, Stdio};
+
+/// The kind of media handled by an [`Encoder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+  Video,
+  Audio,
+  Subtitle,
+}
+
+/// A single row of the `ffmpeg -encoders` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Encoder {
+  /// Name to pass to `-c:v`, `-c:a` or `-c:s`, e.g. `libx264` or `h264_nvenc`.
+  pub name: String,
+  pub kind: MediaKind,
+  pub description: String,
+  /// Flag `F`: frame-level multithreading.
+  pub frame_threads: bool,
+  /// Flag `S`: slice-level multithreading.
+  pub slice_threads: bool,
+  /// Flag `X`: codec is experimental, and needs `-strict experimental`.
+  pub experimental: bool,
+  /// Flag `B`: supports draw_horiz_band.
+  pub draw_horiz_band: bool,
+  /// Flag `D`: supports direct rendering method 1.
+  pub direct_rendering: bool,
+}
+
+/// Alias for `ffmpeg -hide_banner -encoders`, parsing the table of encoders
+/// compiled into the binary.
+pub fn ffmpeg_encoders() -> anyhow::Result<Vec<Encoder>> {
+  ffmpeg_encoders_with_path(ffmpeg_path())
+}
+
+/// Lower level variant of `ffmpeg_encoders` that exposes a customized path
+/// to the ffmpeg binary.
+pub fn ffmpeg_encoders_with_path<S: AsRef<OsStr>>(path: S) -> anyhow::Result<Vec<Encoder>> {
+  Ok(parse_encoders(&query_ffmpeg(path, "-encoders")?))
+}
+
+/// Check whether the local FFmpeg binary supports the named encoder, e.g.
+/// `h264_nvenc` or `libx265`. Returns `false` if FFmpeg can't be run.
+pub fn has_encoder(name: &str) -> bool {
+  ffmpeg_encoders()
+    .map(|encoders| encoders.iter().any(|e| e.name == name))
+    .unwrap_or(false)
+}
+
+/// Parse the output of `ffmpeg -encoders`, skipping the legend printed before
+/// the table.
+///
+/// ```rust
+/// use ffmpeg_sidecar::capabilities::{parse_encoders, MediaKind};
+/// let output = "Encoders:\n V..... = Video\n A..... = Audio\n ------\n V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC (codec h264)\n A....D aac                  AAC (Advanced Audio Coding)\n";
+/// let encoders = parse_encoders(output);
+/// assert!(encoders.len() == 2);
+/// assert!(encoders[0].name == "libx264");
+/// assert!(encoders[0].kind == MediaKind::Video);
+/// assert!(encoders[0].direct_rendering);
+/// assert!(encoders[1].description == "AAC (Advanced Audio Coding)");
+/// ```
+pub fn parse_encoders(output: &str) -> Vec<Encoder> {
+  table_rows(output)
+    .filter_map(|line| {
+      let mut parts = line.splitn(3, char::is_whitespace);
+      let flags = parts.next()?.as_bytes();
+      let name = parts.next()?.to_string();
+      let description = parts.next().unwrap_or_default().trim().to_string();
+      let kind = match flags.first()? {
+        b'V' => MediaKind::Video,
+        b'A' => MediaKind::Audio,
+        b'S' => MediaKind::Subtitle,
+        _ => return None,
+      };
+      let flag = |index: usize, c: u8| flags.get(index) == Some(&c);
+      Some(Encoder {
+        name,
+        kind,
+        description,
+        frame_threads: flag(1, b'F'),
+        slice_threads: flag(2, b'S'),
+        experimental: flag(3, b'X'),
+        draw_horiz_band: flag(4, b'B'),
+        direct_rendering: flag(5, b'D'),
+      })
+    })
+    .collect()
+}
+
+/// A single row of the `ffmpeg -pix_fmts` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PixFmtInfo {
+  /// Name to pass to `-pix_fmt`, e.g. `yuv420p`.
+  pub name: String,
+  pub nb_components: u8,
+  pub bits_per_pixel: u32,
+  /// Flag `I`: supported as an input format for conversion.
+  pub input: bool,
+  /// Flag `O`: supported as an output format for conversion.
+  pub output: bool,
+  /// Flag `H`: hardware accelerated format.
+  pub hardware: bool,
+  /// Flag `P`: paletted format.
+  pub paletted: bool,
+  /// Flag `B`: bitstream format.
+  pub bitstream: bool,
+}
+
+/// Alias for `ffmpeg -hide_banner -pix_fmts`, parsing the table of pixel
+/// formats supported by the binary.
+pub fn ffmpeg_pix_fmts() -> anyhow::Result<Vec<PixFmtInfo>> {
+  ffmpeg_pix_fmts_with_path(ffmpeg_path())
+}
+
+/// Lower level variant of `ffmpeg_pix_fmts` that exposes a customized path
+/// to the ffmpeg binary.
+pub fn ffmpeg_pix_fmts_with_path<S: AsRef<OsStr>>(path: S) -> anyhow::Result<Vec<PixFmtInfo>> {
+  Ok(parse_pix_fmts(&query_ffmpeg(path, "-pix_fmts")?))
+}
+
+/// Parse the output of `ffmpeg -pix_fmts`, skipping the legend and column
+/// headers printed before the table. The trailing `BIT_DEPTHS` column of newer
+/// versions is ignored.
+///
+/// ```rust
+/// use ffmpeg_sidecar::capabilities::parse_pix_fmts;
+/// let output = "Pixel formats:\nI.... = Supported Input  format for conversion\nFLAGS NAME            NB_COMPONENTS BITS_PER_PIXEL BIT_DEPTHS\n-----\nIO... yuv420p                3             12      8-8-8\n..H.. vaapi                  0              0      0\n";
+/// let pix_fmts = parse_pix_fmts(output);
+/// assert!(pix_fmts.len() == 2);
+/// assert!(pix_fmts[0].name == "yuv420p");
+/// assert!(pix_fmts[0].nb_components == 3);
+/// assert!(pix_fmts[0].bits_per_pixel == 12);
+/// assert!(pix_fmts[0].input && pix_fmts[0].output);
+/// assert!(pix_fmts[1].hardware);
+/// ```
+pub fn parse_pix_fmts(output: &str) -> Vec<PixFmtInfo> {
+  table_rows(output)
+    .filter_map(|line| {
+      let mut parts = line.split_whitespace();
+      let flags = parts.next()?.as_bytes();
+      let name = parts.next()?.to_string();
+      let nb_components = parts.next()?.parse().ok()?;
+      let bits_per_pixel = parts.next()?.parse().ok()?;
+      let flag = |index: usize, c: u8| flags.get(index) == Some(&c);
+      Some(PixFmtInfo {
+        name,
+        nb_components,
+        bits_per_pixel,
+        input: flag(0, b'I'),
+        output: flag(1, b'O'),
+        hardware: flag(2, b'H'),
+        paletted: flag(3, b'P'),
+        bitstream: flag(4, b'B'),
+      })
+    })
+    .collect()
+}
+
+/// Alias for `ffmpeg -hide_banner -hwaccels`, listing the hardware
+/// acceleration methods compiled into the binary, such as `cuda`, `vaapi`,
+/// `qsv`, `videotoolbox` or `d3d11va`.
+///
+/// Note that a method being compiled in doesn't guarantee a matching device is
+/// present at runtime.
+pub fn ffmpeg_hwaccels() -> anyhow::Result<Vec<String>> {
+  ffmpeg_hwaccels_with_path(ffmpeg_path())
+}
+
+/// Lower level variant of `ffmpeg_hwaccels` that exposes a customized path
+/// to the ffmpeg binary.
+pub fn ffmpeg_hwaccels_with_path<S: AsRef<OsStr>>(path: S) -> anyhow::Result<Vec<String>> {
+  Ok(parse_hwaccels(&query_ffmpeg(path, "-hwaccels")?))
+}
+
+/// Parse the output of `ffmpeg -hwaccels`, dropping the header line.
+///
+/// ```rust
+/// use ffmpeg_sidecar::capabilities::parse_hwaccels;
+/// let output = "Hardware acceleration methods:\ncuda\nvaapi\nqsv\n\n";
+/// assert!(parse_hwaccels(output) == vec!["cuda", "vaapi", "qsv"]);
+/// ```
+pub fn parse_hwaccels(output: &str) -> Vec<String> {
+  output
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty() && !line.ends_with(':'))
+    .map(|line| line.to_string())
+    .collect()
+}
+
+/// Lines of a table printed by FFmpeg's `-encoders`-style listings, which
+/// follow a legend terminated by a line of dashes.
+fn table_rows(output: &str) -> impl Iterator<Item = &str> {
+  output
+    .lines()
+    .map(str::trim)
+    .skip_while(|line| !line.starts_with("---"))
+    .skip(1)
+    .filter(|line| !line.is_empty())
+}
+
+/// Run FFmpeg with a single informational flag like `-encoders`, returning its
+/// standard output.
+fn query_ffmpeg<S: AsRef<OsStr>>(path: S, flag: &str) -> anyhow::Result<String> {
+  let output = Command::new(&path)
+    .create_no_window()
+    .args(["-hide_banner", flag])
+    .stdin(Stdio::null())
+    .stderr(Stdio::null())
+    .output()
+    .with_context(|| format!("Failed to run ffmpeg {flag}"))?;
+  if !output.status.success() {
+    anyhow::bail!("ffmpeg {flag} exited with non-zero status");
+  }
+  String::from_utf8(output.stdout).with_context(|| format!("Invalid UTF-8 from ffmpeg {flag}"))
+}
@@ -1,10 +1,25 @@
 //! Wrapper around `std::process::Child` containing a spawned FFmpeg command.
 
-use crate::iter::FfmpegIterator;
+use crate::{
+  cancel::{CancelToken, CancellableIterator, TIMEOUT_GRACE_PERIOD},
+  control::{missing_stdin, with_shared_stdin, FfmpegControl, SharedStdin},
+  error::FfmpegError,
+  event::{FfmpegEvent, LogLevel},
+  iter::FfmpegIterator,
+  temp_file::{AtomicOutput, TempFile},
+};
 use anyhow::Context;
 use std::{
-  io::{self, copy, sink, Write},
+  collections::VecDeque,
+  io::{self, copy, sink, Read, Write},
   process::{Child, ChildStderr, ChildStdin, ChildStdout, ExitStatus},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{channel, Receiver},
+    Arc, Mutex,
+  },
+  thread::{sleep, spawn, JoinHandle},
+  time::{Duration, Instant},
 };
 
 /// A wrapper around [`std::process::Child`] containing a spawned FFmpeg command.
@@ -12,6 +27,21 @@ use std::{
 /// piped output frames if applicable.
 pub struct FfmpegChild {
   inner: Child,
+  kill_on_drop: bool,
+  progress_pipe: Option<ProgressPipe>,
+  input_frame_size: Option<usize>,
+  temp_files: Vec<Arc<TempFile>>,
+  /// Moved into place or deleted once the exit status is known.
+  atomic_outputs: Vec<AtomicOutput>,
+  log_capture: Option<Arc<LogCapture>>,
+  stdout_buffer_size: Option<usize>,
+  /// Stdin, moved out of `inner` once shared with an `FfmpegControl`.
+  shared_stdin: Option<SharedStdin>,
+  /// Set once the process has been waited on, so `FfmpegControl` stops
+  /// signalling a PID which may have been reused.
+  exited: Arc<AtomicBool>,
+  /// Forwards events to the receiver of `events_channel()`.
+  events_thread: Option<JoinHandle<()>>,
 }
 
 impl FfmpegChild {
@@ -27,9 +57,154 @@ impl FfmpegChild {
     FfmpegIterator::new(self)
   }
 
+  /// Like [`iter()`](FfmpegChild::iter), but stopping ffmpeg cooperatively
+  /// once `token` is cancelled from elsewhere: `q` is sent over stdin for a
+  /// graceful shutdown, escalating to [`kill()`](FfmpegChild::kill) if ffmpeg
+  /// is still running after `grace_period`. The iterator ends when ffmpeg
+  /// exits.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::{cancel::CancelToken, command::FfmpegCommand};
+  /// use std::{thread, time::Duration};
+  ///
+  /// let token = CancelToken::new();
+  /// let handle = token.clone();
+  /// thread::spawn(move || {
+  ///   thread::sleep(Duration::from_secs(5));
+  ///   handle.cancel();
+  /// });
+  ///
+  /// let mut child = FfmpegCommand::new()
+  ///   .realtime()
+  ///   .testsrc()
+  ///   .output("output/recording.mp4")
+  ///   .spawn()
+  ///   .unwrap();
+  /// for event in child.iter_cancellable(&token, Duration::from_secs(3)).unwrap() {
+  ///   println!("{event:?}");
+  /// }
+  /// child.wait().unwrap();
+  /// ```
+  pub fn iter_cancellable(
+    &mut self,
+    token: &CancelToken,
+    grace_period: Duration,
+  ) -> anyhow::Result<CancellableIterator<'_>> {
+    CancellableIterator::new(self, token.clone(), grace_period)
+  }
+
+  /// Like [`iter()`](FfmpegChild::iter), but reading the events on a
+  /// background thread and sending them to the returned channel, so another
+  /// loop can poll them with `try_recv` or `recv_timeout`.
+  ///
+  /// The thread keeps draining stderr and stdout even if the receiver is
+  /// dropped, so ffmpeg never stalls on a full pipe. Events are buffered until
+  /// received, so keep up with them when reading raw frames. The channel
+  /// closes once ffmpeg exits and its output is drained;
+  /// [`wait()`](FfmpegChild::wait) joins the thread, so every event is in the
+  /// channel by the time it returns.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::{command::FfmpegCommand, event::FfmpegEvent};
+  /// use std::{sync::mpsc::RecvTimeoutError, time::Duration};
+  ///
+  /// let mut child = FfmpegCommand::new().testsrc().output("output/test.mp4").spawn()?;
+  /// let events = child.events_channel()?;
+  /// loop {
+  ///   match events.recv_timeout(Duration::from_millis(100)) {
+  ///     Ok(FfmpegEvent::Progress(progress)) => println!("{}", progress.time),
+  ///     Ok(_) => {}
+  ///     Err(RecvTimeoutError::Timeout) => { /* do other work */ }
+  ///     Err(RecvTimeoutError::Disconnected) => break,
+  ///   }
+  /// }
+  /// child.wait()?;
+  /// # anyhow::Ok(())
+  /// ```
+  pub fn events_channel(&mut self) -> anyhow::Result<Receiver<FfmpegEvent>> {
+    let iter = self.iter()?;
+    let (tx, rx) = channel();
+    self.events_thread = Some(spawn(move || {
+      for event in iter {
+        tx.send(event).ok();
+      }
+    }));
+    Ok(rx)
+  }
+
+  /// Like [`iter()`](FfmpegChild::iter), but stopping ffmpeg if no event
+  /// arrives for `idle`, e.g. from a stalled network input which never makes
+  /// progress, or once it has been running for `total`. As with
+  /// [`iter_cancellable`](FfmpegChild::iter_cancellable), `q` is sent first,
+  /// escalating to [`kill()`](FfmpegChild::kill) after
+  /// [`TIMEOUT_GRACE_PERIOD`](crate::cancel::TIMEOUT_GRACE_PERIOD).
+  ///
+  /// A [`FfmpegEvent::Error`] is yielded on a timeout, and
+  /// [`timed_out()`](CancellableIterator::timed_out) tells which one was
+  /// exceeded. To combine timeouts with a [`CancelToken`] or another grace
+  /// period, use [`CancellableIterator::with_timeout`].
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// use std::time::Duration;
+  ///
+  /// let mut child = FfmpegCommand::new()
+  ///   .input("rtsp://camera.local/stream")
+  ///   .output("output/recording.mp4")
+  ///   .spawn()?;
+  /// let mut iter = child.iter_with_timeout(
+  ///   Some(Duration::from_secs(30)),
+  ///   Some(Duration::from_secs(60 * 60)),
+  /// )?;
+  /// for event in iter.by_ref() {
+  ///   println!("{event:?}");
+  /// }
+  /// if let Some(kind) = iter.timed_out() {
+  ///   eprintln!("gave up: {kind:?} timeout");
+  /// }
+  /// # anyhow::Ok(())
+  /// ```
+  pub fn iter_with_timeout(
+    &mut self,
+    idle: Option<Duration>,
+    total: Option<Duration>,
+  ) -> anyhow::Result<CancellableIterator<'_>> {
+    Ok(
+      CancellableIterator::new(self, CancelToken::new(), TIMEOUT_GRACE_PERIOD)?
+        .with_timeout(idle, total),
+    )
+  }
+
   /// Escape hatch to manually control the process' stdout channel.
   /// Calling this method takes ownership of the stdout channel, so
   /// the iterator will no longer include output frames in the stream of events.
+  ///
+  /// Stdout is piped by default, so an output of `-` or `pipe:1` can be read
+  /// directly, e.g. to stream an encode without a temporary file. FFmpeg
+  /// blocks once its stderr pipe is full, so keep consuming
+  /// [`iter()`](FfmpegChild::iter) on another thread while reading:
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// use std::{io::Read, thread};
+  ///
+  /// let mut child = FfmpegCommand::new()
+  ///   .testsrc()
+  ///   .codec_video("libx264")
+  ///   .format("mp4")
+  ///   .frag_mp4()
+  ///   .output("pipe:1")
+  ///   .spawn()
+  ///   .unwrap();
+  /// let mut stdout = child.take_stdout().unwrap();
+  /// let events = child.iter().unwrap();
+  /// let logger = thread::spawn(move || events.for_each(|event| println!("{event:?}")));
+  ///
+  /// let mut mp4 = Vec::new();
+  /// stdout.read_to_end(&mut mp4).unwrap();
+  /// logger.join().unwrap();
+  /// child.wait().unwrap();
+  /// ```
   pub fn take_stdout(&mut self) -> Option<ChildStdout> {
     self.inner.stdout.take()
   }
@@ -45,7 +220,66 @@ impl FfmpegChild {
   /// This method is mutually exclusive with `send_stdin_command` and `quit`,
   /// which use the stdin channel to send commands to ffmpeg.
   pub fn take_stdin(&mut self) -> Option<ChildStdin> {
-    self.inner.stdin.take()
+    match &self.shared_stdin {
+      Some(shared) => shared.lock().unwrap_or_else(|e| e.into_inner()).take(),
+      None => self.inner.stdin.take(),
+    }
+  }
+
+  /// Close ffmpeg's stdin, signaling the end of a piped input such as frames
+  /// written with [`write_frame`](FfmpegChild::write_frame). ffmpeg then
+  /// finishes encoding and exits, while its stdout and events remain
+  /// readable. Without this, ffmpeg keeps waiting for more input.
+  ///
+  /// Commands like `q` can't be sent afterwards. Does nothing if stdin was
+  /// already closed or taken.
+  pub fn close_stdin(&mut self) {
+    drop(self.take_stdin());
+  }
+
+  /// The OS process ID of ffmpeg.
+  pub fn pid(&self) -> u32 {
+    self.inner.id()
+  }
+
+  /// Get a handle to quit or kill the process from another thread, while this
+  /// thread keeps reading its events. See [`FfmpegControl`].
+  ///
+  /// From then on, stdin is shared with the handles, so it's no longer
+  /// available through [`as_inner_mut`](FfmpegChild::as_inner_mut); the
+  /// methods of `FfmpegChild` using it keep working.
+  pub fn control(&mut self) -> FfmpegControl {
+    let stdin = self
+      .shared_stdin
+      .get_or_insert_with(|| Arc::new(Mutex::new(self.inner.stdin.take())));
+    FfmpegControl::new(self.inner.id(), stdin.clone(), self.exited.clone())
+  }
+
+  /// Run `f` with stdin, wherever it currently lives.
+  fn with_stdin<T>(&mut self, f: impl FnOnce(&mut ChildStdin) -> io::Result<T>) -> io::Result<T> {
+    match &self.shared_stdin {
+      Some(shared) => with_shared_stdin(shared, f),
+      None => f(self.inner.stdin.as_mut().ok_or_else(missing_stdin)?),
+    }
+  }
+
+  /// Take the raw stderr log captured so far, when enabled with
+  /// `FfmpegCommand::capture_log`. Returns `None` if capturing is disabled.
+  ///
+  /// Typically called after [`wait()`](FfmpegChild::wait) to get the complete
+  /// log for a failed encode. The log is captured as stderr is consumed by
+  /// `iter()`, `wait()` or `wait_timeout()`, but not through `take_stderr`.
+  pub fn take_log(&mut self) -> Option<String> {
+    self.log_capture.as_ref().map(|capture| capture.take())
+  }
+
+  /// Like `take_stderr`, but copying everything read into the captured log if
+  /// enabled.
+  pub(crate) fn take_stderr_reader(&mut self) -> Option<StderrReader> {
+    self.take_stderr().map(|inner| StderrReader {
+      inner,
+      capture: self.log_capture.clone(),
+    })
   }
 
   /// Send a command to ffmpeg over stdin, used during interactive mode.
@@ -68,12 +302,38 @@ impl FfmpegChild {
   /// s      Show QP histogram
   /// ```
   pub fn send_stdin_command(&mut self, command: &[u8]) -> anyhow::Result<()> {
-    let mut stdin = self.inner.stdin.take().context("Missing child stdin")?;
-    stdin.write_all(command)?;
-    self.inner.stdin.replace(stdin);
+    self.with_stdin(|stdin| stdin.write_all(command))?;
     Ok(())
   }
 
+  /// Write a single raw video frame to ffmpeg's stdin, for commands declared
+  /// with [`FfmpegCommand::rawvideo_input`]. Returns an `InvalidInput` error
+  /// without writing anything if the length of `frame` doesn't match the
+  /// declared width, height and pix_fmt.
+  ///
+  /// Writing blocks once ffmpeg stops reading, which happens if its logs and
+  /// output aren't consumed. Read events from [`iter()`](FfmpegChild::iter) on
+  /// another thread while writing frames.
+  ///
+  /// Call [`close_stdin()`](FfmpegChild::close_stdin) after the last frame to
+  /// signal the end of the input.
+  pub fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+    let expected = self.input_frame_size.ok_or_else(|| {
+      io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "Unknown input frame size; declare the input with `rawvideo_input()` using a supported pix_fmt",
+      )
+    })?;
+    if frame.len() != expected {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Expected a frame of {expected} bytes, got {}", frame.len()),
+      ));
+    }
+
+    self.with_stdin(|stdin| stdin.write_all(frame))
+  }
+
   /// Send a `q` command to ffmpeg over stdin,
   /// requesting a graceful shutdown as soon as possible.
   ///
@@ -84,6 +344,27 @@ impl FfmpegChild {
     self.send_stdin_command(b"q")
   }
 
+  /// Send a `q` command and wait up to `timeout` for ffmpeg to write the
+  /// trailer and exit, escalating to [`kill()`](FfmpegChild::kill) if it
+  /// doesn't. Covers the common case of cleanly stopping a recording.
+  ///
+  /// Any stderr output which hasn't been consumed by `iter()` is discarded.
+  pub fn quit_and_wait(mut self, timeout: Duration) -> anyhow::Result<ExitStatus> {
+    if let Err(e) = self.quit() {
+      self.kill().ok();
+      self.inner.wait().ok();
+      return Err(e);
+    }
+
+    match self.wait_timeout(timeout)? {
+      Some(status) => Ok(status),
+      None => {
+        self.kill()?;
+        Ok(self.inner.wait()?)
+      }
+    }
+  }
+
   /// Forcibly terminate the inner child process.
   ///
   /// Alternatively, you may choose to gracefully stop the child process by
@@ -94,17 +375,153 @@ impl FfmpegChild {
     self.inner.kill()
   }
 
+  /// Suspend the process with `SIGSTOP`, e.g. to yield to a higher priority
+  /// job. Resume it with [`resume()`](FfmpegChild::resume). Pausing an
+  /// already paused process has no effect.
+  ///
+  /// Returns an error once the process has exited, since its PID may have been
+  /// reused by then. Only supported on Unix; returns an error on other
+  /// platforms.
+  pub fn pause(&mut self) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    return self.send_signal(libc::SIGSTOP, "SIGSTOP");
+    #[cfg(not(unix))]
+    anyhow::bail!("Pausing ffmpeg is unsupported on this platform");
+  }
+
+  /// Resume a process suspended by [`pause()`](FfmpegChild::pause) with
+  /// `SIGCONT`. Resuming a running process has no effect.
+  ///
+  /// Like [`pause()`](FfmpegChild::pause), returns an error once the process
+  /// has exited, and on platforms other than Unix.
+  pub fn resume(&mut self) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    return self.send_signal(libc::SIGCONT, "SIGCONT");
+    #[cfg(not(unix))]
+    anyhow::bail!("Resuming ffmpeg is unsupported on this platform");
+  }
+
+  #[cfg(unix)]
+  fn send_signal(&mut self, signal: libc::c_int, name: &str) -> anyhow::Result<()> {
+    // An exited child is reaped here at the latest, so the PID is still ours
+    // below
+    if let Some(status) = self.inner.try_wait()? {
+      anyhow::bail!("Can't send {name} to ffmpeg, it already exited with {status}");
+    }
+    let pid: libc::pid_t = self.inner.id().try_into()?;
+    if unsafe { libc::kill(pid, signal) } != 0 {
+      return Err(io::Error::last_os_error())
+        .with_context(|| format!("Failed to send {name} to ffmpeg"));
+    }
+    Ok(())
+  }
+
   /// Waits for the inner child process to finish execution.
   ///
   /// Identical to `wait` in [`std::process::Child`].
   pub fn wait(&mut self) -> io::Result<ExitStatus> {
     // If stderr hasn't already been consumed by a method like `iter()`,
     // we need to run it to completion to avoid a deadlock.
-    if let Some(mut stderr) = self.take_stderr() {
+    if let Some(mut stderr) = self.take_stderr_reader() {
       copy(&mut stderr, &mut sink())?;
     };
 
-    self.inner.wait()
+    let status = self.inner.wait()?;
+    if let Some(events_thread) = self.events_thread.take() {
+      events_thread.join().ok();
+    }
+    self.handle_exit(status)?;
+    Ok(status)
+  }
+
+  /// Run ffmpeg to completion, consuming all of its events, and turn a
+  /// non-zero exit into an [`FfmpegError`] with the exit code and the last
+  /// error or fatal log lines, e.g.:
+  ///
+  /// ```txt
+  /// ffmpeg exited with code 1: missing.mp4: No such file or directory
+  /// ```
+  ///
+  /// Output frames and other events are discarded, so this suits
+  /// fire-and-forget jobs writing to a file. If the events were already taken
+  /// with [`iter()`](FfmpegChild::iter), only the exit status is reported.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// FfmpegCommand::new()
+  ///   .input("input.mp4")
+  ///   .output("output.webm")
+  ///   .spawn()?
+  ///   .wait_and_result()?;
+  /// # anyhow::Ok(())
+  /// ```
+  ///
+  /// The returned error can be downcast to inspect the failure:
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::{command::FfmpegCommand, error::FfmpegError, event::FfmpegErrorKind};
+  /// let result = FfmpegCommand::new()
+  ///   .input("input.mp4")
+  ///   .codec_video("libx265")
+  ///   .output("output.mp4")
+  ///   .spawn()?
+  ///   .wait_and_result();
+  /// if let Err(e) = result {
+  ///   if let Some(FfmpegError { kind: Some(FfmpegErrorKind::UnknownEncoder), .. }) = e.downcast_ref() {
+  ///     println!("libx265 is not available");
+  ///   }
+  /// }
+  /// # anyhow::Ok(())
+  /// ```
+  pub fn wait_and_result(&mut self) -> anyhow::Result<()> {
+    let mut errors = Vec::new();
+    if self.inner.stderr.is_some() {
+      for event in self.iter()? {
+        match event {
+          FfmpegEvent::Log(LogLevel::Error | LogLevel::Fatal, line) => errors.push((line, None)),
+          FfmpegEvent::ParsedError(error) => errors.push((error.raw_log_message, Some(error.kind))),
+          FfmpegEvent::ParsedWarning(warning)
+            if matches!(warning.level, LogLevel::Error | LogLevel::Fatal) =>
+          {
+            errors.push((warning.raw_log_message, None))
+          }
+          _ => {}
+        }
+      }
+    }
+
+    let status = self.wait()?;
+    if status.success() {
+      return Ok(());
+    }
+    Err(FfmpegError::new(status, errors).into())
+  }
+
+  /// Waits up to `timeout` for the inner child process to finish execution,
+  /// returning `None` if it's still running. Useful for a watchdog which
+  /// escalates to [`kill()`](FfmpegChild::kill) when ffmpeg hangs, e.g. on a
+  /// stalled network input.
+  ///
+  /// Like [`wait()`](FfmpegChild::wait), any stderr output which hasn't been
+  /// consumed by `iter()` is discarded, on a background thread.
+  pub fn wait_timeout(&mut self, timeout: Duration) -> anyhow::Result<Option<ExitStatus>> {
+    if let Some(mut stderr) = self.take_stderr_reader() {
+      // Keep draining stderr so ffmpeg can't block on a full pipe.
+      spawn(move || copy(&mut stderr, &mut sink()));
+    }
+
+    let deadline = Instant::now() + timeout;
+    loop {
+      if let Some(status) = self.inner.try_wait()? {
+        self.handle_exit(status)?;
+        return Ok(Some(status));
+      }
+      let now = Instant::now();
+      if now >= deadline {
+        return Ok(None);
+      }
+      sleep((deadline - now).min(Duration::from_millis(10)));
+    }
   }
 
   /// Wrap a [`std::process::Child`] in a `FfmpegChild`. Should typically only
@@ -112,14 +529,91 @@ impl FfmpegChild {
   ///
   /// ## Panics
   ///
-  /// Panics if the any of the child process's stdio channels were not piped.
-  /// This could be because ffmpeg was spawned with `-nostdin`, or if the
-  /// `Child` instance was not configured with `stdin(Stdio::piped())`.
+  /// Panics if stderr was not piped, since it's needed to parse events. Stdin
+  /// and stdout may be missing if they were configured otherwise, e.g. with
+  /// [`FfmpegCommand::auto_pipes`](crate::command::FfmpegCommand::auto_pipes)
+  /// disabled.
   pub(crate) fn from_inner(inner: Child) -> Self {
-    assert!(inner.stdin.is_some(), "stdin was not piped");
-    assert!(inner.stdout.is_some(), "stdout was not piped");
     assert!(inner.stderr.is_some(), "stderr was not piped");
-    Self { inner }
+    Self {
+      inner,
+      kill_on_drop: false,
+      progress_pipe: None,
+      input_frame_size: None,
+      temp_files: Vec::new(),
+      atomic_outputs: Vec::new(),
+      log_capture: None,
+      stdout_buffer_size: None,
+      shared_stdin: None,
+      exited: Arc::new(AtomicBool::new(false)),
+      events_thread: None,
+    }
+  }
+
+  /// Record that the process exited, and rename or delete the temporary files
+  /// of [`atomic_output`](crate::command::FfmpegCommand::atomic_output) the
+  /// first time the exit status is known.
+  fn handle_exit(&mut self, status: ExitStatus) -> io::Result<()> {
+    self.exited.store(true, Ordering::SeqCst);
+    for output in std::mem::take(&mut self.atomic_outputs) {
+      output.finish(status.success())?;
+    }
+    Ok(())
+  }
+
+  /// Kill the process when this `FfmpegChild` is dropped. Should typically
+  /// only be set through `FfmpegCommand::kill_on_drop`.
+  pub(crate) fn set_kill_on_drop(&mut self, kill_on_drop: bool) {
+    self.kill_on_drop = kill_on_drop;
+  }
+
+  /// Parse `-progress` reports from stdout rather than output data, or from
+  /// stderr among the log. Should typically only be set by
+  /// `FfmpegCommand::spawn`.
+  pub(crate) fn set_progress_pipe(&mut self, progress_pipe: Option<ProgressPipe>) {
+    self.progress_pipe = progress_pipe;
+  }
+
+  /// The expected length of each frame passed to `write_frame`. Should
+  /// typically only be set through `FfmpegCommand::rawvideo_input`.
+  pub(crate) fn set_input_frame_size(&mut self, input_frame_size: Option<usize>) {
+    self.input_frame_size = input_frame_size;
+  }
+
+  /// Temporary input files, such as a concat list, which must not be deleted
+  /// while the process may still read them.
+  pub(crate) fn set_temp_files(&mut self, temp_files: Vec<Arc<TempFile>>) {
+    self.temp_files = temp_files;
+  }
+
+  /// Outputs to move into place once the process succeeds. Should typically
+  /// only be set through `FfmpegCommand::atomic_output`.
+  pub(crate) fn set_atomic_outputs(&mut self, atomic_outputs: Vec<AtomicOutput>) {
+    self.atomic_outputs = atomic_outputs;
+  }
+
+  /// Capture the raw stderr log, keeping at most the last `max_bytes`. Should
+  /// typically only be set through `FfmpegCommand::capture_log`.
+  pub(crate) fn set_log_capture(&mut self, max_bytes: Option<usize>) {
+    self.log_capture = Some(Arc::new(LogCapture {
+      buffer: Mutex::new(VecDeque::new()),
+      max_bytes,
+    }));
+  }
+
+  /// The capacity of the buffer for reading stdout. Should typically only be
+  /// set through `FfmpegCommand::stdout_buffer_size`.
+  pub(crate) fn set_stdout_buffer_size(&mut self, stdout_buffer_size: Option<usize>) {
+    self.stdout_buffer_size = stdout_buffer_size;
+  }
+
+  /// Where `-progress` reports are written, if anywhere.
+  pub(crate) fn progress_pipe(&self) -> Option<ProgressPipe> {
+    self.progress_pipe
+  }
+
+  pub(crate) fn stdout_buffer_size(&self) -> Option<usize> {
+    self.stdout_buffer_size
   }
 
   /// Escape hatch to access the inner `Child`.
@@ -132,3 +626,73 @@ impl FfmpegChild {
     &mut self.inner
   }
 }
+
+impl Drop for FfmpegChild {
+  fn drop(&mut self) {
+    if self.kill_on_drop {
+      // Reap the process after killing it so it doesn't linger as a zombie.
+      if let Ok(None) = self.inner.try_wait() {
+        self.inner.kill().ok();
+        self.inner.wait().ok();
+      }
+    }
+    if let Ok(Some(status)) = self.inner.try_wait() {
+      // The pipes close once the process exits, so the events thread is about
+      // to finish. While the process runs, it's left detached instead.
+      if let Some(events_thread) = self.events_thread.take() {
+        events_thread.join().ok();
+      }
+      self.handle_exit(status).ok();
+    }
+  }
+}
+
+/// The stream which `-progress` reports are written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ProgressPipe {
+  /// `-progress pipe:1`, in place of output data
+  Stdout,
+  /// `-progress pipe:2`, interleaved with the log
+  Stderr,
+}
+
+/// Raw stderr output shared between the child and whichever thread reads
+/// stderr.
+pub(crate) struct LogCapture {
+  buffer: Mutex<VecDeque<u8>>,
+  max_bytes: Option<usize>,
+}
+
+impl LogCapture {
+  fn append(&self, bytes: &[u8]) {
+    let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+    buffer.extend(bytes);
+    if let Some(max_bytes) = self.max_bytes {
+      let excess = buffer.len().saturating_sub(max_bytes);
+      buffer.drain(..excess);
+    }
+  }
+
+  fn take(&self) -> String {
+    let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+    let bytes: Vec<u8> = buffer.drain(..).collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+  }
+}
+
+/// The child's stderr, copying everything read into the captured log if
+/// enabled.
+pub(crate) struct StderrReader {
+  inner: ChildStderr,
+  capture: Option<Arc<LogCapture>>,
+}
+
+impl Read for StderrReader {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let bytes_read = self.inner.read(buf)?;
+    if let Some(capture) = &self.capture {
+      capture.append(&buf[..bytes_read]);
+    }
+    Ok(bytes_read)
+  }
+}
@@ -1,10 +1,34 @@
 //! Builder interface for FFmpeg commands.
 
-use crate::{child::FfmpegChild, paths::ffmpeg_path};
+#[cfg(feature = "tokio")]
+use crate::async_child::AsyncFfmpegChild;
+use crate::{
+  audio::{LoudnormOptions, Volume},
+  bitrate::Bitrate,
+  child::{FfmpegChild, ProgressPipe},
+  event::{FfmpegEvent, LogLevel},
+  hls::HlsOptions,
+  input::InputOptions,
+  metadata::FfmpegMetadata,
+  paths::{extended_length_path, ffmpeg_path, validate_binary, BinaryKind},
+  pcm::PcmFormat,
+  pix_fmt::bytes_per_frame,
+  segment::{has_counter, SegmentOptions},
+  streaming::StreamProtocol,
+  subtitles::{subtitles_filter, SubtitleStyle},
+  tee::{tee_arg, TeeOutput},
+  temp_file::{AtomicOutput, TempFile},
+  version::{ffmpeg_version_parsed_with_path, FfmpegVersion},
+  x264::MAX_CRF,
+};
 use std::{
-  ffi::OsStr,
+  borrow::Cow,
+  ffi::{OsStr, OsString},
   fmt, io,
+  path::Path,
   process::{Command, CommandArgs, Stdio},
+  sync::Arc,
+  time::Duration,
 };
 
 /// A wrapper around [`std::process::Command`] with some convenient preset
@@ -15,6 +39,27 @@ use std::{
 /// exhaustive list of possible arguments.
 pub struct FfmpegCommand {
   inner: Command,
+  kill_on_drop: bool,
+  input_frame_size: Option<usize>,
+  temp_files: Vec<Arc<TempFile>>,
+  capture_log: Option<Option<usize>>,
+  long_path_safe: bool,
+  /// Simple video filters for the next output, joined into a single `-vf`.
+  video_filters: Vec<String>,
+  /// Simple audio filters for the next output, joined into a single `-af`.
+  audio_filters: Vec<String>,
+  /// Formats set with `input_format` and `output_format`, emitted as `-f`
+  /// right before the next input or output.
+  input_format: Option<String>,
+  output_format: Option<String>,
+  /// The version of the ffmpeg binary, for version-aware methods.
+  version: VersionCheck,
+  auto_pipes: bool,
+  atomic_outputs: Vec<AtomicOutput>,
+  stdout_buffer_size: Option<usize>,
+  /// Set by `with_progress_pipe`; the `-progress` target is chosen at spawn
+  /// time, once it's known whether stdout carries output.
+  wants_progress_pipe: bool,
 }
 
 impl FfmpegCommand {
@@ -47,15 +92,115 @@ impl FfmpegCommand {
     self
   }
 
+  /// Alias for `-f` argument, forcing the container format of the next input.
+  ///
+  /// Unlike [`format`](Self::format), this can be called anywhere before the
+  /// [`input`](Self::input) it applies to: the `-f` is written right before
+  /// its `-i`. Needed for inputs like `pipe:0` or raw streams, which have no
+  /// file extension.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .input_format("mpegts")
+  ///   .output_format("matroska")
+  ///   .input("pipe:0")
+  ///   .codec_video("copy")
+  ///   .output("pipe:1");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-f", "mpegts", "-i", "pipe:0", "-c:v", "copy", "-f", "matroska", "pipe:1"]));
+  /// ```
+  pub fn input_format<S: AsRef<str>>(&mut self, format: S) -> &mut Self {
+    self.input_format = Some(format.as_ref().to_string());
+    self
+  }
+
+  /// Alias for `-f` argument, forcing the container format of the next
+  /// output. Written right before the output path, after any other output
+  /// options; see [`input_format`](Self::input_format).
+  pub fn output_format<S: AsRef<str>>(&mut self, format: S) -> &mut Self {
+    self.output_format = Some(format.as_ref().to_string());
+    self
+  }
+
+  /// Alias for `-movflags` argument.
+  ///
+  /// Set flags of the mov/mp4 muxer, joined with `+`. A flag with a leading
+  /// `+` is added to the defaults instead of replacing them. See
+  /// [`faststart`](Self::faststart) and [`frag_mp4`](Self::frag_mp4) for the
+  /// most common ones.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command.movflags(["frag_keyframe", "empty_moov", "default_base_moof"]);
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-movflags", "frag_keyframe+empty_moov+default_base_moof"]));
+  /// ```
+  pub fn movflags<I, S>(&mut self, flags: I) -> &mut Self
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+  {
+    let flags: Vec<String> = flags
+      .into_iter()
+      .map(|flag| flag.as_ref().to_string())
+      .collect();
+    self.arg("-movflags");
+    self.arg(flags.join("+"));
+    self
+  }
+
+  /// Alias for `-movflags +faststart`.
+  ///
+  /// Move the index (moov atom) of an mp4 to the front of the file once it's
+  /// written, so playback over HTTP can start before the whole file is
+  /// downloaded. Needs a seekable output, not a pipe.
+  pub fn faststart(&mut self) -> &mut Self {
+    self.movflags(["+faststart"])
+  }
+
+  /// Alias for `-movflags frag_keyframe+empty_moov`.
+  ///
+  /// Write a fragmented mp4, starting a new fragment on each keyframe, with
+  /// the header up front. Required for writing mp4 to a pipe or a live
+  /// stream, since the muxer can't seek back to write the header:
+  /// `.format("mp4").frag_mp4().output("pipe:1")`.
+  pub fn frag_mp4(&mut self) -> &mut Self {
+    self.movflags(["frag_keyframe", "empty_moov"])
+  }
+
   /// Alias for `-i` argument, the input file path or URL.
   ///
   /// To take input from stdin, use the value `-` or `pipe:0`.
   pub fn input<S: AsRef<str>>(&mut self, path_or_url: S) -> &mut Self {
+    let path_or_url = self.long_path_arg(path_or_url.as_ref());
+    if let Some(format) = self.input_format.take() {
+      self.format(format);
+    }
     self.arg("-i");
-    self.arg(path_or_url.as_ref());
+    self.arg(path_or_url);
     self
   }
 
+  /// Add an input along with the options that apply to it, like `-ss` or
+  /// `-hwaccel`, which are written right before its `-i`. See
+  /// [`InputOptions`].
+  ///
+  /// Input options set with the other builder methods before calling this
+  /// still apply to this input, as they precede its `-i`.
+  pub fn input_with<S, F>(&mut self, path_or_url: S, options: F) -> &mut Self
+  where
+    S: AsRef<str>,
+    F: FnOnce(&mut InputOptions) -> &mut InputOptions,
+  {
+    let mut input_options = InputOptions::new();
+    options(&mut input_options);
+    self.args(input_options.get_args());
+    self.input(path_or_url)
+  }
+
   /// Alias for the output file path or URL.
   ///
   /// To send output to stdout, use the value `-` or `pipe:1`.
@@ -65,11 +210,67 @@ impl FfmpegCommand {
   /// using this command helps label the purpose of the argument, and makes the
   /// code more readable at a glance.
   pub fn output<S: AsRef<str>>(&mut self, path_or_url: S) -> &mut Self {
-    self.arg(path_or_url.as_ref());
+    let path_or_url = self.long_path_arg(path_or_url.as_ref());
+    self.flush_filters();
+    self.arg(path_or_url);
     self
   }
 
+  /// Like [`output`](Self::output), but crash-safe: ffmpeg writes to a hidden
+  /// temporary file next to `final_path`, which is renamed to `final_path`
+  /// once the process exits successfully, and deleted if it fails. A killed
+  /// or crashed run never leaves a partial file at `final_path`.
+  ///
+  /// The rename happens in [`FfmpegChild::wait`] (and the methods calling it,
+  /// like `wait_and_result`), replacing any existing file at `final_path`. If
+  /// the child is dropped without waiting, the temporary file is cleaned up
+  /// only if the process has already exited, or is killed on drop.
+  ///
+  /// The temporary file keeps the extension of `final_path`, so the format is
+  /// still inferred from it.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// FfmpegCommand::new()
+  ///   .input("input.mp4")
+  ///   .atomic_output("output/video.mp4")
+  ///   .spawn()?
+  ///   .wait_and_result()?;
+  /// # anyhow::Ok(())
+  /// ```
+  pub fn atomic_output<S: AsRef<str>>(&mut self, final_path: S) -> &mut Self {
+    let output = AtomicOutput::new(Path::new(final_path.as_ref()));
+    self.output(output.temp_path().to_string_lossy());
+    self.atomic_outputs.push(output);
+    self
+  }
+
+  /// Preset for discarding the output, e.g. for the first pass of a two-pass
+  /// encode, benchmarking a decoder, or just collecting the logs. Equivalent
+  /// to `-f null NUL` on Windows and `-f null /dev/null` elsewhere.
+  ///
+  /// The streams are still encoded as usual, unless set to copy or
+  /// `wrapped_avframe`. The null muxer never opens the path, so this is
+  /// unaffected by [`no_overwrite`](Self::no_overwrite).
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command.input("input.mp4").null_output();
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// let device = if cfg!(windows) { "NUL" } else { "/dev/null" };
+  /// assert!(args.ends_with(&["-f", "null", device]));
+  /// ```
+  pub fn null_output(&mut self) -> &mut Self {
+    self.output_format("null");
+    self.output(if cfg!(windows) { "NUL" } else { "/dev/null" })
+  }
+
   /// Alias for `-y` argument: overwrite output files without asking.
+  ///
+  /// If neither this nor [`no_overwrite`](FfmpegCommand::no_overwrite) is
+  /// set, `spawn()` adds `-n`, so ffmpeg never blocks waiting for an answer to
+  /// its `Overwrite? [y/N]` prompt on stdin.
   pub fn overwrite(&mut self) -> &mut Self {
     self.arg("-y");
     self
@@ -118,6 +319,14 @@ impl FfmpegCommand {
     self
   }
 
+  /// Alias for `-c copy`: copy every stream of the next output without
+  /// re-encoding. Combine it with a per-stream codec after it, like
+  /// `.copy_all().codec_audio("aac")`, to only re-encode some streams.
+  pub fn copy_all(&mut self) -> &mut Self {
+    self.args(["-c", "copy"]);
+    self
+  }
+
   /// Alias for `-t` argument.
   ///
   /// When used as an input option (before `-i`), limit the duration of data
@@ -131,9 +340,12 @@ impl FfmpegCommand {
   /// manual](https://ffmpeg.org/ffmpeg-utils.html#time-duration-syntax).
   ///
   /// `-to` and `-t` are mutually exclusive and -t has priority.
-  pub fn duration<S: AsRef<str>>(&mut self, duration: S) -> &mut Self {
+  ///
+  /// Accepts either a string or a [`std::time::Duration`], which is formatted
+  /// as `HH:MM:SS.mmm`; see [`TimeArg`].
+  pub fn duration<T: TimeArg>(&mut self, duration: T) -> &mut Self {
     self.arg("-t");
-    self.arg(duration.as_ref());
+    self.arg(duration.to_time_arg());
     self
   }
 
@@ -146,9 +358,45 @@ impl FfmpegCommand {
   ///
   /// `-to` and `-t` (aka `duration()`) are mutually exclusive and `-t` has
   /// priority.
-  pub fn to<S: AsRef<str>>(&mut self, position: S) -> &mut Self {
+  ///
+  /// Accepts either a string or a [`std::time::Duration`]; see [`TimeArg`].
+  pub fn to<T: TimeArg>(&mut self, position: T) -> &mut Self {
     self.arg("-to");
-    self.arg(position.as_ref());
+    self.arg(position.to_time_arg());
+    self
+  }
+
+  /// Alias for `-shortest` argument.
+  ///
+  /// Finish encoding when the shortest output stream ends. Needed when an
+  /// input never ends, like a looped image under a finite audio track:
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .args(["-loop", "1"])
+  ///   .input("cover.png")
+  ///   .input("audio.mp3")
+  ///   .shortest()
+  ///   .output("output.mp4");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-i", "audio.mp3", "-shortest", "output.mp4"]));
+  /// ```
+  pub fn shortest(&mut self) -> &mut Self {
+    self.arg("-shortest");
+    self
+  }
+
+  /// Alias for `-shortest_buf_duration` argument, available since FFmpeg 5.1.
+  ///
+  /// With [`shortest`](Self::shortest), the maximum duration of buffered
+  /// frames in a stream, used to find the shortest stream. Longer buffers
+  /// cut off the output more accurately, at the cost of memory and latency.
+  /// The default is 10 seconds.
+  pub fn shortest_buf_duration(&mut self, duration: Duration) -> &mut Self {
+    self.arg("-shortest_buf_duration");
+    self.arg(duration.as_secs_f64().to_string());
     self
   }
 
@@ -179,9 +427,42 @@ impl FfmpegCommand {
   /// `position` must be a time duration specification, see [(ffmpeg-utils)the
   /// Time duration section in the ffmpeg-utils(1)
   /// manual](https://ffmpeg.org/ffmpeg-utils.html#time-duration-syntax).
-  pub fn seek<S: AsRef<str>>(&mut self, position: S) -> &mut Self {
+  ///
+  /// Accepts either a string or a [`std::time::Duration`]; see [`TimeArg`].
+  /// Call this *before* the `input` to seek; to seek on the output side, call
+  /// [`seek_output`](Self::seek_output) after the inputs instead. Input seeking
+  /// jumps straight to the nearest keyframe and is fast even deep into a long
+  /// file. Output seeking decodes everything up to `position`, which is slow
+  /// but frame-accurate, including with `-c copy` where input seeking can only
+  /// cut on keyframes.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// use std::time::Duration;
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .seek(Duration::from_secs(90))
+  ///   .input("input.mp4")
+  ///   .duration(Duration::from_millis(2500))
+  ///   .output("clip.mp4");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-ss", "00:01:30.000", "-i", "input.mp4", "-t", "00:00:02.500", "clip.mp4"]));
+  /// ```
+  pub fn seek<T: TimeArg>(&mut self, position: T) -> &mut Self {
     self.arg("-ss");
-    self.arg(position.as_ref());
+    self.arg(position.to_time_arg());
+    self
+  }
+
+  /// Alias for `-ss` argument as an output option, to be called after the
+  /// inputs.
+  ///
+  /// Decodes but discards input until the timestamps reach `position`. This is
+  /// slower than input seeking with [`seek`](Self::seek), since everything
+  /// before `position` is decoded, but it's accurate to the frame.
+  pub fn seek_output<T: TimeArg>(&mut self, position: T) -> &mut Self {
+    self.arg("-ss");
+    self.arg(position.to_time_arg());
     self
   }
 
@@ -195,6 +476,97 @@ impl FfmpegCommand {
     self
   }
 
+  /// Alias for `-metadata` argument.
+  ///
+  /// Set a global metadata tag of the next output, like `title`, `artist` or
+  /// a custom key, passed as `-metadata key=value`.
+  ///
+  /// The pair is passed to ffmpeg as a single argument without a shell in
+  /// between, so values containing spaces, quotes or other special characters
+  /// need no escaping. An empty value removes the tag.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .input("input.mp4")
+  ///   .metadata("title", "Rock & Roll \"Live\"")
+  ///   .stream_metadata("a:0", "language", "eng")
+  ///   .output("output.mp4");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&[
+  ///   "-metadata",
+  ///   "title=Rock & Roll \"Live\"",
+  ///   "-metadata:s:a:0",
+  ///   "language=eng",
+  ///   "output.mp4",
+  /// ]));
+  /// ```
+  pub fn metadata<K: AsRef<str>, V: AsRef<str>>(&mut self, key: K, value: V) -> &mut Self {
+    self.arg("-metadata");
+    self.arg(format!("{}={}", key.as_ref(), value.as_ref()));
+    self
+  }
+
+  /// Alias for `-metadata:s:<stream_spec>` argument.
+  ///
+  /// Set a metadata tag on the streams of the next output matching
+  /// `stream_spec`, like `a:0` for the first audio stream. Escaping works the
+  /// same as for [`metadata`](Self::metadata).
+  pub fn stream_metadata<S: AsRef<str>, K: AsRef<str>, V: AsRef<str>>(
+    &mut self,
+    stream_spec: S,
+    key: K,
+    value: V,
+  ) -> &mut Self {
+    self.arg(format!("-metadata:s:{}", stream_spec.as_ref()));
+    self.arg(format!("{}={}", key.as_ref(), value.as_ref()));
+    self
+  }
+
+  /// Alias for `-disposition:<stream_spec>` argument.
+  ///
+  /// Set the dispositions of the streams of the next output matching
+  /// `stream_spec`, like `default` or `forced`, replacing those copied from
+  /// the input. The flags are joined with `+`; pass `&["0"]` or no flags at
+  /// all to clear them. Prefix a flag with `+` or `-` to add or remove it
+  /// while keeping the others.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .input("input.mkv")
+  ///   .disposition("s:0", &["default", "forced"])
+  ///   .disposition("s:1", &[])
+  ///   .disposition("a:0", &["+default", "-comment"])
+  ///   .output("output.mkv");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&[
+  ///   "-disposition:s:0",
+  ///   "default+forced",
+  ///   "-disposition:s:1",
+  ///   "0",
+  ///   "-disposition:a:0",
+  ///   "+default-comment",
+  ///   "output.mkv",
+  /// ]));
+  /// ```
+  pub fn disposition<S: AsRef<str>>(&mut self, stream_spec: S, flags: &[&str]) -> &mut Self {
+    let mut value = String::new();
+    for flag in flags {
+      if !value.is_empty() && !flag.starts_with(['+', '-']) {
+        value.push('+');
+      }
+      value.push_str(flag);
+    }
+    if value.is_empty() {
+      value.push('0');
+    }
+    self.arg(format!("-disposition:{}", stream_spec.as_ref()));
+    self.arg(value)
+  }
+
   /// Alias for `-filter` argument.
   ///
   /// Create the filtergraph specified by `filtergraph` and use it to filter the
@@ -215,6 +587,197 @@ impl FfmpegCommand {
     self
   }
 
+  /// Append a filter to the simple video filtergraph of the next output.
+  ///
+  /// FFmpeg only honors the last `-vf` of each output, so instead of passing
+  /// `-vf` directly, filters added with this method and the helpers built on
+  /// it ([`scale`](Self::scale), [`scale_fit`](Self::scale_fit),
+  /// [`crop`](Self::crop), [`fps`](Self::fps) and
+  /// [`burn_subtitles`](Self::burn_subtitles)) are collected in the order
+  /// they're called and passed as a single comma-joined `-vf` right before the
+  /// next [`output`](Self::output). Any filters still pending at
+  /// [`spawn`](Self::spawn) are appended then, which only takes effect if no
+  /// output path follows them; prefer `output()` over `arg()` for the path.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .input("input.mp4")
+  ///   .crop(1280, 720, 0, 180)
+  ///   .scale(-2, 480)
+  ///   .video_filter("hflip")
+  ///   .output("output.mp4");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-vf", "crop=1280:720:0:180,scale=-2:480,hflip", "output.mp4"]));
+  /// ```
+  pub fn video_filter<S: AsRef<str>>(&mut self, filter: S) -> &mut Self {
+    self.video_filters.push(filter.as_ref().to_string());
+    self
+  }
+
+  /// Append a filter to the simple audio filtergraph of the next output,
+  /// passed as a single comma-joined `-af` like
+  /// [`video_filter`](Self::video_filter).
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .input("input.mp4")
+  ///   .audio_filter("loudnorm")
+  ///   .audio_filter("aresample=48000")
+  ///   .output("output.mp4");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-af", "loudnorm,aresample=48000", "output.mp4"]));
+  /// ```
+  pub fn audio_filter<S: AsRef<str>>(&mut self, filter: S) -> &mut Self {
+    self.audio_filters.push(filter.as_ref().to_string());
+    self
+  }
+
+  /// Change the audio volume with the `volume` filter, by a factor or in
+  /// decibels. Appends to the `-af` chain, see
+  /// [`audio_filter`](Self::audio_filter).
+  pub fn volume(&mut self, volume: Volume) -> &mut Self {
+    self.audio_filter(format!("volume={volume}"))
+  }
+
+  /// Normalize the loudness of the audio to the EBU R128 targets in
+  /// `options`, with the `loudnorm` filter. Appends to the `-af` chain, see
+  /// [`audio_filter`](Self::audio_filter).
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::{audio::LoudnormOptions, command::FfmpegCommand};
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .input("input.mp4")
+  ///   .loudnorm(LoudnormOptions { integrated: -16.0, ..Default::default() })
+  ///   .audio_resample(48000)
+  ///   .output("output.mp4");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-af", "loudnorm=I=-16:LRA=7:TP=-2,aresample=48000", "output.mp4"]));
+  /// ```
+  pub fn loudnorm(&mut self, options: LoudnormOptions) -> &mut Self {
+    self.audio_filter(options.to_string())
+  }
+
+  /// Resample the audio to `sample_rate` in Hz with the `aresample` filter,
+  /// e.g. after `loudnorm`, which outputs 192 kHz. Appends to the `-af`
+  /// chain, see [`audio_filter`](Self::audio_filter).
+  pub fn audio_resample(&mut self, sample_rate: u32) -> &mut Self {
+    self.audio_filter(format!("aresample={sample_rate}"))
+  }
+
+  /// Resize the video with the `scale` filter. Use `-1` for either dimension
+  /// to preserve the aspect ratio, or `-2` to also round it to an even number,
+  /// which most encoders require for `yuv420p`. Appends to the `-vf` chain,
+  /// see [`video_filter`](Self::video_filter).
+  pub fn scale(&mut self, width: i32, height: i32) -> &mut Self {
+    self.video_filter(format!("scale={width}:{height}"))
+  }
+
+  /// Resize the video to fit within `max_width` by `max_height` while
+  /// preserving its aspect ratio, with `force_original_aspect_ratio=decrease`.
+  /// Appends to the `-vf` chain, see [`video_filter`](Self::video_filter).
+  ///
+  /// Unlike `scale(-2, max_height)`, this also limits the width, so portrait
+  /// and landscape inputs both fit the box.
+  pub fn scale_fit(&mut self, max_width: u32, max_height: u32) -> &mut Self {
+    self.video_filter(format!(
+      "scale={max_width}:{max_height}:force_original_aspect_ratio=decrease"
+    ))
+  }
+
+  /// Cut out a `width` by `height` rectangle with its top left corner at `x`,
+  /// `y`, using the `crop` filter. Appends to the `-vf` chain, see
+  /// [`video_filter`](Self::video_filter).
+  pub fn crop(&mut self, width: u32, height: u32, x: u32, y: u32) -> &mut Self {
+    self.video_filter(format!("crop={width}:{height}:{x}:{y}"))
+  }
+
+  /// Convert the video to a constant frame rate by duplicating or dropping
+  /// frames, using the `fps` filter. Appends to the `-vf` chain, see
+  /// [`video_filter`](Self::video_filter).
+  ///
+  /// Unlike the output option [`rate`](Self::rate) (`-r`), which only sets the
+  /// rate the muxer expects and leaves the duplicating and dropping to
+  /// [`fps_mode`](Self::fps_mode), the `fps` filter picks frames by their
+  /// timestamps, at its position in the filter chain. This makes it the right
+  /// tool for variable frame rate sources, and placing it before a `scale`
+  /// avoids scaling frames which would be dropped anyway.
+  ///
+  /// The NTSC rates `23.976`, `29.97`, `59.94` and so on are passed as their
+  /// exact fractions, like `30000/1001`; other rates are passed as written.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command.input("input.mp4").fps(29.97).output("output.mp4");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-vf", "fps=30000/1001", "output.mp4"]));
+  /// ```
+  pub fn fps(&mut self, fps: f32) -> &mut Self {
+    self.video_filter(format!("fps={}", frame_rate_arg(fps)))
+  }
+
+  /// Keep only the keyframes of the video, e.g. for a fast thumbnail sheet,
+  /// with the `select='eq(pict_type,I)'` filter. Appends to the `-vf` chain,
+  /// see [`video_filter`](Self::video_filter).
+  ///
+  /// Also sets a variable frame rate with
+  /// [`frame_sync_mode("vfr")`](Self::frame_sync_mode), so the selected
+  /// frames keep their timestamps instead of being duplicated to fill the
+  /// gaps. Which flag that uses depends on the version set with
+  /// [`assume_version`](Self::assume_version) or
+  /// [`detect_version`](Self::detect_version) before this call.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .assume_version("7.0".parse().unwrap())
+  ///   .input("input.mp4")
+  ///   .select_keyframes()
+  ///   .output("keyframe_%03d.jpg");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&[
+  ///   "-fps_mode", "vfr", "-vf", "select='eq(pict_type,I)'", "keyframe_%03d.jpg"
+  /// ]));
+  /// ```
+  pub fn select_keyframes(&mut self) -> &mut Self {
+    self.video_filter("select='eq(pict_type,I)'");
+    self.frame_sync_mode("vfr")
+  }
+
+  /// Keep only the frames starting a new scene, with the
+  /// `select='gt(scene,{threshold})'` filter. The scene score of each frame
+  /// ranges from `0.0` to `1.0`, and `0.3` to `0.4` is a common threshold;
+  /// lower values select more frames. Appends to the `-vf` chain, see
+  /// [`video_filter`](Self::video_filter).
+  ///
+  /// Like [`select_keyframes`](Self::select_keyframes), this also sets a
+  /// variable frame rate so the selected frames keep their timestamps, with
+  /// the flag matching the version set before this call.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .assume_version("4.4".parse().unwrap())
+  ///   .input("input.mp4")
+  ///   .select_scene_changes(0.4)
+  ///   .output("scene_%03d.jpg");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&[
+  ///   "-vsync", "vfr", "-vf", "select='gt(scene,0.4)'", "scene_%03d.jpg"
+  /// ]));
+  /// ```
+  pub fn select_scene_changes(&mut self, threshold: f32) -> &mut Self {
+    self.video_filter(format!("select='gt(scene,{threshold})'"));
+    self.frame_sync_mode("vfr")
+  }
+
   //// Video option aliases
   //// https://ffmpeg.org/ffmpeg.html#Video-Options
 
@@ -243,6 +806,101 @@ impl FfmpegCommand {
     self
   }
 
+  /// Variant of [`crf`](Self::crf) for libx264 and libx265, returning an
+  /// error instead of letting FFmpeg fail at startup if `crf` is above their
+  /// maximum of [`MAX_CRF`](crate::x264::MAX_CRF).
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// assert!(command.codec_video("libx264").try_crf(23).is_ok());
+  /// assert!(command.try_crf(60).is_err());
+  /// ```
+  pub fn try_crf(&mut self, crf: u8) -> anyhow::Result<&mut Self> {
+    if crf > MAX_CRF {
+      anyhow::bail!("CRF {crf} is out of range for libx264/libx265 (0-{MAX_CRF})");
+    }
+    Ok(self.crf(crf.into()))
+  }
+
+  /// Alias for `-b:v` argument, the target video bitrate.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::{bitrate::Bitrate, command::FfmpegCommand};
+  /// let mut command = FfmpegCommand::new();
+  /// command.video_bitrate(Bitrate::mbps(5)).audio_bitrate("128k".parse().unwrap());
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-b:v", "5M", "-b:a", "128k"]));
+  /// ```
+  pub fn video_bitrate(&mut self, bitrate: Bitrate) -> &mut Self {
+    self.arg("-b:v");
+    self.arg(bitrate.to_string());
+    self
+  }
+
+  /// Alias for `-b:a` argument, the target audio bitrate.
+  pub fn audio_bitrate(&mut self, bitrate: Bitrate) -> &mut Self {
+    self.arg("-b:a");
+    self.arg(bitrate.to_string());
+    self
+  }
+
+  /// Alias for `-g` argument, the GOP size: the maximum number of frames
+  /// between keyframes.
+  pub fn gop_size(&mut self, frames: u32) -> &mut Self {
+    self.arg("-g");
+    self.arg(frames.to_string());
+    self
+  }
+
+  /// Alias for `-keyint_min` argument, the minimum number of frames between
+  /// keyframes. Set it equal to [`gop_size`](FfmpegCommand::gop_size) for a
+  /// fixed keyframe interval, e.g. so HLS or DASH segments line up across
+  /// renditions.
+  pub fn keyint_min(&mut self, frames: u32) -> &mut Self {
+    self.arg("-keyint_min");
+    self.arg(frames.to_string());
+    self
+  }
+
+  /// Set the GOP size from a keyframe `interval` in seconds at the given
+  /// output frame rate, e.g. 2 seconds at 30 fps is `-g 60`. The frame count
+  /// is rounded to the nearest whole frame, and is at least 1.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// use std::time::Duration;
+  /// let mut command = FfmpegCommand::new();
+  /// command.keyframe_interval(Duration::from_secs(2), 29.97);
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-g", "60"]));
+  /// ```
+  pub fn keyframe_interval(&mut self, interval: Duration, fps: f32) -> &mut Self {
+    let frames = (interval.as_secs_f64() * fps as f64).round().max(1.0) as u32;
+    self.gop_size(frames)
+  }
+
+  /// Alias for `-force_key_frames` argument with a list of timestamps,
+  /// forcing a keyframe at each of them.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// use std::time::Duration;
+  /// let mut command = FfmpegCommand::new();
+  /// command.force_keyframes_at(&[Duration::ZERO, Duration::from_millis(2500)]);
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-force_key_frames", "00:00:00.000,00:00:02.500"]));
+  /// ```
+  ///
+  /// For keyframes at a regular interval, an expression is more compact:
+  /// `.args(["-force_key_frames", "expr:gte(t,n_forced*2)"])`.
+  pub fn force_keyframes_at(&mut self, timestamps: &[Duration]) -> &mut Self {
+    let timestamps: Vec<String> = timestamps.iter().map(TimeArg::to_time_arg).collect();
+    self.arg("-force_key_frames");
+    self.arg(timestamps.join(","));
+    self
+  }
+
   /// Alias for `-frames:v` argument.
   ///
   /// Stop writing to the stream after `framecount` frames.
@@ -277,12 +935,25 @@ impl FfmpegCommand {
   /// encoding speed)
   ///
   /// VP9 has no presets
+  ///
+  /// For h264 and h265, a typed [`Preset`](crate::x264::Preset) can be used instead of a string.
   pub fn preset<S: AsRef<str>>(&mut self, preset: S) -> &mut Self {
     self.arg("-preset:v");
     self.arg(preset.as_ref());
     self
   }
 
+  /// Alias for `-tune:v` argument.
+  ///
+  /// Tune the h264 or h265 encoder settings for a particular type of input or
+  /// situation, such as `film`, `animation` or `zerolatency`. Accepts either a
+  /// typed [`Tune`](crate::x264::Tune) or a string.
+  pub fn tune<S: AsRef<str>>(&mut self, tune: S) -> &mut Self {
+    self.arg("-tune:v");
+    self.arg(tune.as_ref());
+    self
+  }
+
   /// Alias for `-r` argument.
   ///
   /// Set frame rate (Hz value, fraction or abbreviation).
@@ -374,6 +1045,22 @@ impl FfmpegCommand {
   /// This option has no effect if the selected hwaccel is not available or not
   /// supported by the chosen decoder.
   ///
+  /// Accepts either a [`HwAccel`] or the method name as a string. Like other
+  /// input options, this must be called *before* the `input` it applies to;
+  /// see [`crate::capabilities::ffmpeg_hwaccels`] for the methods available
+  /// in the local binary.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::{FfmpegCommand, HwAccel};
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .hwaccel(HwAccel::Cuda)
+  ///   .hwaccel_output_format("cuda")
+  ///   .input("input.mp4");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-hwaccel", "cuda", "-hwaccel_output_format", "cuda", "-i", "input.mp4"]));
+  /// ```
+  ///
   /// Note that most acceleration methods are intended for playback and will not
   /// be faster than software decoding on modern CPUs. Additionally, `ffmpeg`
   /// will usually need to copy the decoded frames from the GPU memory into the
@@ -385,6 +1072,18 @@ impl FfmpegCommand {
     self
   }
 
+  /// Alias for `-hwaccel_output_format` argument.
+  ///
+  /// Set the output format to be used by hardware accelerated decoding, e.g.
+  /// `cuda` to keep decoded frames in GPU memory for a hardware encoder,
+  /// instead of copying them back to system memory. Like `hwaccel`, this must
+  /// precede the `input` it applies to.
+  pub fn hwaccel_output_format<S: AsRef<str>>(&mut self, format: S) -> &mut Self {
+    self.arg("-hwaccel_output_format");
+    self.arg(format.as_ref());
+    self
+  }
+
   //// Audio option aliases
   //// https://ffmpeg.org/ffmpeg.html#Audio-Options
 
@@ -439,12 +1138,60 @@ impl FfmpegCommand {
   /// commandline.
   ///
   /// Using this option disables the default mappings for this output file.
+  ///
+  /// Accepts either a raw string like `0:v:0` or a typed
+  /// [`StreamSpec`](crate::stream_spec::StreamSpec).
   pub fn map<S: AsRef<str>>(&mut self, map_string: S) -> &mut Self {
     self.arg("-map");
     self.arg(map_string.as_ref());
     self
   }
 
+  /// Alias for `-map_metadata` argument.
+  ///
+  /// Set the global metadata of the next output from input file index `from`,
+  /// or strip it with `-1`. By default, it is copied from the first input.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .input("input.mkv")
+  ///   .map_metadata(-1)
+  ///   .map_chapters(-1)
+  ///   .codec_video("copy")
+  ///   .codec_audio("copy")
+  ///   .output("output.mkv");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&[
+  ///   "-map_metadata",
+  ///   "-1",
+  ///   "-map_chapters",
+  ///   "-1",
+  ///   "-c:v",
+  ///   "copy",
+  ///   "-c:a",
+  ///   "copy",
+  ///   "output.mkv",
+  /// ]));
+  /// ```
+  pub fn map_metadata(&mut self, from: i32) -> &mut Self {
+    self.arg("-map_metadata");
+    self.arg(from.to_string());
+    self
+  }
+
+  /// Alias for `-map_chapters` argument.
+  ///
+  /// Copy the chapters of the next output from input file index `from`, or
+  /// strip them with `-1`. By default, they are copied from the first input
+  /// with at least one chapter.
+  pub fn map_chapters(&mut self, from: i32) -> &mut Self {
+    self.arg("-map_chapters");
+    self.arg(from.to_string());
+    self
+  }
+
   /// Alias for `-readrate` argument.
   ///
   /// Limit input read speed.
@@ -471,11 +1218,36 @@ impl FfmpegCommand {
   ///
   /// Read input at native frame rate. This is equivalent to setting `-readrate
   /// 1`.
+  ///
+  /// This is an input option, so it must be called before the `input` it
+  /// applies to.
   pub fn realtime(&mut self) -> &mut Self {
     self.arg("-re");
     self
   }
 
+  /// Alias for `-stream_loop` argument.
+  ///
+  /// Set number of times input stream shall be looped. Loop 0 means no loop,
+  /// loop -1 means infinite loop.
+  ///
+  /// This is an input option, so it must be called before the `input` it
+  /// applies to. Combined with [`realtime`](Self::realtime), an infinitely
+  /// looped file or still image can feed a live stream.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command.realtime().loop_input(-1).input("still.png");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-re", "-stream_loop", "-1", "-i", "still.png"]));
+  /// ```
+  pub fn loop_input(&mut self, count: i32) -> &mut Self {
+    self.arg("-stream_loop");
+    self.arg(count.to_string());
+    self
+  }
+
   /// Alias for `-fps_mode` argument.
   ///
   /// Set video sync method / framerate mode. vsync is applied to all output
@@ -501,6 +1273,33 @@ impl FfmpegCommand {
     self
   }
 
+  /// Set the video sync method with whichever flag the ffmpeg binary
+  /// understands: [`fps_mode`](Self::fps_mode) on FFmpeg 5.1 and newer, and
+  /// the deprecated `-vsync` before that. Takes the same values, like `cfr`
+  /// or `passthrough`.
+  ///
+  /// The version is the one set with [`assume_version`](Self::assume_version)
+  /// or [`detect_version`](Self::detect_version) before this call. Building a
+  /// command never runs ffmpeg by itself, so without either `-fps_mode` is
+  /// used.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command.assume_version("4.4.2".parse().unwrap()).frame_sync_mode("cfr");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-vsync", "cfr"]));
+  /// ```
+  pub fn frame_sync_mode<S: AsRef<str>>(&mut self, mode: S) -> &mut Self {
+    let flag = match self.version.known() {
+      Some(version) if !version.at_least(5, 1) => "-vsync",
+      _ => "-fps_mode",
+    };
+    self.arg(flag);
+    self.arg(mode.as_ref());
+    self
+  }
+
   /// Alias for `-bsf:v` argument.
   ///
   /// Set bitstream filters for matching streams. `bitstream_filters` is a
@@ -539,6 +1338,174 @@ impl FfmpegCommand {
     self
   }
 
+  /// Like [`filter_complex`](Self::filter_complex), but reading the
+  /// filtergraph from the file at `path`, with whichever flag the ffmpeg
+  /// binary understands: `-/filter_complex` on FFmpeg 7.0 and newer, and the
+  /// deprecated `-filter_complex_script` before that.
+  ///
+  /// The version is taken as for [`frame_sync_mode`](Self::frame_sync_mode),
+  /// from [`assume_version`](Self::assume_version) or
+  /// [`detect_version`](Self::detect_version); without either,
+  /// `-/filter_complex` is used.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .assume_version("6.1.1".parse().unwrap())
+  ///   .filter_complex_from_file("graph.txt");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-filter_complex_script", "graph.txt"]));
+  /// ```
+  pub fn filter_complex_from_file<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+    let flag = match self.version.known() {
+      Some(version) if !version.at_least(7, 0) => "-filter_complex_script",
+      _ => "-/filter_complex",
+    };
+    self.arg(flag);
+    self.arg(path.as_ref());
+    self
+  }
+
+  /// Like [`filter_complex`](Self::filter_complex), but passing the
+  /// filtergraph through a temporary file with
+  /// [`filter_complex_from_file`](Self::filter_complex_from_file), for graphs
+  /// too long for the command line. Windows limits the whole command line to
+  /// 32767 characters.
+  ///
+  /// The file is deleted once this command and every child spawned from it
+  /// are dropped.
+  pub fn filter_complex_via_file<S: AsRef<str>>(
+    &mut self,
+    filtergraph: S,
+  ) -> io::Result<&mut Self> {
+    let script = TempFile::create("filter_complex.txt", filtergraph.as_ref().as_bytes())?;
+    self.filter_complex_from_file(script.path());
+    self.temp_files.push(Arc::new(script));
+    Ok(self)
+  }
+
+  /// Alias for `-threads` argument.
+  ///
+  /// Set the number of threads used by the codecs. `0` lets each codec choose
+  /// the optimal number, which is its default and usually means one thread per
+  /// core. Set a low count to bound the CPU usage of a job on a shared machine.
+  ///
+  /// Like other codec options, this applies to the next input when given
+  /// before it, and to the next output otherwise, so call it once per input or
+  /// output to limit the whole process.
+  pub fn threads(&mut self, count: u32) -> &mut Self {
+    self.arg("-threads");
+    self.arg(count.to_string());
+    self
+  }
+
+  /// Alias for `-threads 0`, letting the codecs of the next input or output
+  /// choose the optimal thread count.
+  ///
+  /// This appends `-threads 0` like any other argument; it doesn't remove an
+  /// earlier [`threads`](Self::threads), which still applies to the inputs
+  /// and outputs before this call.
+  pub fn threads_optimal(&mut self) -> &mut Self {
+    self.threads(0)
+  }
+
+  /// Alias for `-filter_threads` argument.
+  ///
+  /// Defines how many threads are used to process a filter pipeline. Each
+  /// pipeline will produce a thread pool with this many threads available for
+  /// parallel processing. The default is the number of available CPUs.
+  pub fn filter_threads(&mut self, count: u32) -> &mut Self {
+    self.arg("-filter_threads");
+    self.arg(count.to_string());
+    self
+  }
+
+  /// Alias for `-stats_period` argument.
+  ///
+  /// Set the period at which encoding progress/statistics are updated. Default
+  /// is 0.5 seconds. Applies to both the status line on stderr and
+  /// [`with_progress_pipe`](Self::with_progress_pipe), so it controls how
+  /// often [`FfmpegEvent::Progress`](crate::event::FfmpegEvent::Progress) is
+  /// emitted.
+  ///
+  /// FFmpeg reads the period in whole microseconds and rejects zero, so a
+  /// period shorter than 1 microsecond is an error.
+  pub fn stats_period(&mut self, period: Duration) -> anyhow::Result<&mut Self> {
+    if period < Duration::from_micros(1) {
+      anyhow::bail!("Stats period must be at least 1 microsecond, got {period:?}");
+    }
+    self.arg("-stats_period");
+    self.arg(period.as_secs_f64().to_string());
+    Ok(self)
+  }
+
+  /// Alias for `-stats` and `-nostats` arguments.
+  ///
+  /// Enable or disable the status line which ffmpeg prints on stderr while
+  /// encoding, the source of the
+  /// [`FfmpegEvent::Progress`](crate::event::FfmpegEvent::Progress) events
+  /// unless [`with_progress_pipe`](Self::with_progress_pipe) is used. Enabled
+  /// by default at `-loglevel info` and above. Explicitly enabling it keeps
+  /// the status line even with a lower [`log_level`](Self::log_level), in
+  /// which case it's printed without a level prefix.
+  pub fn stats(&mut self, enabled: bool) -> &mut Self {
+    self.arg(if enabled { "-stats" } else { "-nostats" });
+    self
+  }
+
+  /// Silence everything but errors and progress, for clean logs in
+  /// production. Equivalent to `-hide_banner -loglevel level+error` with
+  /// [`with_progress_pipe`](Self::with_progress_pipe).
+  ///
+  /// The iterator still reports progress, errors and the exit. Without the
+  /// metadata lines logged at `info`, it can't decode output frames on
+  /// stdout though; read stdout directly with
+  /// [`FfmpegChild::take_stdout`] in that case.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command.quiet_progress().testsrc().output("output.mp4");
+  /// let args = command.dry_run();
+  /// assert!(args.windows(2).any(|pair| pair == ["-loglevel", "level+error"]));
+  /// assert!(args.ends_with(&["-progress".into(), "pipe:1".into(), "-nostats".into()]));
+  /// ```
+  pub fn quiet_progress(&mut self) -> &mut Self {
+    self
+      .hide_banner()
+      .log_level(FfmpegLogLevel::Error)
+      .with_progress_pipe()
+  }
+
+  /// Alias for `-benchmark` argument.
+  ///
+  /// Print the CPU time and peak memory used once the run finishes, emitted
+  /// as [`FfmpegEvent::Benchmark`](crate::event::FfmpegEvent::Benchmark).
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::{command::FfmpegCommand, event::FfmpegEvent};
+  ///
+  /// let stats = FfmpegCommand::new()
+  ///   .input("input.mp4")
+  ///   .benchmark()
+  ///   .null_output()
+  ///   .spawn()?
+  ///   .iter()?
+  ///   .find_map(|event| match event {
+  ///     FfmpegEvent::Benchmark(stats) => Some(stats),
+  ///     _ => None,
+  ///   });
+  /// if let Some(stats) = stats {
+  ///   println!("{:?} of CPU, {} KiB peak", stats.utime + stats.stime, stats.max_rss_kb);
+  /// }
+  /// # anyhow::Ok(())
+  /// ```
+  pub fn benchmark(&mut self) -> &mut Self {
+    self.arg("-benchmark");
+    self
+  }
+
   //// Preset argument sets for common use cases.
 
   /// Generate a procedural test video. Equivalent to `ffmpeg -f lavfi -i
@@ -555,21 +1522,533 @@ impl FfmpegCommand {
   /// Preset for emitting raw decoded video frames on stdout. Equivalent to `-f
   /// rawvideo -pix_fmt rgb24 -`.
   pub fn rawvideo(&mut self) -> &mut Self {
-    self.args(["-f", "rawvideo", "-pix_fmt", "rgb24", "-"]);
+    self.args(["-f", "rawvideo", "-pix_fmt", "rgb24"]);
+    self.output("-");
+    self
+  }
+
+  /// Preset for emitting raw interleaved PCM audio on stdout, the audio
+  /// counterpart of [`rawvideo`](Self::rawvideo). Equivalent to `-f {format}
+  /// -c:a pcm_{format} -ar {sample_rate} -ac {channels} pipe:1`.
+  ///
+  /// Read the samples from [`FfmpegChild::take_stdout`] with a
+  /// [`PcmReader`](crate::pcm::PcmReader); otherwise they're emitted as
+  /// `OutputChunk` events.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::{command::FfmpegCommand, pcm::{PcmFormat, PcmReader}};
+  /// let mut child = FfmpegCommand::new()
+  ///   .input("input.mp3")
+  ///   .pcm_output(44100, 2, PcmFormat::S16le)
+  ///   .spawn()?;
+  /// let stdout = child.take_stdout().unwrap();
+  /// for frame in PcmReader::new(stdout, PcmFormat::S16le, 2) {
+  ///   let [left, right] = frame?[..] else { unreachable!() };
+  ///   println!("{left} {right}");
+  /// }
+  /// child.wait()?;
+  /// # anyhow::Ok(())
+  /// ```
+  pub fn pcm_output(&mut self, sample_rate: u32, channels: u32, format: PcmFormat) -> &mut Self {
+    self.format(format.to_string());
+    self.codec_audio(format.codec());
+    self.arg("-ar");
+    self.arg(sample_rate.to_string());
+    self.arg("-ac");
+    self.arg(channels.to_string());
+    self.output("pipe:1");
+    self
+  }
+
+  /// Preset for reading raw video frames from stdin. Equivalent to `-f
+  /// rawvideo -pix_fmt {pix_fmt} -s {width}x{height} -r {fps} -i -`.
+  ///
+  /// Write each frame with [`FfmpegChild::write_frame`], which checks its
+  /// length against the frame size of this geometry.
+  pub fn rawvideo_input<S: AsRef<str>>(
+    &mut self,
+    width: u32,
+    height: u32,
+    pix_fmt: S,
+    fps: f32,
+  ) -> &mut Self {
+    self.input_frame_size = bytes_per_frame(pix_fmt.as_ref(), width, height);
+    self.format("rawvideo");
+    self.pix_fmt(pix_fmt);
+    self.size(width, height);
+    self.rate(fps);
+    self.input("-");
+    self
+  }
+
+  /// Preset for joining files with the concat demuxer. Equivalent to `-f
+  /// concat -safe 0 -i {list}`, where `{list}` is a temporary file containing
+  /// a `file '...'` line for each path.
+  ///
+  /// Paths are made absolute, relative to the [`current_dir`](Self::current_dir)
+  /// if one was set, and quoted per FFmpeg's [escaping
+  /// rules](https://ffmpeg.org/ffmpeg-utils.html#Quoting-and-escaping), so
+  /// spaces, single quotes and non-ASCII characters are all safe. The list file
+  /// is deleted once this command and every child spawned from it are dropped.
+  ///
+  /// The concat demuxer requires all files to share the same codecs and
+  /// stream layout; see <https://trac.ffmpeg.org/wiki/Concatenate>.
+  pub fn concat_demuxer<P: AsRef<Path>>(&mut self, paths: &[P]) -> io::Result<&mut Self> {
+    let current_dir = match self.inner.get_current_dir() {
+      Some(dir) => std::env::current_dir()?.join(dir),
+      None => std::env::current_dir()?,
+    };
+    let mut list = String::new();
+    for path in paths {
+      let mut path = current_dir.join(path);
+      if self.long_path_safe {
+        path = extended_length_path(path);
+      }
+      let path = path.to_str().ok_or_else(|| {
+        io::Error::new(
+          io::ErrorKind::InvalidInput,
+          format!("Path is not valid UTF-8: {}", path.display()),
+        )
+      })?;
+      list.push_str("file '");
+      list.push_str(&path.replace('\'', r"'\''"));
+      list.push_str("'\n");
+    }
+
+    let list_file = TempFile::create("concat.txt", list.as_bytes())?;
+    self.format("concat");
+    self.args(["-safe", "0"]);
+    self.input(list_file.path().to_string_lossy());
+    self.temp_files.push(Arc::new(list_file));
+    Ok(self)
+  }
+
+  /// Preset for writing the same encoded streams to several destinations in
+  /// one pass with the `tee` muxer, e.g. a local file and a live stream.
+  /// Equivalent to `-f tee "{output1}|{output2}|..."`, escaped as described in
+  /// [`tee_arg`](crate::tee::tee_arg).
+  ///
+  /// Like any output, this comes after the encoding options. The `tee` muxer
+  /// doesn't select streams automatically, so add a `map` for each stream to
+  /// include; formats which need global headers (like MP4 or FLV) also need
+  /// `-flags +global_header`.
+  pub fn tee_outputs(&mut self, outputs: &[TeeOutput]) -> &mut Self {
+    self.format("tee");
+    self.output(tee_arg(outputs));
+    self
+  }
+
+  /// Preset for changing the container without re-encoding, e.g. MKV to MP4.
+  /// Equivalent to `-c copy {output}`; ffmpeg picks the muxer from the
+  /// extension of `output`, so add a [`format`](Self::format) before this if
+  /// it has none.
+  ///
+  /// This runs about as fast as copying the file, but fails if the target
+  /// container doesn't support a codec of the input.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command.input("input.mkv").remux("output.mp4");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-i", "input.mkv", "-c", "copy", "output.mp4"]));
+  /// ```
+  pub fn remux<S: AsRef<str>>(&mut self, output: S) -> &mut Self {
+    self.copy_all();
+    self.output(output);
     self
   }
 
+  /// Preset for combining the video of one file with the audio of another,
+  /// e.g. a silent screen recording with a voiceover. Equivalent to `-i
+  /// {video} -i {audio} -map 0:v:0 -map 1:a:0 -c copy -shortest {output}`.
+  ///
+  /// Both streams are copied without re-encoding, so the audio codec must be
+  /// supported by the container of `output`; otherwise see
+  /// [`mux_reencode_audio`](Self::mux_reencode_audio). `-shortest` ends the
+  /// output with the shorter of the two, rather than padding it to the
+  /// longer one.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command.mux("video.mp4", "audio.m4a", "output.mp4");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&[
+  ///   "-i", "video.mp4", "-i", "audio.m4a", "-map", "0:v:0", "-map", "1:a:0",
+  ///   "-c", "copy", "-shortest", "output.mp4",
+  /// ]));
+  /// ```
+  pub fn mux<V: AsRef<str>, A: AsRef<str>, O: AsRef<str>>(
+    &mut self,
+    video: V,
+    audio: A,
+    output: O,
+  ) -> &mut Self {
+    self.mux_inputs(video, audio);
+    self.copy_all();
+    self.shortest();
+    self.output(output);
+    self
+  }
+
+  /// Like [`mux`](Self::mux), but re-encodes the audio with `audio_codec`,
+  /// e.g. `aac` for a WAV file going into an MP4. The video is still copied.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command.mux_reencode_audio("video.mp4", "voiceover.wav", "aac", "output.mp4");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&["-c:v", "copy", "-c:a", "aac", "-shortest", "output.mp4"]));
+  /// ```
+  pub fn mux_reencode_audio<V: AsRef<str>, A: AsRef<str>, C: AsRef<str>, O: AsRef<str>>(
+    &mut self,
+    video: V,
+    audio: A,
+    audio_codec: C,
+    output: O,
+  ) -> &mut Self {
+    self.mux_inputs(video, audio);
+    self.codec_video("copy");
+    self.codec_audio(audio_codec);
+    self.shortest();
+    self.output(output);
+    self
+  }
+
+  /// The inputs and stream selection shared by [`mux`](Self::mux) and
+  /// [`mux_reencode_audio`](Self::mux_reencode_audio).
+  fn mux_inputs<V: AsRef<str>, A: AsRef<str>>(&mut self, video: V, audio: A) {
+    self.input(video);
+    self.input(audio);
+    self.map("0:v:0");
+    self.map("1:a:0");
+  }
+
+  /// Preset for writing a single frame at `at` as an image, e.g. a JPEG or PNG
+  /// thumbnail. Equivalent to `-ss {at} -i {input} -frames:v 1 -q:v 2
+  /// {output}`.
+  ///
+  /// Seeking before the input is fast, since ffmpeg jumps to the nearest
+  /// keyframe and only decodes from there. `-q:v 2` selects a high JPEG
+  /// quality and is ignored by lossless formats like PNG. The image is
+  /// written once [`wait()`](FfmpegChild::wait) returns.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// use std::time::Duration;
+  /// FfmpegCommand::new()
+  ///   .overwrite()
+  ///   .thumbnail("input.mp4", Duration::from_secs(5), "thumbnail.jpg")
+  ///   .spawn()?
+  ///   .wait_and_result()?;
+  /// # anyhow::Ok(())
+  /// ```
+  pub fn thumbnail<S: AsRef<str>, T: AsRef<str>>(
+    &mut self,
+    input: S,
+    at: Duration,
+    output: T,
+  ) -> &mut Self {
+    self.seek(at);
+    self.input(input);
+    self.frames(1);
+    self.args(["-q:v", "2"]);
+    self.output(output);
+    self
+  }
+
+  /// Like [`thumbnail`](Self::thumbnail), but writing the frame as PNG to
+  /// stdout, to get the encoded image without a temporary file. Equivalent to
+  /// `-ss {at} -i {input} -frames:v 1 -f image2pipe -c:v png pipe:1`.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// use std::{io::Read, time::Duration};
+  /// let mut child = FfmpegCommand::new()
+  ///   .thumbnail_png("input.mp4", Duration::from_secs(5))
+  ///   .spawn()?;
+  /// let mut png = Vec::new();
+  /// child.take_stdout().unwrap().read_to_end(&mut png)?;
+  /// child.wait()?;
+  /// # anyhow::Ok(())
+  /// ```
+  pub fn thumbnail_png<S: AsRef<str>>(&mut self, input: S, at: Duration) -> &mut Self {
+    self.seek(at);
+    self.input(input);
+    self.frames(1);
+    self.format("image2pipe");
+    self.codec_video("png");
+    self.output("pipe:1");
+    self
+  }
+
+  /// Preset for cutting the clip between `start` and `end` without
+  /// re-encoding. Equivalent to `-ss {start} -to {end} -i {input} -c copy
+  /// {output}`.
+  ///
+  /// Both timestamps are input options, so `end` is a position in the input
+  /// rather than the length of the clip. Since stream copy can only cut on
+  /// keyframes, the clip actually starts at the nearest keyframe before
+  /// `start`, and may be up to a GOP longer than requested. Use
+  /// [`cut_accurate`](Self::cut_accurate) for a frame-exact cut.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// use std::time::Duration;
+  /// let mut command = FfmpegCommand::new();
+  /// command.cut("input.mp4", Duration::from_secs(10), Duration::from_secs(20), "clip.mp4");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&[
+  ///   "-ss", "00:00:10.000", "-to", "00:00:20.000", "-i", "input.mp4", "-c", "copy", "clip.mp4"
+  /// ]));
+  /// ```
+  pub fn cut<S: AsRef<str>, T: AsRef<str>>(
+    &mut self,
+    input: S,
+    start: Duration,
+    end: Duration,
+    output: T,
+  ) -> &mut Self {
+    self.seek(start);
+    self.to(end);
+    self.input(input);
+    self.copy_all();
+    self.output(output);
+    self
+  }
+
+  /// Like [`cut`](Self::cut), but re-encoding with the default encoders so
+  /// the clip starts exactly at `start`. Equivalent to `-ss {start} -to {end}
+  /// -i {input} {output}`.
+  ///
+  /// The input is still seeked to the keyframe before `start`, and the frames
+  /// in between are decoded and discarded, so this stays fast deep into a long
+  /// file. The whole clip is re-encoded, not just its leading GOP. To choose
+  /// the encoders, build the same command by hand with
+  /// [`codec_video`](Self::codec_video) before the output.
+  pub fn cut_accurate<S: AsRef<str>, T: AsRef<str>>(
+    &mut self,
+    input: S,
+    start: Duration,
+    end: Duration,
+    output: T,
+  ) -> &mut Self {
+    self.seek(start);
+    self.to(end);
+    self.input(input);
+    self.output(output);
+    self
+  }
+
+  /// Preset for splitting the output into files of a fixed duration with the
+  /// segment muxer. Equivalent to `-f segment -segment_time {segment_time}`,
+  /// plus `-reset_timestamps 1` if enabled, followed by the filename template.
+  ///
+  /// Returns an error if the template has no `printf`-style counter like
+  /// `%03d`, since every segment would overwrite the same file.
+  pub fn segment(&mut self, options: SegmentOptions) -> anyhow::Result<&mut Self> {
+    if !has_counter(&options.filename_template) {
+      anyhow::bail!(
+        "Segment filename template must contain a counter like `%03d`: {}",
+        options.filename_template
+      );
+    }
+    self.format("segment");
+    self.arg("-segment_time");
+    self.arg(options.segment_time.as_secs_f64().to_string());
+    if options.reset_timestamps {
+      self.args(["-reset_timestamps", "1"]);
+    }
+    self.output(options.filename_template);
+    Ok(self)
+  }
+
+  /// Preset for reading a numbered image sequence, like the frames of a
+  /// render. Equivalent to `-framerate {framerate} -i {pattern}`, where the
+  /// pattern is a `printf`-style template such as `frame_%04d.png`.
+  ///
+  /// The image2 demuxer starts looking for the first file at numbers 0
+  /// through 4. For a sequence starting elsewhere, like at `frame_0100.png`,
+  /// pass `.args(["-start_number", "100"])` before this.
+  ///
+  /// Returns an error if the pattern has no counter like `%04d`.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .image_sequence_input("render/frame_%04d.png", 24.0)
+  ///   .unwrap()
+  ///   .codec_video("libx264")
+  ///   .output("output.mp4");
+  /// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  /// assert!(args.ends_with(&[
+  ///   "-framerate",
+  ///   "24",
+  ///   "-i",
+  ///   "render/frame_%04d.png",
+  ///   "-c:v",
+  ///   "libx264",
+  ///   "output.mp4",
+  /// ]));
+  /// ```
+  pub fn image_sequence_input<S: AsRef<str>>(
+    &mut self,
+    pattern: S,
+    framerate: f32,
+  ) -> anyhow::Result<&mut Self> {
+    let pattern = pattern.as_ref();
+    if !has_counter(pattern) {
+      anyhow::bail!("Image sequence pattern must contain a counter like `%04d`: {pattern}");
+    }
+    self.arg("-framerate");
+    self.arg(framerate.to_string());
+    self.input(pattern);
+    Ok(self)
+  }
+
+  /// Preset for writing each frame to a numbered image with the image2
+  /// muxer. Equivalent to `-f image2 {pattern}`, where the pattern is a
+  /// `printf`-style template such as `frame_%04d.png`. The extension picks
+  /// the image format.
+  ///
+  /// Numbering starts at 1; pass `.args(["-start_number", "0"])` before this
+  /// to start elsewhere. Combine with [`rate`](Self::rate) to export fewer
+  /// frames than the input has.
+  ///
+  /// Returns an error if the pattern has no counter like `%04d`, since every
+  /// frame would overwrite the same file.
+  pub fn image_sequence_output<S: AsRef<str>>(&mut self, pattern: S) -> anyhow::Result<&mut Self> {
+    let pattern = pattern.as_ref();
+    if !has_counter(pattern) {
+      anyhow::bail!("Image sequence pattern must contain a counter like `%04d`: {pattern}");
+    }
+    self.format("image2");
+    self.output(pattern);
+    Ok(self)
+  }
+
+  /// Preset for HLS output, writing a playlist to `playlist_path` along with
+  /// its media segments. Equivalent to `-f hls -hls_time {segment_duration}`
+  /// followed by the other configured [`HlsOptions`] and the playlist path.
+  pub fn hls_output<S: AsRef<str>>(&mut self, playlist_path: S, options: HlsOptions) -> &mut Self {
+    self.format("hls");
+    self.arg("-hls_time");
+    self.arg(options.segment_duration.as_secs_f64().to_string());
+    if let Some(playlist_type) = options.playlist_type {
+      self.args(["-hls_playlist_type", playlist_type.as_ref()]);
+    }
+    if let Some(template) = options.segment_filename_template {
+      self.args(["-hls_segment_filename", &template]);
+    }
+    if let Some(list_size) = options.list_size {
+      self.arg("-hls_list_size");
+      self.arg(list_size.to_string());
+    }
+    self.output(playlist_path);
+    self
+  }
+
+  /// Preset for streaming live to a server, with the muxer the protocol
+  /// expects: `-f flv` for RTMP, `-f rtsp` for RTSP and `-f mpegts` for SRT,
+  /// along with options suited to live output. See [`StreamProtocol`] for an
+  /// example.
+  ///
+  /// Returns an error if the scheme of `url` doesn't belong to `protocol`.
+  /// When streaming a file rather than a live source, also read it at its
+  /// native rate with [`realtime`](Self::realtime).
+  pub fn stream_output<S: AsRef<str>>(
+    &mut self,
+    url: S,
+    protocol: StreamProtocol,
+  ) -> anyhow::Result<&mut Self> {
+    let url = url.as_ref();
+    if !protocol.matches_url(url) {
+      anyhow::bail!(
+        "{protocol:?} stream URL must start with {}: {url}",
+        protocol
+          .schemes()
+          .iter()
+          .map(|scheme| format!("{scheme}://"))
+          .collect::<Vec<_>>()
+          .join(", ")
+      );
+    }
+    self.format(protocol.format());
+    self.args(protocol.output_args());
+    self.output(url);
+    Ok(self)
+  }
+
+  /// Preset for hardcoding subtitles into the video with the `subtitles`
+  /// filter. Equivalent to `-vf subtitles=filename={path}:force_style={style}`,
+  /// with the path and style escaped as described in
+  /// [`subtitles_filter`](crate::subtitles::subtitles_filter).
+  ///
+  /// Requires an FFmpeg build with libass. Appends to the `-vf` chain, so it
+  /// composes with other simple video filters, see
+  /// [`video_filter`](Self::video_filter).
+  pub fn burn_subtitles<P: AsRef<Path>>(
+    &mut self,
+    path: P,
+    style: Option<SubtitleStyle>,
+  ) -> &mut Self {
+    let mut path = path.as_ref().to_path_buf();
+    if self.long_path_safe {
+      path = extended_length_path(path);
+    }
+    self.video_filter(subtitles_filter(&path, style.as_ref()))
+  }
+
   /// Configure the ffmpeg command to produce output on stdout.
   ///
   /// Synchronizes two changes:
   /// 1. Pass `pipe:1` to the ffmpeg command ("output on stdout")
   /// 2. Set the `stdout` field of the inner `Command` to `Stdio::piped()`
   pub fn pipe_stdout(&mut self) -> &mut Self {
-    self.arg("-");
+    self.output("-");
     self.inner.stdout(Stdio::piped());
     self
   }
 
+  /// Report progress through the machine-readable `-progress` output instead
+  /// of the human-readable status line on stderr, which ffmpeg rate-limits
+  /// and overwrites in place. Also passes `-nostats` to silence the status
+  /// line.
+  ///
+  /// Each `key=value` block is emitted as a regular
+  /// [`FfmpegEvent::Progress`](crate::event::FfmpegEvent::Progress), ending
+  /// with the block containing `progress=end`.
+  ///
+  /// The report is written to stdout with `-progress pipe:1`, unless an
+  /// output already occupies stdout, like [`rawvideo()`](FfmpegCommand::rawvideo)
+  /// or [`pipe_stdout()`](FfmpegCommand::pipe_stdout). It's then written to
+  /// stderr with `-progress pipe:2` and parsed from among the log lines
+  /// instead. The arguments are added when the command is spawned, so this
+  /// can be called before or after the outputs.
+  ///
+  /// Progress events are read from wherever the arguments send them:
+  /// - by default, from the status line on stderr, unless hidden by
+  ///   `-nostats` or a `-loglevel` below `info`
+  /// - from stdout for `-progress pipe:1`, `-progress pipe:` or `-progress -`
+  /// - from stderr for `-progress pipe:2`
+  ///
+  /// A `-progress` to a file or URL isn't read.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command.with_progress_pipe().testsrc().rawvideo();
+  /// let args = command.dry_run();
+  /// assert!(args.windows(2).any(|pair| pair == ["-progress", "pipe:2"]));
+  /// ```
+  pub fn with_progress_pipe(&mut self) -> &mut Self {
+    self.wants_progress_pipe = true;
+    self
+  }
+
   /// Automatically applied in the constructor of `FfmpegCommand`. Configures
   /// logging with a level and format expected by the log parser.
   ///
@@ -577,17 +2056,35 @@ impl FfmpegCommand {
   ///
   /// The `level` flag adds a prefix to all log messages with the log level in
   /// square brackets, allowing the parser to distinguish between ambiguous
-  /// messages like warnings vs errors.
+  /// messages like warnings vs errors, see
+  /// [`try_parse_log_level`](crate::log_parser::try_parse_log_level).
   ///
   /// The `+info` flag enables the `info` log level, which is the default level.
   ///
-  /// If this settings is manually overridden, the log parser should still work,
-  /// but lose some semantic distinction between log levels.
-  fn set_expected_loglevel(&mut self) -> &mut Self {
+  /// Only needed for a command created with `FfmpegCommand::from(Command)`. If
+  /// this setting is manually overridden, the log parser should still work,
+  /// but has to guess the level of each message from its content.
+  pub fn log_with_level_prefix(&mut self) -> &mut Self {
     self.args(["-loglevel", "level+info"]);
     self
   }
 
+  /// Alias for `-loglevel` argument, keeping the `level` prefix expected by
+  /// the log parser. Equivalent to `ffmpeg -loglevel level+{log_level}`.
+  ///
+  /// Higher levels like `Verbose` and `Debug` add lines which are emitted as
+  /// [`crate::event::LogLevel::Unknown`] log events, while lower levels like
+  /// `Error` and `Quiet` suppress the metadata and progress lines that are
+  /// logged at `info`, so fewer parsed events are available. Since the
+  /// iterator relies on that metadata to decode output frames, read stdout
+  /// directly with [`FfmpegChild::take_stdout`] when combining a lower level
+  /// with output on stdout.
+  pub fn log_level(&mut self, log_level: FfmpegLogLevel) -> &mut Self {
+    self.arg("-loglevel");
+    self.arg(format!("level+{}", log_level.as_ref()));
+    self
+  }
+
   //// `std::process::Command` passthrough methods
 
   ///
@@ -613,6 +2110,64 @@ impl FfmpegCommand {
     self
   }
 
+  /// Sets an environment variable for the ffmpeg process, such as
+  /// `CUDA_VISIBLE_DEVICES` or `AV_LOG_FORCE_COLOR`.
+  ///
+  /// For example, `FFREPORT=file=job.log:level=32` makes ffmpeg write a full
+  /// log of the run to `job.log`, alongside the usual stderr. See
+  /// <https://ffmpeg.org/ffmpeg.html#Generic-options> under `-report`.
+  ///
+  /// Identical to `env` in [`std::process::Command`].
+  pub fn env<K, V>(&mut self, key: K, value: V) -> &mut Self
+  where
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+  {
+    self.inner.env(key, value);
+    self
+  }
+
+  /// Sets multiple environment variables for the ffmpeg process.
+  ///
+  /// Identical to `envs` in [`std::process::Command`].
+  pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Self
+  where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+  {
+    self.inner.envs(vars);
+    self
+  }
+
+  /// Sets the working directory of the ffmpeg process.
+  ///
+  /// Relative inputs and outputs are then resolved by ffmpeg against `dir`.
+  /// Call this before [`concat_demuxer`](Self::concat_demuxer), which writes
+  /// the paths of its list file as absolute paths, resolved against `dir`
+  /// too. Relative [`atomic_output`](Self::atomic_output) paths are moved into
+  /// place within `dir`.
+  ///
+  /// Identical to `current_dir` in [`std::process::Command`].
+  pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+    self.inner.current_dir(dir);
+    self
+  }
+
+  /// The atomic outputs to move into place after the process exits. ffmpeg
+  /// resolves relative ones in its own working directory, so they're resolved
+  /// the same way here.
+  fn spawned_atomic_outputs(&self) -> Vec<AtomicOutput> {
+    match self.inner.get_current_dir() {
+      Some(dir) => self
+        .atomic_outputs
+        .iter()
+        .map(|output| output.relative_to(dir))
+        .collect(),
+      None => self.atomic_outputs.clone(),
+    }
+  }
+
   /// Returns an iterator of the arguments that will be passed to the program.
   ///
   /// Identical to `get_args` in [`std::process::Command`].
@@ -620,18 +2175,130 @@ impl FfmpegCommand {
     self.inner.get_args()
   }
 
+  /// Returns the exact arguments that [`spawn`](Self::spawn) would pass to
+  /// the program, without spawning it. Unlike [`get_args`](Self::get_args),
+  /// this includes the filters and output format still pending for the last
+  /// output, and the `-n` appended to prevent the overwrite prompt.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command.input("input.mp4").output("output.mp4").scale(640, -2);
+  /// let args = command.dry_run();
+  /// assert!(args[args.len() - 3..] == ["-vf", "scale=640:-2", "-n"]);
+  /// ```
+  pub fn dry_run(&self) -> Vec<OsString> {
+    let mut args: Vec<OsString> = self.get_args().map(OsStr::to_os_string).collect();
+    args.extend(self.pending_args().into_iter().map(OsString::from));
+    if !args.iter().any(|arg| is_overwrite_arg(arg)) {
+      args.push("-n".into());
+    }
+    args.extend(self.progress_args().into_iter().map(OsString::from));
+    args
+  }
+
+  /// The program and [`dry_run`](Self::dry_run) arguments as a single line,
+  /// quoted for a POSIX shell, to paste into a terminal or a log. Arguments
+  /// which aren't valid UTF-8 are converted lossily.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new_with_path("ffmpeg");
+  /// command
+  ///   .input("my video.mp4")
+  ///   .video_filter("drawtext=text='Hi'")
+  ///   .output("out.mp4");
+  /// let command_string = command.to_command_string();
+  /// assert!(command_string.starts_with("ffmpeg "));
+  /// assert!(command_string.ends_with(r#" -i 'my video.mp4' -vf 'drawtext=text='\''Hi'\''' out.mp4 -n"#));
+  /// ```
+  pub fn to_command_string(&self) -> String {
+    let program = self.inner.get_program().to_os_string();
+    std::iter::once(program)
+      .chain(self.dry_run())
+      .map(|arg| shell_quote(&arg.to_string_lossy()).into_owned())
+      .collect::<Vec<_>>()
+      .join(" ")
+  }
+
   /// Appends `-n` (no overwrite) to the args list if needed.
   /// The interactive "Would you like to overwrite?" prompt is problematic,
   /// since it won't be parsed by the log parser and the process will appear
   /// to hang indefinitely without any indication of what's happening.
   fn prevent_overwrite_prompt(&mut self) -> &mut Self {
-    let is_overwrite_arg = |arg| arg == "-y" || arg == "-n" || arg == "-nostdin";
     if !self.get_args().any(is_overwrite_arg) {
       self.no_overwrite();
     }
     self
   }
 
+  /// Pipe stdin and stdout if the arguments use them, unless disabled with
+  /// [`auto_pipes`](FfmpegCommand::auto_pipes).
+  fn configure_pipes(&mut self) -> &mut Self {
+    if !self.auto_pipes {
+      return self;
+    }
+    let reads_stdin = self
+      .get_args()
+      .collect::<Vec<_>>()
+      .windows(2)
+      .any(|pair| pair[0] == "-i" && is_pipe(pair[1], "0"));
+    if reads_stdin {
+      self.inner.stdin(Stdio::piped());
+    }
+    if self.writes_stdout() {
+      self.inner.stdout(Stdio::piped());
+    }
+    self
+  }
+
+  /// Whether any argument other than an input is `-` or `pipe:1`, such as an
+  /// output on stdout.
+  fn writes_stdout(&self) -> bool {
+    let args: Vec<_> = self.get_args().collect();
+    args
+      .iter()
+      .enumerate()
+      .any(|(i, arg)| is_pipe(arg, "1") && (i == 0 || args[i - 1] != "-i"))
+  }
+
+  /// The `-progress` arguments requested by
+  /// [`with_progress_pipe`](Self::with_progress_pipe), steered to stderr if an
+  /// output occupies stdout.
+  fn progress_args(&self) -> Vec<&'static str> {
+    if !self.wants_progress_pipe {
+      return Vec::new();
+    }
+    match self.writes_stdout() {
+      true => vec!["-progress", "pipe:2", "-nostats"],
+      false => vec!["-progress", "pipe:1", "-nostats"],
+    }
+  }
+
+  /// Append the arguments of [`progress_args`](Self::progress_args).
+  fn flush_progress_pipe(&mut self) {
+    let args = self.progress_args();
+    self.wants_progress_pipe = false;
+    self.args(args);
+  }
+
+  /// Where `-progress` reports are written, so they can be parsed from stdout
+  /// rather than output data, or from among the log lines on stderr.
+  fn progress_pipe(&self) -> Option<ProgressPipe> {
+    let args: Vec<_> = self.get_args().collect();
+    let target = args
+      .windows(2)
+      .find(|pair| pair[0] == "-progress")
+      .map(|pair| pair[1])?;
+    if ["pipe:1", "pipe:", "-"].iter().any(|p| target == *p) {
+      Some(ProgressPipe::Stdout)
+    } else if target == "pipe:2" {
+      Some(ProgressPipe::Stderr)
+    } else {
+      None
+    }
+  }
+
   /// Spawn the ffmpeg command as a child process, wrapping it in a
   /// `FfmpegChild` interface.
   ///
@@ -641,8 +2308,112 @@ impl FfmpegCommand {
   ///
   /// Identical to `spawn` in [`std::process::Command`].
   pub fn spawn(&mut self) -> io::Result<FfmpegChild> {
+    self.flush_filters();
+    self.prevent_overwrite_prompt();
+    self.flush_progress_pipe();
+    self.configure_pipes();
+    let progress_pipe = self.progress_pipe();
+    let mut child = self.inner.spawn().map(FfmpegChild::from_inner)?;
+    child.set_kill_on_drop(self.kill_on_drop);
+    child.set_progress_pipe(progress_pipe);
+    child.set_input_frame_size(self.input_frame_size);
+    child.set_temp_files(self.temp_files.clone());
+    child.set_atomic_outputs(self.spawned_atomic_outputs());
+    child.set_stdout_buffer_size(self.stdout_buffer_size);
+    if let Some(max_bytes) = self.capture_log {
+      child.set_log_capture(max_bytes);
+    }
+    Ok(child)
+  }
+
+  /// Spawn the command under the Tokio runtime, with stderr and stdout read
+  /// asynchronously through [`AsyncFfmpegChild::events`]. Must be called from
+  /// within a Tokio runtime.
+  ///
+  /// The `tokio::process::Command` is rebuilt from the program, arguments,
+  /// environment and working directory of the inner `Command`, with all stdio
+  /// piped; other settings made through `as_inner_mut()` are not carried over.
+  /// Log capture with [`capture_log`](FfmpegCommand::capture_log) is not
+  /// supported for async children.
+  #[cfg(feature = "tokio")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+  pub fn spawn_async(&mut self) -> io::Result<AsyncFfmpegChild> {
+    self.flush_filters();
     self.prevent_overwrite_prompt();
-    self.inner.spawn().map(FfmpegChild::from_inner)
+    self.flush_progress_pipe();
+    let progress_pipe = self.progress_pipe();
+
+    let mut command = tokio::process::Command::new(self.inner.get_program());
+    command.args(self.inner.get_args());
+    for (key, value) in self.inner.get_envs() {
+      match value {
+        Some(value) => command.env(key, value),
+        None => command.env_remove(key),
+      };
+    }
+    if let Some(dir) = self.inner.get_current_dir() {
+      command.current_dir(dir);
+    }
+    command
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .kill_on_drop(self.kill_on_drop);
+    #[cfg(windows)]
+    command.creation_flags(0x08000000);
+
+    let inner = command.spawn()?;
+    Ok(AsyncFfmpegChild::from_inner(
+      inner,
+      progress_pipe,
+      self.temp_files.clone(),
+      self.spawned_atomic_outputs(),
+    ))
+  }
+
+  /// Read the metadata of the inputs without producing any output, as a
+  /// lightweight alternative to [`ffprobe`](crate::ffprobe) when it isn't
+  /// installed.
+  ///
+  /// Runs the command as given, which should only contain inputs, like
+  /// `ffmpeg -i input.mp4`. FFmpeg prints the input metadata and then exits
+  /// with "At least one output file must be specified"; that expected error is
+  /// ignored. The returned metadata has `inputs`, their durations and
+  /// `input_streams` filled in, while the output fields stay empty and
+  /// [`is_completed`](FfmpegMetadata::is_completed) is `false`.
+  ///
+  /// Returns an error with the logged errors if no input could be read, e.g.
+  /// because the file doesn't exist.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let metadata = FfmpegCommand::new().input("input.mp4").probe_metadata().unwrap();
+  /// println!("{:?} seconds", metadata.duration());
+  /// for stream in metadata.input_streams {
+  ///   println!("{}: {}", stream.stream_index, stream.format);
+  /// }
+  /// ```
+  pub fn probe_metadata(&mut self) -> anyhow::Result<FfmpegMetadata> {
+    let mut child = self.spawn()?;
+    let mut metadata = FfmpegMetadata::new();
+    let mut errors = Vec::new();
+    for event in child.iter()? {
+      match event {
+        FfmpegEvent::ParsedInput(_)
+        | FfmpegEvent::ParsedInputStream(_)
+        | FfmpegEvent::ParsedDuration(_)
+        | FfmpegEvent::ParsedStreamTag(_) => metadata.handle_event(&Some(event))?,
+        FfmpegEvent::Log(LogLevel::Error | LogLevel::Fatal, e) => errors.push(e),
+        FfmpegEvent::ParsedError(e) => errors.push(e.raw_log_message),
+        _ => {}
+      }
+    }
+    child.wait()?;
+
+    if metadata.inputs.is_empty() {
+      anyhow::bail!("No input metadata was found: {}", errors.join("\n"));
+    }
+    Ok(metadata)
   }
 
   /// Print a command that can be copy-pasted to run in the terminal. Requires
@@ -676,6 +2447,157 @@ impl FfmpegCommand {
     self
   }
 
+  /// Kill the spawned process when its [`FfmpegChild`] is dropped, so that an
+  /// early return or panic doesn't leave ffmpeg running in the background.
+  /// Disabled by default.
+  ///
+  /// The process is killed forcibly (`SIGKILL` on Unix) rather than asked to
+  /// quit with `q`, so output files may be left incomplete or unplayable. Use
+  /// [`FfmpegChild::quit_and_wait`] to stop a recording cleanly.
+  pub fn kill_on_drop(&mut self, kill_on_drop: bool) -> &mut Self {
+    self.kill_on_drop = kill_on_drop;
+    self
+  }
+
+  /// Pipe stdin and stdout when spawning, if the arguments read an input from
+  /// `-` or `pipe:0`, or write an output to `-` or `pipe:1`. Enabled by
+  /// default.
+  ///
+  /// `new()` already pipes all stdio, so this mostly matters for a command
+  /// created from a custom `Command`. Disable it to keep stdio configured
+  /// through [`as_inner_mut()`](FfmpegCommand::as_inner_mut), such as a file
+  /// passed as stdin for `-i pipe:0`, which would otherwise be replaced.
+  pub fn auto_pipes(&mut self, auto_pipes: bool) -> &mut Self {
+    self.auto_pipes = auto_pipes;
+    self
+  }
+
+  /// Read stdout through a buffer of `bytes`, rather than the default 8 KiB,
+  /// when iterating over [`FfmpegChild::iter`]. Large raw frames, like 4K
+  /// `rgb24` at about 24 MiB each, are read with fewer system calls, which
+  /// helps keep up with ffmpeg. The size of the chunks emitted for non-raw
+  /// outputs also follows this size.
+  ///
+  /// A few frames worth, e.g. `4 * width * height * 3` for `rgb24`, is a
+  /// reasonable starting point. Not supported by
+  /// [`spawn_async`](FfmpegCommand::spawn_async).
+  pub fn stdout_buffer_size(&mut self, bytes: usize) -> &mut Self {
+    self.stdout_buffer_size = Some(bytes);
+    self
+  }
+
+  /// On Windows, pass local file paths longer than `MAX_PATH` (260
+  /// characters), and UNC paths like `\\server\share\video.mp4`, to ffmpeg as
+  /// extended-length `\\?\` paths. Without this, ffmpeg may fail to open
+  /// them. Disabled by default, and has no effect on other platforms.
+  ///
+  /// Applies to the paths given to [`input`](FfmpegCommand::input),
+  /// [`output`](FfmpegCommand::output),
+  /// [`concat_demuxer`](FfmpegCommand::concat_demuxer) and
+  /// [`burn_subtitles`](FfmpegCommand::burn_subtitles) after this is called,
+  /// so call it first. URLs, pipes like `-` and `pipe:1`, and short local
+  /// paths are passed through unchanged; see
+  /// [`extended_length_path`](crate::paths::extended_length_path).
+  pub fn long_path_safe(&mut self) -> &mut Self {
+    self.long_path_safe = true;
+    self
+  }
+
+  /// Use `version` for version-aware methods like
+  /// [`frame_sync_mode`](Self::frame_sync_mode) called after this, instead of
+  /// querying the ffmpeg binary with
+  /// [`detect_version`](Self::detect_version). Useful when the command is
+  /// built for another machine.
+  pub fn assume_version(&mut self, version: FfmpegVersion) -> &mut Self {
+    self.version = VersionCheck::Known(version);
+    self
+  }
+
+  /// Run `ffmpeg -version` with the program of this command, and use its
+  /// version for version-aware methods like
+  /// [`frame_sync_mode`](Self::frame_sync_mode) called after this.
+  ///
+  /// Returns an error if the version can't be determined, e.g. because the
+  /// binary isn't installed. The command can still be built and spawned; the
+  /// version-aware methods then use the flags of the latest FFmpeg.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut command = FfmpegCommand::new();
+  /// command
+  ///   .detect_version()
+  ///   .unwrap()
+  ///   .input("input.mp4")
+  ///   .frame_sync_mode("cfr")
+  ///   .output("output.mp4");
+  /// ```
+  pub fn detect_version(&mut self) -> anyhow::Result<&mut Self> {
+    match ffmpeg_version_parsed_with_path(self.inner.get_program()) {
+      Ok(version) => {
+        self.version = VersionCheck::Known(version);
+        Ok(self)
+      }
+      Err(e) => {
+        self.version = VersionCheck::Unknown;
+        Err(e)
+      }
+    }
+  }
+
+  /// Emit the pending simple filters as a single `-vf` and `-af`, and the
+  /// pending output format, before an output.
+  fn flush_filters(&mut self) {
+    let args = self.pending_args();
+    self.video_filters.clear();
+    self.audio_filters.clear();
+    self.output_format = None;
+    self.args(args);
+  }
+
+  /// The arguments which [`flush_filters`](Self::flush_filters) would emit.
+  fn pending_args(&self) -> Vec<String> {
+    let mut args = Vec::new();
+    if !self.video_filters.is_empty() {
+      args.extend(["-vf".to_string(), self.video_filters.join(",")]);
+    }
+    if !self.audio_filters.is_empty() {
+      args.extend(["-af".to_string(), self.audio_filters.join(",")]);
+    }
+    if let Some(format) = &self.output_format {
+      args.extend(["-f".to_string(), format.clone()]);
+    }
+    args
+  }
+
+  /// Rewrite an input or output argument for `long_path_safe`, if it's a long
+  /// or UNC local path.
+  fn long_path_arg(&self, path_or_url: &str) -> String {
+    let is_local_path =
+      path_or_url != "-" && !path_or_url.contains("://") && !path_or_url.starts_with("pipe:");
+    if !self.long_path_safe || !cfg!(windows) || !is_local_path {
+      return path_or_url.to_string();
+    }
+    let extended = extended_length_path(path_or_url);
+    let is_unc = path_or_url.starts_with(r"\\") || path_or_url.starts_with("//");
+    match extended.to_str() {
+      // The extended path is 4 characters longer than the absolute path
+      Some(extended) if is_unc || extended.len() > 260 + 4 => extended.to_string(),
+      _ => path_or_url.to_string(),
+    }
+  }
+
+  /// Capture every raw line ffmpeg writes to stderr, alongside the usual
+  /// event parsing, for retrieval with [`FfmpegChild::take_log`] once the
+  /// process exits. Useful for attaching the full log to a bug report.
+  ///
+  /// With `max_bytes`, only the last `max_bytes` of the log are kept, so a
+  /// runaway log can't exhaust memory; the end of the log is usually where
+  /// the errors are.
+  pub fn capture_log(&mut self, max_bytes: Option<usize>) -> &mut Self {
+    self.capture_log = Some(max_bytes);
+    self
+  }
+
   /// Disable creating a new console window for the spawned process on Windows.
   /// Has no effect on other platforms. This can be useful when spawning a command
   /// from a GUI program.
@@ -700,8 +2622,8 @@ impl FfmpegCommand {
     inner.stdout(Stdio::piped());
 
     // Configure `FfmpegCommand`
-    let mut ffmpeg_command = Self { inner };
-    ffmpeg_command.set_expected_loglevel();
+    let mut ffmpeg_command = Self::from(inner);
+    ffmpeg_command.log_with_level_prefix();
     ffmpeg_command.create_no_window();
     ffmpeg_command
   }
@@ -736,10 +2658,26 @@ impl fmt::Debug for FfmpegCommand {
 impl From<Command> for FfmpegCommand {
   /// Convert a `Command` into a `FfmpegCommand`, making no guarantees about the
   /// validity of its configured arguments and stdio. For example,
-  /// `set_expected_loglevel()` is not automatically applied, which can have
+  /// `log_with_level_prefix()` is not automatically applied, which can have
   /// unexpected effects on log parsing.
   fn from(inner: Command) -> Self {
-    Self { inner }
+    Self {
+      inner,
+      kill_on_drop: false,
+      input_frame_size: None,
+      temp_files: Vec::new(),
+      capture_log: None,
+      long_path_safe: false,
+      video_filters: Vec::new(),
+      audio_filters: Vec::new(),
+      input_format: None,
+      output_format: None,
+      version: VersionCheck::NotChecked,
+      auto_pipes: true,
+      atomic_outputs: Vec::new(),
+      stdout_buffer_size: None,
+      wants_progress_pipe: false,
+    }
   }
 }
 
@@ -749,18 +2687,172 @@ impl From<FfmpegCommand> for Command {
   }
 }
 
+/// A time value for options like `-ss`, `-t` and `-to`: either a string in
+/// FFmpeg's [time duration
+/// syntax](https://ffmpeg.org/ffmpeg-utils.html#time-duration-syntax), passed
+/// through unchanged, or a [`Duration`], formatted as `HH:MM:SS.mmm`.
+///
+/// ```rust
+/// use ffmpeg_sidecar::command::TimeArg;
+/// use std::time::Duration;
+/// assert!(Duration::from_millis(3_723_456).to_time_arg() == "01:02:03.456");
+/// assert!("10".to_time_arg() == "10");
+/// ```
+pub trait TimeArg {
+  fn to_time_arg(&self) -> String;
+}
+
+impl TimeArg for Duration {
+  fn to_time_arg(&self) -> String {
+    let secs = self.as_secs();
+    format!(
+      "{:02}:{:02}:{:02}.{:03}",
+      secs / 3600,
+      secs / 60 % 60,
+      secs % 60,
+      self.subsec_millis()
+    )
+  }
+}
+
+impl TimeArg for &Duration {
+  fn to_time_arg(&self) -> String {
+    (*self).to_time_arg()
+  }
+}
+
+impl TimeArg for &str {
+  fn to_time_arg(&self) -> String {
+    self.to_string()
+  }
+}
+
+impl TimeArg for String {
+  fn to_time_arg(&self) -> String {
+    self.clone()
+  }
+}
+
+impl TimeArg for &String {
+  fn to_time_arg(&self) -> String {
+    self.to_string()
+  }
+}
+
+/// What's known about the version of the ffmpeg binary a command runs, set
+/// with [`FfmpegCommand::assume_version`] or
+/// [`FfmpegCommand::detect_version`].
+#[derive(Debug, Clone, PartialEq)]
+enum VersionCheck {
+  /// Neither assumed nor detected
+  NotChecked,
+  /// Detection failed, e.g. because the binary isn't installed
+  Unknown,
+  Known(FfmpegVersion),
+}
+
+impl VersionCheck {
+  fn known(&self) -> Option<&FfmpegVersion> {
+    match self {
+      VersionCheck::Known(version) => Some(version),
+      VersionCheck::NotChecked | VersionCheck::Unknown => None,
+    }
+  }
+}
+
+/// Verbosity levels accepted by [`FfmpegCommand::log_level`], from least to
+/// most verbose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FfmpegLogLevel {
+  /// Show nothing at all; be silent.
+  Quiet,
+  /// Only show fatal errors which could lead the process to crash, such as an
+  /// assertion failure.
+  Panic,
+  /// Only show fatal errors. These are errors after which the process
+  /// absolutely cannot continue.
+  Fatal,
+  /// Show all errors, including ones which can be recovered from.
+  Error,
+  /// Show all warnings and errors.
+  Warning,
+  /// Show informative messages during processing, in addition to warnings and
+  /// errors. This is the default value.
+  Info,
+  /// Same as `Info`, except more verbose.
+  Verbose,
+  /// Show everything, including debugging information.
+  Debug,
+  /// Even more verbose than `Debug`.
+  Trace,
+}
+
+impl AsRef<str> for FfmpegLogLevel {
+  fn as_ref(&self) -> &str {
+    match self {
+      FfmpegLogLevel::Quiet => "quiet",
+      FfmpegLogLevel::Panic => "panic",
+      FfmpegLogLevel::Fatal => "fatal",
+      FfmpegLogLevel::Error => "error",
+      FfmpegLogLevel::Warning => "warning",
+      FfmpegLogLevel::Info => "info",
+      FfmpegLogLevel::Verbose => "verbose",
+      FfmpegLogLevel::Debug => "debug",
+      FfmpegLogLevel::Trace => "trace",
+    }
+  }
+}
+
+/// Hardware acceleration methods accepted by [`FfmpegCommand::hwaccel`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HwAccel {
+  /// Automatically select the hardware acceleration method.
+  Auto,
+  /// NVIDIA CUDA (NVDEC).
+  Cuda,
+  /// Video Acceleration API, mainly Intel and AMD on Linux.
+  Vaapi,
+  /// Intel QuickSync Video.
+  Qsv,
+  /// Apple VideoToolbox on MacOS.
+  VideoToolbox,
+  /// Direct3D 11 video acceleration on Windows.
+  D3d11va,
+  /// DirectX Video Acceleration 2 on Windows.
+  Dxva2,
+  /// Video Decode and Presentation API for Unix.
+  Vdpau,
+}
+
+impl AsRef<str> for HwAccel {
+  fn as_ref(&self) -> &str {
+    match self {
+      HwAccel::Auto => "auto",
+      HwAccel::Cuda => "cuda",
+      HwAccel::Vaapi => "vaapi",
+      HwAccel::Qsv => "qsv",
+      HwAccel::VideoToolbox => "videotoolbox",
+      HwAccel::D3d11va => "d3d11va",
+      HwAccel::Dxva2 => "dxva2",
+      HwAccel::Vdpau => "vdpau",
+    }
+  }
+}
+
+impl fmt::Display for HwAccel {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_ref())
+  }
+}
+
 /// Verify whether ffmpeg is installed on the system. This will return true if
 /// there is an ffmpeg binary in the PATH, or in the same directory as the Rust
 /// executable.
+///
+/// The binary must identify itself as ffmpeg, so this is false if
+/// [`ffmpeg_path`] points at e.g. ffprobe; see [`validate_binary`].
 pub fn ffmpeg_is_installed() -> bool {
-  Command::new(ffmpeg_path())
-    .arg("-version")
-    .create_no_window()
-    .stderr(Stdio::null())
-    .stdout(Stdio::null())
-    .status()
-    .map(|s| s.success())
-    .unwrap_or_else(|_| false)
+  validate_binary(ffmpeg_path()).is_ok_and(|kind| kind == BinaryKind::Ffmpeg)
 }
 
 pub(crate) trait BackgroundCommand {
@@ -777,3 +2869,39 @@ impl BackgroundCommand for Command {
     self
   }
 }
+
+/// Whether an argument refers to the standard stream `fd`, like `-`, `pipe:`
+/// or `pipe:1`.
+///
+/// A bare `pipe` is a plain file name to ffmpeg, not the pipe protocol.
+fn is_pipe(arg: &OsStr, fd: &str) -> bool {
+  arg == "-" || arg == "pipe:" || arg == format!("pipe:{fd}").as_str()
+}
+
+/// Whether an argument already decides what happens to existing outputs, so
+/// that ffmpeg won't prompt.
+fn is_overwrite_arg(arg: &OsStr) -> bool {
+  arg == "-y" || arg == "-n" || arg == "-nostdin"
+}
+
+/// Quote an argument for a POSIX shell, if it contains anything but plainly
+/// safe characters.
+fn shell_quote(arg: &str) -> Cow<'_, str> {
+  let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=,+@%^".contains(c);
+  if !arg.is_empty() && arg.chars().all(is_safe) {
+    Cow::Borrowed(arg)
+  } else {
+    Cow::Owned(format!("'{}'", arg.replace('\'', r"'\''")))
+  }
+}
+
+/// Format a frame rate for a filter argument, using the exact fraction for
+/// NTSC rates (`n * 1000/1001`) which can't be represented as a decimal.
+fn frame_rate_arg(fps: f32) -> String {
+  let ntsc = (fps * 1.001).round();
+  if ntsc >= 1.0 && fps.fract() != 0.0 && (fps - ntsc * 1000.0 / 1001.0).abs() < 0.005 {
+    format!("{}/1001", ntsc as u32 * 1000)
+  } else {
+    fps.to_string()
+  }
+}
@@ -0,0 +1,115 @@
+//! Controlling a running FFmpeg process from another thread.
+
+use std::{
+  io::{self, Write},
+  process::ChildStdin,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+  },
+};
+
+use anyhow::Context;
+
+/// The stdin of an FFmpeg process, shared between its `FfmpegChild` and every
+/// `FfmpegControl` handle.
+pub(crate) type SharedStdin = Arc<Mutex<Option<ChildStdin>>>;
+
+/// Run `f` with the stdin in `shared`, failing with `BrokenPipe` if it was
+/// taken.
+pub(crate) fn with_shared_stdin<T>(
+  shared: &SharedStdin,
+  f: impl FnOnce(&mut ChildStdin) -> io::Result<T>,
+) -> io::Result<T> {
+  let mut stdin = shared.lock().unwrap_or_else(|e| e.into_inner());
+  match stdin.as_mut() {
+    Some(stdin) => f(stdin),
+    None => Err(missing_stdin()),
+  }
+}
+
+pub(crate) fn missing_stdin() -> io::Error {
+  io::Error::new(
+    io::ErrorKind::BrokenPipe,
+    "Missing child stdin; it may have been taken with `take_stdin()`",
+  )
+}
+
+/// A cheaply cloneable handle for stopping an FFmpeg process from another
+/// thread, such as a supervisor, while the thread owning the
+/// [`FfmpegChild`](crate::child::FfmpegChild) drives its events. Created by
+/// [`FfmpegChild::control`](crate::child::FfmpegChild::control).
+///
+/// ```rust,no_run
+/// use ffmpeg_sidecar::command::FfmpegCommand;
+/// use std::{thread, time::Duration};
+///
+/// let mut child = FfmpegCommand::new().testsrc().rawvideo().spawn().unwrap();
+/// let control = child.control();
+/// thread::spawn(move || {
+///   thread::sleep(Duration::from_secs(5));
+///   control.quit().ok();
+/// });
+/// for frame in child.iter().unwrap().filter_frames() {
+///   println!("frame {}", frame.frame_num);
+/// }
+/// child.wait().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct FfmpegControl {
+  pid: u32,
+  stdin: SharedStdin,
+  exited: Arc<AtomicBool>,
+}
+
+impl FfmpegControl {
+  pub(crate) fn new(pid: u32, stdin: SharedStdin, exited: Arc<AtomicBool>) -> Self {
+    Self { pid, stdin, exited }
+  }
+
+  /// The OS process ID of ffmpeg, e.g. to correlate with system metrics.
+  pub fn pid(&self) -> u32 {
+    self.pid
+  }
+
+  /// Whether the `FfmpegChild` has observed the process exiting, through
+  /// `wait()` or `wait_timeout()`.
+  pub fn has_exited(&self) -> bool {
+    self.exited.load(Ordering::SeqCst)
+  }
+
+  /// Send a command to ffmpeg over stdin, like
+  /// [`FfmpegChild::send_stdin_command`](crate::child::FfmpegChild::send_stdin_command).
+  pub fn send_stdin_command(&self, command: &[u8]) -> anyhow::Result<()> {
+    with_shared_stdin(&self.stdin, |stdin| stdin.write_all(command))?;
+    Ok(())
+  }
+
+  /// Send a `q` command to ffmpeg over stdin, requesting a graceful shutdown
+  /// as soon as possible.
+  pub fn quit(&self) -> anyhow::Result<()> {
+    self.send_stdin_command(b"q")
+  }
+
+  /// Forcibly terminate the process with `SIGKILL`. Does nothing once the
+  /// `FfmpegChild` has waited for the process, since its PID may have been
+  /// reused by then.
+  ///
+  /// Only supported on Unix; returns an error on other platforms, where
+  /// [`quit()`](Self::quit) still works.
+  pub fn kill(&self) -> anyhow::Result<()> {
+    if self.has_exited() {
+      return Ok(());
+    }
+    #[cfg(unix)]
+    {
+      let pid: libc::pid_t = self.pid.try_into()?;
+      if unsafe { libc::kill(pid, libc::SIGKILL) } != 0 {
+        return Err(io::Error::last_os_error()).context("Failed to kill ffmpeg");
+      }
+      Ok(())
+    }
+    #[cfg(not(unix))]
+    anyhow::bail!("Killing ffmpeg through a control handle is unsupported on this platform");
+  }
+}
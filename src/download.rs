@@ -1,6 +1,7 @@
 //! Utilities for downloading and unpacking FFmpeg binaries.
 
 use anyhow::Result;
+use std::time::Duration;
 
 #[cfg(feature = "download_ffmpeg")]
 use std::path::{Path, PathBuf};
@@ -8,9 +9,60 @@ use std::path::{Path, PathBuf};
 /// The default directory name for unpacking a downloaded FFmpeg release archive.
 pub const UNPACK_DIRNAME: &str = "ffmpeg_release_temp";
 
+/// Retry policy for downloads, for [`download_ffmpeg_with_config`] and
+/// [`download_ffmpeg_package_with_config`].
+///
+/// Only transient failures are retried: connection errors, a connection lost
+/// mid-download, and server errors (`5xx` and `429 Too Many Requests`).
+/// Client errors like `404 Not Found` fail immediately. The delay doubles
+/// after every failed attempt, starting at `base_delay`.
+///
+/// The other download functions use the default, which makes up to 3
+/// attempts, waiting 1 and then 2 seconds in between.
+///
+/// ```rust
+/// use ffmpeg_sidecar::download::DownloadConfig;
+/// use std::time::Duration;
+///
+/// let config = DownloadConfig {
+///   max_attempts: 5,
+///   base_delay: Duration::from_millis(500),
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadConfig {
+  /// Total number of attempts, including the first; `1` disables retries.
+  pub max_attempts: u32,
+  /// Delay before the first retry, doubled for each one after it.
+  pub base_delay: Duration,
+}
+
+impl Default for DownloadConfig {
+  fn default() -> Self {
+    Self {
+      max_attempts: 3,
+      base_delay: Duration::from_secs(1),
+    }
+  }
+}
+
+impl DownloadConfig {
+  /// The delay before retrying, after `failed_attempts` attempts have failed.
+  #[cfg(feature = "download_ffmpeg")]
+  pub(crate) fn retry_delay(&self, failed_attempts: u32) -> Duration {
+    self
+      .base_delay
+      .saturating_mul(2u32.saturating_pow(failed_attempts.saturating_sub(1)))
+  }
+}
+
 /// URL of a manifest file containing the latest published build of FFmpeg. The
 /// correct URL for the target platform is baked in at compile time.
 pub fn ffmpeg_manifest_url() -> Result<&'static str> {
+  if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+    return Ok("https://johnvansickle.com/ffmpeg/release-readme.txt");
+  }
+
   if cfg!(not(target_arch = "x86_64")) {
     anyhow::bail!("Downloads must be manually provided for non-x86_64 architectures");
   }
@@ -33,6 +85,9 @@ pub fn ffmpeg_download_url() -> Result<&'static str> {
     Ok("https://www.gyan.dev/ffmpeg/builds/ffmpeg-release-essentials.zip")
   } else if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
     Ok("https://johnvansickle.com/ffmpeg/releases/ffmpeg-release-amd64-static.tar.xz")
+  } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+    Ok("https://johnvansickle.com/ffmpeg/releases/ffmpeg-release-arm64-static.tar.xz")
+  // Raspberry Pi, Graviton
   } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
     Ok("https://evermeet.cx/ffmpeg/getrelease/zip")
   } else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
@@ -42,24 +97,371 @@ pub fn ffmpeg_download_url() -> Result<&'static str> {
   }
 }
 
+/// URL for a separate FFprobe archive, for providers which don't bundle it with
+/// FFmpeg. Returns `None` where the FFmpeg archive already contains FFprobe
+/// (Windows and Linux), or where no separate download is known (MacOS
+/// aarch64).
+pub fn ffprobe_download_url() -> Option<&'static str> {
+  if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+    Some("https://evermeet.cx/ffmpeg/getrelease/ffprobe/zip")
+  } else {
+    None
+  }
+}
+
+/// Variant of [`ffmpeg_download_url`] pointing at a mirror, keeping the
+/// archive filename of the target platform.
+///
+/// ```rust
+/// use ffmpeg_sidecar::download::{ffmpeg_download_url, ffmpeg_download_url_from};
+/// if let Ok(default_url) = ffmpeg_download_url() {
+///   let mirror_url = ffmpeg_download_url_from("https://mirror.example.com/ffmpeg/").unwrap();
+///   let filename = default_url.rsplit('/').next().unwrap();
+///   assert!(mirror_url == format!("https://mirror.example.com/ffmpeg/{filename}"));
+/// }
+/// ```
+pub fn ffmpeg_download_url_from(base_url: &str) -> Result<String> {
+  let filename = ffmpeg_download_url()?
+    .rsplit('/')
+    .next()
+    .unwrap_or_default();
+  Ok(format!("{}/{filename}", base_url.trim_end_matches('/')))
+}
+
+/// URL for a specific published FFmpeg release, for pinning the exact build
+/// that gets downloaded. The correct provider for the target platform is baked
+/// in at compile time.
+///
+/// Resolvable versions per platform:
+/// - **Windows x86_64**: release tags mirrored on GitHub by gyan.dev, e.g.
+///   `"7.1"` or `"6.1.1"`. See <https://github.com/GyanD/codexffmpeg/releases>.
+/// - **MacOS x86_64**: any build archived on evermeet.cx, either a release such
+///   as `"6.1.1"` or a dated snapshot such as `"117523-g1a2b3c4d5e"`. See
+///   <https://evermeet.cx/ffmpeg/>.
+/// - **Linux** and **MacOS aarch64**: unsupported, since those providers only
+///   publish the latest release.
+///
+/// ```rust
+/// use ffmpeg_sidecar::download::ffmpeg_download_url_for_version;
+/// if let Ok(url) = ffmpeg_download_url_for_version("6.1.1") {
+///   assert!(url.contains("6.1.1"));
+/// }
+/// ```
+pub fn ffmpeg_download_url_for_version(version: &str) -> Result<String> {
+  let version = version.trim().trim_start_matches(['n', 'v']);
+  if version.is_empty() {
+    anyhow::bail!("No FFmpeg version provided");
+  }
+
+  if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+    Ok(format!(
+      "https://github.com/GyanD/codexffmpeg/releases/download/{version}/ffmpeg-{version}-essentials_build.zip"
+    ))
+  } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+    Ok(format!("https://evermeet.cx/ffmpeg/ffmpeg-{version}.zip"))
+  } else if cfg!(any(target_os = "linux", target_os = "macos")) {
+    anyhow::bail!("Pinned FFmpeg versions are unsupported on this platform, since its download provider only publishes the latest release.")
+  } else {
+    anyhow::bail!("Unsupported platform; you can provide your own URL instead and call download_ffmpeg_package directly.")
+  }
+}
+
 /// Check if FFmpeg is installed, and if it's not, download and unpack it.
 /// Automatically selects the correct binaries for Windows, Linux, and MacOS.
 /// The binaries will be placed in the same directory as the Rust executable.
 ///
 /// If FFmpeg is already installed, the method exits early without downloading
 /// anything.
+///
+/// Like all of the functions installing FFmpeg, this is safe to call from
+/// several processes at once: installs into the same directory are guarded by
+/// a lock file, so a process which loses the race waits for the other to
+/// finish instead of unpacking over it.
 #[cfg(feature = "download_ffmpeg")]
 pub fn auto_download() -> Result<()> {
-  use crate::{command::ffmpeg_is_installed, paths::sidecar_dir};
+  download_ffmpeg_with_config(&DownloadConfig::default())
+}
+
+/// Variant of [`auto_download`] with a custom retry policy for transient
+/// network failures, e.g. more attempts on a flaky CI network.
+#[cfg(feature = "download_ffmpeg")]
+pub fn download_ffmpeg_with_config(config: &DownloadConfig) -> Result<()> {
+  install_if_missing(
+    ffmpeg_download_url()?,
+    ffprobe_download_url(),
+    None,
+    config,
+    |_, _| {},
+  )
+}
+
+/// Make sure a working FFmpeg binary is available, downloading it if needed,
+/// and return the path to invoke it with. Intended as a single call at app
+/// startup.
+///
+/// This is idempotent and safe to call from several processes at once, see
+/// [`auto_download`].
+#[cfg(feature = "download_ffmpeg")]
+pub fn ensure_ffmpeg() -> Result<PathBuf> {
+  install_if_missing(
+    ffmpeg_download_url()?,
+    ffprobe_download_url(),
+    None,
+    &DownloadConfig::default(),
+    |_, _| {},
+  )?;
+  Ok(crate::paths::ffmpeg_path())
+}
+
+/// Lock file held for the duration of an install, removed on drop. While it's
+/// held, a background thread keeps refreshing its modification time, so a
+/// lock which isn't refreshed anymore was left behind by a crashed process,
+/// however long the download takes.
+#[cfg(feature = "download_ffmpeg")]
+pub(crate) struct InstallLock {
+  path: PathBuf,
+  heartbeat: Option<(std::sync::mpsc::Sender<()>, std::thread::JoinHandle<()>)>,
+}
+
+#[cfg(feature = "download_ffmpeg")]
+impl InstallLock {
+  pub(crate) const FILENAME: &'static str = "ffmpeg_sidecar_download.lock";
+
+  /// How often the holder refreshes the modification time of the lock file.
+  const REFRESH_EVERY: Duration = Duration::from_secs(10);
+
+  /// Locks which haven't been refreshed for this long are assumed to be left
+  /// behind by a crashed process.
+  const STALE_AFTER: Duration = Duration::from_secs(60);
+
+  /// Wait until no other process is installing into `dir`, creating it if
+  /// needed, and lock it.
+  pub(crate) fn acquire(dir: &Path) -> Result<Self> {
+    use anyhow::Context;
+    use std::{fs::OpenOptions, io::ErrorKind, thread::sleep};
+
+    std::fs::create_dir_all(dir)
+      .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+    let path = dir.join(Self::FILENAME);
+    loop {
+      match OpenOptions::new().write(true).create_new(true).open(&path) {
+        Ok(file) => {
+          return Ok(Self {
+            path,
+            heartbeat: Some(Self::refresh(file)),
+          })
+        }
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+          let stale = std::fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > Self::STALE_AFTER);
+          if stale {
+            std::fs::remove_file(&path).ok();
+          } else {
+            sleep(Duration::from_millis(100));
+          }
+        }
+        Err(e) => {
+          return Err(e).with_context(|| format!("Failed to create lock file {}", path.display()))
+        }
+      }
+    }
+  }
+
+  /// Touch the lock file every `REFRESH_EVERY` on a background thread, until
+  /// the returned sender is dropped.
+  fn refresh(file: std::fs::File) -> (std::sync::mpsc::Sender<()>, std::thread::JoinHandle<()>) {
+    use std::{
+      sync::mpsc::{channel, RecvTimeoutError},
+      time::SystemTime,
+    };
+
+    let (tx, rx) = channel();
+    let thread = std::thread::spawn(move || {
+      while let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(Self::REFRESH_EVERY) {
+        file.set_modified(SystemTime::now()).ok();
+      }
+    });
+    (tx, thread)
+  }
+}
+
+#[cfg(feature = "download_ffmpeg")]
+impl Drop for InstallLock {
+  fn drop(&mut self) {
+    if let Some((stop, thread)) = self.heartbeat.take() {
+      drop(stop);
+      thread.join().ok();
+    }
+    std::fs::remove_file(&self.path).ok();
+  }
+}
+
+/// Variant of [`auto_download`] which fetches the archive from a mirror
+/// instead, such as an internal proxy in an air-gapped environment. The
+/// mirror must host the same archive filename as the default download URL for
+/// the target platform; see [`ffmpeg_download_url_from`].
+///
+/// Only the FFmpeg archive is fetched from the mirror, so on platforms where
+/// [`ffprobe_download_url`] is needed FFprobe won't be installed.
+#[cfg(feature = "download_ffmpeg")]
+pub fn download_ffmpeg_from(base_url: &str) -> Result<()> {
+  install_if_missing(
+    &ffmpeg_download_url_from(base_url)?,
+    None,
+    None,
+    &DownloadConfig::default(),
+    |_, _| {},
+  )
+}
+
+/// Variant of [`auto_download`] which reports download progress to
+/// `on_progress`, e.g. to render a progress bar. See
+/// [`download_ffmpeg_package_with_progress`] for details.
+#[cfg(feature = "download_ffmpeg")]
+pub fn download_ffmpeg_with_progress(on_progress: impl FnMut(u64, Option<u64>)) -> Result<()> {
+  install_if_missing(
+    ffmpeg_download_url()?,
+    ffprobe_download_url(),
+    None,
+    &DownloadConfig::default(),
+    on_progress,
+  )
+}
+
+/// Download and unpack a specific FFmpeg release, as resolved by
+/// [`ffmpeg_download_url_for_version`]. Unlike [`auto_download`], this always
+/// downloads, replacing any binaries already in the sidecar directory.
+#[cfg(feature = "download_ffmpeg")]
+pub fn download_ffmpeg_version(version: &str) -> Result<()> {
+  let ffprobe_url = ffprobe_download_url()
+    .is_some()
+    .then(|| ffprobe_download_url_for_version(version));
+  install(
+    &ffmpeg_download_url_for_version(version)?,
+    ffprobe_url.as_deref(),
+    None,
+    &DownloadConfig::default(),
+    |_, _| {},
+    false,
+  )
+}
+
+/// Counterpart of [`ffmpeg_download_url_for_version`] for the separate FFprobe
+/// archive on MacOS.
+#[cfg(feature = "download_ffmpeg")]
+fn ffprobe_download_url_for_version(version: &str) -> String {
+  let version = version.trim().trim_start_matches(['n', 'v']);
+  format!("https://evermeet.cx/ffmpeg/ffprobe-{version}.zip")
+}
+
+/// Variant of [`auto_download`] which unpacks the binaries into `dir` instead
+/// of next to the Rust executable, which is read-only in many container
+/// images. The directory is created if needed. Returns the path of the FFmpeg
+/// binary in `dir`.
+///
+/// If a working FFmpeg binary is already in `dir`, nothing is downloaded.
+///
+/// [`ffmpeg_path`](crate::paths::ffmpeg_path) doesn't look in `dir` by
+/// itself; either pass the returned path to
+/// [`FfmpegCommand::new_with_path`](crate::command::FfmpegCommand::new_with_path),
+/// or set [`FFMPEG_PATH_ENV`](crate::paths::FFMPEG_PATH_ENV) (and
+/// [`FFPROBE_PATH_ENV`](crate::ffprobe::FFPROBE_PATH_ENV)) to `dir`.
+///
+/// ```rust,no_run
+/// use ffmpeg_sidecar::{command::FfmpegCommand, download::download_ffmpeg_to};
+/// use std::path::Path;
+///
+/// let ffmpeg = download_ffmpeg_to(Path::new("/tmp/ffmpeg")).unwrap();
+/// FfmpegCommand::new_with_path(ffmpeg).testsrc().rawvideo().spawn().unwrap();
+/// ```
+#[cfg(feature = "download_ffmpeg")]
+pub fn download_ffmpeg_to(dir: &Path) -> Result<PathBuf> {
+  use crate::paths::binary_in_dir;
+  use anyhow::Context;
+
+  std::fs::create_dir_all(dir)
+    .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+  let ffmpeg = binary_in_dir(dir, "ffmpeg");
+  if !runs(&ffmpeg) {
+    install_to(
+      dir,
+      ffmpeg_download_url()?,
+      ffprobe_download_url(),
+      None,
+      &DownloadConfig::default(),
+      |_, _| {},
+      true,
+    )?;
+  }
+  Ok(ffmpeg)
+}
+
+/// Whether the FFmpeg binary at `path` runs successfully.
+#[cfg(feature = "download_ffmpeg")]
+fn runs(path: &Path) -> bool {
+  use crate::command::BackgroundCommand;
+  use std::process::{Command, Stdio};
+
+  Command::new(path)
+    .arg("-version")
+    .create_no_window()
+    .stderr(Stdio::null())
+    .stdout(Stdio::null())
+    .status()
+    .is_ok_and(|s| s.success())
+}
+
+/// Shared implementation of `auto_download` and its variants.
+#[cfg(feature = "download_ffmpeg")]
+fn install_if_missing(
+  download_url: &str,
+  ffprobe_url: Option<&str>,
+  expected_sha256: Option<&str>,
+  config: &DownloadConfig,
+  on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<()> {
+  use crate::command::ffmpeg_is_installed;
 
   if ffmpeg_is_installed() {
     return Ok(());
   }
 
-  let download_url = ffmpeg_download_url()?;
-  let destination = sidecar_dir()?;
-  let archive_path = download_ffmpeg_package(download_url, &destination)?;
-  unpack_ffmpeg(&archive_path, &destination)?;
+  install(
+    download_url,
+    ffprobe_url,
+    expected_sha256,
+    config,
+    on_progress,
+    true,
+  )
+}
+
+/// Download, verify and unpack an archive into the sidecar directory, followed
+/// by the separate FFprobe archive if there is one. See [`install_to`] for
+/// `if_missing`.
+#[cfg(feature = "download_ffmpeg")]
+fn install(
+  download_url: &str,
+  ffprobe_url: Option<&str>,
+  expected_sha256: Option<&str>,
+  config: &DownloadConfig,
+  on_progress: impl FnMut(u64, Option<u64>),
+  if_missing: bool,
+) -> Result<()> {
+  use crate::{command::ffmpeg_is_installed, paths::sidecar_dir};
+
+  install_to(
+    &sidecar_dir()?,
+    download_url,
+    ffprobe_url,
+    expected_sha256,
+    config,
+    on_progress,
+    if_missing,
+  )?;
 
   if !ffmpeg_is_installed() {
     anyhow::bail!("FFmpeg failed to install, please install manually.");
@@ -68,6 +470,72 @@ pub fn auto_download() -> Result<()> {
   Ok(())
 }
 
+/// Download, verify and unpack the archives into `destination`, checking that
+/// the FFmpeg binary there runs.
+///
+/// Holds the [`InstallLock`] of `destination` throughout. With `if_missing`,
+/// nothing is downloaded if the FFmpeg binary there runs once the lock is
+/// acquired, e.g. because another process installed it while we waited.
+#[cfg(feature = "download_ffmpeg")]
+fn install_to(
+  destination: &Path,
+  download_url: &str,
+  ffprobe_url: Option<&str>,
+  expected_sha256: Option<&str>,
+  config: &DownloadConfig,
+  on_progress: impl FnMut(u64, Option<u64>),
+  if_missing: bool,
+) -> Result<()> {
+  use crate::paths::binary_in_dir;
+
+  let _lock = InstallLock::acquire(destination)?;
+  if if_missing && runs(&binary_in_dir(destination, "ffmpeg")) {
+    return Ok(());
+  }
+
+  let archive_path = download_package(
+    download_url,
+    destination,
+    config,
+    expected_sha256,
+    on_progress,
+  )?;
+  unpack_ffmpeg(&archive_path, destination)?;
+
+  if let Some(ffprobe_url) = ffprobe_url {
+    let archive_path =
+      download_ffmpeg_package_with_config(ffprobe_url, destination, config, |_, _| {})?;
+    unpack_ffmpeg(&archive_path, destination)?;
+  }
+
+  if !runs(&binary_in_dir(destination, "ffmpeg")) {
+    anyhow::bail!(
+      "FFmpeg failed to install to {}, please install manually.",
+      destination.display()
+    );
+  }
+
+  Ok(())
+}
+
+/// Variant of [`auto_download`] which verifies the SHA-256 checksum of the
+/// downloaded archive against `expected_sha256` (as a hex string) before
+/// unpacking it. The checksum only covers the FFmpeg archive, so on platforms
+/// where [`ffprobe_download_url`] is needed FFprobe won't be installed.
+///
+/// On a mismatch the archive is deleted and an error containing the computed
+/// checksum is returned, so nothing corrupt or tampered is left behind.
+#[cfg(feature = "download_ffmpeg")]
+pub fn download_ffmpeg_with_checksum(expected_sha256: &str) -> Result<()> {
+  install_if_missing(
+    ffmpeg_download_url()?,
+    None,
+    Some(expected_sha256),
+    &DownloadConfig::default(),
+    |_, _| {},
+  )
+}
+
 /// Parse the the MacOS version number from a JSON string manifest file.
 ///
 /// Example input: <https://evermeet.cx/ffmpeg/info/ffmpeg/release>
@@ -141,33 +609,287 @@ pub fn check_latest_version() -> Result<String> {
 /// Make an HTTP request to download an archive from the latest published release online.
 #[cfg(feature = "download_ffmpeg")]
 pub fn download_ffmpeg_package(url: &str, download_dir: &Path) -> Result<PathBuf> {
+  download_ffmpeg_package_with_progress(url, download_dir, |_, _| {})
+}
+
+/// Variant of [`download_ffmpeg_package`] which reports progress to
+/// `on_progress` with the number of bytes downloaded so far, and the total
+/// size of the archive if the server sent a `Content-Length`.
+///
+/// The callback fires about every 64 KiB, and once more when the download
+/// completes.
+#[cfg(feature = "download_ffmpeg")]
+pub fn download_ffmpeg_package_with_progress(
+  url: &str,
+  download_dir: &Path,
+  on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<PathBuf> {
+  let config = DownloadConfig {
+    max_attempts: 1,
+    ..Default::default()
+  };
+  download_package(url, download_dir, &config, None, on_progress)
+}
+
+/// Variant of [`download_ffmpeg_package_with_progress`] which retries
+/// transient failures according to `config`, see [`DownloadConfig`].
+///
+/// The archive is first written to a `.part` file next to it. When the
+/// connection is lost mid-download, the retry asks for the rest with a
+/// `Range` header and appends it to the partial file if the server responds
+/// with `206 Partial Content`, or starts over if it ignores the range. The
+/// file is only moved into place once its size matches the `Content-Length`.
+#[cfg(feature = "download_ffmpeg")]
+pub fn download_ffmpeg_package_with_config(
+  url: &str,
+  download_dir: &Path,
+  config: &DownloadConfig,
+  on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<PathBuf> {
+  download_package(url, download_dir, config, None, on_progress)
+}
+
+/// Shared implementation of the `download_ffmpeg_package` variants: download
+/// to a partial file with retries, verify it, and move it into place.
+#[cfg(feature = "download_ffmpeg")]
+fn download_package(
+  url: &str,
+  download_dir: &Path,
+  config: &DownloadConfig,
+  expected_sha256: Option<&str>,
+  mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<PathBuf> {
   use anyhow::Context;
-  use std::{fs::File, io::copy, path::Path};
+  use std::fs::{remove_file, rename};
 
   let filename = Path::new(url)
     .file_name()
     .context("Failed to get filename")?;
-
   let archive_path = download_dir.join(filename);
+  let mut partial_filename = filename.to_os_string();
+  partial_filename.push(".part");
+  let partial_path = download_dir.join(partial_filename);
+
+  // A partial file left behind by an earlier call may be of another release
+  // with the same filename, so it's only resumed within this call
+  remove_file(&partial_path).ok();
+
+  let mut failed_attempts = 0;
+  loop {
+    match download_attempt(url, &partial_path, &mut on_progress) {
+      Ok(()) => break,
+      Err(e) if is_transient(&e) && failed_attempts + 1 < config.max_attempts => {
+        failed_attempts += 1;
+        std::thread::sleep(config.retry_delay(failed_attempts));
+      }
+      Err(e) => {
+        // Don't leave a truncated archive behind
+        remove_file(&partial_path).ok();
+        return Err(e).with_context(|| {
+          format!(
+            "Download of {url} failed after {} attempt(s)",
+            failed_attempts + 1
+          )
+        });
+      }
+    }
+  }
 
-  let response = ureq::get(url).call().context("Failed to download ffmpeg")?;
+  if let Some(sha256) = expected_sha256 {
+    verify_checksum(&partial_path, sha256)?;
+  }
+  // `rename` won't replace an existing file on Windows
+  if archive_path.exists() {
+    remove_file(&archive_path)?;
+  }
+  rename(&partial_path, &archive_path).context("Failed to move ffmpeg download into place")?;
+  Ok(archive_path)
+}
 
-  let mut file =
-    File::create(&archive_path).context("Failed to create file for ffmpeg download")?;
+/// Make a single request for `url`, resuming the download into
+/// `partial_path` if it already has some of the file.
+#[cfg(feature = "download_ffmpeg")]
+fn download_attempt(
+  url: &str,
+  partial_path: &Path,
+  on_progress: &mut impl FnMut(u64, Option<u64>),
+) -> Result<()> {
+  use anyhow::Context;
+  use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+  };
 
-  copy(&mut response.into_reader(), &mut file)
-    .context("Failed to write ffmpeg download to file")?;
+  const REPORT_INTERVAL: u64 = 64 * 1024;
 
-  Ok(archive_path)
+  let offset = std::fs::metadata(partial_path).map_or(0, |m| m.len());
+  let mut request = ureq::get(url);
+  if offset > 0 {
+    request = request.set("Range", &format!("bytes={offset}-"));
+  }
+  let response = match request.call() {
+    // The partial file can't be resumed, e.g. because it's already complete
+    Err(ureq::Error::Status(416, _)) if offset > 0 => {
+      std::fs::remove_file(partial_path)?;
+      return download_attempt(url, partial_path, on_progress);
+    }
+    response => response.context("Failed to download ffmpeg")?,
+  };
+
+  let resumed = offset > 0 && response.status() == 206;
+  let (file, mut downloaded) = match resumed {
+    true => (OpenOptions::new().append(true).open(partial_path), offset),
+    false => (File::create(partial_path), 0),
+  };
+  let mut file = file.context("Failed to create file for ffmpeg download")?;
+  // The length of a compressed body doesn't match the size of the file
+  let total = response
+    .header("Content-Length")
+    .filter(|_| response.header("Content-Encoding").is_none())
+    .and_then(|len| len.parse::<u64>().ok())
+    .map(|len| downloaded + len);
+
+  let mut reader = response.into_reader();
+  let mut buffer = vec![0u8; REPORT_INTERVAL as usize];
+  let mut last_reported = downloaded;
+  loop {
+    let bytes_read = match reader.read(&mut buffer) {
+      Ok(0) => break,
+      Ok(n) => n,
+      Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+      Err(e) => return Err(anyhow::Error::new(e).context(DownloadInterrupted)),
+    };
+    file
+      .write_all(&buffer[..bytes_read])
+      .context("Failed to write ffmpeg download to file")?;
+    downloaded += bytes_read as u64;
+    if downloaded - last_reported >= REPORT_INTERVAL {
+      on_progress(downloaded, total);
+      last_reported = downloaded;
+    }
+  }
+
+  if last_reported != downloaded || downloaded == 0 {
+    on_progress(downloaded, total);
+  }
+
+  match total {
+    Some(total) if downloaded != total => Err(
+      anyhow::anyhow!("Expected {total} bytes, but received {downloaded}")
+        .context(DownloadInterrupted),
+    ),
+    _ => Ok(()),
+  }
+}
+
+/// Context attached to a read error from the response body, marking the
+/// connection as lost mid-download rather than a failure to write the file.
+#[cfg(feature = "download_ffmpeg")]
+#[derive(Debug)]
+struct DownloadInterrupted;
+
+#[cfg(feature = "download_ffmpeg")]
+impl std::fmt::Display for DownloadInterrupted {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str("Connection lost while downloading ffmpeg")
+  }
+}
+
+/// Whether a failed download is worth retrying: connection problems and server
+/// errors, but not client errors like a 404 or a failure to write the file.
+#[cfg(feature = "download_ffmpeg")]
+fn is_transient(error: &anyhow::Error) -> bool {
+  use ureq::ErrorKind;
+
+  if error.downcast_ref::<DownloadInterrupted>().is_some() {
+    return true;
+  }
+  match error.downcast_ref::<ureq::Error>() {
+    Some(ureq::Error::Status(code, _)) => *code >= 500 || *code == 429,
+    Some(ureq::Error::Transport(transport)) => matches!(
+      transport.kind(),
+      ErrorKind::Dns | ErrorKind::ConnectionFailed | ErrorKind::Io | ErrorKind::ProxyConnect
+    ),
+    None => false,
+  }
+}
+
+/// Variant of [`download_ffmpeg_package`] which verifies the SHA-256 checksum
+/// of the downloaded archive against `expected_sha256` (as a hex string). On a
+/// mismatch the archive is deleted, and the error includes the computed
+/// checksum for pinning. Transient failures are retried with the default
+/// [`DownloadConfig`].
+#[cfg(feature = "download_ffmpeg")]
+pub fn download_ffmpeg_package_with_checksum(
+  url: &str,
+  download_dir: &Path,
+  expected_sha256: &str,
+) -> Result<PathBuf> {
+  download_package(
+    url,
+    download_dir,
+    &DownloadConfig::default(),
+    Some(expected_sha256),
+    |_, _| {},
+  )
+}
+
+/// Compare the SHA-256 checksum of a downloaded archive, deleting it on a
+/// mismatch.
+#[cfg(feature = "download_ffmpeg")]
+fn verify_checksum(archive_path: &Path, expected_sha256: &str) -> Result<()> {
+  let verified = sha256_file(archive_path).and_then(|actual| {
+    match actual.eq_ignore_ascii_case(expected_sha256.trim()) {
+      true => Ok(()),
+      false => anyhow::bail!(
+        "Checksum mismatch for {}: expected SHA-256 {}, but computed {actual}",
+        archive_path.display(),
+        expected_sha256.trim(),
+      ),
+    }
+  });
+
+  if verified.is_err() {
+    std::fs::remove_file(archive_path).ok();
+  }
+  verified
+}
+
+/// Compute the SHA-256 checksum of a file as a lowercase hex string.
+///
+/// ```rust
+/// use ffmpeg_sidecar::download::sha256_file;
+/// let path = std::env::temp_dir().join("ffmpeg_sidecar_sha256_doctest.txt");
+/// std::fs::write(&path, "abc").unwrap();
+/// let checksum = sha256_file(&path).unwrap();
+/// assert!(checksum == "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+#[cfg(feature = "download_ffmpeg")]
+pub fn sha256_file(path: &Path) -> Result<String> {
+  use anyhow::Context;
+  use sha2::{Digest, Sha256};
+  use std::{fs::File, io::copy};
+
+  let mut file =
+    File::open(path).with_context(|| format!("Failed to open {} for hashing", path.display()))?;
+  let mut hasher = Sha256::new();
+  copy(&mut file, &mut hasher)?;
+  Ok(format!("{:x}", hasher.finalize()))
 }
 
 /// After downloading, unpacks the archive to a folder, moves the binaries to
 /// their final location, and deletes the archive and temporary folder.
+///
+/// The binaries are searched for by name anywhere in the archive, preferring
+/// those in a `bin` folder, so a provider reorganizing its folders doesn't
+/// break the download.
 #[cfg(feature = "download_ffmpeg")]
 pub fn unpack_ffmpeg(from_archive: &PathBuf, binary_folder: &Path) -> Result<()> {
   use anyhow::Context;
   use std::{
-    fs::{create_dir_all, read_dir, remove_dir_all, remove_file, rename, File},
+    env::consts::EXE_SUFFIX,
+    fs::{create_dir_all, remove_dir_all, remove_file, rename, File},
     path::Path,
   };
 
@@ -197,34 +919,30 @@ pub fn unpack_ffmpeg(from_archive: &PathBuf, binary_folder: &Path) -> Result<()>
       .context("Failed to unpack ffmpeg")?;
   }
 
-  // Move binaries
-  let (ffmpeg, ffplay, ffprobe) = if cfg!(target_os = "windows") {
-    let inner_folder = read_dir(&temp_folder)?
-      .next()
-      .context("Failed to get inner folder")??;
-    (
-      inner_folder.path().join("bin/ffmpeg.exe"),
-      inner_folder.path().join("bin/ffplay.exe"),
-      inner_folder.path().join("bin/ffprobe.exe"),
-    )
-  } else if cfg!(target_os = "linux") {
-    let inner_folder = read_dir(&temp_folder)?
-      .next()
-      .context("Failed to get inner folder")??;
-    (
-      inner_folder.path().join("./ffmpeg"),
-      inner_folder.path().join("./ffplay"), // <- no ffplay on linux
-      inner_folder.path().join("./ffprobe"),
-    )
-  } else if cfg!(target_os = "macos") {
-    (
-      temp_folder.join("ffmpeg"),
-      temp_folder.join("ffplay"),  // <-- no ffplay on mac
-      temp_folder.join("ffprobe"), // <-- no ffprobe on mac
-    )
-  } else {
-    anyhow::bail!("Unsupported platform");
-  };
+  // Find binaries. There's no ffplay in the Linux and MacOS builds, and
+  // FFprobe comes in a separate archive on MacOS.
+  let files = list_files(&temp_folder)?;
+  let find = |name: &str| find_binary(&files, &format!("{name}{EXE_SUFFIX}"));
+  let (ffmpeg, ffplay, ffprobe) = (find("ffmpeg"), find("ffplay"), find("ffprobe"));
+  if ffmpeg.is_none() && ffprobe.is_none() {
+    let mut contents: Vec<_> = files
+      .iter()
+      .take(100)
+      .map(|path| {
+        let path = path.strip_prefix(&temp_folder).unwrap_or(path);
+        format!("  {}", path.display())
+      })
+      .collect();
+    if files.len() > contents.len() {
+      contents.push(format!("  ... and {} more", files.len() - contents.len()));
+    }
+    remove_dir_all(&temp_folder).ok();
+    anyhow::bail!(
+      "No ffmpeg{EXE_SUFFIX} or ffprobe{EXE_SUFFIX} found in {}, which contains:\n{}",
+      from_archive.display(),
+      contents.join("\n")
+    );
+  }
 
   // Move binaries
   let move_bin = |path: &Path| {
@@ -233,18 +951,24 @@ pub fn unpack_ffmpeg(from_archive: &PathBuf, binary_folder: &Path) -> Result<()>
         .file_name()
         .with_context(|| format!("Path {} does not have a file_name", path.to_string_lossy()))?,
     );
-    rename(path, file_name)?;
+    // `rename` won't replace an existing file on Windows
+    if file_name.exists() {
+      remove_file(&file_name)?;
+    }
+    rename(path, &file_name)?;
+
+    // Archives don't always preserve the executable bit
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      std::fs::set_permissions(&file_name, std::fs::Permissions::from_mode(0o755))?;
+    }
+
     anyhow::Ok(())
   };
 
-  move_bin(&ffmpeg)?;
-
-  if ffprobe.exists() {
-    move_bin(&ffprobe)?;
-  }
-
-  if ffplay.exists() {
-    move_bin(&ffplay)?;
+  for path in [ffmpeg, ffprobe, ffplay].into_iter().flatten() {
+    move_bin(path)?;
   }
 
   // Delete archive and unpacked files
@@ -258,3 +982,35 @@ pub fn unpack_ffmpeg(from_archive: &PathBuf, binary_folder: &Path) -> Result<()>
 
   Ok(())
 }
+
+/// Every file below `folder`, recursively, in a stable order.
+#[cfg(feature = "download_ffmpeg")]
+fn list_files(folder: &Path) -> Result<Vec<PathBuf>> {
+  let mut files = Vec::new();
+  let mut entries: Vec<_> = std::fs::read_dir(folder)?.collect::<std::io::Result<_>>()?;
+  entries.sort_by_key(|entry| entry.file_name());
+  for entry in entries {
+    if entry.file_type()?.is_dir() {
+      files.extend(list_files(&entry.path())?);
+    } else {
+      files.push(entry.path());
+    }
+  }
+  Ok(files)
+}
+
+/// The file named `name` among `files`, preferring one in a `bin` folder,
+/// then the least nested.
+#[cfg(feature = "download_ffmpeg")]
+fn find_binary<'a>(files: &'a [PathBuf], name: &str) -> Option<&'a PathBuf> {
+  files
+    .iter()
+    .filter(|path| path.file_name().is_some_and(|file_name| file_name == name))
+    .min_by_key(|path| {
+      let in_bin = path
+        .parent()
+        .and_then(Path::file_name)
+        .is_some_and(|parent| parent == "bin");
+      (!in_bin, path.components().count())
+    })
+}
@@ -0,0 +1,79 @@
+//! A structured error for an FFmpeg process which exited unsuccessfully.
+
+use std::{fmt, process::ExitStatus};
+
+use crate::event::FfmpegErrorKind;
+
+/// The number of error log lines kept in [`FfmpegError::stderr_tail`].
+pub const STDERR_TAIL_LINES: usize = 10;
+
+/// The exit status of a failed FFmpeg process together with the errors it
+/// logged, returned by
+/// [`FfmpegChild::wait_and_result`](crate::child::FfmpegChild::wait_and_result)
+/// inside an `anyhow::Error`. Recover it with `downcast_ref`.
+///
+/// Displays as the exit code followed by the most relevant error message,
+/// without its log level and context prefixes:
+///
+/// ```txt
+/// ffmpeg exited with code 1: Unknown encoder 'libx265'
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct FfmpegError {
+  /// The exit code, or `None` if ffmpeg was terminated by a signal.
+  pub exit_code: Option<i32>,
+  /// The last error or fatal log lines, separated by newlines.
+  pub stderr_tail: String,
+  /// The cause of the failure, if one of the error lines was recognized.
+  pub kind: Option<FfmpegErrorKind>,
+}
+
+impl FfmpegError {
+  /// Collect the error log lines of a process, with the kind of each line
+  /// recognized by the log parser.
+  pub(crate) fn new(status: ExitStatus, errors: Vec<(String, Option<FfmpegErrorKind>)>) -> Self {
+    let kind = errors.iter().rev().find_map(|(_, kind)| *kind);
+    let skip = errors.len().saturating_sub(STDERR_TAIL_LINES);
+    let stderr_tail = errors
+      .into_iter()
+      .skip(skip)
+      .map(|(line, _)| line.trim_end().to_string())
+      .collect::<Vec<_>>()
+      .join("\n");
+    Self {
+      exit_code: status.code(),
+      stderr_tail,
+      kind,
+    }
+  }
+
+  /// The last error message, without prefixes like `[fatal]` or
+  /// `[vost#0:0 @ 0x600003bd4000]`.
+  pub fn message(&self) -> Option<&str> {
+    let line = self.stderr_tail.lines().last()?;
+    let mut message = line.trim();
+    while let Some(rest) = message.strip_prefix('[') {
+      match rest.split_once("] ") {
+        Some((_, rest)) => message = rest.trim_start(),
+        None => break,
+      }
+    }
+    Some(message)
+  }
+}
+
+impl fmt::Display for FfmpegError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.exit_code {
+      Some(code) => write!(f, "ffmpeg exited with code {code}")?,
+      None => write!(f, "ffmpeg exited with a signal")?,
+    }
+    if let Some(message) = self.message() {
+      write!(f, ": {message}")?;
+    }
+    Ok(())
+  }
+}
+
+impl std::error::Error for FfmpegError {}
@@ -1,22 +1,75 @@
 //! Any event that occurs during the execution of an FFmpeg command.
 
+use std::{collections::HashMap, time::Duration};
+
 /// Any event that occurs during the execution of an FFmpeg command,
 /// including log messages, parsed metadata, progress updates, and output.
+///
+/// With the `serde` feature, events serialize with the variant name under
+/// `"type"` and its payload under `"data"`, e.g.
+/// `{"type":"Log","data":["Info","..."]}`. The pixel data of output frames is
+/// skipped.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "data"))]
 pub enum FfmpegEvent {
   ParsedVersion(FfmpegVersion),
   ParsedConfiguration(FfmpegConfiguration),
-  ParsedStreamMapping(String),
+  ParsedStreamMapping(FfmpegStreamMapping),
   ParsedInput(FfmpegInput),
+  /// An `Output #0, mp4, to 'out.mp4':` line, printed once ffmpeg starts
+  /// writing the output. Followed by a `ParsedOutputStream` for each of its
+  /// streams, whose `parent_index` is the index of this output.
   ParsedOutput(FfmpegOutput),
   ParsedInputStream(Stream),
   ParsedOutputStream(Stream),
   ParsedDuration(FfmpegDuration),
+  /// The `Press [q] to stop, [?] for help` banner, printed once ffmpeg has
+  /// set up its inputs and outputs and starts processing. Only printed when
+  /// ffmpeg reads commands from stdin, i.e. without `-nostdin`.
+  ProcessingStarted(String),
+  /// A tag from the `Metadata:` block nested under an input or output stream,
+  /// such as `language` or `title`.
+  ParsedStreamTag(FfmpegStreamTag),
   Log(LogLevel, String),
+  /// An error or fatal log message matching a common failure, such as a
+  /// missing input file. Other errors are emitted as `Log` events.
+  ParsedError(FfmpegLogError),
+  /// A recurring warning about a problem with the input or output, such as
+  /// non-monotonic timestamps while remuxing. These can flood the log by the
+  /// thousands; see
+  /// [`FfmpegIterator::dedup_warnings`](crate::iter::FfmpegIterator::dedup_warnings).
+  ParsedWarning(FfmpegLogWarning),
+  /// A `Last message repeated N times` line, which ffmpeg prints instead of
+  /// repeating the same message. The event before it, e.g. a `Log` or
+  /// `ParsedWarning`, stands for `count` more identical messages.
+  Repeated(FfmpegRepeated),
+  /// A warning that an option is deprecated, like `-vsync is deprecated. Use
+  /// -fps_mode`, with the replacement when FFmpeg suggests one.
+  Deprecation(FfmpegDeprecation),
   LogEOF,
   /// An error that didn't originate from the ffmpeg logs
   Error(String),
   Progress(FfmpegProgress),
+  /// The final stats of the run, parsed from the last status line, which
+  /// ffmpeg terminates with a newline instead of a carriage return. Emitted
+  /// right after the `Progress` event for the same line, so it marks the end
+  /// of processing, e.g. to time a decode-only `-f null -` benchmark.
+  Summary(FfmpegSummary),
+  /// The size of each kind of stream in an output, from the `video:... audio:...
+  /// muxing overhead: ...` line printed once the output is finished. Not
+  /// printed below `-loglevel info`.
+  MuxingStats(FfmpegMuxingStats),
+  /// The CPU time and peak memory of the run, from the `bench:` lines printed
+  /// at exit with
+  /// [`FfmpegCommand::benchmark`](crate::command::FfmpegCommand::benchmark).
+  /// Emitted right after the `Log` event for the last of the two lines.
+  Benchmark(BenchmarkStats),
+  /// Frames were duplicated or dropped to match the output framerate, or
+  /// dropped by a capture device which couldn't keep up. The framerate
+  /// messages are only logged at `-loglevel verbose` or higher; the running
+  /// totals are also available on `FfmpegProgress`.
+  ParsedDupDrop(FfmpegDupDrop),
   OutputFrame(OutputVideoFrame),
   /// A chunk of data that may not correspond to a complete frame.
   /// For example, it may contain encoded h264.
@@ -28,6 +81,7 @@ pub enum FfmpegEvent {
 
 /// The internal log level designated by FFmpeg on each message.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LogLevel {
   Info,
   Warning,
@@ -36,24 +90,191 @@ pub enum LogLevel {
   Unknown,
 }
 
+/// An error or fatal message from the ffmpeg logs, categorized by its cause.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FfmpegLogError {
+  pub kind: FfmpegErrorKind,
+  /// Either `LogLevel::Error` or `LogLevel::Fatal`, or `LogLevel::Warning`
+  /// for [`FfmpegErrorKind::UnprobeableStream`], which ffmpeg logs as a
+  /// warning before failing
+  pub level: LogLevel,
+  /// The line that this error was parsed from
+  pub raw_log_message: String,
+}
+
+/// Common causes of ffmpeg failures, recognized from their log messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FfmpegErrorKind {
+  /// `No such file or directory`: an input (or output directory) doesn't exist
+  NoSuchFile,
+  /// `Invalid data found when processing input`: the input is corrupt or not
+  /// a recognized media format
+  InvalidData,
+  /// `Permission denied`: an input can't be read or an output can't be written
+  PermissionDenied,
+  /// `Unknown encoder` or `Encoder not found`: the requested codec isn't
+  /// included in this ffmpeg build
+  UnknownEncoder,
+  /// A hardware device or accelerated codec failed to initialize, e.g.
+  /// `Cannot load libcuda.so.1` or `Failed to initialise VAAPI connection`.
+  /// Carries the backend if the message names one. Typically recoverable by
+  /// retrying with a software codec.
+  HardwareInit(Option<HardwareBackend>),
+  /// A hardware device ran out of a shared resource, e.g.
+  /// `CUDA_ERROR_OUT_OF_MEMORY` or the NVENC session limit
+  /// (`OpenEncodeSessionEx failed: out of memory`). Unlike `HardwareInit`,
+  /// the same command is likely to succeed later once other jobs release the
+  /// device, so queue it rather than falling back or giving up.
+  HardwareExhausted(Option<HardwareBackend>),
+  /// `Could not find codec parameters for stream 0` or `could not find
+  /// corresponding track id`: probing couldn't make sense of a stream, which
+  /// usually means the input is corrupt or truncated, but may be salvageable
+  /// by remuxing or repairing it. Unlike `UnknownEncoder`, the codec itself
+  /// is supported. Carries the index of the stream if the message names one.
+  UnprobeableStream(Option<u32>),
+}
+
+/// A recurring warning from the ffmpeg logs, categorized by its cause.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FfmpegLogWarning {
+  pub kind: FfmpegWarningKind,
+  /// Usually `LogLevel::Warning`, but some of these are logged as errors
+  pub level: LogLevel,
+  /// How many warnings of this kind have been logged so far, including this
+  /// one
+  pub count: u32,
+  /// The line that this warning was parsed from
+  pub raw_log_message: String,
+}
+
+/// A folded run of identical log messages, emitted as
+/// [`FfmpegEvent::Repeated`].
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FfmpegRepeated {
+  /// How many times the previous message was repeated, not counting itself
+  pub count: u32,
+  /// The line that this was parsed from
+  pub raw_log_message: String,
+}
+
+/// A deprecated option reported in the ffmpeg logs.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FfmpegDeprecation {
+  /// The deprecated option as printed, e.g. `-vsync` or `sub_charenc_mode`
+  pub option: String,
+  /// The option to use instead, e.g. `-fps_mode`, if the message names one
+  pub suggested: Option<String>,
+  /// The line that this deprecation was parsed from
+  pub raw_log_message: String,
+}
+
+/// Common warnings, recognized from their log messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FfmpegWarningKind {
+  /// `Non-monotonic DTS in output stream`: the muxer received a timestamp
+  /// earlier than the previous one, and shifted it
+  NonMonotonicDts,
+  /// `Application provided invalid, non monotonically increasing dts to
+  /// muxer`: like `NonMonotonicDts`, but the packet is rejected, which
+  /// usually ends the run with an error
+  InvalidDts,
+  /// A bitstream that needs conversion for the output container, such as
+  /// `Malformed AAC bitstream detected: use the audio bitstream filter
+  /// 'aac_adtstoasc'`. The message names the bitstream filter to apply with
+  /// `-bsf`.
+  MalformedBitstream,
+}
+
+/// The hardware acceleration API named in a
+/// [`FfmpegErrorKind::HardwareInit`] or
+/// [`FfmpegErrorKind::HardwareExhausted`] error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HardwareBackend {
+  /// NVIDIA CUDA, including NVENC and NVDEC
+  Cuda,
+  Vaapi,
+  /// Intel Quick Sync Video, through libmfx or libvpl
+  Qsv,
+  VideoToolbox,
+  D3d11va,
+  Dxva2,
+  Vulkan,
+  /// AMD Advanced Media Framework
+  Amf,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FfmpegInput {
   pub index: u32,
   pub duration: Option<f64>,
+  /// The demuxer(s) which recognized the input, comma-joined as logged, e.g.
+  /// `mov,mp4,m4a,3gp,3g2,mj2` or `matroska,webm`
+  pub format: Option<String>,
   pub raw_log_message: String,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FfmpegDuration {
   pub input_index: u32,
   pub duration: f64,
+  /// The `start:` timestamp of the input, in seconds.
+  pub start_time: Option<f64>,
+  /// The overall `bitrate:` of the input, in kilobits per second.
+  pub bitrate_kbps: Option<u32>,
+  pub raw_log_message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FfmpegStreamTag {
+  /// Whether the tag belongs to an output stream rather than an input stream
+  pub is_output: bool,
+  /// The index of the input or output that the stream belongs to
+  pub parent_index: u32,
+  /// The index of the stream inside the input or output
+  pub stream_index: u32,
+  pub key: String,
+  /// The tag value. Values spanning multiple lines are joined with `\n`, with
+  /// one event emitted per line containing the value so far.
+  pub value: String,
+  pub raw_log_message: String,
+}
+
+/// One line of the `Stream mapping:` block, showing where an output stream
+/// comes from and which decoder and encoder were chosen for it, e.g.
+/// `Stream #0:0 -> #0:0 (h264 (h264_cuvid) -> hevc (hevc_nvenc))`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FfmpegStreamMapping {
+  /// The input stream like `0:0`, or a filter graph pad like `amix:default`
+  pub source: String,
+  /// The output stream like `0:0`, or a filter graph pad like `amix:input0`
+  pub dest: String,
+  /// The decoder, e.g. `h264` for the native decoder or `h264_cuvid`
+  pub decoder: Option<String>,
+  /// The encoder, e.g. `libx265` or `hevc_nvenc`
+  pub encoder: Option<String>,
+  /// Whether the stream is copied without re-encoding (`-c copy`)
+  pub copy: bool,
   pub raw_log_message: String,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FfmpegOutput {
   pub to: String,
   pub index: u32,
+  /// The muxer writing the output, e.g. `mp4` or `rawvideo`
+  pub format: String,
   pub raw_log_message: String,
 }
 
@@ -66,6 +287,7 @@ impl FfmpegOutput {
 
 /// Represents metadata about a stream.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Stream {
   /// Corresponds to stream `-f` parameter, e.g. `rawvideo`, `h264`, `opus` or `srt`.
   pub format: String,
@@ -77,11 +299,35 @@ pub struct Stream {
   pub stream_index: u32,
   /// The stderr line that this stream was parsed from.
   pub raw_log_message: String,
+  /// Tags from the stream's `Metadata:` block, such as `language` or `title`.
+  /// Always empty on `ParsedInputStream` and `ParsedOutputStream` events,
+  /// since the tags are logged afterwards; they're filled in on
+  /// [`FfmpegMetadata`](crate::metadata::FfmpegMetadata).
+  pub tags: HashMap<String, String>,
+  /// The disposition flags printed at the end of the stream line, like
+  /// `default`, `forced` or `hearing impaired`, in the order printed.
+  pub dispositions: Vec<String>,
   // Data that is specific to a certain stream type.
   pub type_specific_data: StreamTypeSpecificData,
 }
 
 impl Stream {
+  /// Whether the stream has the disposition `disposition`, as printed by
+  /// ffmpeg, e.g. `forced` or `hearing impaired`.
+  pub fn has_disposition(&self, disposition: &str) -> bool {
+    self.dispositions.iter().any(|d| d == disposition)
+  }
+  /// Whether the stream is flagged `default`, the track players pick without
+  /// a preference from the user.
+  pub fn is_default(&self) -> bool {
+    self.has_disposition("default")
+  }
+  /// Whether the stream is flagged `forced`, typically subtitles for foreign
+  /// dialogue which should be shown even with subtitles turned off.
+  pub fn is_forced(&self) -> bool {
+    self.has_disposition("forced")
+  }
+
   pub fn is_audio(&self) -> bool {
     matches!(self.type_specific_data, StreamTypeSpecificData::Audio(_))
   }
@@ -91,6 +337,15 @@ impl Stream {
   pub fn is_video(&self) -> bool {
     matches!(self.type_specific_data, StreamTypeSpecificData::Video(_))
   }
+  pub fn is_data(&self) -> bool {
+    matches!(self.type_specific_data, StreamTypeSpecificData::Data())
+  }
+  pub fn is_attachment(&self) -> bool {
+    matches!(
+      self.type_specific_data,
+      StreamTypeSpecificData::Attachment()
+    )
+  }
   pub fn is_other(&self) -> bool {
     matches!(self.type_specific_data, StreamTypeSpecificData::Other())
   }
@@ -113,24 +368,38 @@ impl Stream {
 /// streams or that are only found in video streams, etc. Storing this in an enum allows function to
 /// accept the generic `Stream` type regardless of its actual type (audio, video, ...).
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StreamTypeSpecificData {
   Audio(AudioStream),
   Video(VideoStream),
   Subtitle(),
+  /// A data stream, such as a timecode track (`tmcd`) or GoPro telemetry
+  /// (`gpmd`)
+  Data(),
+  /// An attached file, such as a font in a Matroska file
+  Attachment(),
+  /// A stream of a type not recognized by the parser
   Other(),
 }
 
 /// Represents metadata that is specific to audio streams.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AudioStream {
   /// The sample rate of the audio stream, e.g. 48000 (Hz)
   pub sample_rate: u32,
-  /// The number of channels of the audio stream, e.g. `stereo`, `5.1` or `7.1`
+  /// The channel layout of the audio stream as printed by ffmpeg, e.g.
+  /// `stereo`, `5.1` or `7.1`, or `3 channels` for unnamed layouts
   pub channels: String,
+  /// The number of channels, e.g. `2` for `stereo` or `6` for `5.1`
+  pub channel_count: Option<u16>,
+  /// The named channel layout, or `None` if ffmpeg only printed a channel count
+  pub channel_layout: Option<String>,
 }
 
 /// Represents metadata that is specific to video streams.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VideoStream {
   /// Corresponds to stream `-pix_fmt` parameter, e.g. `rgb24`
   pub pix_fmt: String,
@@ -140,27 +409,52 @@ pub struct VideoStream {
   pub height: u32,
   /// Framerate in frames per second
   pub fps: f32,
+  /// The `tbr` rate guessed from the timestamps. It differs from `fps` for
+  /// variable frame rate content.
+  pub tbr: Option<f32>,
+  /// Sample (pixel) aspect ratio like `1:1`, from `[SAR 1:1 DAR 16:9]`
+  pub sample_aspect_ratio: Option<String>,
+  /// Display aspect ratio like `16:9`, from `[SAR 1:1 DAR 16:9]`
+  pub display_aspect_ratio: Option<String>,
+  /// Bitrate of the stream in kilobits per second, if reported
+  pub bitrate_kbps: Option<u32>,
+  /// `tv` (limited) or `pc` (full), from e.g. `yuv420p10le(tv, bt2020nc/bt2020/smpte2084)`
+  pub color_range: Option<String>,
+  /// Color space (matrix coefficients), e.g. `bt2020nc`
+  pub color_space: Option<String>,
+  /// Color primaries, e.g. `bt2020`
+  pub color_primaries: Option<String>,
+  /// Transfer characteristics, e.g. `smpte2084` for HDR10 or `arib-std-b67`
+  /// for HLG
+  pub color_transfer: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FfmpegVersion {
   pub version: String,
   pub raw_log_message: String,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FfmpegConfiguration {
   pub configuration: Vec<String>,
   pub raw_log_message: String,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FfmpegProgress {
-  /// index of the current output frame
+  /// index of the current output frame, or `0` for audio-only outputs
   pub frame: u32,
 
-  /// frames per second
-  pub fps: f32,
+  /// Frames per second, averaged over the run so far, or `None` if there's no
+  /// rate yet: when ffmpeg omits it, as for audio-only outputs, or reports
+  /// `0.0` during the first second, before it computes one. `Some(0.0)` is a
+  /// genuine stall, which can only be told apart using the `elapsed=` field
+  /// of FFmpeg 6.1 and later.
+  pub fps: Option<f32>,
 
   /// Quality factor (if applicable)
   pub q: f32,
@@ -168,23 +462,143 @@ pub struct FfmpegProgress {
   /// Current total size of the output in kilobytes
   pub size_kb: u32,
 
+  /// Current total size of the output in bytes. Exact when reported by
+  /// `-progress`, but only accurate to the nearest kilobyte when parsed from
+  /// the status line.
+  pub total_size: Option<u64>,
+
   /// The raw time string in a format like `00:03:29.04`
   pub time: String,
 
-  /// Bitrate in kilo**bits** per second
-  pub bitrate_kbps: f32,
+  /// The output timestamp parsed from `time`, or `None` if it's `N/A` or
+  /// negative, as reported before the first frame of some streams. Compare it
+  /// to the expected duration to estimate the remaining time.
+  pub out_time: Option<Duration>,
+
+  /// Bitrate in kilo**bits** per second, or `None` if reported as `N/A`
+  pub bitrate_kbps: Option<f32>,
 
-  /// Processing speed as a ratio of the input duration
+  /// Processing speed as a ratio of the input duration, or `None` if reported
+  /// as `N/A`
   ///
   /// - 1x is realtime
   /// - 2x means 2 seconds of input are processed in 1 second of wall clock time
-  pub speed: f32,
+  pub speed: Option<f32>,
+
+  /// Total number of duplicated frames, if any have been duplicated
+  pub dup_frames: Option<u32>,
+
+  /// Total number of dropped frames, if any have been dropped
+  pub drop_frames: Option<u32>,
+
+  /// Running total of frames lost so far: those dropped to match the output
+  /// framerate, as in `drop_frames` or `N frames dropped` warnings, plus those
+  /// dropped by a capture device whose buffer overflowed, logged as
+  /// `real-time buffer ... too full ...! frame dropped!`. Above zero, a live
+  /// capture isn't keeping up with its source.
+  ///
+  /// Dropped frames are only counted from the log when progress is parsed
+  /// from stderr too; a `-progress pipe:1` report on stdout only includes
+  /// `drop_frames`.
+  pub dropped_frames: u32,
+
+  /// How much of the input has been processed, from `0.0` to `1.0`. Only set
+  /// by [`FfmpegIterator::with_eta`](crate::iter::FfmpegIterator::with_eta)
+  /// once the input duration is known.
+  pub fraction: Option<f32>,
+
+  /// Estimated wall clock time until FFmpeg finishes, based on the recent
+  /// processing speed. Only set by
+  /// [`FfmpegIterator::with_eta`](crate::iter::FfmpegIterator::with_eta).
+  pub eta: Option<Duration>,
 
   /// The line that this progress was parsed from
   pub raw_log_message: String,
 }
 
+/// The totals from the final status line, emitted as
+/// [`FfmpegEvent::Summary`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FfmpegSummary {
+  /// Total number of frames written to the output
+  pub frames: u32,
+
+  /// Average frames per second over the whole run
+  pub fps: f32,
+
+  /// Average processing speed as a ratio of the input duration, or `None` if
+  /// reported as `N/A`
+  pub speed: Option<f32>,
+
+  /// Wall clock duration of the run, from the `elapsed=` field printed by
+  /// FFmpeg 6.1 and later, or `None` on older versions
+  pub elapsed: Option<Duration>,
+
+  /// Total number of frames lost over the whole run, see
+  /// [`FfmpegProgress::dropped_frames`]. Above zero, e.g. a capture is
+  /// degraded.
+  pub dropped_frames: u32,
+
+  /// Final size of all outputs in bytes, from the `Lsize=` field, or `None`
+  /// if reported as `N/A`, e.g. for `-f null`. Unlike the `size=` of interim
+  /// progress, this includes the trailer written once encoding ends. Accurate
+  /// to the nearest kilobyte.
+  pub total_size: Option<u64>,
+
+  /// The line that these stats were parsed from
+  pub raw_log_message: String,
+}
+
+/// The sizes from the final `muxing overhead` line of an output, emitted as
+/// [`FfmpegEvent::MuxingStats`]. Sizes are in kilobytes, or kibibytes as
+/// printed by FFmpeg 7.0 and later.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FfmpegMuxingStats {
+  /// The index of the output, from the `[out#0/mp4 @ ...]` prefix of FFmpeg
+  /// 6.1 and later, or `None` on older versions
+  pub output_index: Option<u32>,
+  pub video_kb: u64,
+  pub audio_kb: u64,
+  pub subtitle_kb: u64,
+  /// Streams of other kinds, such as data or attachments
+  pub other_kb: u64,
+  pub global_headers_kb: u64,
+  /// The container overhead as a percentage of the stream sizes, or `None` if
+  /// reported as `unknown`, e.g. when nothing was written
+  pub muxing_overhead: Option<f32>,
+  /// The line that these stats were parsed from
+  pub raw_log_message: String,
+}
+
+/// Resource usage of a whole run, emitted as [`FfmpegEvent::Benchmark`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BenchmarkStats {
+  /// CPU time spent in user space, `utime=`
+  pub utime: Duration,
+  /// CPU time spent in the kernel, `stime=`
+  pub stime: Duration,
+  /// Wall clock time, `rtime=`
+  pub rtime: Duration,
+  /// Peak resident memory in kibibytes, `maxrss=`, or 0 where FFmpeg can't
+  /// measure it
+  pub max_rss_kb: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FfmpegDupDrop {
+  /// Number of frames duplicated in this event
+  pub dup: u32,
+  /// Number of frames dropped in this event
+  pub drop: u32,
+  pub raw_log_message: String,
+}
+
 #[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OutputVideoFrame {
   /// The width of this video frame in pixels
   pub width: u32,
@@ -195,9 +609,20 @@ pub struct OutputVideoFrame {
   pub pix_fmt: String,
   /// The index of the FFmpeg output stream that emitted this frame.
   /// In a typical case, there is only one output stream and this will be 0.
+  ///
+  /// Counts only the raw video streams interleaved on stdout, in order; see
+  /// `parent_index` and `stream_index` for where the stream is in the
+  /// command.
   pub output_index: u32,
+  /// The index of the output this frame was written to, as in `Output #1`.
+  pub parent_index: u32,
+  /// The index of the stream inside that output, as in `Stream #1:0`.
+  pub stream_index: u32,
   /// Raw image frame data. The layout of the pixels in memory depends on
   /// `width`, `height`, and `pix_fmt`.
+  ///
+  /// Not serialized with the `serde` feature, to keep messages small.
+  #[cfg_attr(feature = "serde", serde(skip))]
   pub data: Vec<u8>,
   /// Index of current frame, starting at 0 and monotonically increasing by 1
   pub frame_num: u32,
@@ -205,6 +630,58 @@ pub struct OutputVideoFrame {
   pub timestamp: f32,
 }
 
+#[cfg(feature = "image")]
+#[cfg_attr(docsrs, doc(cfg(feature = "image")))]
+impl OutputVideoFrame {
+  /// Convert an `rgb24` frame into an [`image::RgbImage`]. Returns `None` for
+  /// other pixel formats, or if `data` doesn't match the frame dimensions.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::event::OutputVideoFrame;
+  ///
+  /// let mut frame = OutputVideoFrame {
+  ///   width: 2,
+  ///   height: 1,
+  ///   pix_fmt: "rgb24".to_string(),
+  ///   output_index: 0,
+  ///   parent_index: 0,
+  ///   stream_index: 0,
+  ///   data: vec![255, 0, 0, 0, 0, 255],
+  ///   frame_num: 0,
+  ///   timestamp: 0.0,
+  /// };
+  /// let image = frame.to_rgb_image().unwrap();
+  /// assert!(image.get_pixel(1, 0).0 == [0, 0, 255]);
+  ///
+  /// frame.data.pop();
+  /// assert!(frame.to_rgb_image().is_none());
+  /// assert!(frame.to_rgba_image().is_none());
+  /// ```
+  pub fn to_rgb_image(&self) -> Option<image::RgbImage> {
+    self.to_image_buffer("rgb24", 3)
+  }
+
+  /// Convert an `rgba` frame into an [`image::RgbaImage`]. Returns `None` for
+  /// other pixel formats, or if `data` doesn't match the frame dimensions.
+  pub fn to_rgba_image(&self) -> Option<image::RgbaImage> {
+    self.to_image_buffer("rgba", 4)
+  }
+
+  fn to_image_buffer<P: image::Pixel<Subpixel = u8>>(
+    &self,
+    pix_fmt: &str,
+    bytes_per_pixel: usize,
+  ) -> Option<image::ImageBuffer<P, Vec<u8>>> {
+    let expected_len = (self.width as usize)
+      .checked_mul(self.height as usize)?
+      .checked_mul(bytes_per_pixel)?;
+    if self.pix_fmt != pix_fmt || self.data.len() != expected_len {
+      return None;
+    }
+    image::ImageBuffer::from_raw(self.width, self.height, self.data.clone())
+  }
+}
+
 impl std::fmt::Debug for OutputVideoFrame {
   /// Omit the `data` field from the debug output
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -213,6 +690,8 @@ impl std::fmt::Debug for OutputVideoFrame {
       .field("height", &self.height)
       .field("pix_fmt", &self.pix_fmt)
       .field("output_index", &self.output_index)
+      .field("parent_index", &self.parent_index)
+      .field("stream_index", &self.stream_index)
       .finish()
   }
 }
@@ -1,19 +1,40 @@
 //! Utilities related to the FFprobe binary.
 
-use std::collections::HashMap;
-use std::thread::spawn;
-use crate::command::BackgroundCommand;
+use crate::{
+  command::BackgroundCommand,
+  paths::{path_from_env, validate_binary, BinaryKind},
+  version::{check_min_version, FfmpegVersion},
+};
 use anyhow::Context;
-use std::{env::current_exe, ffi::OsStr, path::PathBuf};
+use serde::{
+  de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+  Deserialize, Deserializer,
+};
 use std::{
+  collections::HashMap, env::current_exe, ffi::OsStr, fmt, io::BufReader, path::PathBuf,
+  str::FromStr, sync::mpsc::sync_channel, thread::spawn, time::Duration,
+};
+use std::{
+  io,
   path::Path,
-  process::{Command, Stdio},
+  process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus, Stdio},
 };
 
+/// Environment variable overriding the location of the FFprobe binary, used
+/// by [`ffprobe_path`]. Set it to either the binary itself or the directory
+/// containing it.
+pub const FFPROBE_PATH_ENV: &str = "FFPROBE_SIDECAR_PATH";
+
 /// Returns the path of the downloaded FFprobe executable, or falls back to
 /// assuming its installed in the system path. Note that not all FFmpeg
 /// distributions include FFprobe.
+///
+/// If the `FFPROBE_SIDECAR_PATH` environment variable is set, it takes
+/// precedence over both.
 pub fn ffprobe_path() -> PathBuf {
+  if let Some(path) = path_from_env(FFPROBE_PATH_ENV, "ffprobe") {
+    return path;
+  }
   let default = Path::new("ffprobe").to_path_buf();
   match ffprobe_sidecar_path() {
     Ok(sidecar_path) => match sidecar_path.exists() {
@@ -47,28 +68,237 @@ pub fn ffprobe_version() -> anyhow::Result<String> {
 /// Lower level variant of `ffprobe_version` that exposes a customized the path
 /// to the ffmpeg binary.
 pub fn ffprobe_version_with_path<S: AsRef<OsStr>>(path: S) -> anyhow::Result<String> {
+  let banner = ffprobe_version_raw_with_path(path)?;
+  parse_ffprobe_version(&banner).with_context(|| {
+    format!(
+      "Failed to parse ffprobe version from banner: {}",
+      banner.lines().next().unwrap_or_default()
+    )
+  })
+}
+
+/// Alias for `ffprobe -version`, parsing the version number into a
+/// [`FfmpegVersion`] for comparisons.
+pub fn ffprobe_version_parsed() -> anyhow::Result<FfmpegVersion> {
+  ffprobe_version_parsed_with_path(ffprobe_path())
+}
+
+/// Lower level variant of `ffprobe_version_parsed` that exposes a customized
+/// path to the ffprobe binary.
+pub fn ffprobe_version_parsed_with_path<S: AsRef<OsStr>>(path: S) -> anyhow::Result<FfmpegVersion> {
+  ffprobe_version_with_path(path)?.parse()
+}
+
+/// Like [`ffprobe_is_installed`], but also requiring version `min` or newer.
+/// See [`require_ffprobe_version`] for an error describing the mismatch.
+pub fn ffprobe_is_installed_min(min: FfmpegVersion) -> bool {
+  require_ffprobe_version(min).is_ok()
+}
+
+/// Check that ffprobe is installed with version `min` or newer, returning the
+/// installed version. Fails with a message like `ffprobe 4.4.2 found, need
+/// 5.0+`, suitable for checking requirements at startup.
+pub fn require_ffprobe_version(min: FfmpegVersion) -> anyhow::Result<FfmpegVersion> {
+  require_ffprobe_version_with_path(ffprobe_path(), min)
+}
+
+/// Lower level variant of `require_ffprobe_version` that exposes a customized
+/// path to the ffprobe binary.
+pub fn require_ffprobe_version_with_path<S: AsRef<OsStr>>(
+  path: S,
+  min: FfmpegVersion,
+) -> anyhow::Result<FfmpegVersion> {
+  let version = ffprobe_version_parsed_with_path(path).context("ffprobe not found")?;
+  check_min_version("ffprobe", version, &min)
+}
+
+/// Alias for `ffprobe -version`, returning the entire unparsed output.
+pub fn ffprobe_version_raw() -> anyhow::Result<String> {
+  ffprobe_version_raw_with_path(ffprobe_path())
+}
+
+/// Lower level variant of `ffprobe_version_raw` that exposes a customized path
+/// to the ffprobe binary.
+pub fn ffprobe_version_raw_with_path<S: AsRef<OsStr>>(path: S) -> anyhow::Result<String> {
   let output = Command::new(&path)
     .arg("-version")
     .create_no_window()
     .output()?;
 
-  // note:version parsing is not implemented for ffprobe
+  if !output.status.success() {
+    anyhow::bail!("ffprobe -version exited with non-zero status");
+  }
 
   Ok(String::from_utf8(output.stdout)?)
 }
 
+/// Parses the version number from the first line of `ffprobe -version`.
+///
+/// Release builds, distro-packaged builds and git builds are all supported;
+/// the version token is returned verbatim.
+///
+/// ## Examples
+///
+/// ```rust
+/// use ffmpeg_sidecar::ffprobe::parse_ffprobe_version;
+///
+/// let banner = "ffprobe version 6.1.1 Copyright (c) 2007-2023 the FFmpeg developers\nbuilt with gcc 12";
+/// assert!(parse_ffprobe_version(banner) == Some("6.1.1".to_string()));
+///
+/// let distro = "ffprobe version n6.0 Copyright (c) 2007-2023 the FFmpeg developers";
+/// assert!(parse_ffprobe_version(distro) == Some("n6.0".to_string()));
+///
+/// let git = "ffprobe version N-110000-gabc1234 Copyright (c) 2007-2023 the FFmpeg developers";
+/// assert!(parse_ffprobe_version(git) == Some("N-110000-gabc1234".to_string()));
+///
+/// assert!(parse_ffprobe_version("ffprobe version Copyright").is_none());
+/// assert!(parse_ffprobe_version("ffmpeg version 6.1.1").is_none());
+/// ```
+pub fn parse_ffprobe_version(banner: &str) -> Option<String> {
+  banner
+    .lines()
+    .next()?
+    .trim()
+    .strip_prefix("ffprobe version ")?
+    .split_whitespace()
+    .next()
+    .filter(|token| token.chars().any(|c| c.is_ascii_digit()))
+    .map(|s| s.to_string())
+}
+
 /// Verify whether ffprobe is installed on the system. This will return true if
 /// there is an ffprobe binary in the PATH, or in the same directory as the Rust
 /// executable.
+///
+/// The binary must identify itself as ffprobe, so this is false if
+/// [`ffprobe_path`] points at e.g. ffmpeg; see [`validate_binary`].
 pub fn ffprobe_is_installed() -> bool {
-  Command::new(ffprobe_path())
-    .create_no_window()
-    .arg("-version")
-    .stderr(Stdio::null())
-    .stdout(Stdio::null())
-    .status()
-    .map(|s| s.success())
-    .unwrap_or_else(|_| false)
+  validate_binary(ffprobe_path()).is_ok_and(|kind| kind == BinaryKind::Ffprobe)
+}
+
+/// Count the exact number of frames in the first video stream of a file by
+/// decoding it with `-count_frames`.
+///
+/// Slower than reading `nb_frames` from the container, but reliable for files
+/// with missing or inaccurate metadata. Returns an error if the file has no
+/// video stream.
+pub fn ffprobe_frame_count<P: AsRef<Path>>(path: P) -> anyhow::Result<u64> {
+  let output = FfprobeCommand::new()
+    .select_streams("v:0")
+    .count_frames()
+    .show_entries("stream=nb_read_frames")
+    .print_format("default=nokey=1:noprint_wrappers=1")
+    .arg(path.as_ref())
+    .run_to_string()?;
+
+  let value = output.trim();
+  value.parse::<u64>().with_context(|| match value {
+    "" => "No video stream found".to_string(),
+    _ => format!("Failed to parse frame count from ffprobe output: {value}"),
+  })
+}
+
+/// Count the exact number of samples per channel in the first audio stream
+/// of a file, by decoding it and summing the `nb_samples` of every frame.
+///
+/// Unlike the container duration, which is often rounded or estimated, this
+/// is sample-accurate, e.g. for gapless playback or checking lip sync. For
+/// lossy codecs, it includes the priming samples of the encoder. Returns an
+/// error if the file has no audio stream.
+pub fn ffprobe_audio_samples<P: AsRef<Path>>(path: P) -> anyhow::Result<u64> {
+  ffprobe_audio_samples_and_rate(path.as_ref()).map(|(samples, _)| samples)
+}
+
+/// The [`ffprobe_audio_samples`] of the first audio stream of a file, divided
+/// by its sample rate. Returns an error if the file has no audio stream.
+pub fn ffprobe_audio_duration<P: AsRef<Path>>(path: P) -> anyhow::Result<Duration> {
+  let (samples, sample_rate) = ffprobe_audio_samples_and_rate(path.as_ref())?;
+  let seconds = samples / sample_rate;
+  let nanos = (samples % sample_rate) * 1_000_000_000 / sample_rate;
+  Ok(Duration::new(seconds, nanos as u32))
+}
+
+fn ffprobe_audio_samples_and_rate(path: &Path) -> anyhow::Result<(u64, u64)> {
+  let output = FfprobeCommand::new()
+    .select_streams("a:0")
+    .show_entries("stream=sample_rate:frame=nb_samples")
+    .print_format("default=noprint_wrappers=1")
+    .arg(path)
+    .run_to_string()?;
+
+  let mut samples = 0;
+  let mut sample_rate = None;
+  for line in output.lines() {
+    let parse = |value: &str| {
+      value
+        .trim()
+        .parse::<u64>()
+        .with_context(|| format!("Failed to parse ffprobe output: {line}"))
+    };
+    if let Some(value) = line.strip_prefix("nb_samples=") {
+      samples += parse(value)?;
+    } else if let Some(value) = line.strip_prefix("sample_rate=") {
+      sample_rate = Some(parse(value)?);
+    }
+  }
+  match sample_rate {
+    Some(sample_rate) if sample_rate > 0 => Ok((samples, sample_rate)),
+    Some(_) => anyhow::bail!("Unknown sample rate of the audio stream"),
+    None => anyhow::bail!("No audio stream found"),
+  }
+}
+
+/// Read the duration of a file from its container metadata.
+///
+/// Returns an error if ffprobe can't determine the duration, such as for live
+/// streams or some raw formats.
+pub fn ffprobe_duration<P: AsRef<Path>>(path: P) -> anyhow::Result<Duration> {
+  let output = FfprobeCommand::new()
+    .show_entries("format=duration")
+    .print_format("default=noprint_wrappers=1:nokey=1")
+    .arg(path.as_ref())
+    .run_to_string()?;
+
+  let value = output.trim();
+  let seconds = value.parse::<f64>().with_context(|| match value {
+    "" | "N/A" => "Duration not available".to_string(),
+    _ => format!("Failed to parse duration from ffprobe output: {value}"),
+  })?;
+  Duration::try_from_secs_f64(seconds).with_context(|| format!("Invalid duration: {seconds}"))
+}
+
+/// Read the `(width, height)` of the first video stream of a file.
+///
+/// Returns an error if the file has no video stream.
+pub fn ffprobe_resolution<P: AsRef<Path>>(path: P) -> anyhow::Result<(u32, u32)> {
+  let output = FfprobeCommand::new()
+    .select_streams("v:0")
+    .show_entries("stream=width,height")
+    .print_format("default=noprint_wrappers=1:nokey=1")
+    .arg(path.as_ref())
+    .run_to_string()?;
+
+  let mut lines = output.lines().map(str::trim);
+  let (width, height) = match (lines.next(), lines.next()) {
+    (Some(width), Some(height)) => (width, height),
+    _ => anyhow::bail!("No video stream found"),
+  };
+  let parse = |value: &str| {
+    value
+      .parse::<u32>()
+      .with_context(|| format!("Failed to parse resolution from ffprobe output: {output}"))
+  };
+  Ok((parse(width)?, parse(height)?))
+}
+
+/// Read the chapter markers of a file, e.g. of a podcast or audiobook. Returns
+/// an empty list if it has none.
+pub fn ffprobe_chapters<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<FfprobeChapter>> {
+  let output = FfprobeCommand::new()
+    .show_chapters()
+    .arg(path.as_ref())
+    .run()?;
+  Ok(output.chapters)
 }
 
 /// A wrapper around [`std::process::Command`] with some convenient preset
@@ -78,7 +308,7 @@ pub fn ffprobe_is_installed() -> bool {
 /// documentation: <https://ffmpeg.org/ffprobe.html>. Refer there for the
 /// exhaustive list of possible arguments.
 pub struct FfprobeCommand {
-  inner: Command
+  inner: Command,
 }
 
 impl FfprobeCommand {
@@ -109,20 +339,26 @@ impl FfprobeCommand {
     self
   }
 
-  ///Set list of entries to show. 
-  /// 
-  /// Entries are specified according to the following syntax. section_entries 
-  /// contains a list of section entries separated by :. Each section entry is 
-  /// composed by a section name (or unique name), optionally followed by a list 
+  /// Typed variant of [`print_format()`](FfprobeCommand::print_format), which
+  /// rules out misspelled writer names.
+  pub fn print_format_typed(&mut self, format: OutputFormat) -> &mut Self {
+    self.print_format(format.to_string())
+  }
+
+  ///Set list of entries to show.
+  ///
+  /// Entries are specified according to the following syntax. section_entries
+  /// contains a list of section entries separated by :. Each section entry is
+  /// composed by a section name (or unique name), optionally followed by a list
   /// of entries local to that section, separated by ,.
-  /// 
-  /// If section name is specified but is followed by no =, all entries are printed 
-  /// to output, together with all the contained sections. Otherwise only the 
-  /// entries specified in the local section entries list are printed. In particular, 
-  /// if = is specified but the list of local entries is empty, then no entries will 
+  ///
+  /// If section name is specified but is followed by no =, all entries are printed
+  /// to output, together with all the contained sections. Otherwise only the
+  /// entries specified in the local section entries list are printed. In particular,
+  /// if = is specified but the list of local entries is empty, then no entries will
   /// be shown for that section.
-  /// 
-  /// Note that the order of specification of the local section entries is not honored 
+  ///
+  /// Note that the order of specification of the local section entries is not honored
   /// in the output, and the usual display order will be retained.
   pub fn show_entries<S: AsRef<str>>(&mut self, entries: S) -> &mut Self {
     self.arg("-show_entries");
@@ -130,6 +366,247 @@ impl FfprobeCommand {
     self
   }
 
+  /// Typed variant of [`show_entries()`](FfprobeCommand::show_entries), which
+  /// assembles the syntax from an [`EntrySelector`]. With
+  /// [`run()`](FfprobeCommand::run), only the selected fields are populated.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::ffprobe::{EntrySelector, FfprobeCommand};
+  ///
+  /// let mut entries = EntrySelector::new();
+  /// entries.stream(["width", "height"]).format(["duration"]);
+  /// let output = FfprobeCommand::new()
+  ///   .show_entries_typed(&entries)
+  ///   .input("input.mp4")
+  ///   .run()
+  ///   .unwrap();
+  /// println!("{:?} {:?}", output.streams[0].width, output.format.unwrap().duration);
+  /// ```
+  pub fn show_entries_typed(&mut self, entries: &EntrySelector) -> &mut Self {
+    self.show_entries(entries.to_string())
+  }
+
+  /// Alias for `-i` argument, the input file path or URL.
+  ///
+  /// The input can also be passed as a positional argument with `.arg()`.
+  pub fn input<S: AsRef<str>>(&mut self, path_or_url: S) -> &mut Self {
+    self.arg("-i");
+    self.arg(path_or_url.as_ref());
+    self
+  }
+
+  /// Read the input from stdin (`-i pipe:0`), to probe data that isn't on
+  /// disk. Write the bytes to [`FfprobeChild::take_stdin`] after spawning, and
+  /// drop it to signal the end of the input.
+  ///
+  /// ffprobe can identify most formats from a non-seekable pipe given enough
+  /// bytes, but some information which requires seeking (like an accurate
+  /// `duration` for some containers) may be reported as `N/A`.
+  pub fn input_stdin(&mut self) -> &mut Self {
+    self.inner.stdin(Stdio::piped());
+    self.input("pipe:0")
+  }
+
+  //// Main option aliases
+  //// https://ffmpeg.org/ffprobe.html#Main-options
+
+  /// Alias for `-select_streams` argument.
+  ///
+  /// Select only the streams specified by `stream_specifier`. This option
+  /// affects only the options related to streams (e.g. `show_streams`,
+  /// `show_packets`, etc.).
+  ///
+  /// The specifier is passed through as-is; common forms are:
+  ///
+  /// - `v`, `a`, `s`, `d`, `t`: all video, audio, subtitle, data or attachment
+  ///   streams
+  /// - `v:0`: the first video stream
+  /// - `a:1`: the second audio stream
+  /// - `3`: the stream with index 3
+  /// - `m:language:eng`: streams with a matching metadata tag
+  ///
+  /// See the [Stream specifiers](https://ffmpeg.org/ffmpeg.html#Stream-specifiers)
+  /// section for the full syntax.
+  pub fn select_streams<S: AsRef<str>>(&mut self, stream_specifier: S) -> &mut Self {
+    self.arg("-select_streams");
+    self.arg(stream_specifier.as_ref());
+    self
+  }
+
+  /// Alias for `-count_frames` argument.
+  ///
+  /// Count the number of frames per stream and report it in the
+  /// corresponding stream section (`nb_read_frames`). This requires decoding
+  /// the whole input, so it is much slower than reading container metadata.
+  pub fn count_frames(&mut self) -> &mut Self {
+    self.arg("-count_frames");
+    self
+  }
+
+  /// Alias for `-count_packets` argument.
+  ///
+  /// Count the number of packets per stream and report it in the
+  /// corresponding stream section (`nb_read_packets`).
+  pub fn count_packets(&mut self) -> &mut Self {
+    self.arg("-count_packets");
+    self
+  }
+
+  /// Alias for `-show_streams` argument.
+  ///
+  /// Show information about each media stream contained in the input
+  /// multimedia stream.
+  ///
+  /// Each media stream information is printed within a dedicated section with
+  /// name "STREAM". When deserialized with [`run()`](FfprobeCommand::run), the
+  /// streams are available in [`FfprobeOutput::streams`].
+  pub fn show_streams(&mut self) -> &mut Self {
+    self.arg("-show_streams");
+    self
+  }
+
+  /// Alias for `-show_format` argument.
+  ///
+  /// Show information about the container format of the input multimedia
+  /// stream.
+  ///
+  /// All the container format information is printed within a section with
+  /// name "FORMAT". When deserialized with [`run()`](FfprobeCommand::run), it
+  /// is available in [`FfprobeOutput::format`].
+  pub fn show_format(&mut self) -> &mut Self {
+    self.arg("-show_format");
+    self
+  }
+
+  /// Alias for `-show_chapters` argument.
+  ///
+  /// Show information about chapters stored in the format. When deserialized
+  /// with [`run()`](FfprobeCommand::run), they are available in
+  /// [`FfprobeOutput::chapters`].
+  pub fn show_chapters(&mut self) -> &mut Self {
+    self.arg("-show_chapters");
+    self
+  }
+
+  /// Alias for `-show_frames` argument.
+  ///
+  /// Show information about each frame and subtitle contained in the input
+  /// multimedia stream. Combine with
+  /// [`select_streams()`](FfprobeCommand::select_streams) to limit the output
+  /// to a single stream.
+  pub fn show_frames(&mut self) -> &mut Self {
+    self.arg("-show_frames");
+    self
+  }
+
+  //// Run and deserialize
+
+  /// Run ffprobe to completion with the JSON writer and deserialize the
+  /// sections it printed.
+  ///
+  /// `-print_format json` is appended automatically, overriding any earlier
+  /// `print_format()` call. Only the sections requested with methods like
+  /// [`show_streams()`](FfprobeCommand::show_streams) will be populated.
+  ///
+  /// Returns an error including ffprobe's stderr if it exits with a non-zero
+  /// status, such as for a missing or unreadable input, instead of trying to
+  /// parse its empty or partial output.
+  pub fn run(&mut self) -> anyhow::Result<FfprobeOutput> {
+    self.print_format_typed(OutputFormat::Json);
+    let output = self.run_to_string()?;
+    serde_json::from_str(&output).context("Failed to parse ffprobe JSON output")
+  }
+
+  /// Run ffprobe to completion with the JSON writer and parse its output
+  /// without deserializing into any struct, for fields that
+  /// [`FfprobeOutput`] doesn't cover.
+  ///
+  /// `-print_format json` is appended automatically, like in
+  /// [`run()`](FfprobeCommand::run). Returns an error including ffprobe's
+  /// stderr if it exits with a non-zero status.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::ffprobe::FfprobeCommand;
+  ///
+  /// let json = FfprobeCommand::new()
+  ///   .show_format()
+  ///   .input("input.mp4")
+  ///   .run_json_value()
+  ///   .unwrap();
+  /// println!("{}", json["format"]["probe_score"]);
+  /// ```
+  pub fn run_json_value(&mut self) -> anyhow::Result<serde_json::Value> {
+    self.print_format_typed(OutputFormat::Json);
+    let output = self.run_to_string()?;
+    serde_json::from_str(&output).context("Failed to parse ffprobe JSON output")
+  }
+
+  /// Spawn ffprobe with `-show_frames -print_format json` and iterate over the
+  /// decoded frames as they are printed, without waiting for the whole input
+  /// to be read.
+  ///
+  /// The JSON output is parsed incrementally on a background thread. The
+  /// iterator ends when ffprobe finishes, or early if its output can't be
+  /// parsed. Dropping the iterator before the end kills the ffprobe process.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::ffprobe::FfprobeCommand;
+  ///
+  /// let keyframes = FfprobeCommand::new()
+  ///   .select_streams("v:0")
+  ///   .input("input.mp4")
+  ///   .frames()
+  ///   .unwrap()
+  ///   .filter(|frame| frame.key_frame)
+  ///   .count();
+  /// ```
+  pub fn frames(&mut self) -> anyhow::Result<impl Iterator<Item = FfprobeFrame>> {
+    self.show_frames();
+    self.print_format_typed(OutputFormat::Json);
+    let mut child = self
+      .inner
+      .stdin(Stdio::null())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::null())
+      .spawn()
+      .context("Failed to spawn ffprobe")?;
+    let stdout = child
+      .stdout
+      .take()
+      .context("Failed to take ffprobe stdout")?;
+
+    let (tx, rx) = sync_channel::<FfprobeFrame>(0);
+    spawn(move || {
+      let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(stdout));
+      deserializer
+        .deserialize_map(FramesVisitor(|frame| tx.send(frame).is_ok()))
+        .ok();
+      child.kill().ok();
+      child.wait().ok();
+    });
+
+    Ok(rx.into_iter())
+  }
+
+  /// Run ffprobe to completion and return its stdout as a string, or an error
+  /// containing its stderr if it exits with a non-zero status.
+  fn run_to_string(&mut self) -> anyhow::Result<String> {
+    let output = self
+      .inner
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .output()
+      .context("Failed to run ffprobe")?;
+    if !output.status.success() {
+      anyhow::bail!(
+        "ffprobe exited with {}: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr).trim()
+      );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+  }
+
   //// `std::process::Command` passthrough methods
 
   ///
@@ -154,4 +631,612 @@ impl FfprobeCommand {
     }
     self
   }
-}
\ No newline at end of file
+
+  /// Sets an environment variable for the ffprobe process, such as
+  /// `CUDA_VISIBLE_DEVICES` or `AV_LOG_FORCE_COLOR`.
+  ///
+  /// Identical to `env` in [`std::process::Command`].
+  pub fn env<K, V>(&mut self, key: K, value: V) -> &mut Self
+  where
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+  {
+    self.inner.env(key, value);
+    self
+  }
+
+  /// Sets multiple environment variables for the ffprobe process.
+  ///
+  /// Identical to `envs` in [`std::process::Command`].
+  pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Self
+  where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+  {
+    self.inner.envs(vars);
+    self
+  }
+
+  /// Sets the working directory of the ffprobe process.
+  ///
+  /// Identical to `current_dir` in [`std::process::Command`].
+  pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+    self.inner.current_dir(dir);
+    self
+  }
+
+  /// Spawn ffprobe with stdout piped, leaving the output for the caller to
+  /// read and parse through [`FfprobeChild::take_stdout`].
+  ///
+  /// Stderr is inherited by default so that error diagnostics still reach the
+  /// terminal. To capture them instead, set
+  /// `as_inner_mut().stderr(Stdio::piped())` before spawning and read them with
+  /// [`FfprobeChild::take_stderr`].
+  ///
+  /// Please note that if the result is not used with
+  /// [`wait()`](FfprobeChild::wait) the process is not cleaned up correctly
+  /// resulting in a zombie process until your main thread exits.
+  pub fn spawn(&mut self) -> anyhow::Result<FfprobeChild> {
+    self.create_no_window();
+    let inner = self
+      .inner
+      .stdout(Stdio::piped())
+      .spawn()
+      .context("Failed to spawn ffprobe")?;
+    Ok(FfprobeChild { inner })
+  }
+
+  /// Disable creating a new console window for the spawned process on Windows.
+  /// Has no effect on other platforms. This can be useful when spawning a command
+  /// from a GUI program.
+  ///
+  /// This is called automatically in the constructor. To override, use
+  /// `CommandExt::creation_flags()` directly on the inner `Command`.
+  pub fn create_no_window(&mut self) -> &mut Self {
+    self.as_inner_mut().create_no_window();
+    self
+  }
+
+  //// Constructors
+  pub fn new() -> Self {
+    Self::new_with_path(ffprobe_path())
+  }
+
+  pub fn new_with_path<S: AsRef<OsStr>>(path_to_ffprobe_binary: S) -> Self {
+    let mut ffprobe_command = Self {
+      inner: Command::new(&path_to_ffprobe_binary),
+    };
+    ffprobe_command.create_no_window();
+    ffprobe_command
+  }
+
+  //// Escape hatches
+
+  /// Escape hatch to access the inner `Command`.
+  pub fn as_inner(&mut self) -> &Command {
+    &self.inner
+  }
+
+  /// Escape hatch to mutably access the inner `Command`.
+  pub fn as_inner_mut(&mut self) -> &mut Command {
+    &mut self.inner
+  }
+}
+
+impl Default for FfprobeCommand {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl fmt::Debug for FfprobeCommand {
+  /// Format the program and arguments of a Command for display. Any non-utf8
+  /// data is lossily converted using the utf8 replacement character.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.inner.fmt(f)
+  }
+}
+
+impl From<Command> for FfprobeCommand {
+  /// Convert a `Command` into a `FfprobeCommand`, making no guarantees about
+  /// the validity of its configured arguments.
+  fn from(inner: Command) -> Self {
+    Self { inner }
+  }
+}
+
+impl From<FfprobeCommand> for Command {
+  fn from(val: FfprobeCommand) -> Self {
+    val.inner
+  }
+}
+
+/// A wrapper around [`std::process::Child`] containing a spawned ffprobe
+/// command, returned by [`FfprobeCommand::spawn`].
+pub struct FfprobeChild {
+  inner: Child,
+}
+
+impl FfprobeChild {
+  /// Take ownership of the process' stdout channel, where ffprobe prints the
+  /// requested sections.
+  pub fn take_stdout(&mut self) -> Option<ChildStdout> {
+    self.inner.stdout.take()
+  }
+
+  /// Take ownership of the process' stdin channel. Returns `None` unless the
+  /// command was configured with
+  /// [`input_stdin()`](FfprobeCommand::input_stdin).
+  pub fn take_stdin(&mut self) -> Option<ChildStdin> {
+    self.inner.stdin.take()
+  }
+
+  /// Take ownership of the process' stderr channel. Returns `None` unless
+  /// stderr was piped on the inner `Command` before spawning.
+  pub fn take_stderr(&mut self) -> Option<ChildStderr> {
+    self.inner.stderr.take()
+  }
+
+  /// Forcibly terminate the inner child process.
+  ///
+  /// Identical to `kill` in [`std::process::Child`].
+  pub fn kill(&mut self) -> io::Result<()> {
+    self.inner.kill()
+  }
+
+  /// Waits for the inner child process to finish execution.
+  ///
+  /// Identical to `wait` in [`std::process::Child`].
+  pub fn wait(&mut self) -> io::Result<ExitStatus> {
+    self.inner.wait()
+  }
+
+  /// Escape hatch to access the inner `Child`.
+  pub fn as_inner(&mut self) -> &Child {
+    &self.inner
+  }
+
+  /// Escape hatch to mutably access the inner `Child`.
+  pub fn as_inner_mut(&mut self) -> &mut Child {
+    &mut self.inner
+  }
+}
+
+/// The writers available to `-print_format`.
+///
+/// <https://ffmpeg.org/ffprobe.html#Writers>
+///
+/// ```rust
+/// use ffmpeg_sidecar::ffprobe::OutputFormat;
+/// assert!(OutputFormat::Json.to_string() == "json");
+/// assert!(OutputFormat::Default.to_string() == "default");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+  /// Prints each section in the form `[SECTION] key=value [/SECTION]`
+  Default,
+  Json,
+  Csv,
+  Flat,
+  Ini,
+  Xml,
+}
+
+impl fmt::Display for OutputFormat {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let name = match self {
+      OutputFormat::Default => "default",
+      OutputFormat::Json => "json",
+      OutputFormat::Csv => "csv",
+      OutputFormat::Flat => "flat",
+      OutputFormat::Ini => "ini",
+      OutputFormat::Xml => "xml",
+    };
+    f.write_str(name)
+  }
+}
+
+/// Builder for the `section=entry,entry:section=entry` syntax of
+/// [`FfprobeCommand::show_entries`], selecting only the fields to print, e.g.
+/// to avoid printing every stream of a huge file. Entries added to the same
+/// section more than once are merged.
+///
+/// ```rust
+/// use ffmpeg_sidecar::ffprobe::EntrySelector;
+/// let mut entries = EntrySelector::new();
+/// entries
+///   .stream(["width", "height"])
+///   .format(["duration"])
+///   .stream(["codec_name"])
+///   .section("stream_tags", ["language"]);
+/// assert!(entries.to_string() == "stream=width,height,codec_name:format=duration:stream_tags=language");
+///
+/// let mut all = EntrySelector::new();
+/// all.section("format", [] as [&str; 0]);
+/// assert!(all.to_string() == "format");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntrySelector {
+  sections: Vec<(String, Vec<String>)>,
+}
+
+impl EntrySelector {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Select `entries` of the section named `section`, like `stream`,
+  /// `format` or `stream_tags`. No entries selects all of them.
+  ///
+  /// ## Panics
+  ///
+  /// Panics if a name contains one of the separators `=`, `,` or `:`.
+  pub fn section<S, I, E>(&mut self, section: S, entries: I) -> &mut Self
+  where
+    S: AsRef<str>,
+    I: IntoIterator<Item = E>,
+    E: AsRef<str>,
+  {
+    let section = section.as_ref();
+    let entries: Vec<String> = entries
+      .into_iter()
+      .map(|entry| entry.as_ref().to_string())
+      .collect();
+    for name in entries.iter().map(String::as_str).chain([section]) {
+      assert!(
+        !name.contains(['=', ',', ':']),
+        "Invalid ffprobe entry name: {name:?}"
+      );
+    }
+
+    match self.sections.iter_mut().find(|(name, _)| name == section) {
+      Some((_, existing)) => existing.extend(entries),
+      None => self.sections.push((section.to_string(), entries)),
+    }
+    self
+  }
+
+  /// Select entries of each stream, like `width` or `codec_name`.
+  pub fn stream<I: IntoIterator<Item = E>, E: AsRef<str>>(&mut self, entries: I) -> &mut Self {
+    self.section("stream", entries)
+  }
+
+  /// Select entries of the container format, like `duration` or `bit_rate`.
+  pub fn format<I: IntoIterator<Item = E>, E: AsRef<str>>(&mut self, entries: I) -> &mut Self {
+    self.section("format", entries)
+  }
+}
+
+impl fmt::Display for EntrySelector {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (i, (section, entries)) in self.sections.iter().enumerate() {
+      if i > 0 {
+        f.write_str(":")?;
+      }
+      f.write_str(section)?;
+      if !entries.is_empty() {
+        write!(f, "={}", entries.join(","))?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// The deserialized JSON output of an ffprobe command. Each field corresponds
+/// to one of the `-show_*` sections, and is left empty unless that section
+/// was requested.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct FfprobeOutput {
+  /// Populated by `-show_streams`.
+  #[serde(default)]
+  pub streams: Vec<FfprobeStream>,
+  /// Populated by `-show_format`.
+  pub format: Option<FfprobeFormat>,
+  /// Populated by `-show_frames`.
+  #[serde(default)]
+  pub frames: Vec<FfprobeFrame>,
+  /// Populated by `-show_chapters`.
+  #[serde(default)]
+  pub chapters: Vec<FfprobeChapter>,
+}
+
+/// Information about a single stream, as printed by `-show_streams`.
+///
+/// Only the most common fields are included. Numeric fields which ffprobe
+/// prints as strings (like `bit_rate`) are parsed, and are `None` when missing
+/// or `N/A`.
+///
+/// ## Example
+///
+/// ```rust
+/// use ffmpeg_sidecar::ffprobe::FfprobeStream;
+///
+/// let json = r#"{
+///   "index": 0,
+///   "codec_name": "h264",
+///   "codec_type": "video",
+///   "width": 320,
+///   "height": 240,
+///   "pix_fmt": "yuv444p",
+///   "r_frame_rate": "25/1",
+///   "bit_rate": "37980",
+///   "duration": "5.000000",
+///   "tags": { "language": "und", "handler_name": "VideoHandler" }
+/// }"#;
+/// let stream: FfprobeStream = serde_json::from_str(json).unwrap();
+/// assert!(stream.codec_name.as_deref() == Some("h264"));
+/// assert!(stream.width == Some(320));
+/// assert!(stream.bit_rate == Some(37980));
+/// assert!(stream.duration == Some(5.0));
+/// assert!(stream.tags["language"] == "und");
+///
+/// let audio: FfprobeStream = serde_json::from_str(r#"{ "index": 1, "bit_rate": "N/A" }"#).unwrap();
+/// assert!(audio.width.is_none());
+/// assert!(audio.bit_rate.is_none());
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct FfprobeStream {
+  /// The index of the stream inside the input. Missing, and `0`, if not
+  /// selected with `show_entries`.
+  #[serde(default)]
+  pub index: u32,
+  /// Short codec name, e.g. `h264`, `aac` or `subrip`.
+  pub codec_name: Option<String>,
+  /// One of `video`, `audio`, `subtitle`, `data` or `attachment`.
+  pub codec_type: Option<String>,
+  /// Width in pixels (video only)
+  pub width: Option<u32>,
+  /// Height in pixels (video only)
+  pub height: Option<u32>,
+  /// Pixel format, e.g. `yuv420p` (video only)
+  pub pix_fmt: Option<String>,
+  /// Real base framerate as a fraction, e.g. `30000/1001`
+  pub r_frame_rate: Option<String>,
+  /// Bitrate in bits per second
+  #[serde(default, deserialize_with = "deserialize_lenient")]
+  pub bit_rate: Option<u64>,
+  /// Duration in seconds
+  #[serde(default, deserialize_with = "deserialize_lenient")]
+  pub duration: Option<f64>,
+  /// Stream tags such as `language` or `title`
+  #[serde(default)]
+  pub tags: HashMap<String, String>,
+}
+
+/// Information about the container format, as printed by `-show_format`.
+///
+/// ## Example
+///
+/// ```rust
+/// use ffmpeg_sidecar::ffprobe::FfprobeFormat;
+///
+/// let json = r#"{
+///   "filename": "output/test.mp4",
+///   "nb_streams": 1,
+///   "format_name": "mov,mp4,m4a,3gp,3g2,mj2",
+///   "format_long_name": "QuickTime / MOV",
+///   "duration": "5.000000",
+///   "size": "26528",
+///   "bit_rate": "42444",
+///   "tags": { "encoder": "Lavf60.3.100" }
+/// }"#;
+/// let format: FfprobeFormat = serde_json::from_str(json).unwrap();
+/// assert!(format.format_name.as_deref() == Some("mov,mp4,m4a,3gp,3g2,mj2"));
+/// assert!(format.nb_streams == Some(1));
+/// assert!(format.duration == Some(5.0));
+/// assert!(format.size == Some(26528));
+/// assert!(format.bit_rate == Some(42444));
+/// assert!(format.tags["encoder"] == "Lavf60.3.100");
+///
+/// let live: FfprobeFormat = serde_json::from_str(r#"{ "filename": "pipe:0" }"#).unwrap();
+/// assert!(live.duration.is_none());
+/// assert!(live.size.is_none());
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct FfprobeFormat {
+  /// The input path or URL
+  pub filename: Option<String>,
+  /// Comma-separated list of matching demuxer names, e.g. `mov,mp4,m4a,3gp,3g2,mj2`
+  pub format_name: Option<String>,
+  /// Human-readable name of the container format
+  pub format_long_name: Option<String>,
+  /// Duration in seconds
+  #[serde(default, deserialize_with = "deserialize_lenient")]
+  pub duration: Option<f64>,
+  /// Size of the input in bytes
+  #[serde(default, deserialize_with = "deserialize_lenient")]
+  pub size: Option<u64>,
+  /// Overall bitrate in bits per second
+  #[serde(default, deserialize_with = "deserialize_lenient")]
+  pub bit_rate: Option<u64>,
+  /// Number of streams in the container
+  pub nb_streams: Option<u32>,
+  /// Container-level tags such as `title` or `encoder`
+  #[serde(default)]
+  pub tags: HashMap<String, String>,
+}
+
+/// A chapter marker, as printed by `-show_chapters`.
+///
+/// ffprobe prints each boundary both in ticks of `time_base` (`start`, `end`)
+/// and in seconds (`start_time`, `end_time`); only the seconds are kept.
+///
+/// ## Example
+///
+/// ```rust
+/// use ffmpeg_sidecar::ffprobe::FfprobeChapter;
+///
+/// let json = r#"{
+///   "id": 0,
+///   "time_base": "1/1000",
+///   "start": 0,
+///   "start_time": "0.000000",
+///   "end": 90500,
+///   "end_time": "90.500000",
+///   "tags": { "title": "Introduction" }
+/// }"#;
+/// let chapter: FfprobeChapter = serde_json::from_str(json).unwrap();
+/// assert!(chapter.id == 0);
+/// assert!(chapter.start_time == Some(0.0));
+/// assert!(chapter.end_time == Some(90.5));
+/// assert!(chapter.title() == Some("Introduction"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct FfprobeChapter {
+  /// Identifier of the chapter, unique within the file but not necessarily
+  /// sequential.
+  pub id: i64,
+  /// Start of the chapter in seconds
+  #[serde(default, deserialize_with = "deserialize_lenient")]
+  pub start_time: Option<f64>,
+  /// End of the chapter in seconds
+  #[serde(default, deserialize_with = "deserialize_lenient")]
+  pub end_time: Option<f64>,
+  /// Chapter tags, usually including `title`
+  #[serde(default)]
+  pub tags: HashMap<String, String>,
+}
+
+impl FfprobeChapter {
+  /// The `title` tag of the chapter, if set.
+  pub fn title(&self) -> Option<&str> {
+    self.tags.get("title").map(String::as_str)
+  }
+}
+
+/// Information about a single decoded frame, as printed by `-show_frames`.
+///
+/// Timestamps are in seconds and are `None` when missing or `N/A`. Newer
+/// versions of ffprobe print `pts_time` and `duration_time` instead of the
+/// older `pkt_pts_time` and `pkt_duration_time`, so both spellings are kept.
+///
+/// ## Example
+///
+/// ```rust
+/// use ffmpeg_sidecar::ffprobe::FfprobeFrame;
+///
+/// let json = r#"{
+///   "media_type": "video",
+///   "stream_index": 0,
+///   "key_frame": 1,
+///   "pts_time": "0.040000",
+///   "pkt_dts_time": "N/A",
+///   "duration_time": "0.040000",
+///   "pict_type": "I"
+/// }"#;
+/// let frame: FfprobeFrame = serde_json::from_str(json).unwrap();
+/// assert!(frame.media_type == "video");
+/// assert!(frame.key_frame);
+/// assert!(frame.pts_time == Some(0.04));
+/// assert!(frame.pkt_dts_time.is_none());
+/// assert!(frame.pict_type.as_deref() == Some("I"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct FfprobeFrame {
+  /// `video`, `audio` or `subtitle`
+  #[serde(default)]
+  pub media_type: String,
+  /// Index of the stream this frame belongs to
+  #[serde(default)]
+  pub stream_index: u32,
+  /// Whether this is a keyframe
+  #[serde(default, deserialize_with = "deserialize_flag")]
+  pub key_frame: bool,
+  /// Presentation timestamp in seconds (FFmpeg 5.0 and later)
+  #[serde(default, deserialize_with = "deserialize_lenient")]
+  pub pts_time: Option<f64>,
+  /// Presentation timestamp in seconds (before FFmpeg 5.0)
+  #[serde(default, deserialize_with = "deserialize_lenient")]
+  pub pkt_pts_time: Option<f64>,
+  /// Decoding timestamp in seconds
+  #[serde(default, deserialize_with = "deserialize_lenient")]
+  pub pkt_dts_time: Option<f64>,
+  /// Frame duration in seconds (FFmpeg 6.0 and later)
+  #[serde(default, deserialize_with = "deserialize_lenient")]
+  pub duration_time: Option<f64>,
+  /// Frame duration in seconds (before FFmpeg 6.0)
+  #[serde(default, deserialize_with = "deserialize_lenient")]
+  pub pkt_duration_time: Option<f64>,
+  /// Picture type of video frames, e.g. `I`, `P` or `B`
+  pub pict_type: Option<String>,
+}
+
+/// Walks the top-level JSON object printed by ffprobe, handing each element of
+/// the `frames` array to the callback as soon as it has been read, and
+/// skipping every other section. The callback returns `false` to stop early.
+struct FramesVisitor<F>(F);
+
+impl<'de, F: FnMut(FfprobeFrame) -> bool> Visitor<'de> for FramesVisitor<F> {
+  type Value = ();
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("an ffprobe JSON object")
+  }
+
+  fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<(), A::Error> {
+    while let Some(key) = map.next_key::<String>()? {
+      if key == "frames" {
+        map.next_value_seed(FramesSeed(&mut self.0))?;
+      } else {
+        map.next_value::<IgnoredAny>()?;
+      }
+    }
+    Ok(())
+  }
+}
+
+struct FramesSeed<'a, F>(&'a mut F);
+
+impl<'de, F: FnMut(FfprobeFrame) -> bool> DeserializeSeed<'de> for FramesSeed<'_, F> {
+  type Value = ();
+
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+    deserializer.deserialize_seq(self)
+  }
+}
+
+impl<'de, F: FnMut(FfprobeFrame) -> bool> Visitor<'de> for FramesSeed<'_, F> {
+  type Value = ();
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("an array of frames")
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+    while let Some(frame) = seq.next_element::<FfprobeFrame>()? {
+      if !(self.0)(frame) {
+        return Err(de::Error::custom("frame receiver was dropped"));
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Deserializes a flag that ffprobe prints as `0` or `1`.
+fn deserialize_flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+  Ok(u8::deserialize(deserializer)? != 0)
+}
+
+/// Deserializes a value that ffprobe may print either as a JSON number or as a
+/// string, treating unparsable strings like `N/A` as `None`.
+fn deserialize_lenient<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+  D: Deserializer<'de>,
+  T: FromStr + Deserialize<'de>,
+{
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum StringOrNumber<T> {
+    Number(T),
+    String(String),
+  }
+
+  Ok(
+    match Option::<StringOrNumber<T>>::deserialize(deserializer)? {
+      Some(StringOrNumber::Number(n)) => Some(n),
+      Some(StringOrNumber::String(s)) => s.trim().parse().ok(),
+      None => None,
+    },
+  )
+}
@@ -0,0 +1,128 @@
+//! Builder for `-filter_complex` filtergraph descriptions.
+
+use std::fmt;
+
+/// A fluent builder for the filtergraph syntax described in the ffmpeg-filters
+/// manual: <https://ffmpeg.org/ffmpeg-filters.html#Filtergraph-syntax-1>.
+///
+/// Filters added one after another are linked into a chain (`a,b`). Labelled
+/// pads are attached with `input` (before a filter) and `output` (after it);
+/// a filter added after an `output` label starts a new chain (`a[x];[x]b`).
+///
+/// ```rust
+/// use ffmpeg_sidecar::{command::FfmpegCommand, filter_graph::FilterGraph};
+/// let mut graph = FilterGraph::new();
+/// graph
+///   .input("0:v")
+///   .scale(1280, 720)
+///   .output("scaled")
+///   .input("scaled")
+///   .input("1:v")
+///   .overlay(10, 10)
+///   .output("out");
+/// assert!(graph.to_string() == "[0:v]scale=1280:720[scaled];[scaled][1:v]overlay=10:10[out]");
+///
+/// FfmpegCommand::new()
+///   .input("video.mp4")
+///   .input("logo.png")
+///   .filter_complex(&graph)
+///   .map("[out]")
+///   .output("output.mp4");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterGraph {
+  graph: String,
+  pending_inputs: Vec<String>,
+  chain_closed: bool,
+}
+
+impl FilterGraph {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Attach a labelled input pad, like `0:v` or a label from an earlier
+  /// `output`, to the next filter. Surrounding brackets are optional.
+  pub fn input<S: AsRef<str>>(&mut self, label: S) -> &mut Self {
+    self
+      .pending_inputs
+      .push(strip_brackets(label.as_ref()).to_string());
+    self
+  }
+
+  /// Label the output pad of the previous filter, so it can be referenced by a
+  /// later `input` or with `-map "[label]"`. Call repeatedly for filters with
+  /// several outputs, like `split`.
+  pub fn output<S: AsRef<str>>(&mut self, label: S) -> &mut Self {
+    self.graph.push('[');
+    self.graph.push_str(strip_brackets(label.as_ref()));
+    self.graph.push(']');
+    self.chain_closed = true;
+    self
+  }
+
+  /// Add an arbitrary filter, e.g. `filter("hflip", "")` or
+  /// `filter("fade", "t=in:st=0:d=1")`. An empty `args` omits the `=`.
+  pub fn filter<S: AsRef<str>, T: AsRef<str>>(&mut self, name: S, args: T) -> &mut Self {
+    if !self.graph.is_empty() {
+      self.graph.push(if self.chain_closed { ';' } else { ',' });
+    }
+    self.chain_closed = false;
+
+    for label in self.pending_inputs.drain(..) {
+      self.graph.push('[');
+      self.graph.push_str(&label);
+      self.graph.push(']');
+    }
+
+    self.graph.push_str(name.as_ref());
+    if !args.as_ref().is_empty() {
+      self.graph.push('=');
+      self.graph.push_str(args.as_ref());
+    }
+    self
+  }
+
+  /// The `scale` filter. Use `-1` for either dimension to preserve the aspect
+  /// ratio, or `-2` to also keep it divisible by 2.
+  pub fn scale(&mut self, width: i32, height: i32) -> &mut Self {
+    self.filter("scale", format!("{width}:{height}"))
+  }
+
+  /// The `overlay` filter, drawing the second input on top of the first at
+  /// the given position.
+  pub fn overlay(&mut self, x: i32, y: i32) -> &mut Self {
+    self.filter("overlay", format!("{x}:{y}"))
+  }
+
+  /// The `concat` filter, joining `segments` consecutive groups of inputs,
+  /// each with `video_streams` video and `audio_streams` audio streams.
+  pub fn concat(&mut self, segments: u32, video_streams: u32, audio_streams: u32) -> &mut Self {
+    self.filter(
+      "concat",
+      format!("n={segments}:v={video_streams}:a={audio_streams}"),
+    )
+  }
+
+  /// The `split` filter, duplicating its input into `outputs` identical
+  /// streams. Follow it with one `output` call per stream.
+  pub fn split(&mut self, outputs: u32) -> &mut Self {
+    self.filter("split", outputs.to_string())
+  }
+}
+
+impl AsRef<str> for FilterGraph {
+  fn as_ref(&self) -> &str {
+    &self.graph
+  }
+}
+
+impl fmt::Display for FilterGraph {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.graph)
+  }
+}
+
+fn strip_brackets(label: &str) -> &str {
+  label.trim().trim_start_matches('[').trim_end_matches(']')
+}
@@ -0,0 +1,90 @@
+//! Reading raw video frames from outputs other than stdout.
+
+use std::io::{self, ErrorKind, Read};
+
+use anyhow::Context;
+
+use crate::{
+  event::{OutputVideoFrame, Stream},
+  pix_fmt::bytes_per_frame,
+};
+
+/// Reads the frames of a single `rawvideo` output stream, one
+/// [`OutputVideoFrame`] at a time.
+///
+/// FFmpeg can only interleave outputs on stdout if their framerates match.
+/// Outputs of a filtergraph with their own sizes and rates, such as several
+/// thumbnail sizes made in one pass, can instead each be written to a
+/// named pipe (see `NamedPipe` with the `named_pipes` feature) or socket, and
+/// read with a `FrameReader` for the matching stream from
+/// [`FfmpegMetadata::output_streams`](crate::metadata::FfmpegMetadata::output_streams).
+/// Frames carry the `parent_index` and `stream_index` of that stream.
+///
+/// A partial frame at the end of the input is discarded.
+///
+/// ```rust
+/// use ffmpeg_sidecar::{frame_reader::FrameReader, log_parser::try_parse_stream};
+/// let line = "[info]   Stream #1:0: Video: rawvideo (RGB[24] / 0x18424752), rgb24(progressive), 2x1, q=2-31, 25 fps, 25 tbn\n";
+/// let stream = try_parse_stream(line).unwrap();
+/// let pixels = [0u8; 2 * 6 + 1];
+/// let frames: Vec<_> = FrameReader::new(pixels.as_slice(), &stream)
+///   .unwrap()
+///   .collect::<Result<_, _>>()
+///   .unwrap();
+/// assert!(frames.len() == 2);
+/// assert!(frames[1].parent_index == 1 && frames[1].frame_num == 1);
+/// assert!(frames[1].timestamp == 0.04);
+/// ```
+pub struct FrameReader<R: Read> {
+  reader: R,
+  stream: Stream,
+  frame_size: usize,
+  frame_num: u32,
+}
+
+impl<R: Read> FrameReader<R> {
+  /// Returns `Err` if `stream` isn't a `rawvideo` stream with a known pixel
+  /// format.
+  pub fn new(reader: R, stream: &Stream) -> anyhow::Result<Self> {
+    let video_data = stream
+      .video_data()
+      .filter(|_| stream.format == "rawvideo")
+      .with_context(|| format!("Not a rawvideo stream: {}", stream.raw_log_message.trim()))?;
+    let frame_size = bytes_per_frame(&video_data.pix_fmt, video_data.width, video_data.height)
+      .with_context(|| format!("Unsupported pixel format: {}", video_data.pix_fmt))?;
+    Ok(Self {
+      reader,
+      stream: stream.clone(),
+      frame_size,
+      frame_num: 0,
+    })
+  }
+}
+
+impl<R: Read> Iterator for FrameReader<R> {
+  type Item = io::Result<OutputVideoFrame>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let mut data = vec![0; self.frame_size];
+    match self.reader.read_exact(&mut data) {
+      Ok(()) => {
+        let video_data = self.stream.video_data()?;
+        let frame = OutputVideoFrame {
+          width: video_data.width,
+          height: video_data.height,
+          pix_fmt: video_data.pix_fmt.clone(),
+          output_index: 0,
+          parent_index: self.stream.parent_index,
+          stream_index: self.stream.stream_index,
+          data,
+          frame_num: self.frame_num,
+          timestamp: self.frame_num as f32 / video_data.fps,
+        };
+        self.frame_num += 1;
+        Some(Ok(frame))
+      }
+      Err(e) if e.kind() == ErrorKind::UnexpectedEof => None,
+      Err(e) => Some(Err(e)),
+    }
+  }
+}
@@ -0,0 +1,77 @@
+//! Options for HLS (HTTP Live Streaming) output.
+
+use std::time::Duration;
+
+/// Configuration for [`FfmpegCommand::hls_output`](crate::command::FfmpegCommand::hls_output).
+/// See <https://ffmpeg.org/ffmpeg-formats.html#hls-2> for more details.
+///
+/// ```rust
+/// use ffmpeg_sidecar::{
+///   command::FfmpegCommand,
+///   hls::{HlsOptions, HlsPlaylistType},
+/// };
+/// use std::time::Duration;
+///
+/// let mut command = FfmpegCommand::new();
+/// command.input("input.mp4").hls_output(
+///   "output/stream.m3u8",
+///   HlsOptions {
+///     segment_duration: Duration::from_secs(6),
+///     playlist_type: Some(HlsPlaylistType::Vod),
+///     segment_filename_template: Some("output/segment_%03d.ts".to_string()),
+///     list_size: Some(0),
+///   },
+/// );
+/// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+/// assert!(args.ends_with(&[
+///   "-f", "hls", "-hls_time", "6", "-hls_playlist_type", "vod",
+///   "-hls_segment_filename", "output/segment_%03d.ts", "-hls_list_size", "0",
+///   "output/stream.m3u8",
+/// ]));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HlsOptions {
+  /// Target duration of each segment, `-hls_time`. Segments are cut on the
+  /// next keyframe after this duration, so the actual length depends on the
+  /// encoder's keyframe interval. Defaults to 2 seconds.
+  pub segment_duration: Duration,
+  /// `-hls_playlist_type`. If `None`, the playlist is a live sliding window.
+  pub playlist_type: Option<HlsPlaylistType>,
+  /// `-hls_segment_filename`, a `printf`-style template such as
+  /// `segment_%03d.ts`. If `None`, segments are named after the playlist.
+  pub segment_filename_template: Option<String>,
+  /// `-hls_list_size`, the maximum number of entries in the playlist, with
+  /// `0` keeping all of them. If `None`, FFmpeg's default of 5 applies.
+  pub list_size: Option<u32>,
+}
+
+impl Default for HlsOptions {
+  fn default() -> Self {
+    Self {
+      segment_duration: Duration::from_secs(2),
+      playlist_type: None,
+      segment_filename_template: None,
+      list_size: None,
+    }
+  }
+}
+
+/// Values of the `-hls_playlist_type` option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HlsPlaylistType {
+  /// Segments are only appended to the playlist, which is finalized when the
+  /// encode finishes. Implies a `list_size` of 0.
+  Event,
+  /// A complete playlist for video on demand, which never changes once
+  /// written. Implies a `list_size` of 0.
+  Vod,
+}
+
+impl AsRef<str> for HlsPlaylistType {
+  fn as_ref(&self) -> &str {
+    match self {
+      HlsPlaylistType::Event => "event",
+      HlsPlaylistType::Vod => "vod",
+    }
+  }
+}
@@ -0,0 +1,112 @@
+//! Options scoped to a single input, for
+//! [`FfmpegCommand::input_with`](crate::command::FfmpegCommand::input_with).
+
+use crate::command::TimeArg;
+
+/// Input options, like `-ss` or `-hwaccel`, which are written right before
+/// the `-i` of the input they belong to.
+///
+/// FFmpeg applies an input option to the next `-i` on the command line, so the
+/// plain builder methods on `FfmpegCommand` already work when called in the
+/// right order. Collecting them in a closure makes the association explicit,
+/// and keeps them from leaking onto the wrong input as a command grows.
+///
+/// ```rust
+/// use ffmpeg_sidecar::command::FfmpegCommand;
+/// use std::time::Duration;
+/// let mut command = FfmpegCommand::new();
+/// command
+///   .input_with("main.mp4", |i| i.seek(Duration::from_secs(30)).hwaccel("cuda"))
+///   .input_with("logo.png", |i| i.loop_input(-1))
+///   .output("output.mp4");
+/// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+/// assert!(args.ends_with(&[
+///   "-ss", "00:00:30.000", "-hwaccel", "cuda", "-i", "main.mp4",
+///   "-stream_loop", "-1", "-i", "logo.png",
+///   "output.mp4",
+/// ]));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputOptions {
+  args: Vec<String>,
+}
+
+impl InputOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Alias for `-ss`: seek to `position` in this input, landing on the
+  /// nearest keyframe before it.
+  pub fn seek<T: TimeArg>(&mut self, position: T) -> &mut Self {
+    self.arg("-ss");
+    self.arg(position.to_time_arg())
+  }
+
+  /// Alias for `-t`: limit the duration of data read from this input.
+  pub fn duration<T: TimeArg>(&mut self, duration: T) -> &mut Self {
+    self.arg("-t");
+    self.arg(duration.to_time_arg())
+  }
+
+  /// Alias for `-to`: stop reading this input at `position`.
+  pub fn to<T: TimeArg>(&mut self, position: T) -> &mut Self {
+    self.arg("-to");
+    self.arg(position.to_time_arg())
+  }
+
+  /// Alias for `-re`: read this input at its native frame rate.
+  pub fn realtime(&mut self) -> &mut Self {
+    self.arg("-re")
+  }
+
+  /// Alias for `-readrate`: read this input at `speed` times its native
+  /// frame rate.
+  pub fn readrate(&mut self, speed: f32) -> &mut Self {
+    self.arg("-readrate");
+    self.arg(speed.to_string())
+  }
+
+  /// Alias for `-stream_loop`: loop this input `count` more times, or forever
+  /// with `-1`.
+  pub fn loop_input(&mut self, count: i32) -> &mut Self {
+    self.arg("-stream_loop");
+    self.arg(count.to_string())
+  }
+
+  /// Alias for `-hwaccel`: decode this input with a hardware accelerator.
+  pub fn hwaccel<S: AsRef<str>>(&mut self, hwaccel: S) -> &mut Self {
+    self.arg("-hwaccel");
+    self.arg(hwaccel)
+  }
+
+  /// Alias for `-hwaccel_output_format`.
+  pub fn hwaccel_output_format<S: AsRef<str>>(&mut self, format: S) -> &mut Self {
+    self.arg("-hwaccel_output_format");
+    self.arg(format)
+  }
+
+  /// Alias for `-f`: force the demuxer of this input, e.g. for raw data
+  /// without a header.
+  pub fn format<S: AsRef<str>>(&mut self, format: S) -> &mut Self {
+    self.arg("-f");
+    self.arg(format)
+  }
+
+  /// Alias for `-c:v`: force the video decoder of this input.
+  pub fn codec_video<S: AsRef<str>>(&mut self, codec: S) -> &mut Self {
+    self.arg("-c:v");
+    self.arg(codec)
+  }
+
+  /// Escape hatch for any other input option, written as is.
+  pub fn arg<S: AsRef<str>>(&mut self, arg: S) -> &mut Self {
+    self.args.push(arg.as_ref().to_string());
+    self
+  }
+
+  /// The options in the order they were added.
+  pub fn get_args(&self) -> &[String] {
+    &self.args
+  }
+}
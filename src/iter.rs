@@ -1,41 +1,59 @@
 //! A stream of events from an FFmpeg process.
 
 use std::{
-  io::{BufReader, ErrorKind, Read},
+  collections::VecDeque,
+  io::{BufRead, BufReader, ErrorKind, Read},
   process::{ChildStderr, ChildStdout},
-  sync::mpsc::{sync_channel, Receiver, SyncSender},
+  sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender},
   thread::JoinHandle,
+  time::{Duration, Instant},
 };
 
 use anyhow::Context;
 
 use crate::{
-  child::FfmpegChild,
-  event::{FfmpegEvent, FfmpegOutput, FfmpegProgress, LogLevel, OutputVideoFrame, Stream},
-  log_parser::FfmpegLogParser,
+  child::{FfmpegChild, ProgressPipe},
+  event::{
+    FfmpegEvent, FfmpegLogWarning, FfmpegOutput, FfmpegProgress, LogLevel, OutputVideoFrame, Stream,
+  },
+  log_parser::{try_parse_progress_block, FfmpegLogParser},
   metadata::FfmpegMetadata,
-  pix_fmt::get_bytes_per_frame,
+  pix_fmt::bytes_per_frame,
 };
 
 /// An iterator over events from an ffmpeg process, including parsed metadata, progress, and raw video frames.
+///
+/// The iterator doesn't own the process, so it can't stop it. Idle and total
+/// timeouts are set when creating the iterator instead, with
+/// [`FfmpegChild::iter_with_timeout`] or
+/// [`CancellableIterator::with_timeout`](crate::cancel::CancellableIterator::with_timeout),
+/// which send `q` and then kill ffmpeg once a timeout is exceeded.
 pub struct FfmpegIterator {
   rx: Receiver<FfmpegEvent>,
   tx: Option<SyncSender<FfmpegEvent>>,
   stdout: Option<ChildStdout>,
+  stdout_buffer_size: Option<usize>,
   metadata: FfmpegMetadata,
 }
 
 impl FfmpegIterator {
   pub fn new(child: &mut FfmpegChild) -> anyhow::Result<Self> {
-    let stderr = child.take_stderr().context("No stderr channel\n - Did you call `take_stderr` elsewhere?\n - Did you forget to call `.stderr(Stdio::piped)` on the `ChildProcess`?")?;
+    let stderr = child.take_stderr_reader().context("No stderr channel\n - Did you call `take_stderr` elsewhere?\n - Did you forget to call `.stderr(Stdio::piped)` on the `ChildProcess`?")?;
     let (tx, rx) = sync_channel::<FfmpegEvent>(0);
-    spawn_stderr_thread(stderr, tx.clone());
-    let stdout = child.take_stdout();
+    let mut stdout = child.take_stdout();
+    let progress_pipe = child.progress_pipe();
+    spawn_stderr_thread_inner(stderr, tx.clone(), progress_pipe);
+    if progress_pipe == Some(ProgressPipe::Stdout) {
+      if let Some(stdout) = stdout.take() {
+        spawn_progress_thread(stdout, tx.clone());
+      }
+    }
 
     Ok(Self {
       rx,
       tx: Some(tx),
       stdout,
+      stdout_buffer_size: child.stdout_buffer_size(),
       metadata: FfmpegMetadata::new(),
     })
   }
@@ -53,11 +71,12 @@ impl FfmpegIterator {
 
     // Handle stdout
     if let Some(stdout) = self.stdout.take() {
-      spawn_stdout_thread(
+      spawn_stdout_thread_inner(
         stdout,
         self.tx.take().context("missing channel tx")?,
         self.metadata.output_streams.clone(),
         self.metadata.outputs.clone(),
+        self.stdout_buffer_size,
       );
     }
 
@@ -76,7 +95,15 @@ impl FfmpegIterator {
           let errors = event_queue
             .iter()
             .filter_map(|e| match e {
-              FfmpegEvent::Error(e) | FfmpegEvent::Log(LogLevel::Error, e) => Some(e.to_string()),
+              FfmpegEvent::Error(e) | FfmpegEvent::Log(LogLevel::Error | LogLevel::Fatal, e) => {
+                Some(e.to_string())
+              }
+              FfmpegEvent::ParsedError(e) => Some(e.raw_log_message.clone()),
+              FfmpegEvent::ParsedWarning(w)
+                if matches!(w.level, LogLevel::Error | LogLevel::Fatal) =>
+              {
+                Some(w.raw_log_message.clone())
+              }
               _ => None,
             })
             .collect::<Vec<String>>()
@@ -92,16 +119,145 @@ impl FfmpegIterator {
     Ok(self.metadata.clone())
   }
 
+  /// Like `next()`, but giving up after `timeout`. Returns `Err` if no event
+  /// arrived in time, or `Ok` with the result of `next()` otherwise.
+  pub(crate) fn next_timeout(
+    &mut self,
+    timeout: Duration,
+  ) -> Result<Option<FfmpegEvent>, RecvTimeoutError> {
+    let item = match self.rx.recv_timeout(timeout) {
+      Ok(item) => Some(item),
+      Err(RecvTimeoutError::Disconnected) => None,
+      Err(e) => return Err(e),
+    };
+    Ok(self.handle_item(item))
+  }
+
+  /// Update the metadata with a received event, starting the stdout thread
+  /// once it's complete.
+  fn handle_item(&mut self, item: Option<FfmpegEvent>) -> Option<FfmpegEvent> {
+    if let Some(FfmpegEvent::LogEOF) = item {
+      self.tx.take(); // drop the tx so that the receiver can close
+    }
+
+    if !self.metadata.is_completed() {
+      match self.metadata.handle_event(&item) {
+        Err(e) => return Some(FfmpegEvent::Error(e.to_string())),
+        // TODO in this case, the preceding `item` is lost;
+        // Probably better to queue it as the next item.
+        Ok(()) if self.metadata.is_completed() => {
+          if let Err(e) = self.start_stdout() {
+            return Some(FfmpegEvent::Error(e.to_string()));
+            // Same problem as above
+          }
+        }
+
+        _ => {}
+      }
+    }
+
+    item
+  }
+
   //// Iterator filters
 
-  /// Returns an iterator over error messages (`FfmpegEvent::Error` and `FfmpegEvent::LogError`).
+  /// Returns an iterator over error messages (`FfmpegEvent::Error`,
+  /// `FfmpegEvent::ParsedError`, and `FfmpegEvent::Log` or
+  /// `FfmpegEvent::ParsedWarning` with `LogLevel::Error` or `LogLevel::Fatal`).
   pub fn filter_errors(self) -> impl Iterator<Item = String> {
     self.filter_map(|event| match event {
-      FfmpegEvent::Error(e) | FfmpegEvent::Log(LogLevel::Error, e) => Some(e),
+      FfmpegEvent::Error(e) | FfmpegEvent::Log(LogLevel::Error | LogLevel::Fatal, e) => Some(e),
+      FfmpegEvent::ParsedError(e) => Some(e.raw_log_message),
+      FfmpegEvent::ParsedWarning(w) if matches!(w.level, LogLevel::Error | LogLevel::Fatal) => {
+        Some(w.raw_log_message)
+      }
       _ => None,
     })
   }
 
+  /// Collapse floods of repeated warnings (`FfmpegEvent::ParsedWarning`),
+  /// like thousands of `Non-monotonic DTS` messages while remuxing. Only the
+  /// first warning of each kind is yielded as it happens; the total of every
+  /// kind logged more than once follows as a final `ParsedWarning` with its
+  /// last occurrence and `count`, right before `LogEOF`. A `Last message
+  /// repeated N times` line after a warning adds to its total instead of
+  /// being yielded as `FfmpegEvent::Repeated`. All other events pass through
+  /// unchanged.
+  ///
+  /// With `escalate_after`, a kind reaching that many warnings also yields an
+  /// `FfmpegEvent::Error`, once, so the run can be stopped early.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::{command::FfmpegCommand, event::FfmpegEvent};
+  /// let mut child = FfmpegCommand::new().input("input.ts").remux("output.mp4").spawn().unwrap();
+  /// for event in child.iter().unwrap().dedup_warnings(Some(100)) {
+  ///   match event {
+  ///     FfmpegEvent::ParsedWarning(warning) => println!("{:?} x{}", warning.kind, warning.count),
+  ///     FfmpegEvent::Error(error) => eprintln!("{error}"),
+  ///     _ => {}
+  ///   }
+  /// }
+  /// ```
+  pub fn dedup_warnings(self, escalate_after: Option<u32>) -> impl Iterator<Item = FfmpegEvent> {
+    let mut repeated: Vec<FfmpegLogWarning> = Vec::new();
+    let mut pending: VecDeque<FfmpegEvent> = VecDeque::new();
+    // The warning from the previous event, which a `Repeated` event refers to
+    let mut last_warning: Option<FfmpegLogWarning> = None;
+    let mut events = self;
+    std::iter::from_fn(move || loop {
+      if let Some(event) = pending.pop_front() {
+        return Some(event);
+      }
+      let Some(event) = events.next() else {
+        // Also flush the totals if the iterator ended without `LogEOF`
+        if repeated.is_empty() {
+          return None;
+        }
+        pending.extend(repeated.drain(..).map(FfmpegEvent::ParsedWarning));
+        continue;
+      };
+      // The parser's running counts already include any folded repeats
+      let (warning, previous_count) = match event {
+        FfmpegEvent::ParsedWarning(warning) => {
+          let previous_count = warning.count.saturating_sub(1);
+          (warning, previous_count)
+        }
+        FfmpegEvent::Repeated(folded) => match last_warning.take() {
+          Some(mut warning) => {
+            let previous_count = warning.count;
+            warning.count += folded.count;
+            (warning, previous_count)
+          }
+          None => return Some(FfmpegEvent::Repeated(folded)),
+        },
+        FfmpegEvent::LogEOF => {
+          last_warning = None;
+          pending.extend(repeated.drain(..).map(FfmpegEvent::ParsedWarning));
+          pending.push_back(FfmpegEvent::LogEOF);
+          continue;
+        }
+        event => {
+          last_warning = None;
+          return Some(event);
+        }
+      };
+      last_warning = Some(warning.clone());
+      if escalate_after.is_some_and(|n| previous_count < n && warning.count >= n) {
+        pending.push_back(FfmpegEvent::Error(format!(
+          "{} {:?} warnings logged, the last one: {}",
+          warning.count, warning.kind, warning.raw_log_message
+        )));
+      }
+      if warning.count == 1 {
+        pending.push_front(FfmpegEvent::ParsedWarning(warning));
+      } else if let Some(last) = repeated.iter_mut().find(|w| w.kind == warning.kind) {
+        *last = warning;
+      } else {
+        repeated.push(warning);
+      }
+    })
+  }
+
   /// Filter out all events except for progress (`FfmpegEvent::Progress`).
   pub fn filter_progress(self) -> impl Iterator<Item = FfmpegProgress> {
     self.filter_map(|event| match event {
@@ -110,6 +266,44 @@ impl FfmpegIterator {
     })
   }
 
+  /// Fill in the [`fraction`](FfmpegProgress::fraction) and
+  /// [`eta`](FfmpegProgress::eta) of each `FfmpegEvent::Progress`, once the
+  /// duration of the first input has been parsed. Both stay `None` while the
+  /// duration is unknown, like for live streams, and `eta` also stays `None`
+  /// until the speed can be measured. All events are yielded unchanged
+  /// otherwise.
+  ///
+  /// The ETA comes from the speed over the last few seconds, so it adapts to
+  /// parts of the input which are slower to encode than others.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::{command::FfmpegCommand, event::FfmpegEvent};
+  /// let mut child = FfmpegCommand::new().input("input.mp4").output("output.mkv").spawn().unwrap();
+  /// for event in child.iter().unwrap().with_eta() {
+  ///   if let FfmpegEvent::Progress(progress) = event {
+  ///     if let (Some(fraction), Some(eta)) = (progress.fraction, progress.eta) {
+  ///       println!("{:.0}%, {}s left", fraction * 100.0, eta.as_secs());
+  ///     }
+  ///   }
+  /// }
+  /// ```
+  pub fn with_eta(self) -> impl Iterator<Item = FfmpegEvent> {
+    let mut estimator = EtaEstimator::default();
+    self.map(move |event| match event {
+      FfmpegEvent::ParsedDuration(duration) => {
+        if estimator.duration.is_none() && duration.duration > 0.0 {
+          estimator.duration = Some(duration.duration);
+        }
+        FfmpegEvent::ParsedDuration(duration)
+      }
+      FfmpegEvent::Progress(mut progress) => {
+        estimator.annotate(&mut progress, Instant::now());
+        FfmpegEvent::Progress(progress)
+      }
+      event => event,
+    })
+  }
+
   /// Filter out all events except for output frames (`FfmpegEvent::OutputFrame`).
   pub fn filter_frames(self) -> impl Iterator<Item = OutputVideoFrame> {
     self.filter_map(|event| match event {
@@ -118,6 +312,31 @@ impl FfmpegIterator {
     })
   }
 
+  /// Like [`filter_frames`](Self::filter_frames), but only yielding frames
+  /// with the given pixel format, e.g. when a filtergraph sends several
+  /// outputs of different formats to stdout. Other frames are skipped, and the
+  /// remaining ones keep their order.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::command::FfmpegCommand;
+  /// let mut child = FfmpegCommand::new()
+  ///   .testsrc()
+  ///   .filter_complex("split[a][b];[b]format=gray[gray]")
+  ///   .map("[a]").pix_fmt("rgb24").format("rawvideo").output("-")
+  ///   .map("[gray]").pix_fmt("gray").format("rawvideo").output("-")
+  ///   .spawn()
+  ///   .unwrap();
+  /// for frame in child.iter().unwrap().frames_matching("gray") {
+  ///   assert!(frame.data.len() == (frame.width * frame.height) as usize);
+  /// }
+  /// ```
+  pub fn frames_matching(self, pix_fmt: &str) -> impl Iterator<Item = OutputVideoFrame> {
+    let pix_fmt = pix_fmt.to_string();
+    self
+      .filter_frames()
+      .filter(move |frame| frame.pix_fmt == pix_fmt)
+  }
+
   /// Filter out all events except for output chunks (`FfmpegEvent::OutputChunk`).
   pub fn filter_chunks(self) -> impl Iterator<Item = Vec<u8>> {
     self.filter_map(|event| match event {
@@ -126,56 +345,133 @@ impl FfmpegIterator {
     })
   }
 
+  /// Forward every log message to [`tracing`], while still yielding all
+  /// events unchanged.
+  ///
+  /// Messages are emitted under the `ffmpeg_sidecar::ffmpeg` target, at the
+  /// level matching their [`LogLevel`]: `info!` for info, `warn!` for
+  /// warnings, and `error!` for errors, fatal messages and
+  /// `FfmpegEvent::Error`. Lines with an unknown level go to `debug!`.
+  ///
+  /// ```rust,no_run
+  /// use ffmpeg_sidecar::{command::FfmpegCommand, event::FfmpegEvent};
+  /// let mut child = FfmpegCommand::new().testsrc().rawvideo().spawn().unwrap();
+  /// for event in child.iter().unwrap().trace() {
+  ///   if let FfmpegEvent::OutputFrame(frame) = event {
+  ///     println!("frame {}", frame.frame_num);
+  ///   }
+  /// }
+  /// ```
+  #[cfg(feature = "tracing")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+  pub fn trace(self) -> impl Iterator<Item = FfmpegEvent> {
+    self.inspect(trace_event)
+  }
+
   /// Iterator over every message from ffmpeg's stderr as a raw string.
   /// Conceptually equivalent to `BufReader::new(ffmpeg_stderr).lines()`.
   pub fn into_ffmpeg_stderr(self) -> impl Iterator<Item = String> {
     self.filter_map(|event| match event {
       FfmpegEvent::ParsedVersion(x) => Some(x.raw_log_message),
       FfmpegEvent::ParsedConfiguration(x) => Some(x.raw_log_message),
-      FfmpegEvent::ParsedStreamMapping(x) => Some(x),
+      FfmpegEvent::ParsedStreamMapping(x) => Some(x.raw_log_message),
       FfmpegEvent::ParsedOutput(x) => Some(x.raw_log_message),
       FfmpegEvent::ParsedInputStream(x) => Some(x.raw_log_message),
       FfmpegEvent::ParsedOutputStream(x) => Some(x.raw_log_message),
       FfmpegEvent::Log(_, x) => Some(x),
+      FfmpegEvent::ParsedError(x) => Some(x.raw_log_message),
+      FfmpegEvent::ParsedWarning(x) => Some(x.raw_log_message),
+      FfmpegEvent::Repeated(x) => Some(x.raw_log_message),
+      FfmpegEvent::Deprecation(x) => Some(x.raw_log_message),
       FfmpegEvent::LogEOF => None,
       FfmpegEvent::Error(_) => None,
       FfmpegEvent::Progress(x) => Some(x.raw_log_message),
+      // Same line as the preceding `Progress`
+      FfmpegEvent::Summary(_) => None,
+      FfmpegEvent::MuxingStats(x) => Some(x.raw_log_message),
+      // Parsed from the preceding `Log` lines
+      FfmpegEvent::Benchmark(_) => None,
+      FfmpegEvent::ParsedDupDrop(x) => Some(x.raw_log_message),
       FfmpegEvent::OutputFrame(_) => None,
       FfmpegEvent::OutputChunk(_) => None,
       FfmpegEvent::Done => None,
       FfmpegEvent::ParsedInput(input) => Some(input.raw_log_message),
       FfmpegEvent::ParsedDuration(duration) => Some(duration.raw_log_message),
+      FfmpegEvent::ParsedStreamTag(tag) => Some(tag.raw_log_message),
+      FfmpegEvent::ProcessingStarted(x) => Some(x),
     })
   }
 }
 
-impl Iterator for FfmpegIterator {
-  type Item = FfmpegEvent;
-
-  fn next(&mut self) -> Option<Self::Item> {
-    let item = self.rx.recv().ok();
+/// The `tracing` target for messages forwarded by [`FfmpegIterator::trace`].
+#[cfg(feature = "tracing")]
+const TRACING_TARGET: &str = "ffmpeg_sidecar::ffmpeg";
+
+#[cfg(feature = "tracing")]
+fn trace_event(event: &FfmpegEvent) {
+  let (level, message) = match event {
+    FfmpegEvent::Log(level, message) => (level, message),
+    FfmpegEvent::ParsedError(error) => (&error.level, &error.raw_log_message),
+    FfmpegEvent::ParsedWarning(warning) => (&warning.level, &warning.raw_log_message),
+    FfmpegEvent::Deprecation(deprecation) => (&LogLevel::Warning, &deprecation.raw_log_message),
+    FfmpegEvent::Error(message) => (&LogLevel::Error, message),
+    _ => return,
+  };
+  match level {
+    LogLevel::Info => tracing::info!(target: TRACING_TARGET, "{message}"),
+    LogLevel::Warning => tracing::warn!(target: TRACING_TARGET, "{message}"),
+    LogLevel::Error | LogLevel::Fatal => tracing::error!(target: TRACING_TARGET, "{message}"),
+    LogLevel::Unknown => tracing::debug!(target: TRACING_TARGET, "{message}"),
+  }
+}
 
-    if let Some(FfmpegEvent::LogEOF) = item {
-      self.tx.take(); // drop the tx so that the receiver can close
-    }
+/// How far back [`FfmpegIterator::with_eta`] looks to measure the speed.
+const ETA_WINDOW: Duration = Duration::from_secs(5);
+
+/// State of [`FfmpegIterator::with_eta`].
+#[derive(Default)]
+struct EtaEstimator {
+  /// Input duration in seconds.
+  duration: Option<f64>,
+  /// Recent progress, as the wall clock time and the output timestamp in
+  /// seconds, oldest first.
+  samples: VecDeque<(Instant, f64)>,
+}
 
-    if !self.metadata.is_completed() {
-      match self.metadata.handle_event(&item) {
-        Err(e) => return Some(FfmpegEvent::Error(e.to_string())),
-        // TODO in this case, the preceding `item` is lost;
-        // Probably better to queue it as the next item.
-        Ok(()) if self.metadata.is_completed() => {
-          if let Err(e) = self.start_stdout() {
-            return Some(FfmpegEvent::Error(e.to_string()));
-            // Same problem as above
-          }
-        }
+impl EtaEstimator {
+  fn annotate(&mut self, progress: &mut FfmpegProgress, now: Instant) {
+    let (Some(duration), Some(out_time)) = (self.duration, progress.out_time) else {
+      return;
+    };
+    let position = out_time.as_secs_f64();
+    progress.fraction = Some((position / duration).clamp(0.0, 1.0) as f32);
 
-        _ => {}
-      }
+    // Keep one sample older than the window, so it always spans its length
+    while self.samples.len() > 1 && now.duration_since(self.samples[1].0) >= ETA_WINDOW {
+      self.samples.pop_front();
     }
+    self.samples.push_back((now, position));
+
+    // Fall back to the average speed reported by ffmpeg until there is a
+    // measurable difference between samples
+    let (since, start) = self.samples[0];
+    let elapsed = now.duration_since(since).as_secs_f64();
+    let speed = if elapsed > 0.0 && position > start {
+      Some((position - start) / elapsed)
+    } else {
+      progress.speed.map(f64::from).filter(|&s| s > 0.0)
+    };
+    let remaining = (duration - position).max(0.0);
+    progress.eta = speed.and_then(|speed| Duration::try_from_secs_f64(remaining / speed).ok());
+  }
+}
 
-    item
+impl Iterator for FfmpegIterator {
+  type Item = FfmpegEvent;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let item = self.rx.recv().ok();
+    self.handle_item(item)
   }
 }
 
@@ -185,8 +481,97 @@ pub fn spawn_stdout_thread(
   tx: SyncSender<FfmpegEvent>,
   output_streams: Vec<Stream>,
   outputs: Vec<FfmpegOutput>,
+) -> JoinHandle<()> {
+  spawn_stdout_thread_inner(stdout, tx, output_streams, outputs, None)
+}
+
+fn spawn_stdout_thread_inner(
+  stdout: ChildStdout,
+  tx: SyncSender<FfmpegEvent>,
+  output_streams: Vec<Stream>,
+  outputs: Vec<FfmpegOutput>,
+  buffer_size: Option<usize>,
 ) -> JoinHandle<()> {
   std::thread::spawn(move || {
+    let layout = match StdoutLayout::new(&output_streams, &outputs) {
+      Ok(layout) => layout,
+      Err(e) => {
+        tx.send(FfmpegEvent::Error(e)).ok();
+        return;
+      }
+    };
+    if let Some(warning) = &layout.warning {
+      tx.send(FfmpegEvent::Error(warning.clone())).ok();
+    }
+
+    let mut reader = match buffer_size {
+      Some(capacity) => BufReader::with_capacity(capacity, stdout),
+      None => BufReader::new(stdout),
+    };
+    match &layout.frame_sizes {
+      None => {
+        // Arbitrary default buffer size for receiving indeterminate chunks
+        // of any encoder or container output, when frame boundaries are unknown
+        let mut chunk_buffer = vec![0u8; buffer_size.unwrap_or(CHUNK_SIZE).max(1)];
+        loop {
+          match reader.read(chunk_buffer.as_mut_slice()) {
+            Ok(0) => break,
+            Ok(bytes_read) => {
+              let mut data = vec![0; bytes_read];
+              data.clone_from_slice(&chunk_buffer[..bytes_read]);
+              tx.send(FfmpegEvent::OutputChunk(data)).ok()
+            }
+            Err(e) => match e.kind() {
+              ErrorKind::UnexpectedEof => break,
+              e => tx.send(FfmpegEvent::Error(e.to_string())).ok(),
+            },
+          };
+        }
+      }
+      Some(frame_sizes) => {
+        // Read each frame straight into the buffer sent with its event,
+        // rather than copying it out of a reused one
+        let mut frame_num = 0;
+        loop {
+          let mut buffer = vec![0u8; frame_sizes[frame_num % frame_sizes.len()]];
+          match reader.read_exact(buffer.as_mut_slice()) {
+            Ok(_) => tx
+              .send(FfmpegEvent::OutputFrame(layout.frame(frame_num, buffer)))
+              .ok(),
+            Err(e) => match e.kind() {
+              ErrorKind::UnexpectedEof => break,
+              e => tx.send(FfmpegEvent::Error(e.to_string())).ok(),
+            },
+          };
+          frame_num += 1;
+        }
+      }
+    }
+
+    tx.send(FfmpegEvent::Done).ok();
+  })
+}
+
+/// Size of the buffer for reading stdout in chunked mode.
+pub(crate) const CHUNK_SIZE: usize = 65_536;
+
+/// How the bytes on ffmpeg's stdout are split into events, worked out from
+/// the output metadata. Shared by the blocking and async readers.
+pub(crate) struct StdoutLayout {
+  /// The size of each interleaved raw video frame, or `None` to read in
+  /// arbitrary chunks when frame boundaries can't be determined.
+  pub(crate) frame_sizes: Option<Vec<usize>>,
+  /// The raw video streams on stdout, in the order their frames are
+  /// interleaved. Empty in chunked mode.
+  streams: Vec<Stream>,
+  /// A non-fatal problem to report before reading, such as falling back to
+  /// chunked mode.
+  pub(crate) warning: Option<String>,
+}
+
+impl StdoutLayout {
+  /// Returns `Err` with a message if nothing can be read from stdout.
+  pub(crate) fn new(output_streams: &[Stream], outputs: &[FfmpegOutput]) -> Result<Self, String> {
     // Filter streams which are sent to stdout
     let stdout_streams = output_streams.iter().filter(|stream| {
       outputs
@@ -197,13 +582,12 @@ pub fn spawn_stdout_thread(
 
     // Exit early if nothing is being sent to stdout
     if stdout_streams.clone().count() == 0 {
-      tx.send(FfmpegEvent::Error("No streams found".to_owned()))
-        .ok();
-      return;
+      return Err("No streams found".to_owned());
     }
 
     // If the size of a frame can't be determined, it will be read in arbitrary chunks.
     let mut chunked_mode = false;
+    let mut warning = None;
 
     // Immediately default to chunked mode for non-video streams
     let stdout_video_streams = stdout_streams.clone().filter(|stream| stream.is_video());
@@ -229,15 +613,17 @@ pub fn spawn_stdout_thread(
           return 0;
         };
 
-        // This may trigger either on an unsupported pixel format, or
-        // framebuffers with non-byte-aligned sizes. FFmpeg will pad these with
-        // zeroes, but we can't predict the exact padding or end size on every format.
-        let Some(bytes_per_frame) = get_bytes_per_frame(video_data) else {
+        // This may trigger either on an unsupported pixel format, or packed
+        // framebuffers with non-byte-aligned sizes. Planar formats like
+        // `yuv420p` are sized plane by plane, so odd dimensions are supported.
+        let Some(bytes_per_frame) =
+          bytes_per_frame(&video_data.pix_fmt, video_data.width, video_data.height)
+        else {
           chunked_mode = true;
           return 0;
         };
 
-        bytes_per_frame as usize
+        bytes_per_frame
       })
       .collect();
 
@@ -262,80 +648,73 @@ pub fn spawn_stdout_thread(
     if any_mismatched_framerates {
       // This edge case is probably not what the user was intending,
       // so we'll notify with an error.
-      tx.send(FfmpegEvent::Error(
-        "Multiple output streams with different framerates are not supported when outputting to stdout. Falling back to chunked mode.".to_owned()
-      )).ok();
+      warning = Some("Multiple output streams with different framerates are not supported when outputting to stdout. Falling back to chunked mode.".to_owned());
       chunked_mode = true;
     }
 
-    let mut reader = BufReader::new(stdout);
     if chunked_mode {
-      // Arbitrary default buffer size for receiving indeterminate chunks
-      // of any encoder or container output, when frame boundaries are unknown
-      let mut chunk_buffer = vec![0u8; 65_536];
-      loop {
-        match reader.read(chunk_buffer.as_mut_slice()) {
-          Ok(0) => break,
-          Ok(bytes_read) => {
-            let mut data = vec![0; bytes_read];
-            data.clone_from_slice(&chunk_buffer[..bytes_read]);
-            tx.send(FfmpegEvent::OutputChunk(data)).ok()
-          }
-          Err(e) => match e.kind() {
-            ErrorKind::UnexpectedEof => break,
-            e => tx.send(FfmpegEvent::Error(e.to_string())).ok(),
-          },
-        };
-      }
-    } else {
-      // Prepare frame buffers
-      let mut frame_buffers = frame_buffer_sizes
-        .iter()
-        .map(|&size| vec![0u8; size])
-        .collect::<Vec<Vec<u8>>>();
-
-      // Empty buffer array is unexpected at this point, since we've already ruled out
-      // both chunked mode and non-stdout streams.
-      if frame_buffers.is_empty() {
-        tx.send(FfmpegEvent::Error("No frame buffers found".to_owned()))
-          .ok();
-        return;
-      }
+      return Ok(Self {
+        frame_sizes: None,
+        streams: Vec::new(),
+        warning,
+      });
+    }
 
-      // Read into buffers
-      let num_frame_buffers = frame_buffers.len();
-      let mut frame_buffer_index = (0..frame_buffers.len()).cycle();
-      let mut frame_num = 0;
-      loop {
-        let i = frame_buffer_index.next().unwrap();
-        let video_stream = &output_streams[i];
-        let video_data = video_stream.video_data().unwrap();
-        let buffer = &mut frame_buffers[i];
-        let output_frame_num = frame_num / num_frame_buffers;
-        let timestamp = output_frame_num as f32 / video_data.fps;
-        frame_num += 1;
-
-        match reader.read_exact(buffer.as_mut_slice()) {
-          Ok(_) => tx
-            .send(FfmpegEvent::OutputFrame(OutputVideoFrame {
-              width: video_data.width,
-              height: video_data.height,
-              pix_fmt: video_data.pix_fmt.clone(),
-              output_index: i as u32,
-              data: buffer.clone(),
-              frame_num: output_frame_num as u32,
-              timestamp,
-            }))
-            .ok(),
-          Err(e) => match e.kind() {
-            ErrorKind::UnexpectedEof => break,
-            e => tx.send(FfmpegEvent::Error(e.to_string())).ok(),
-          },
-        };
-      }
+    // Empty buffer array is unexpected at this point, since we've already ruled out
+    // both chunked mode and non-stdout streams.
+    if frame_buffer_sizes.is_empty() {
+      return Err("No frame buffers found".to_owned());
     }
 
-    tx.send(FfmpegEvent::Done).ok();
+    Ok(Self {
+      frame_sizes: Some(frame_buffer_sizes),
+      streams: stdout_video_streams.cloned().collect(),
+      warning,
+    })
+  }
+
+  /// Wrap the `frame_num`th frame read from stdout, counting across all
+  /// interleaved outputs.
+  pub(crate) fn frame(&self, frame_num: usize, data: Vec<u8>) -> OutputVideoFrame {
+    let num_frame_buffers = self.frame_sizes.as_ref().map_or(1, |sizes| sizes.len());
+    let i = frame_num % num_frame_buffers;
+    let stream = &self.streams[i];
+    let video_data = stream.video_data().unwrap();
+    let output_frame_num = frame_num / num_frame_buffers;
+    OutputVideoFrame {
+      width: video_data.width,
+      height: video_data.height,
+      pix_fmt: video_data.pix_fmt.clone(),
+      output_index: i as u32,
+      parent_index: stream.parent_index,
+      stream_index: stream.stream_index,
+      data,
+      frame_num: output_frame_num as u32,
+      timestamp: output_frame_num as f32 / video_data.fps,
+    }
+  }
+}
+
+/// Spawn a thread which reads `-progress pipe:1` reports from ffmpeg's stdout,
+/// emitting one `FfmpegEvent::Progress` per block of `key=value` lines.
+pub fn spawn_progress_thread(stdout: ChildStdout, tx: SyncSender<FfmpegEvent>) -> JoinHandle<()> {
+  std::thread::spawn(move || {
+    let reader = BufReader::new(stdout);
+    let mut block = String::new();
+    for line in reader.lines() {
+      let Ok(line) = line else { break };
+      block.push_str(&line);
+      block.push('\n');
+      // Every block is terminated by `progress=continue` or `progress=end`
+      if line.starts_with("progress=") {
+        if let Some(progress) = try_parse_progress_block(&block) {
+          if tx.send(FfmpegEvent::Progress(progress)).is_err() {
+            break;
+          }
+        }
+        block.clear();
+      }
+    }
   })
 }
 
@@ -343,15 +722,32 @@ pub fn spawn_stdout_thread(
 /// The cadence is controlled by the synchronous `tx` channel, which blocks
 /// until a receiver is ready to receive the next event.
 pub fn spawn_stderr_thread(stderr: ChildStderr, tx: SyncSender<FfmpegEvent>) -> JoinHandle<()> {
+  spawn_stderr_thread_inner(stderr, tx, None)
+}
+
+fn spawn_stderr_thread_inner<R: Read + Send + 'static>(
+  stderr: R,
+  tx: SyncSender<FfmpegEvent>,
+  progress_pipe: Option<ProgressPipe>,
+) -> JoinHandle<()> {
+  // The final status line is still printed to stderr despite `-nostats`;
+  // demote it to a log message so progress is only reported once.
+  let progress_as_log = progress_pipe == Some(ProgressPipe::Stdout);
   std::thread::spawn(move || {
     let reader = BufReader::new(stderr);
     let mut parser = FfmpegLogParser::new(reader);
+    if progress_pipe == Some(ProgressPipe::Stderr) {
+      parser.parse_progress_blocks();
+    }
     loop {
       match parser.parse_next_event() {
         Ok(FfmpegEvent::LogEOF) => {
           tx.send(FfmpegEvent::LogEOF).ok();
           break;
         }
+        Ok(FfmpegEvent::Progress(progress)) if progress_as_log => tx
+          .send(FfmpegEvent::Log(LogLevel::Info, progress.raw_log_message))
+          .ok(),
         Ok(event) => tx.send(event).ok(),
         Err(e) => {
           eprintln!("Error parsing ffmpeg output: {}", e);
@@ -27,19 +27,43 @@
 #[cfg(test)]
 mod test;
 
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub mod async_child;
+pub mod audio;
+pub mod bitrate;
+pub mod cancel;
+pub mod capabilities;
 pub mod child;
 pub mod comma_iter;
 pub mod command;
+pub mod control;
 pub mod download;
+pub mod error;
 pub mod event;
 pub mod ffprobe;
+pub mod filter_graph;
+pub mod frame_reader;
+pub mod hls;
+pub mod input;
 pub mod iter;
 pub mod log_parser;
 pub mod metadata;
 pub mod paths;
+pub mod pcm;
+pub mod pipeline;
 pub mod pix_fmt;
 pub mod read_until_any;
+pub mod segment;
+pub mod stream_spec;
+pub mod streaming;
+pub mod subtitles;
+pub mod tee;
+mod temp_file;
+pub mod two_pass;
+pub mod util;
 pub mod version;
+pub mod x264;
 
 #[cfg(feature = "named_pipes")]
 #[cfg_attr(docsrs, doc(cfg(feature = "named_pipes")))]
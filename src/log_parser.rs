@@ -1,12 +1,19 @@
 //! Internal methods for parsing FFmpeg CLI log output.
 
-use std::io::{BufReader, Read};
+use std::{
+  collections::HashMap,
+  io::{BufReader, Read},
+  time::Duration,
+};
 
 use crate::{
   comma_iter::CommaIter,
   event::{
-    AudioStream, FfmpegConfiguration, FfmpegDuration, FfmpegEvent, FfmpegInput, FfmpegOutput,
-    FfmpegProgress, FfmpegVersion, LogLevel, Stream, StreamTypeSpecificData, VideoStream,
+    AudioStream, BenchmarkStats, FfmpegConfiguration, FfmpegDeprecation, FfmpegDupDrop,
+    FfmpegDuration, FfmpegErrorKind, FfmpegEvent, FfmpegInput, FfmpegLogError, FfmpegLogWarning,
+    FfmpegMuxingStats, FfmpegOutput, FfmpegProgress, FfmpegRepeated, FfmpegStreamMapping,
+    FfmpegStreamTag, FfmpegSummary, FfmpegVersion, FfmpegWarningKind, HardwareBackend, LogLevel,
+    Stream, StreamTypeSpecificData, VideoStream,
   },
   read_until_any::read_until_any,
 };
@@ -19,9 +26,20 @@ enum LogSection {
   Other,
 }
 
+/// The most recently parsed stream, used to attach the tags from its
+/// `Metadata:` block.
+#[derive(Debug, Clone, PartialEq)]
+struct StreamContext {
+  parent_index: u32,
+  stream_index: u32,
+  indent: usize,
+  metadata_indent: Option<usize>,
+  last_tag: Option<(String, String)>,
+}
+
 pub struct FfmpegLogParser<R: Read> {
   reader: BufReader<R>,
-  cur_section: LogSection,
+  lines: LogLineParser,
 }
 
 impl<R: Read> FfmpegLogParser<R> {
@@ -37,86 +55,331 @@ impl<R: Read> FfmpegLogParser<R> {
   /// - `\r\n` (Windows)
   /// - `\r` (Windows, progress updates which overwrite the previous line)
   pub fn parse_next_event(&mut self) -> anyhow::Result<FfmpegEvent> {
+    if let Some(event) = self.lines.take_pending() {
+      return Ok(event);
+    }
+
     let mut buf = Vec::<u8>::new();
     let bytes_read = read_until_any(&mut self.reader, &[b'\r', b'\n'], &mut buf);
-    let line_cow = String::from_utf8_lossy(buf.as_slice());
-    let line = line_cow.trim();
-    let raw_log_message = line.to_string();
+    join_crlf(&mut buf, self.reader.buffer());
     match bytes_read? {
       0 => Ok(FfmpegEvent::LogEOF),
-      _ => {
-        // Track log section
-        if let Some(input_number) = try_parse_input(line) {
-          self.cur_section = LogSection::Input(input_number);
-          return Ok(FfmpegEvent::ParsedInput(FfmpegInput {
-            index: input_number,
-            duration: None,
-            raw_log_message,
-          }));
-        } else if let Some(output) = try_parse_output(line) {
-          self.cur_section = LogSection::Output(output.index);
-          return Ok(FfmpegEvent::ParsedOutput(output));
-        } else if line.contains("Stream mapping:") {
-          self.cur_section = LogSection::StreamMapping;
-        }
-
-        // Parse
-        if let Some(version) = try_parse_version(line) {
-          Ok(FfmpegEvent::ParsedVersion(FfmpegVersion {
-            version,
-            raw_log_message,
-          }))
-        } else if let Some(configuration) = try_parse_configuration(line) {
-          Ok(FfmpegEvent::ParsedConfiguration(FfmpegConfiguration {
-            configuration,
-            raw_log_message,
-          }))
-        } else if let Some(duration) = try_parse_duration(line) {
-          match self.cur_section {
-            LogSection::Input(input_index) => Ok(FfmpegEvent::ParsedDuration(FfmpegDuration {
-              input_index,
-              duration,
-              raw_log_message,
-            })),
-            _ => Ok(FfmpegEvent::Log(LogLevel::Info, line.to_string())),
-          }
-        } else if self.cur_section == LogSection::StreamMapping && line.contains("  Stream #") {
-          Ok(FfmpegEvent::ParsedStreamMapping(line.to_string()))
-        } else if let Some(stream) = try_parse_stream(line) {
-          match self.cur_section {
-            LogSection::Input(_) => Ok(FfmpegEvent::ParsedInputStream(stream)),
-            LogSection::Output(_) => Ok(FfmpegEvent::ParsedOutputStream(stream)),
-            LogSection::Other | LogSection::StreamMapping => Err(anyhow::Error::msg(format!(
-              "Unexpected stream specification: {}",
-              line
-            ))),
-          }
-        } else if let Some(progress) = try_parse_progress(line) {
-          self.cur_section = LogSection::Other;
-          Ok(FfmpegEvent::Progress(progress))
-        } else if line.contains("[info]") {
-          Ok(FfmpegEvent::Log(LogLevel::Info, line.to_string()))
-        } else if line.contains("[warning]") {
-          Ok(FfmpegEvent::Log(LogLevel::Warning, line.to_string()))
-        } else if line.contains("[error]") {
-          Ok(FfmpegEvent::Log(LogLevel::Error, line.to_string()))
-        } else if line.contains("[fatal]") {
-          Ok(FfmpegEvent::Log(LogLevel::Fatal, line.to_string()))
-        } else {
-          Ok(FfmpegEvent::Log(LogLevel::Unknown, line.to_string()))
-        }
-      }
+      _ => self
+        .lines
+        .parse_line(&String::from_utf8_lossy(buf.as_slice())),
     }
   }
 
   pub fn new(inner: R) -> Self {
     Self {
       reader: BufReader::new(inner),
+      lines: LogLineParser::new(),
+    }
+  }
+
+  /// Parse `-progress pipe:2` reports among the log lines, see
+  /// [`LogLineParser::parse_progress_blocks`].
+  pub(crate) fn parse_progress_blocks(&mut self) {
+    self.lines.parse_progress_blocks();
+  }
+}
+
+/// The parsing state of [`FfmpegLogParser`], fed one line at a time without
+/// doing any I/O itself, so the same parser can be driven by the blocking
+/// reader threads and by the async event stream.
+#[derive(Debug, Clone)]
+pub(crate) struct LogLineParser {
+  cur_section: LogSection,
+  cur_stream: Option<StreamContext>,
+  /// A second event parsed from the last line, returned by `take_pending`
+  pending: Option<FfmpegEvent>,
+  /// Running totals for `FfmpegLogWarning::count`
+  warning_counts: HashMap<FfmpegWarningKind, u32>,
+  /// The kind of warning parsed from the previous line, whose total grows by
+  /// the count of a following `Last message repeated N times`
+  last_warning: Option<FfmpegWarningKind>,
+  /// The `utime=`, `stime=` and `rtime=` of a `bench:` line, until the
+  /// `maxrss=` line completes them, or vice versa
+  benchmark_times: Option<(Duration, Duration, Duration)>,
+  benchmark_max_rss: Option<u64>,
+  /// The `key=value` lines of a `-progress pipe:2` report read so far, or
+  /// `None` unless progress is reported on stderr
+  progress_block: Option<String>,
+  /// Frames reported dropped to match the output framerate, which the status
+  /// line also counts as `drop=`
+  logged_drops: u32,
+  /// Frames reported dropped by a capture device before decoding
+  capture_drops: u32,
+}
+
+/// Restore the `\n` of a `\r\n` line ending which `read_until_any` split
+/// after the `\r`, if it's already buffered in `next`, so a final log line
+/// can be told apart from a status line ending in `\r`. The `\n` itself is
+/// still skipped by the next read.
+pub(crate) fn join_crlf(buf: &mut Vec<u8>, next: &[u8]) {
+  if buf.last() == Some(&b'\r') && next.first() == Some(&b'\n') {
+    buf.push(b'\n');
+  }
+}
+
+impl LogLineParser {
+  pub(crate) fn new() -> Self {
+    Self {
       cur_section: LogSection::Other,
+      cur_stream: None,
+      pending: None,
+      warning_counts: HashMap::new(),
+      last_warning: None,
+      benchmark_times: None,
+      benchmark_max_rss: None,
+      progress_block: None,
+      logged_drops: 0,
+      capture_drops: 0,
+    }
+  }
+
+  /// Expect `-progress pipe:2` reports among the log lines. Each `key=value`
+  /// line is still emitted as a `Log`, followed by a `Progress` for the whole
+  /// block once its `progress=` line is read. The status line is demoted to a
+  /// `Log`, so progress is only reported once.
+  pub(crate) fn parse_progress_blocks(&mut self) {
+    self.progress_block = Some(String::new());
+  }
+
+  /// Take the extra event parsed from the previous line, if any, to be
+  /// emitted before parsing the next one.
+  pub(crate) fn take_pending(&mut self) -> Option<FfmpegEvent> {
+    self.pending.take()
+  }
+
+  /// Parse a single line of stderr, including its trailing delimiter, if any.
+  pub(crate) fn parse_line(&mut self, line: &str) -> anyhow::Result<FfmpegEvent> {
+    // Status lines end with `\r` to overwrite each other, except the last,
+    // which also reports the final size as `Lsize=`
+    let is_final_line = line.ends_with('\n') || line.contains("Lsize=");
+    let line = line.trim();
+    let raw_log_message = line.to_string();
+    let last_warning = self.last_warning.take();
+    if let Some(block) = self
+      .progress_block
+      .as_mut()
+      .filter(|_| is_progress_line(line))
+    {
+      block.push_str(line);
+      block.push('\n');
+      // Every block is terminated by `progress=continue` or `progress=end`
+      if line.starts_with("progress=") {
+        let progress = try_parse_progress_block(block);
+        block.clear();
+        self.pending = progress.map(|mut progress| {
+          progress.dropped_frames = self.count_dropped_frames(&progress);
+          FfmpegEvent::Progress(progress)
+        });
+      }
+      return Ok(FfmpegEvent::Log(LogLevel::Unknown, raw_log_message));
+    }
+
+    // Track metadata blocks nested under a stream
+    if let Some(tag) = self.try_parse_stream_tag(line) {
+      return Ok(FfmpegEvent::ParsedStreamTag(tag));
+    }
+
+    // Track log section
+    if let Some(input_number) = try_parse_input(line) {
+      self.cur_stream = None;
+      self.cur_section = LogSection::Input(input_number);
+      return Ok(FfmpegEvent::ParsedInput(FfmpegInput {
+        index: input_number,
+        duration: None,
+        format: try_parse_input_format(line),
+        raw_log_message,
+      }));
+    } else if let Some(output) = try_parse_output(line) {
+      self.cur_stream = None;
+      self.cur_section = LogSection::Output(output.index);
+      return Ok(FfmpegEvent::ParsedOutput(output));
+    } else if line.contains("Stream mapping:") {
+      self.cur_stream = None;
+      self.cur_section = LogSection::StreamMapping;
+    }
+
+    // Parse
+    if let Some(version) = try_parse_version(line) {
+      Ok(FfmpegEvent::ParsedVersion(FfmpegVersion {
+        version,
+        raw_log_message,
+      }))
+    } else if let Some(configuration) = try_parse_configuration(line) {
+      Ok(FfmpegEvent::ParsedConfiguration(FfmpegConfiguration {
+        configuration,
+        raw_log_message,
+      }))
+    } else if let Some(duration) = try_parse_duration(line) {
+      match self.cur_section {
+        LogSection::Input(input_index) => Ok(FfmpegEvent::ParsedDuration(FfmpegDuration {
+          input_index,
+          duration,
+          start_time: try_parse_start_time(line),
+          bitrate_kbps: try_parse_input_bitrate(line),
+          raw_log_message,
+        })),
+        _ => Ok(FfmpegEvent::Log(LogLevel::Info, line.to_string())),
+      }
+    } else if self.cur_section == LogSection::StreamMapping && line.contains("  Stream #") {
+      match try_parse_stream_mapping(line) {
+        Some(mapping) => Ok(FfmpegEvent::ParsedStreamMapping(mapping)),
+        None => Ok(FfmpegEvent::Log(LogLevel::Info, line.to_string())),
+      }
+    } else if let Some(stream) = try_parse_stream(line) {
+      self.cur_stream = Some(StreamContext {
+        parent_index: stream.parent_index,
+        stream_index: stream.stream_index,
+        indent: log_indent(line),
+        metadata_indent: None,
+        last_tag: None,
+      });
+      match self.cur_section {
+        LogSection::Input(_) => Ok(FfmpegEvent::ParsedInputStream(stream)),
+        LogSection::Output(_) => Ok(FfmpegEvent::ParsedOutputStream(stream)),
+        LogSection::Other | LogSection::StreamMapping => Err(anyhow::Error::msg(format!(
+          "Unexpected stream specification: {}",
+          line
+        ))),
+      }
+    } else if let Some(mut progress) = try_parse_progress(line) {
+      self.cur_section = LogSection::Other;
+      progress.dropped_frames = self.count_dropped_frames(&progress);
+      if is_final_line {
+        self.pending = Some(FfmpegEvent::Summary(summarize_progress(&progress)));
+      }
+      match self.progress_block {
+        Some(_) => Ok(FfmpegEvent::Log(LogLevel::Info, raw_log_message)),
+        None => Ok(FfmpegEvent::Progress(progress)),
+      }
+    } else if let Some(stats) = try_parse_muxing_stats(line) {
+      Ok(FfmpegEvent::MuxingStats(stats))
+    } else if self.track_benchmark(line) {
+      Ok(FfmpegEvent::Log(LogLevel::Info, line.to_string()))
+    } else if let Some(dup_drop) = try_parse_dup_drop(line) {
+      if is_capture_drop(line) {
+        self.capture_drops += dup_drop.drop;
+      } else {
+        self.logged_drops += dup_drop.drop;
+      }
+      Ok(FfmpegEvent::ParsedDupDrop(dup_drop))
+    } else if let Some(count) = try_parse_repeated(line) {
+      if let Some(kind) = last_warning {
+        *self.warning_counts.entry(kind).or_insert(0) += count;
+        self.last_warning = Some(kind);
+      }
+      Ok(FfmpegEvent::Repeated(FfmpegRepeated {
+        count,
+        raw_log_message,
+      }))
+    } else if let Some(mut warning) = try_parse_warning(line) {
+      let count = self.warning_counts.entry(warning.kind).or_insert(0);
+      *count += 1;
+      warning.count = *count;
+      self.last_warning = Some(warning.kind);
+      Ok(FfmpegEvent::ParsedWarning(warning))
+    } else if let Some(deprecation) = try_parse_deprecation(line) {
+      Ok(FfmpegEvent::Deprecation(deprecation))
+    } else if line.contains("Press [q] to stop") {
+      Ok(FfmpegEvent::ProcessingStarted(line.to_string()))
+    } else if let Some(error) = try_parse_error(line) {
+      Ok(FfmpegEvent::ParsedError(error))
+    } else {
+      Ok(FfmpegEvent::Log(log_level(line), line.to_string()))
+    }
+  }
+
+  /// The running total of dropped frames as of `progress`. Framerate drops
+  /// may be both logged and counted on the status line, so only the larger
+  /// count is used, while capture drops come on top.
+  fn count_dropped_frames(&self, progress: &FfmpegProgress) -> u32 {
+    let framerate_drops = progress.drop_frames.unwrap_or(0).max(self.logged_drops);
+    framerate_drops + self.capture_drops
+  }
+
+  /// Record a `bench:` line from `-benchmark`, queueing a `Benchmark` event
+  /// once both the times and the peak memory are known. Returns whether the
+  /// line was one of them.
+  fn track_benchmark(&mut self, line: &str) -> bool {
+    if let Some(times) = try_parse_benchmark_times(line) {
+      self.benchmark_times = Some(times);
+    } else if let Some(max_rss) = try_parse_benchmark_max_rss(line) {
+      self.benchmark_max_rss = Some(max_rss);
+    } else {
+      return false;
+    }
+    if let (Some((utime, stime, rtime)), Some(max_rss_kb)) =
+      (self.benchmark_times, self.benchmark_max_rss)
+    {
+      self.benchmark_times = None;
+      self.benchmark_max_rss = None;
+      self.pending = Some(FfmpegEvent::Benchmark(BenchmarkStats {
+        utime,
+        stime,
+        rtime,
+        max_rss_kb,
+      }));
+    }
+    true
+  }
+
+  /// Parse a `key : value` line from the `Metadata:` block of the current
+  /// stream, tracking the start and end of the block by its indentation.
+  fn try_parse_stream_tag(&mut self, line: &str) -> Option<FfmpegStreamTag> {
+    let is_output = match self.cur_section {
+      LogSection::Input(_) => false,
+      LogSection::Output(_) => true,
+      _ => return None,
+    };
+    let stream = self.cur_stream.as_mut()?;
+    let indent = log_indent(line);
+    let content = line.strip_prefix("[info]")?.trim();
+
+    match stream.metadata_indent {
+      Some(metadata_indent) if indent > metadata_indent => {
+        let (key, value) = content.split_once(':')?;
+        let (key, value) = (key.trim(), value.trim());
+        let (key, value) = match (key, &stream.last_tag) {
+          // Continuation of a multi-line value
+          ("", Some((last_key, last_value))) => {
+            (last_key.clone(), format!("{last_value}\n{value}"))
+          }
+          ("", None) => return None,
+          (key, _) => (key.to_string(), value.to_string()),
+        };
+        stream.last_tag = Some((key.clone(), value.clone()));
+        Some(FfmpegStreamTag {
+          is_output,
+          parent_index: stream.parent_index,
+          stream_index: stream.stream_index,
+          key,
+          value,
+          raw_log_message: line.to_string(),
+        })
+      }
+      _ => {
+        // Any other nested block (like `Side data:`) or less indented line
+        // ends the metadata block.
+        stream.metadata_indent =
+          (indent > stream.indent && content == "Metadata:").then_some(indent);
+        stream.last_tag = None;
+        None
+      }
     }
   }
 }
 
+/// Count the spaces between the log level prefix and the message, which
+/// ffmpeg uses to nest metadata under its input, output or stream.
+fn log_indent(line: &str) -> usize {
+  let message = line.split_once(']').map_or(line, |(_, message)| message);
+  message.len() - message.trim_start().len()
+}
+
 /// Parses the ffmpeg version string from the stderr stream,
 /// typically the very first line of output:
 ///
@@ -191,6 +454,28 @@ pub fn try_parse_input(string: &str) -> Option<u32> {
     .and_then(|s| s.parse::<u32>().ok())
 }
 
+/// Parse the demuxer(s) from an input section, which may differ from what the
+/// file extension suggests.
+///
+/// ## Example:
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_input_format;
+/// let line = "[info] Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'file.mkv':\n";
+/// let format = try_parse_input_format(line);
+/// assert!(format == Some("mov,mp4,m4a,3gp,3g2,mj2".to_string()));
+/// ```
+pub fn try_parse_input_format(string: &str) -> Option<String> {
+  let (format, _) = string
+    .strip_prefix("[info]")
+    .unwrap_or(string)
+    .trim()
+    .strip_prefix("Input #")?
+    .split_once(" from '")?;
+  let format = format.split_once(',')?.1.trim().trim_end_matches(',');
+  (!format.is_empty()).then(|| format.to_string())
+}
+
 /// ## Example:
 ///
 /// ```rust
@@ -221,7 +506,107 @@ pub fn try_parse_duration(string: &str) -> Option<f64> {
     .and_then(parse_time_str)
 }
 
-/// Parse an output section like the following, extracting the index of the input:
+/// Parse the `start:` timestamp in seconds from an input's `Duration:` line.
+///
+/// ## Example:
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_start_time;
+/// let line = "[info]   Duration: 00:01:23.45, start: 1.400000, bitrate: 1234 kb/s\n";
+/// assert!(try_parse_start_time(line) == Some(1.4));
+/// ```
+pub fn try_parse_start_time(string: &str) -> Option<f64> {
+  duration_line_field(string, "start:")?.parse().ok()
+}
+
+/// Parse the overall `bitrate:` in kilobits per second from an input's
+/// `Duration:` line. Returns `None` for `bitrate: N/A`.
+///
+/// ## Example:
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_input_bitrate;
+/// let line = "[info]   Duration: 00:01:23.45, start: 0.000000, bitrate: 1234 kb/s\n";
+/// assert!(try_parse_input_bitrate(line) == Some(1234));
+///
+/// let line = "[info]   Duration: N/A, start: 0.000000, bitrate: N/A\n";
+/// assert!(try_parse_input_bitrate(line) == None);
+/// ```
+pub fn try_parse_input_bitrate(string: &str) -> Option<u32> {
+  duration_line_field(string, "bitrate:")?
+    .strip_suffix("kb/s")?
+    .trim()
+    .parse()
+    .ok()
+}
+
+/// Find the first comma-separated field with the given prefix on a
+/// `Duration:` line, returning its trimmed value.
+fn duration_line_field<'a>(string: &'a str, prefix: &str) -> Option<&'a str> {
+  string
+    .strip_prefix("[info]")
+    .unwrap_or(string)
+    .trim()
+    .strip_prefix("Duration:")?
+    .split(',')
+    .find_map(|field| field.trim().strip_prefix(prefix))
+    .map(str::trim)
+}
+
+/// Read the level prefix which `-loglevel level+...` adds to each message,
+/// after the `[context @ 0x...]` prefixes naming its source, if any. Returns
+/// `None` for a line without one. Levels more verbose than `info` map to
+/// `LogLevel::Unknown`, and `panic` to `LogLevel::Fatal`.
+///
+/// ## Examples
+/// ```rust
+/// use ffmpeg_sidecar::{event::LogLevel, log_parser::try_parse_log_level};
+/// let line = "[mp4 @ 0x7f8] [warning] Codec for stream 0 does not use global headers";
+/// assert!(try_parse_log_level(line) == Some(LogLevel::Warning));
+///
+/// // Only the prefix counts, not a level mentioned in the message
+/// let line = "[info] Metadata: comment : [error] isn't an error";
+/// assert!(try_parse_log_level(line) == Some(LogLevel::Info));
+///
+/// assert!(try_parse_log_level("[verbose] [lavfi @ 0x7f8] Setting 'size'") == Some(LogLevel::Unknown));
+/// assert!(try_parse_log_level("Error opening input file") == None);
+/// ```
+pub fn try_parse_log_level(string: &str) -> Option<LogLevel> {
+  let mut rest = string.trim_start();
+  loop {
+    let (token, after) = rest.strip_prefix('[')?.split_once(']')?;
+    match token {
+      "info" => return Some(LogLevel::Info),
+      "warning" => return Some(LogLevel::Warning),
+      "error" => return Some(LogLevel::Error),
+      "fatal" | "panic" => return Some(LogLevel::Fatal),
+      "verbose" | "debug" | "trace" => return Some(LogLevel::Unknown),
+      // The source of the message, like `[mp4 @ 0x7f8]` or `[out#0/mp4 @ 0x7f8]`
+      token if token.contains(" @ ") => rest = after.trim_start(),
+      _ => return None,
+    }
+  }
+}
+
+/// The level of a log line, from its level prefix if it has one. Otherwise
+/// guessed from the message, e.g. for a command without
+/// [`log_with_level_prefix`](crate::command::FfmpegCommand::log_with_level_prefix).
+fn log_level(string: &str) -> LogLevel {
+  if let Some(level) = try_parse_log_level(string) {
+    return level;
+  }
+  let lowercase = string.to_lowercase();
+  if lowercase.contains("error") {
+    LogLevel::Error
+  } else if lowercase.contains("warning") {
+    LogLevel::Warning
+  } else {
+    LogLevel::Unknown
+  }
+}
+
+/// Parse an output section like the following, extracting the index, format
+/// and path of the output:
 ///
 /// ## Example:
 ///
@@ -233,6 +618,7 @@ pub fn try_parse_duration(string: &str) -> Option<f64> {
 /// assert!(output == Some(FfmpegOutput {
 ///   index: 0,
 ///   to: "test.mp4".to_string(),
+///   format: "mp4".to_string(),
 ///   raw_log_message: line.to_string(),
 /// }));
 /// ```
@@ -252,16 +638,19 @@ pub fn try_parse_output(mut string: &str) -> Option<FfmpegOutput> {
     .and_then(|s| s.split(',').next())
     .and_then(|s| s.parse::<u32>().ok())?;
 
-  let to = string
-    .split(" to '")
-    .nth(1)?
-    .split('\'')
-    .next()?
+  let (format, to) = string.split_once(" to '")?;
+  let format = format
+    .split_once(',')?
+    .1
+    .trim()
+    .trim_end_matches(',')
     .to_string();
+  let to = to.split('\'').next()?.to_string();
 
   Some(FfmpegOutput {
     index,
     to,
+    format,
     raw_log_message,
   })
 }
@@ -288,8 +677,41 @@ pub fn try_parse_output(mut string: &str) -> Option<FfmpegOutput> {
 /// assert!(video_data.width == 320);
 /// assert!(video_data.height == 240);
 /// assert!(video_data.fps == 25.0);
+/// assert!(video_data.tbr == Some(25.0));
+/// assert!(video_data.sample_aspect_ratio.as_deref() == Some("1:1"));
+/// assert!(video_data.display_aspect_ratio.as_deref() == Some("4:3"));
+/// assert!(video_data.bitrate_kbps.is_none());
+/// assert!(video_data.color_range.is_none() && video_data.color_space.is_none());
 ///  ```
 ///
+/// Variable frame rate content reports a `tbr` different from its `fps`:
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_stream;
+/// let line = "[info]   Stream #0:0[0x1](und): Video: h264 (High) (avc1 / 0x31637661), yuv420p(tv, bt709, progressive), 1920x1080 [SAR 1:1 DAR 16:9], 5000 kb/s, 29.83 fps, 60 tbr, 90k tbn (default)\n";
+/// let video_data = try_parse_stream(line).unwrap().video_data().unwrap().clone();
+/// assert!(video_data.width == 1920 && video_data.height == 1080);
+/// assert!(video_data.bitrate_kbps == Some(5000));
+/// assert!(video_data.fps == 29.83);
+/// assert!(video_data.tbr == Some(60.0));
+/// assert!(video_data.display_aspect_ratio.as_deref() == Some("16:9"));
+/// assert!(video_data.color_transfer.as_deref() == Some("bt709"));
+/// ```
+///
+/// HDR streams report their color space, primaries and transfer
+/// characteristics after the pixel format:
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_stream;
+/// let line = "[info]   Stream #0:0: Video: hevc (Main 10), yuv420p10le(tv, bt2020nc/bt2020/smpte2084), 3840x2160 [SAR 1:1 DAR 16:9], 23.98 fps, 23.98 tbr, 1k tbn\n";
+/// let video_data = try_parse_stream(line).unwrap().video_data().unwrap().clone();
+/// assert!(video_data.pix_fmt == "yuv420p10le");
+/// assert!(video_data.color_range.as_deref() == Some("tv"));
+/// assert!(video_data.color_space.as_deref() == Some("bt2020nc"));
+/// assert!(video_data.color_primaries.as_deref() == Some("bt2020"));
+/// assert!(video_data.color_transfer.as_deref() == Some("smpte2084"));
+/// ```
+///
 ///  #### Output stream
 ///
 ///  ```rust
@@ -324,6 +746,8 @@ pub fn try_parse_output(mut string: &str) -> Option<FfmpegOutput> {
 /// let audio_data = stream.audio_data().unwrap();
 /// assert!(audio_data.sample_rate == 48000);
 /// assert!(audio_data.channels == "stereo");
+/// assert!(audio_data.channel_count == Some(2));
+/// assert!(audio_data.channel_layout.as_deref() == Some("stereo"));
 /// ```
 ///
 /// ```rust
@@ -338,6 +762,7 @@ pub fn try_parse_output(mut string: &str) -> Option<FfmpegOutput> {
 /// let audio_data = stream.audio_data().unwrap();
 /// assert!(audio_data.sample_rate == 48000);
 /// assert!(audio_data.channels == "7.1");
+/// assert!(audio_data.channel_count == Some(8));
 /// ```
 ///
 /// ### Output stream
@@ -354,6 +779,15 @@ pub fn try_parse_output(mut string: &str) -> Option<FfmpegOutput> {
 /// let audio_data = stream.audio_data().unwrap();
 /// assert!(audio_data.sample_rate == 44100);
 /// assert!(audio_data.channels == "mono");
+/// assert!(audio_data.channel_count == Some(1));
+/// ```
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_stream;
+/// let line = "[info]   Stream #0:1: Audio: pcm_s16le, 44100 Hz, 3 channels, s16, 2116 kb/s\n";
+/// let audio_data = try_parse_stream(line).unwrap().audio_data().unwrap().clone();
+/// assert!(audio_data.channel_count == Some(3));
+/// assert!(audio_data.channel_layout.is_none());
 /// ```
 ///
 /// ### Subtitle
@@ -369,6 +803,17 @@ pub fn try_parse_output(mut string: &str) -> Option<FfmpegOutput> {
 /// assert!(stream.parent_index == 0);
 /// assert!(stream.stream_index == 4);
 /// assert!(stream.is_subtitle());
+/// assert!(stream.dispositions == ["default", "forced"]);
+/// assert!(stream.is_default() && stream.is_forced());
+/// ```
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_stream;
+/// let line = "[info]   Stream #0:5(eng): Subtitle: subrip (hearing impaired)\n";
+/// let stream = try_parse_stream(line).unwrap();
+/// assert!(stream.format == "subrip");
+/// assert!(stream.has_disposition("hearing impaired"));
+/// assert!(!stream.is_default() && !stream.is_forced());
 /// ```
 ///
 /// ```rust
@@ -381,7 +826,7 @@ pub fn try_parse_output(mut string: &str) -> Option<FfmpegOutput> {
 /// assert!(stream.stream_index == 13);
 /// assert!(stream.is_subtitle());
 /// ```
-/// ### Other
+/// ### Data
 ///
 /// #### Input Stream
 ///
@@ -393,17 +838,43 @@ pub fn try_parse_output(mut string: &str) -> Option<FfmpegOutput> {
 /// assert!(stream.language == "und");
 /// assert!(stream.parent_index == 0);
 /// assert!(stream.stream_index == 2);
-/// assert!(stream.is_other());
+/// assert!(stream.is_data());
 /// ```
 ///
 /// ```rust
 /// use ffmpeg_sidecar::log_parser::try_parse_stream;
-/// let line = "[info]   Stream #0:2[0x3](eng): Data: bin_data (text / 0x74786574)\n";
+/// let line = "[info]   Stream #0:2[0x3](eng): Data: bin_data (gpmd / 0x646D7067), 61 kb/s (default)\n";
 /// let stream = try_parse_stream(line).unwrap();
 /// assert!(stream.format == "bin_data");
 /// assert!(stream.language == "eng");
 /// assert!(stream.parent_index == 0);
 /// assert!(stream.stream_index == 2);
+/// assert!(stream.is_data());
+/// ```
+///
+/// ### Attachment
+///
+/// #### Input Stream
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_stream;
+/// let line = "[info]   Stream #0:3: Attachment: ttf\n";
+/// let stream = try_parse_stream(line).unwrap();
+/// assert!(stream.format == "ttf");
+/// assert!(stream.stream_index == 3);
+/// assert!(stream.is_attachment());
+/// ```
+///
+/// ### Other
+///
+/// Unrecognized stream types are kept rather than skipped, so the streams of
+/// an input are always all counted.
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_stream;
+/// let line = "[info]   Stream #0:4: Unknown: none\n";
+/// let stream = try_parse_stream(line).unwrap();
+/// assert!(stream.format == "none");
 /// assert!(stream.is_other());
 /// ```
 pub fn try_parse_stream(string: &str) -> Option<Stream> {
@@ -446,6 +917,8 @@ pub fn try_parse_stream(string: &str) -> Option<Stream> {
     "Audio" => try_parse_audio_stream(comma_iter)?,
     "Subtitle" => StreamTypeSpecificData::Subtitle(),
     "Video" => try_parse_video_stream(comma_iter)?,
+    "Data" => StreamTypeSpecificData::Data(),
+    "Attachment" => StreamTypeSpecificData::Attachment(),
     _ => StreamTypeSpecificData::Other(),
   };
 
@@ -454,11 +927,57 @@ pub fn try_parse_stream(string: &str) -> Option<Stream> {
     language,
     parent_index,
     stream_index,
+    dispositions: parse_dispositions(&raw_log_message),
     raw_log_message,
     type_specific_data,
+    tags: HashMap::new(),
   })
 }
 
+/// The dispositions which ffmpeg prints in parentheses at the end of a stream
+/// line, from `dump_stream_format` in `libavformat/dump.c`.
+const DISPOSITIONS: [&str; 19] = [
+  "default",
+  "dub",
+  "original",
+  "comment",
+  "lyrics",
+  "karaoke",
+  "forced",
+  "hearing impaired",
+  "visual impaired",
+  "clean effects",
+  "attached pic",
+  "timed thumbnails",
+  "non-diegetic",
+  "captions",
+  "descriptions",
+  "metadata",
+  "dependent",
+  "still image",
+  "multilayer",
+];
+
+/// Collect the trailing disposition flags of a stream line, like `(default)
+/// (forced)`. Other parentheticals, like the profile in `h264 (High)`, stop
+/// the search.
+fn parse_dispositions(line: &str) -> Vec<String> {
+  let mut rest = line.trim_end();
+  let mut dispositions = Vec::new();
+  while let Some((before, flag)) = rest
+    .strip_suffix(')')
+    .and_then(|rest| rest.rsplit_once('('))
+  {
+    if !DISPOSITIONS.contains(&flag) {
+      break;
+    }
+    dispositions.push(flag.to_string());
+    rest = before.trim_end();
+  }
+  dispositions.reverse();
+  dispositions
+}
+
 /// Parses the log output part that is specific to audio streams.
 fn try_parse_audio_stream(mut comma_iter: CommaIter) -> Option<StreamTypeSpecificData> {
   let sample_rate = comma_iter
@@ -469,47 +988,175 @@ fn try_parse_audio_stream(mut comma_iter: CommaIter) -> Option<StreamTypeSpecifi
     .ok()?;
 
   let channels = comma_iter.next()?.trim().to_string();
+  let channel_count = parse_channel_count(&channels);
+  // Unnamed layouts are printed as a plain count, e.g. `3 channels`
+  let channel_layout = match channels.contains("channels") {
+    true => None,
+    false => Some(channels.clone()),
+  };
 
   Some(StreamTypeSpecificData::Audio(AudioStream {
     sample_rate,
     channels,
+    channel_count,
+    channel_layout,
   }))
 }
 
+/// Determine the number of channels from a channel layout as printed by
+/// ffmpeg, e.g. `stereo`, `5.1(side)`, `7.1.4` or `3 channels`.
+///
+/// ## Examples
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::parse_channel_count;
+/// assert!(parse_channel_count("mono") == Some(1));
+/// assert!(parse_channel_count("stereo") == Some(2));
+/// assert!(parse_channel_count("5.1(side)") == Some(6));
+/// assert!(parse_channel_count("7.1.4") == Some(12));
+/// assert!(parse_channel_count("3 channels (FL+FR+LFE)") == Some(3));
+/// assert!(parse_channel_count("unknown") == None);
+/// ```
+pub fn parse_channel_count(layout: &str) -> Option<u16> {
+  let layout = layout.trim();
+  if let Some((count, _)) = layout.split_once(" channels") {
+    return count.trim().parse().ok();
+  }
+
+  // Strip variants like `(side)` or `(wide)`
+  let name = layout.split('(').next()?.trim();
+  match name {
+    "mono" => Some(1),
+    "stereo" | "downmix" | "binaural" => Some(2),
+    "quad" => Some(4),
+    "hexagonal" => Some(6),
+    "octagonal" | "cube" => Some(8),
+    "hexadecagonal" => Some(16),
+    // Numeric layouts like `5.1` sum to their channel count
+    _ => name
+      .split('.')
+      .map(|n| n.parse::<u16>().ok())
+      .sum::<Option<u16>>()
+      .filter(|_| name.contains('.')),
+  }
+}
+
 /// Parses the log output part that is specific to video streams.
 fn try_parse_video_stream(mut comma_iter: CommaIter) -> Option<StreamTypeSpecificData> {
-  let pix_fmt = comma_iter
-    .next()?
-    .trim()
+  let pix_fmt_part = comma_iter.next()?.trim();
+  let pix_fmt = pix_fmt_part
     .split(&[' ', '(']) // trim trailing junk like "(tv, progressive)"
     .next()?
     .to_string();
+  let color = parse_color_info(pix_fmt_part);
 
-  let dims = comma_iter.next()?.split_whitespace().next()?;
+  // e.g. `1920x1080 [SAR 1:1 DAR 16:9]`
+  let mut dims_part = comma_iter.next()?.split_whitespace();
+  let dims = dims_part.next()?;
   let mut dims_iter = dims.split('x');
   let width = dims_iter.next()?.parse::<u32>().ok()?;
   let height = dims_iter.next()?.parse::<u32>().ok()?;
+  let mut sample_aspect_ratio = None;
+  let mut display_aspect_ratio = None;
+  while let Some(token) = dims_part.next() {
+    let ratio = dims_part
+      .next()
+      .map(|r| r.trim_end_matches(']').to_string());
+    match token {
+      "[SAR" => sample_aspect_ratio = ratio,
+      "DAR" => display_aspect_ratio = ratio,
+      _ => {}
+    }
+  }
 
-  // FPS does not have to be the next part, so we iterate until we find it. There is nothing else we
-  // are interested in at this point, so its OK to skip anything in-between.
-  let fps = comma_iter
-    .find_map(|part| {
-      if part.trim().ends_with("fps") {
-        part.split_whitespace().next()
-      } else {
-        None
-      }
-    })
-    .and_then(|fps_str| fps_str.parse::<f32>().ok())?;
+  // The remaining parts are optional and vary between streams, like `q=2-31`
+  // or `(default)`, so pick out the known ones by their unit.
+  let mut fps = None;
+  let mut tbr = None;
+  let mut bitrate_kbps = None;
+  for part in comma_iter {
+    let part = part.trim();
+    if let Some(value) = part.strip_suffix(" fps") {
+      fps = parse_rate(value);
+    } else if let Some(value) = part.strip_suffix(" tbr") {
+      tbr = parse_rate(value);
+    } else if let Some(value) = part.strip_suffix(" kb/s") {
+      bitrate_kbps = value.trim().parse::<u32>().ok();
+    }
+  }
 
   Some(StreamTypeSpecificData::Video(VideoStream {
     pix_fmt,
     width,
     height,
-    fps,
+    fps: fps?,
+    tbr,
+    sample_aspect_ratio,
+    display_aspect_ratio,
+    bitrate_kbps,
+    color_range: color.range,
+    color_space: color.space,
+    color_primaries: color.primaries,
+    color_transfer: color.transfer,
   }))
 }
 
+/// The color properties in the parentheses after a pixel format.
+#[derive(Default)]
+struct ColorInfo {
+  range: Option<String>,
+  space: Option<String>,
+  primaries: Option<String>,
+  transfer: Option<String>,
+}
+
+/// Parse the color properties from a pixel format like
+/// `yuv420p10le(tv, bt2020nc/bt2020/smpte2084, progressive)`. FFmpeg prints
+/// the color space, primaries and transfer separated by `/`, or a single name
+/// if all three are the same, like `bt709`.
+fn parse_color_info(pix_fmt: &str) -> ColorInfo {
+  // Field orders and chroma locations, which may follow the colors
+  const OTHER_PROPERTIES: &[&str] = &[
+    "progressive",
+    "top first",
+    "bottom first",
+    "top coded first (swapped)",
+    "bottom coded first (swapped)",
+    "left",
+    "center",
+    "topleft",
+    "top",
+    "bottomleft",
+    "bottom",
+  ];
+
+  let mut info = ColorInfo::default();
+  let Some((_, properties)) = pix_fmt.split_once('(') else {
+    return info;
+  };
+  let properties = properties.strip_suffix(')').unwrap_or(properties);
+  for property in properties.split(',').map(str::trim) {
+    if property == "tv" || property == "pc" {
+      info.range = Some(property.to_string());
+    } else if info.space.is_none() && !OTHER_PROPERTIES.contains(&property) {
+      let mut names = property.split('/');
+      let space = names.next().unwrap_or(property);
+      info.space = Some(space.to_string());
+      info.primaries = Some(names.next().unwrap_or(space).to_string());
+      info.transfer = Some(names.next().unwrap_or(space).to_string());
+    }
+  }
+  info
+}
+
+/// Parse a rate like `29.97` or `1k`, as printed for `fps` and `tbr`.
+fn parse_rate(value: &str) -> Option<f32> {
+  match value.trim().strip_suffix('k') {
+    Some(thousands) => thousands.parse::<f32>().ok().map(|n| n * 1000.0),
+    None => value.trim().parse::<f32>().ok(),
+  }
+}
+
 /// Parse a progress update line from ffmpeg.
 ///
 /// ## Example
@@ -518,101 +1165,824 @@ fn try_parse_video_stream(mut comma_iter: CommaIter) -> Option<StreamTypeSpecifi
 /// let line = "[info] frame= 1996 fps=1984 q=-1.0 Lsize=     372kB time=00:01:19.72 bitrate=  38.2kbits/s speed=79.2x\n";
 /// let progress = try_parse_progress(line).unwrap();
 /// assert!(progress.frame == 1996);
-/// assert!(progress.fps == 1984.0);
+/// assert!(progress.fps == Some(1984.0));
 /// assert!(progress.q == -1.0);
 /// assert!(progress.size_kb == 372);
 /// assert!(progress.time == "00:01:19.72");
-/// assert!(progress.bitrate_kbps == 38.2);
-/// assert!(progress.speed == 79.2);
+/// assert!(progress.bitrate_kbps == Some(38.2));
+/// assert!(progress.speed == Some(79.2));
+/// ```
+///
+/// Audio-only outputs omit `frame`, `fps` and `q`:
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_progress;
+/// let line = "[info] size=     512KiB time=00:00:30.00 bitrate= 139.8kbits/s speed=  60x";
+/// let progress = try_parse_progress(line).unwrap();
+/// assert!(progress.frame == 0);
+/// assert!(progress.fps.is_none());
+/// assert!(progress.speed == Some(60.0));
 /// ```
 pub fn try_parse_progress(mut string: &str) -> Option<FfmpegProgress> {
   let raw_log_message = string.to_string();
 
   string = string.strip_prefix("[info]").unwrap_or(string).trim();
 
-  let frame = string
-    .split("frame=")
-    .nth(1)?
-    .split_whitespace()
-    .next()?
-    .parse::<u32>()
-    .ok()?;
-  let fps = string
-    .split("fps=")
-    .nth(1)?
-    .split_whitespace()
-    .next()?
-    .parse::<f32>()
-    .ok()?;
-  let q = string
-    .split("q=")
-    .nth(1)?
-    .split_whitespace()
-    .next()?
-    .parse::<f32>()
-    .ok()?;
-  let size_kb = string
+  // Omitted for audio-only outputs
+  let field = |key: &str| string.split(key).nth(1)?.split_whitespace().next();
+  let frame = match field("frame=") {
+    Some(frame) => frame.parse::<u32>().ok()?,
+    None => 0,
+  };
+  let fps = match field("fps=") {
+    Some(fps) => Some(fps.parse::<f32>().ok()?),
+    None => None,
+  };
+  let fps = reported_fps(fps, parse_elapsed(string));
+  let q = match field("q=") {
+    Some(q) => q.parse::<f32>().ok()?,
+    None => 0.0,
+  };
+  let size = string
     .split("size=") // captures "Lsize=" AND "size="
     .nth(1)?
     .split_whitespace()
-    .next()
-    .map(|s| s.trim())
-    .and_then(|s| {
-      s.strip_suffix("KiB") // FFmpeg v7.0 and later
-        .or_else(|| s.strip_suffix("kB")) // FFmpeg v6.0 and prior
-    })?
-    .parse::<u32>()
-    .ok()?;
+    .next()?
+    .trim();
+  // `N/A` for outputs without a size, like `-f null`
+  let size_kb = match size {
+    "N/A" => None,
+    _ => Some(
+      size
+        .strip_suffix("KiB") // FFmpeg v7.0 and later
+        .or_else(|| size.strip_suffix("kB"))? // FFmpeg v6.0 and prior
+        .parse::<u32>()
+        .ok()?,
+    ),
+  };
   let time = string
     .split("time=")
     .nth(1)?
     .split_whitespace()
     .next()?
     .to_string();
-  let bitrate_kbps = string
-    .split("bitrate=")
-    .nth(1)?
-    .split_whitespace()
-    .next()?
-    .trim()
-    .replace("kbits/s", "")
-    .parse::<f32>()
-    .unwrap_or(0.0); // handles "N/A"
-  let speed = string
-    .split("speed=")
-    .nth(1)?
-    .split_whitespace()
-    .next()?
-    .strip_suffix('x')
-    .map(|s| s.parse::<f32>().unwrap_or(0.0))
-    .unwrap_or(0.0);
+  let out_time = parse_out_time(&time);
+  let total_size = size_kb.map(|size_kb| size_kb as u64 * 1024);
+  let bitrate_kbps =
+    parse_bitrate_kbps(string.split("bitrate=").nth(1)?.split_whitespace().next()?);
+  let speed = parse_speed(string.split("speed=").nth(1)?.split_whitespace().next()?);
+  // Only present once frames have been duplicated or dropped
+  let dup_frames = string
+    .split(" dup=")
+    .nth(1)
+    .and_then(|s| s.split_whitespace().next()?.parse::<u32>().ok());
+  let drop_frames = string
+    .split(" drop=")
+    .nth(1)
+    .and_then(|s| s.split_whitespace().next()?.parse::<u32>().ok());
 
   Some(FfmpegProgress {
     frame,
     fps,
     q,
-    size_kb,
+    size_kb: size_kb.unwrap_or(0),
+    total_size,
+    out_time,
     time,
     bitrate_kbps,
     speed,
+    dup_frames,
+    drop_frames,
+    dropped_frames: drop_frames.unwrap_or(0),
+    fraction: None,
+    eta: None,
     raw_log_message,
   })
 }
 
-/// Parse a time string in the format `HOURS:MM:SS.MILLISECONDS` into a number of seconds.
-///
-/// <https://trac.ffmpeg.org/wiki/Seeking#Timeunitsyntax>
+/// Build the final stats from the progress parsed from the last status line,
+/// adding the `elapsed=` field printed by FFmpeg 6.1 and later.
+fn summarize_progress(progress: &FfmpegProgress) -> FfmpegSummary {
+  let elapsed = parse_elapsed(&progress.raw_log_message);
+  FfmpegSummary {
+    frames: progress.frame,
+    fps: progress.fps.unwrap_or(0.0),
+    speed: progress.speed,
+    elapsed,
+    dropped_frames: progress.dropped_frames,
+    total_size: progress.total_size,
+    raw_log_message: progress.raw_log_message.clone(),
+  }
+}
+
+/// Whether a line is part of a `-progress` report, like `out_time_us=1000000`
+/// or `bitrate=  38.2kbits/s`, rather than a log message or status line.
+fn is_progress_line(line: &str) -> bool {
+  line.split_once('=').is_some_and(|(key, value)| {
+    !key.is_empty()
+      && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+      && !value.trim().contains(char::is_whitespace)
+  })
+}
+
+/// Parse `bench: utime=0.456s stime=0.012s rtime=0.478s`.
+fn try_parse_benchmark_times(string: &str) -> Option<(Duration, Duration, Duration)> {
+  let (_, fields) = string.split_once("bench: ")?;
+  let time = |key: &str| -> Option<Duration> {
+    let value = fields
+      .split_whitespace()
+      .find_map(|f| f.strip_prefix(key))?;
+    Duration::try_from_secs_f64(value.strip_suffix('s')?.parse().ok()?).ok()
+  };
+  Some((time("utime=")?, time("stime=")?, time("rtime=")?))
+}
+
+/// Parse `bench: maxrss=123456KiB`, or `kB` before FFmpeg 6.1.
+fn try_parse_benchmark_max_rss(string: &str) -> Option<u64> {
+  let (_, rest) = string.split_once("bench: maxrss=")?;
+  let digits = rest.trim_end_matches(|c: char| !c.is_ascii_digit());
+  digits.parse().ok()
+}
+
+/// Recognize an error or fatal log message caused by a common failure, like a
+/// missing input file.
 ///
 /// ## Examples
-///
 /// ```rust
-/// use ffmpeg_sidecar::log_parser::parse_time_str;
-/// assert!(parse_time_str("00:00:00.00") == Some(0.0));
-/// assert!(parse_time_str("5") == Some(5.0));
-/// assert!(parse_time_str("0.123") == Some(0.123));
-/// assert!(parse_time_str("1:00.0") == Some(60.0));
-/// assert!(parse_time_str("1:01.0") == Some(61.0));
-/// assert!(parse_time_str("1:01:01.123") == Some(3661.123));
+/// use ffmpeg_sidecar::event::{FfmpegErrorKind, HardwareBackend, LogLevel};
+/// use ffmpeg_sidecar::log_parser::try_parse_error;
+///
+/// let line = "[error] missing.mp4: No such file or directory";
+/// let error = try_parse_error(line).unwrap();
+/// assert!(error.kind == FfmpegErrorKind::NoSuchFile);
+/// assert!(error.level == LogLevel::Error);
+///
+/// let line = "[vost#0:0 @ 0x600003bd4000] [fatal] Unknown encoder 'libfoo'";
+/// let error = try_parse_error(line).unwrap();
+/// assert!(error.kind == FfmpegErrorKind::UnknownEncoder);
+/// assert!(error.level == LogLevel::Fatal);
+///
+/// let line = "[AVHWDeviceContext @ 0x5581] [error] Cannot load libcuda.so.1";
+/// let error = try_parse_error(line).unwrap();
+/// assert!(error.kind == FfmpegErrorKind::HardwareInit(Some(HardwareBackend::Cuda)));
+///
+/// let line = "[h264_nvenc @ 0x5581] [error] OpenEncodeSessionEx failed: out of memory (10): (no details)";
+/// let error = try_parse_error(line).unwrap();
+/// assert!(error.kind == FfmpegErrorKind::HardwareExhausted(Some(HardwareBackend::Cuda)));
+///
+/// let line = "[mov,mp4,m4a,3gp,3g2,mj2 @ 0x5581] [warning] Could not find codec parameters for stream 1 (Video: h264 (avc1 / 0x31637661), none, 1920x1080): unspecified pixel format";
+/// let error = try_parse_error(line).unwrap();
+/// assert!(error.kind == FfmpegErrorKind::UnprobeableStream(Some(1)));
+/// assert!(error.level == LogLevel::Warning);
+///
+/// assert!(try_parse_error("[info] No such file or directory").is_none());
+/// assert!(try_parse_error("[error] Something else went wrong").is_none());
+/// ```
+pub fn try_parse_error(string: &str) -> Option<FfmpegLogError> {
+  let kind = try_parse_error_kind(string)?;
+  let level = match log_level(string) {
+    level @ (LogLevel::Error | LogLevel::Fatal) => level,
+    LogLevel::Warning if matches!(kind, FfmpegErrorKind::UnprobeableStream(_)) => LogLevel::Warning,
+    _ => return None,
+  };
+
+  Some(FfmpegLogError {
+    kind,
+    level,
+    raw_log_message: string.to_string(),
+  })
+}
+
+fn try_parse_error_kind(string: &str) -> Option<FfmpegErrorKind> {
+  let kind = if string.contains("No such file or directory") {
+    FfmpegErrorKind::NoSuchFile
+  } else if string.contains("Invalid data found when processing input") {
+    FfmpegErrorKind::InvalidData
+  } else if string.contains("Permission denied") {
+    FfmpegErrorKind::PermissionDenied
+  } else if string.contains("Unknown encoder") || string.contains("Encoder not found") {
+    FfmpegErrorKind::UnknownEncoder
+  } else if is_hardware_exhaustion(string) {
+    FfmpegErrorKind::HardwareExhausted(try_parse_hardware_backend(string))
+  } else if is_hardware_init_failure(string) {
+    FfmpegErrorKind::HardwareInit(try_parse_hardware_backend(string))
+  } else if let Some((_, rest)) = string.split_once("Could not find codec parameters for stream ") {
+    let index = rest
+      .split(|c: char| !c.is_ascii_digit())
+      .next()
+      .and_then(|index| index.parse().ok());
+    FfmpegErrorKind::UnprobeableStream(index)
+  } else if string.contains("could not find corresponding") {
+    FfmpegErrorKind::UnprobeableStream(None)
+  } else {
+    return None;
+  };
+  Some(kind)
+}
+
+/// Parse a line from the `Stream mapping:` block, which ffmpeg prints before
+/// processing starts.
+///
+/// ## Examples
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_stream_mapping;
+///
+/// let line = "[info]   Stream #0:0 -> #0:0 (h264 (native) -> hevc (libx265))";
+/// let mapping = try_parse_stream_mapping(line).unwrap();
+/// assert!(mapping.source == "0:0" && mapping.dest == "0:0");
+/// assert!(mapping.decoder.as_deref() == Some("h264"));
+/// assert!(mapping.encoder.as_deref() == Some("libx265"));
+///
+/// let line = "[info]   Stream #0:1 -> #0:1 (copy)";
+/// let mapping = try_parse_stream_mapping(line).unwrap();
+/// assert!(mapping.copy && mapping.encoder.is_none());
+///
+/// let line = "[info]   Stream #1:0 (aac) -> amix:input1 (graph 0)";
+/// let mapping = try_parse_stream_mapping(line).unwrap();
+/// assert!(mapping.source == "1:0" && mapping.dest == "amix:input1");
+/// assert!(mapping.decoder.as_deref() == Some("aac") && mapping.encoder.is_none());
+///
+/// assert!(try_parse_stream_mapping("[info] Stream mapping:").is_none());
+/// ```
+pub fn try_parse_stream_mapping(string: &str) -> Option<FfmpegStreamMapping> {
+  let content = string.strip_prefix("[info]").unwrap_or(string).trim();
+  let (source, dest) = split_top_level(content, " -> ")?;
+  let (source, source_codec) = split_mapping_pad(source);
+  let (dest, dest_codec) = split_mapping_pad(dest);
+
+  let mut mapping = FfmpegStreamMapping {
+    source: source.to_string(),
+    dest: dest.to_string(),
+    decoder: None,
+    encoder: None,
+    copy: false,
+    raw_log_message: string.to_string(),
+  };
+  match dest_codec.as_deref() {
+    Some("copy") => mapping.copy = true,
+    Some(codecs) => match split_top_level(codecs, " -> ") {
+      Some((decoder, encoder)) => {
+        mapping.decoder = Some(codec_implementation(decoder));
+        mapping.encoder = Some(codec_implementation(encoder));
+      }
+      // Filter graph outputs list only the encoder
+      None if !codecs.starts_with("graph ") => mapping.encoder = Some(codec_implementation(codecs)),
+      None => {}
+    },
+    None => {}
+  }
+  // Filter graph inputs list only the decoder
+  if let Some(codec) = source_codec {
+    mapping.decoder = Some(codec_implementation(&codec));
+  }
+  Some(mapping)
+}
+
+/// Split `string` at the first `delimiter` outside of parentheses.
+fn split_top_level<'a>(string: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+  let mut depth = 0;
+  for (i, c) in string.char_indices() {
+    match c {
+      '(' => depth += 1,
+      ')' => depth -= 1,
+      _ if depth == 0 && string[i..].starts_with(delimiter) => {
+        return Some((&string[..i], &string[i + delimiter.len()..]));
+      }
+      _ => {}
+    }
+  }
+  None
+}
+
+/// Split one side of a stream mapping like `#0:0 (copy)` or `Stream #0:1` into
+/// the stream or pad name and the contents of the trailing parentheses.
+fn split_mapping_pad(side: &str) -> (&str, Option<String>) {
+  let side = side.trim();
+  let (name, codecs) = match side.find(" (") {
+    Some(i) if side.ends_with(')') => (&side[..i], Some(side[i + 2..side.len() - 1].to_string())),
+    _ => (side, None),
+  };
+  let name = name.trim_start_matches("Stream ").trim_start_matches('#');
+  (name, codecs)
+}
+
+/// The decoder or encoder in a codec description like `h264 (h264_cuvid)`,
+/// where `native` means the one named after the codec.
+fn codec_implementation(codec: &str) -> String {
+  let codec = codec.trim();
+  match codec.split_once(" (") {
+    Some((name, "native)")) => name.to_string(),
+    Some((_, implementation)) => implementation.trim_end_matches(')').to_string(),
+    None => codec.to_string(),
+  }
+}
+
+/// Messages logged when a hardware device, or a codec relying on one, can't be
+/// initialized, e.g. because the driver or GPU is missing.
+const HARDWARE_INIT_FAILURES: &[&str] = &[
+  "Cannot load cuda",
+  "Cannot load libcuda",
+  "Cannot load nvcuda",
+  "Cannot load libnvidia-encode",
+  "Cannot init CUDA",
+  "No NVENC capable devices found",
+  "OpenEncodeSessionEx failed",
+  "Failed to initialise VAAPI connection",
+  "No VA display found",
+  "Error initializing an MFX session",
+  "Error creating a MFX session",
+  "Error initializing the MFX video",
+  "Failed to create Direct3D device",
+  "Failed to create D3D11VA video device",
+  "Failed to create VideoToolbox session",
+  "Device creation failed",
+  "for option 'init_hw_device'",
+  "No device available for decoder",
+  "Failed setup for format",
+];
+
+/// Messages of a hardware device running out of memory or encoder sessions,
+/// which are checked before `HARDWARE_INIT_FAILURES` since some of them share
+/// a prefix, like `OpenEncodeSessionEx failed`.
+const HARDWARE_EXHAUSTION: &[&str] = &[
+  "CUDA_ERROR_OUT_OF_MEMORY",
+  "NV_ENC_ERR_OUT_OF_MEMORY",
+  "OpenEncodeSessionEx failed: out of memory",
+  // Reported by older drivers once the session limit is reached
+  "OpenEncodeSessionEx failed: incompatible client key",
+  "VA_STATUS_ERROR_ALLOCATION_FAILED",
+  "MFX_ERR_MEMORY_ALLOC",
+];
+
+fn is_hardware_exhaustion(string: &str) -> bool {
+  HARDWARE_EXHAUSTION
+    .iter()
+    .any(|pattern| string.contains(pattern))
+}
+
+fn is_hardware_init_failure(string: &str) -> bool {
+  HARDWARE_INIT_FAILURES
+    .iter()
+    .any(|pattern| string.contains(pattern))
+}
+
+/// Identify the hardware acceleration API named in a log message, if any.
+///
+/// ## Examples
+/// ```rust
+/// use ffmpeg_sidecar::event::HardwareBackend;
+/// use ffmpeg_sidecar::log_parser::try_parse_hardware_backend;
+///
+/// let line = "[AVHWDeviceContext @ 0x55d0] [error] Failed to initialise VAAPI connection: -1 (unknown libva error).";
+/// assert!(try_parse_hardware_backend(line) == Some(HardwareBackend::Vaapi));
+///
+/// let line = "[h264_nvenc @ 0x55d0] [error] OpenEncodeSessionEx failed: unsupported device (2): (no details)";
+/// assert!(try_parse_hardware_backend(line) == Some(HardwareBackend::Cuda));
+///
+/// assert!(try_parse_hardware_backend("[error] Device creation failed: -12.").is_none());
+/// ```
+pub fn try_parse_hardware_backend(string: &str) -> Option<HardwareBackend> {
+  let lower = string.to_lowercase();
+  let backends = [
+    (["cuda", "nvenc", "nvdec"].as_slice(), HardwareBackend::Cuda),
+    (&["cuvid", "nvidia"], HardwareBackend::Cuda),
+    (&["vaapi", "va display", "libva"], HardwareBackend::Vaapi),
+    (&["qsv", "mfx"], HardwareBackend::Qsv),
+    (&["videotoolbox"], HardwareBackend::VideoToolbox),
+    (&["d3d11"], HardwareBackend::D3d11va),
+    (&["dxva2", "direct3d"], HardwareBackend::Dxva2),
+    (&["vulkan"], HardwareBackend::Vulkan),
+    (&["_amf", "amf "], HardwareBackend::Amf),
+  ];
+  backends
+    .iter()
+    .find(|(keywords, _)| keywords.iter().any(|keyword| lower.contains(keyword)))
+    .map(|(_, backend)| *backend)
+}
+
+/// Parse the line ffmpeg prints after finishing an output, with the size of
+/// each kind of stream and the container overhead.
+///
+/// ## Examples
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_muxing_stats;
+///
+/// let line = "[out#0/mp4 @ 0x600000f0c000] [info] video:1234KiB audio:456KiB subtitle:0KiB other streams:0KiB global headers:0KiB muxing overhead: 0.345678%";
+/// let stats = try_parse_muxing_stats(line).unwrap();
+/// assert!(stats.output_index == Some(0));
+/// assert!(stats.video_kb == 1234 && stats.audio_kb == 456 && stats.subtitle_kb == 0);
+/// assert!(stats.muxing_overhead == Some(0.345678));
+///
+/// // FFmpeg 6.0 and older
+/// let line = "[info] video:0kB audio:188kB subtitle:0kB other streams:0kB global headers:0kB muxing overhead: unknown";
+/// let stats = try_parse_muxing_stats(line).unwrap();
+/// assert!(stats.output_index.is_none());
+/// assert!(stats.audio_kb == 188);
+/// assert!(stats.muxing_overhead.is_none());
+///
+/// assert!(try_parse_muxing_stats("[info] video:1234kB").is_none());
+/// ```
+pub fn try_parse_muxing_stats(string: &str) -> Option<FfmpegMuxingStats> {
+  let (_, overhead) = string.split_once("muxing overhead:")?;
+  let size = |key: &str| -> Option<u64> {
+    let (_, rest) = string.split_once(key)?;
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+  };
+  let output_index = string.split_once("[out#").and_then(|(_, rest)| {
+    rest
+      .split(|c: char| !c.is_ascii_digit())
+      .next()?
+      .parse()
+      .ok()
+  });
+
+  Some(FfmpegMuxingStats {
+    output_index,
+    video_kb: size("video:")?,
+    audio_kb: size("audio:")?,
+    subtitle_kb: size("subtitle:").unwrap_or(0),
+    other_kb: size("other streams:").unwrap_or(0),
+    global_headers_kb: size("global headers:").unwrap_or(0),
+    muxing_overhead: overhead.trim().trim_end_matches('%').parse().ok(),
+    raw_log_message: string.to_string(),
+  })
+}
+
+/// Parse the number of repeats from the line ffmpeg prints in place of a run
+/// of identical messages.
+///
+/// ## Examples
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_repeated;
+/// let line = "[mp4 @ 0x5581] [warning]     Last message repeated 1234 times";
+/// assert!(try_parse_repeated(line) == Some(1234));
+/// assert!(try_parse_repeated("[info] Last message repeated 1 times") == Some(1));
+/// assert!(try_parse_repeated("[info] Last message was dropped") == None);
+/// ```
+pub fn try_parse_repeated(string: &str) -> Option<u32> {
+  let (_, rest) = string.split_once("Last message repeated ")?;
+  rest.strip_suffix(" times")?.trim().parse().ok()
+}
+
+/// Parse a message about duplicated or dropped frames, which ffmpeg logs at
+/// `-loglevel verbose` when converting between framerates, or about frames
+/// dropped because the output or a capture device couldn't keep up.
+///
+/// ## Examples
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_dup_drop;
+/// let dup = try_parse_dup_drop("[verbose] *** 3 dup!").unwrap();
+/// assert!(dup.dup == 3 && dup.drop == 0);
+///
+/// let drop = try_parse_dup_drop("[verbose] *** drop!").unwrap();
+/// assert!(drop.dup == 0 && drop.drop == 1);
+///
+/// let drop = try_parse_dup_drop("[verbose] *** dropping frame 12 from stream 0 at ts 11").unwrap();
+/// assert!(drop.drop == 1);
+///
+/// let drop = try_parse_dup_drop("[vost#0:0/libx264 @ 0x600] [warning] 12 frames dropped").unwrap();
+/// assert!(drop.drop == 12);
+///
+/// let capture = "[dshow @ 0x600] [error] real-time buffer [screen-capture-recorder] [video input] too full or near too full (101% of size: 3041280 [rtbufsize parameter])! frame dropped!";
+/// assert!(try_parse_dup_drop(capture).unwrap().drop == 1);
+///
+/// let progress = "[info] frame=  100 fps=0.0 q=-1.0 size=10KiB time=00:00:04.00 bitrate=20.0kbits/s dup=3 drop=1 speed=8x";
+/// assert!(try_parse_dup_drop(progress).is_none());
+/// ```
+pub fn try_parse_dup_drop(string: &str) -> Option<FfmpegDupDrop> {
+  let (dup, drop) = match string.split_once("*** ") {
+    Some((_, message)) => {
+      let message = message.trim();
+      if let Some(count) = message.strip_suffix(" dup!") {
+        (count.trim().parse::<u32>().ok()?, 0)
+      } else if message == "drop!" || message.starts_with("dropping frame") {
+        (0, 1)
+      } else {
+        return None;
+      }
+    }
+    None if is_capture_drop(string) => (0, 1),
+    None => {
+      let message = string.trim_end();
+      let count = message
+        .strip_suffix(" frames dropped")
+        .or_else(|| message.strip_suffix(" frame dropped"))?;
+      (0, count.rsplit(' ').next()?.parse::<u32>().ok()?)
+    }
+  };
+
+  Some(FfmpegDupDrop {
+    dup,
+    drop,
+    raw_log_message: string.to_string(),
+  })
+}
+
+/// Whether a line reports a frame dropped by a capture device, like dshow,
+/// because its real-time buffer is full.
+fn is_capture_drop(string: &str) -> bool {
+  string.trim_end().ends_with("frame dropped!")
+}
+
+/// Recognize a recurring warning about timestamps or bitstreams, with a
+/// `count` of 1. Like `Non-monotonic DTS`, some of them are logged at the
+/// error level.
+///
+/// ## Examples
+/// ```rust
+/// use ffmpeg_sidecar::event::{FfmpegWarningKind, LogLevel};
+/// use ffmpeg_sidecar::log_parser::try_parse_warning;
+///
+/// let line = "[mp4 @ 0x7f8] [warning] Non-monotonic DTS in output stream 0:1; previous: 1024, current: 1000; changing to 1025. This may result in incorrect timestamps in the output file.";
+/// assert!(try_parse_warning(line).unwrap().kind == FfmpegWarningKind::NonMonotonicDts);
+///
+/// let line = "[mp4 @ 0x7f8] [error] Application provided invalid, non monotonically increasing dts to muxer in stream 0: 512 >= 256";
+/// let warning = try_parse_warning(line).unwrap();
+/// assert!(warning.kind == FfmpegWarningKind::InvalidDts);
+/// assert!(warning.level == LogLevel::Error);
+///
+/// let line = "[mp4 @ 0x7f8] [error] Malformed AAC bitstream detected: use the audio bitstream filter 'aac_adtstoasc' to fix it ('-bsf:a aac_adtstoasc' option with ffmpeg)";
+/// assert!(try_parse_warning(line).unwrap().kind == FfmpegWarningKind::MalformedBitstream);
+///
+/// assert!(try_parse_warning("[warning] Something else").is_none());
+/// ```
+pub fn try_parse_warning(string: &str) -> Option<FfmpegLogWarning> {
+  let kind = if string.contains("Non-monotonic DTS") || string.contains("Non-monotonous DTS") {
+    FfmpegWarningKind::NonMonotonicDts
+  } else if string.contains("non monotonically increasing dts") {
+    FfmpegWarningKind::InvalidDts
+  } else if string.contains("bitstream filter") && string.contains("alformed") {
+    FfmpegWarningKind::MalformedBitstream
+  } else {
+    return None;
+  };
+
+  Some(FfmpegLogWarning {
+    kind,
+    level: log_level(string),
+    count: 1,
+    raw_log_message: string.to_string(),
+  })
+}
+
+/// Parse a warning about a deprecated option, like `-vsync is deprecated. Use
+/// -fps_mode`. The suggested replacement is only picked up if it's an option
+/// starting with `-`, following `use`.
+///
+/// ## Examples
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_deprecation;
+///
+/// let line = "[warning] -vsync is deprecated. Use -fps_mode";
+/// let deprecation = try_parse_deprecation(line).unwrap();
+/// assert!(deprecation.option == "-vsync");
+/// assert!(deprecation.suggested.as_deref() == Some("-fps_mode"));
+///
+/// let line = "[warning] -filter_complex_script is deprecated, use -/filter_complex /path/to/script instead";
+/// assert!(try_parse_deprecation(line).unwrap().suggested.as_deref() == Some("-/filter_complex"));
+///
+/// let line = "[ass @ 0x5590] [warning] The \"sub_charenc_mode\" option is deprecated";
+/// let deprecation = try_parse_deprecation(line).unwrap();
+/// assert!(deprecation.option == "sub_charenc_mode");
+/// assert!(deprecation.suggested.is_none());
+///
+/// // Not about an option
+/// let line = "[swscaler @ 0x7f8] [warning] deprecated pixel format used, make sure you did set range correctly";
+/// assert!(try_parse_deprecation(line).is_none());
+/// ```
+pub fn try_parse_deprecation(string: &str) -> Option<FfmpegDeprecation> {
+  let (before, after) = string.split_once(" deprecated")?;
+  let subject = before
+    .strip_suffix(" is")
+    .or_else(|| before.strip_suffix(" are"))?;
+  let subject = subject.strip_suffix(" option").unwrap_or(subject);
+  let option = subject
+    .split_whitespace()
+    .last()?
+    .trim_matches(|c| matches!(c, '"' | '\'' | '`'));
+  if option.is_empty() || option.starts_with('[') {
+    return None;
+  }
+
+  let suggested = after
+    .split_once("use ")
+    .or_else(|| after.split_once("Use "))
+    .and_then(|(_, rest)| rest.split_whitespace().next())
+    .map(|s| s.trim_end_matches(['.', ',', ';']))
+    .filter(|s| s.starts_with('-') && s.len() > 1)
+    .map(|s| s.to_string());
+
+  Some(FfmpegDeprecation {
+    option: option.to_string(),
+    suggested,
+    raw_log_message: string.to_string(),
+  })
+}
+
+/// Parse a block of `key=value` lines written by `-progress`, terminated by a
+/// `progress=continue` or `progress=end` line.
+///
+/// Bitrate and speed are `None` when reported as `N/A`. Other fields which
+/// ffmpeg reports as `N/A` (or omits, like `q` for audio-only outputs) default
+/// to zero.
+///
+/// ## Example
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::try_parse_progress_block;
+/// let block = "frame=250\nfps=97.63\nstream_0_0_q=-1.0\nbitrate=  38.2kbits/s\n\
+///   total_size=393264\nout_time_us=10000000\nout_time_ms=10000000\n\
+///   out_time=00:00:10.000000\ndup_frames=0\ndrop_frames=0\nspeed=3.9x\nprogress=end\n";
+/// let progress = try_parse_progress_block(block).unwrap();
+/// assert!(progress.frame == 250);
+/// assert!(progress.fps == Some(97.63));
+/// assert!(progress.q == -1.0);
+/// assert!(progress.size_kb == 384);
+/// assert!(progress.total_size == Some(393264));
+/// assert!(progress.dup_frames.is_none());
+/// assert!(progress.time == "00:00:10.000000");
+/// assert!(progress.out_time == Some(std::time::Duration::from_secs(10)));
+/// assert!(progress.bitrate_kbps == Some(38.2));
+/// assert!(progress.speed == Some(3.9));
+///
+/// assert!(try_parse_progress_block("frame=1\n").is_none());
+/// ```
+pub fn try_parse_progress_block(block: &str) -> Option<FfmpegProgress> {
+  let raw_log_message = block.to_string();
+
+  let mut frame = 0;
+  let mut fps = None;
+  let mut q = 0.0;
+  let mut size_kb = 0;
+  let mut total_size = None;
+  let mut dup_frames = None;
+  let mut drop_frames = None;
+  let mut time = None;
+  let mut out_time_us = None;
+  let mut bitrate_kbps = None;
+  let mut speed = None;
+  let mut is_terminated = false;
+
+  for line in block.lines() {
+    let Some((key, value)) = line.split_once('=') else {
+      continue;
+    };
+    let value = value.trim();
+    match key.trim() {
+      "frame" => frame = value.parse().unwrap_or(0),
+      "fps" => fps = value.parse().ok(),
+      // Quality is reported per output stream, e.g. `stream_0_0_q`
+      key if key.ends_with("_q") => q = value.parse().unwrap_or(0.0),
+      "total_size" => {
+        total_size = value.parse::<u64>().ok();
+        size_kb = total_size.map(|b| (b / 1024) as u32).unwrap_or(0);
+      }
+      "dup_frames" => dup_frames = value.parse().ok().filter(|&n| n > 0),
+      "drop_frames" => drop_frames = value.parse().ok().filter(|&n| n > 0),
+      "out_time" => time = Some(value.to_string()),
+      "out_time_us" => out_time_us = value.parse::<u64>().ok(),
+      "bitrate" => bitrate_kbps = parse_bitrate_kbps(value),
+      "speed" => speed = parse_speed(value),
+      "progress" => is_terminated = true,
+      _ => {}
+    }
+  }
+
+  if !is_terminated {
+    return None;
+  }
+
+  // Prefer the exact microseconds; negative values fail to parse as unsigned
+  let out_time = match out_time_us {
+    Some(us) => Some(Duration::from_micros(us)),
+    None => time.as_deref().and_then(parse_out_time),
+  };
+
+  // Older versions of ffmpeg may omit `out_time` while still reporting microseconds.
+  let time = time.or_else(|| {
+    out_time_us.map(|us| {
+      let seconds = us / 1_000_000;
+      format!(
+        "{:02}:{:02}:{:02}.{:06}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        us % 1_000_000
+      )
+    })
+  })?;
+
+  Some(FfmpegProgress {
+    frame,
+    // `-progress` doesn't report the elapsed time
+    fps: reported_fps(fps, None),
+    q,
+    size_kb,
+    total_size,
+    out_time,
+    time,
+    bitrate_kbps,
+    speed,
+    dup_frames,
+    drop_frames,
+    dropped_frames: drop_frames.unwrap_or(0),
+    fraction: None,
+    eta: None,
+    raw_log_message,
+  })
+}
+
+/// The wall clock time from the `elapsed=` field of a status line, printed by
+/// FFmpeg 6.1 and later.
+fn parse_elapsed(line: &str) -> Option<Duration> {
+  parse_out_time(line.split("elapsed=").nth(1)?.split_whitespace().next()?)
+}
+
+/// Treat a reported rate of `0.0` as missing, unless more than a second has
+/// elapsed: ffmpeg prints `0.0` until then, even once frames are encoded.
+fn reported_fps(fps: Option<f32>, elapsed: Option<Duration>) -> Option<f32> {
+  match fps {
+    Some(fps) if fps == 0.0 => elapsed.filter(|e| *e > Duration::from_secs(1)).map(|_| 0.0),
+    fps => fps,
+  }
+}
+
+/// Parse a progress timestamp like `00:03:29.04` into a `Duration`. Returns
+/// `None` for `N/A` and for negative values like `-577014:32:22.77`, which
+/// some streams report before their first frame.
+///
+/// ## Examples
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::parse_out_time;
+/// use std::time::Duration;
+/// assert!(parse_out_time("00:00:12.34") == Some(Duration::from_millis(12_340)));
+/// assert!(parse_out_time("N/A") == None);
+/// assert!(parse_out_time("-577014:32:22.77") == None);
+/// ```
+pub fn parse_out_time(time: &str) -> Option<Duration> {
+  if time.starts_with('-') {
+    return None;
+  }
+  parse_time_str(time).and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+}
+
+/// Parse a bitrate like `1500.2kbits/s` into kilobits per second, normalizing
+/// other units like `mbits/s`. Returns `None` for `N/A`.
+///
+/// ## Examples
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::parse_bitrate_kbps;
+/// assert!(parse_bitrate_kbps("1500.2kbits/s") == Some(1500.2));
+/// assert!(parse_bitrate_kbps("1.5mbits/s") == Some(1500.0));
+/// assert!(parse_bitrate_kbps("500bits/s") == Some(0.5));
+/// assert!(parse_bitrate_kbps("N/A") == None);
+/// ```
+pub fn parse_bitrate_kbps(str: &str) -> Option<f32> {
+  let str = str.trim().to_lowercase();
+  let (number, multiplier) = if let Some(n) = str.strip_suffix("kbits/s") {
+    (n, 1.0)
+  } else if let Some(n) = str.strip_suffix("mbits/s") {
+    (n, 1_000.0)
+  } else if let Some(n) = str.strip_suffix("gbits/s") {
+    (n, 1_000_000.0)
+  } else if let Some(n) = str.strip_suffix("bits/s") {
+    (n, 0.001)
+  } else {
+    (str.as_str(), 1.0)
+  };
+  number.trim().parse::<f32>().ok().map(|n| n * multiplier)
+}
+
+/// Parse a processing speed like `2.3x` into a ratio. Returns `None` for `N/A`.
+///
+/// ## Examples
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::parse_speed;
+/// assert!(parse_speed("2.3x") == Some(2.3));
+/// assert!(parse_speed(" 283x") == Some(283.0));
+/// assert!(parse_speed("N/A") == None);
+/// ```
+pub fn parse_speed(str: &str) -> Option<f32> {
+  str.trim().strip_suffix('x')?.trim().parse::<f32>().ok()
+}
+
+/// Parse a time string in the format `HOURS:MM:SS.MILLISECONDS` into a number of seconds.
+///
+/// <https://trac.ffmpeg.org/wiki/Seeking#Timeunitsyntax>
+///
+/// ## Examples
+///
+/// ```rust
+/// use ffmpeg_sidecar::log_parser::parse_time_str;
+/// assert!(parse_time_str("00:00:00.00") == Some(0.0));
+/// assert!(parse_time_str("5") == Some(5.0));
+/// assert!(parse_time_str("0.123") == Some(0.123));
+/// assert!(parse_time_str("1:00.0") == Some(60.0));
+/// assert!(parse_time_str("1:01.0") == Some(61.0));
+/// assert!(parse_time_str("1:01:01.123") == Some(3661.123));
 /// assert!(parse_time_str("N/A") == None);
 /// ```
 pub fn parse_time_str(str: &str) -> Option<f64> {
@@ -713,28 +2083,626 @@ mod tests {
     let line = "[info] frame=    5 fps=0.0 q=-1.0 Lsize=      10KiB time=00:00:03.00 bitrate=  27.2kbits/s speed= 283x\n";
     let progress = try_parse_progress(line).unwrap();
     assert!(progress.frame == 5);
-    assert!(progress.fps == 0.0);
+    assert!(progress.fps.is_none());
     assert!(progress.q == -1.0);
     assert!(progress.size_kb == 10);
     assert!(progress.time == "00:00:03.00");
-    assert!(progress.bitrate_kbps == 27.2);
-    assert!(progress.speed == 283.0);
+    assert!(progress.out_time == Some(Duration::from_secs(3)));
+    assert!(progress.bitrate_kbps == Some(27.2));
+    assert!(progress.speed == Some(283.0));
+  }
+
+  #[test]
+  fn test_parse_progress_dup_drop() {
+    let line = "[info] frame=  100 fps=0.0 q=-1.0 Lsize=      10KiB time=00:00:04.00 bitrate=  20.0kbits/s dup=3 drop=1 speed=8.02x\n";
+    let progress = try_parse_progress(line).unwrap();
+    assert!(progress.frame == 100);
+    assert!(progress.total_size == Some(10 * 1024));
+    assert!(progress.dup_frames == Some(3));
+    assert!(progress.drop_frames == Some(1));
+    assert!(progress.speed == Some(8.02));
+
+    let line = "[info] frame=    5 fps=0.0 q=-1.0 Lsize=      10KiB time=00:00:03.00 bitrate=  27.2kbits/s speed= 283x\n";
+    let progress = try_parse_progress(line).unwrap();
+    assert!(progress.dup_frames.is_none());
+    assert!(progress.drop_frames.is_none());
+  }
+
+  #[test]
+  fn test_parse_stream_tags() {
+    let stderr = "[info] Input #0, matroska,webm, from 'input.mkv':
+[info]   Metadata:
+[info]     encoder         : libebml v1.4.2 + libmatroska v1.6.4
+[info]   Duration: 00:00:05.00, start: 0.000000, bitrate: 1234 kb/s
+[info]   Stream #0:0: Video: h264 (High), yuv420p(progressive), 320x240, 25 fps, 25 tbr, 1k tbn (default)
+[info]     Metadata:
+[info]       DURATION        : 00:00:05.000000000
+[info]   Stream #0:1(ger): Audio: aac (LC), 48000 Hz, stereo, fltp (default)
+[info]     Metadata:
+[info]       title           : Deutsch
+[info]       comment         : first line
+[info]                       : second line
+[info]     Side data:
+[info]       audio service type: main
+[info] Stream mapping:
+";
+    let mut parser = FfmpegLogParser::new(Cursor::new(stderr));
+    let mut tags = Vec::new();
+    loop {
+      match parser.parse_next_event().unwrap() {
+        FfmpegEvent::LogEOF => break,
+        FfmpegEvent::ParsedStreamTag(tag) => tags.push(tag),
+        _ => {}
+      }
+    }
+
+    let summary: Vec<_> = tags
+      .iter()
+      .map(|t| (t.stream_index, t.key.as_str(), t.value.as_str()))
+      .collect();
+    assert!(
+      summary
+        == [
+          (0, "DURATION", "00:00:05.000000000"),
+          (1, "title", "Deutsch"),
+          (1, "comment", "first line"),
+          (1, "comment", "first line\nsecond line"),
+        ]
+    );
+    assert!(tags.iter().all(|t| !t.is_output && t.parent_index == 0));
+  }
+
+  #[test]
+  fn test_parse_data_and_attachment_streams() {
+    let stderr = "[info] Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'GX010001.MP4':
+[info]   Duration: 00:00:10.01, start: 0.000000, bitrate: 45128 kb/s
+[info]   Stream #0:0[0x1](eng): Video: hevc (Main) (hvc1 / 0x31637668), yuvj420p(pc, bt709), 1920x1080 [SAR 1:1 DAR 16:9], 44873 kb/s, 59.94 fps, 59.94 tbr, 60k tbn (default)
+[info]   Stream #0:1[0x2](eng): Audio: aac (LC) (mp4a / 0x6134706D), 48000 Hz, stereo, fltp, 189 kb/s (default)
+[info]   Stream #0:2[0x3](eng): Data: none (tmcd / 0x64636D74) (default)
+[info]   Stream #0:3[0x4](eng): Data: bin_data (gpmd / 0x646D7067), 48 kb/s (default)
+[info]   Stream #0:4: Attachment: ttf
+[info]     Metadata:
+[info]       filename        : font.ttf
+[info]       mimetype        : font/ttf
+[info] Stream mapping:
+";
+    let mut parser = FfmpegLogParser::new(Cursor::new(stderr));
+    let mut streams = Vec::new();
+    loop {
+      match parser.parse_next_event().unwrap() {
+        FfmpegEvent::LogEOF => break,
+        FfmpegEvent::ParsedInputStream(stream) => streams.push(stream),
+        _ => {}
+      }
+    }
+
+    assert!(streams.len() == 5);
+    assert!(streams[0].is_video() && streams[1].is_audio());
+    assert!(streams[2].is_data() && streams[2].format == "none");
+    assert!(streams[3].is_data() && streams[3].format == "bin_data");
+    assert!(streams[4].is_attachment() && streams[4].format == "ttf");
+  }
+
+  #[test]
+  fn test_parse_dispositions() {
+    let cases: [(&str, &[&str]); 4] = [
+      (
+        "[info]   Stream #0:0[0x1](und): Video: h264 (High) (avc1 / 0x31637661), yuv420p(progressive), 1280x720, 2500 kb/s, 25 fps, 25 tbr, 12800 tbn (default) (original)",
+        &["default", "original"],
+      ),
+      (
+        "[info]   Stream #0:1[0x2](eng): Audio: aac (LC) (mp4a / 0x6134706D), 48000 Hz, stereo, fltp, 128 kb/s (default) (visual impaired) (descriptions)",
+        &["default", "visual impaired", "descriptions"],
+      ),
+      (
+        "[info]   Stream #0:0: Video: av1 (libdav1d) (Main), yuv420p(tv, progressive), 1920x1080, 25 fps, 25 tbr, 1k tbn",
+        &[],
+      ),
+      (
+        "[info]   Stream #0:3(jpn): Subtitle: hdmv_pgs_subtitle, 1920x1080 (forced)\n",
+        &["forced"],
+      ),
+    ];
+    for (line, dispositions) in cases {
+      let stream = try_parse_stream(line).unwrap();
+      assert!(stream.dispositions == dispositions, "{line}");
+    }
+  }
+
+  #[test]
+  fn test_parse_summary() {
+    // `-f null -` status lines, overwriting each other with `\r` until the
+    // last one, which ends with `\r\n` on Windows
+    for ending in ["\n", "\r\n"] {
+      let stderr = format!(
+        "[info] frame=  120 fps=0.0 q=-0.0 size=N/A time=00:00:04.80 bitrate=N/A speed=9.58x elapsed=0:00:00.50    \r\
+[info] frame=  250 fps=249 q=-0.0 Lsize=N/A time=00:00:10.00 bitrate=N/A speed=9.96x elapsed=0:00:01.00    {ending}\
+[info] [out#0/null @ 0x600] video:105KiB audio:0KiB muxing overhead: unknown\n"
+      );
+      let mut parser = FfmpegLogParser::new(Cursor::new(stderr));
+      let mut events = Vec::new();
+      loop {
+        match parser.parse_next_event().unwrap() {
+          FfmpegEvent::LogEOF => break,
+          event => events.push(event),
+        }
+      }
+
+      assert!(events.len() == 4);
+      let FfmpegEvent::Progress(progress) = &events[0] else {
+        panic!("expected progress: {:?}", events[0]);
+      };
+      assert!(progress.frame == 120 && progress.total_size.is_none());
+      assert!(matches!(events[1], FfmpegEvent::Progress(_)));
+      let FfmpegEvent::Summary(summary) = &events[2] else {
+        panic!("expected a summary: {:?}", events[2]);
+      };
+      assert!(summary.frames == 250);
+      assert!(summary.fps == 249.0);
+      assert!(summary.speed == Some(9.96));
+      assert!(summary.elapsed == Some(Duration::from_secs(1)));
+      assert!(summary.total_size.is_none());
+      let FfmpegEvent::MuxingStats(stats) = &events[3] else {
+        panic!("expected muxing stats: {:?}", events[3]);
+      };
+      assert!(stats.video_kb == 105 && stats.muxing_overhead.is_none());
+    }
+  }
+
+  #[test]
+  fn test_parse_summary_lsize() {
+    // The final line is recognized by `Lsize=` even without its line ending,
+    // e.g. when stderr is closed right after it
+    let stderr = "[info] frame=   60 fps=0.0 q=28.0 size=     256KiB time=00:00:02.00 bitrate=1048.6kbits/s speed=3.9x\r\
+[info] frame=  125 fps=118 q=-1.0 Lsize=     612KiB time=00:00:04.96 bitrate=1010.8kbits/s speed=4.68x";
+    let mut parser = FfmpegLogParser::new(Cursor::new(stderr));
+    let mut events = Vec::new();
+    loop {
+      match parser.parse_next_event().unwrap() {
+        FfmpegEvent::LogEOF => break,
+        event => events.push(event),
+      }
+    }
+
+    assert!(events.len() == 3);
+    let FfmpegEvent::Progress(progress) = &events[0] else {
+      panic!("expected progress: {:?}", events[0]);
+    };
+    assert!(progress.total_size == Some(256 * 1024));
+    let FfmpegEvent::Summary(summary) = &events[2] else {
+      panic!("expected a summary: {:?}", events[2]);
+    };
+    assert!(summary.frames == 125);
+    assert!(summary.total_size == Some(612 * 1024));
   }
 
   /// Check for handling first progress message w/ bitrate=N/A and speed=N/A
   /// These never appeared on Windows but showed up on Ubuntu and MacOS
+  #[test]
+  fn test_parse_verbose_lines() {
+    // Excerpt from `-loglevel level+debug`, with extra lines between the usual
+    // metadata, which should come through as unknown log messages
+    let stderr = "[debug] Splitting the commandline.
+[info] Input #0, lavfi, from 'testsrc':
+[verbose] [lavfi @ 0x7f8] Setting 'size' to value '320x240'
+[info]   Duration: N/A, start: 0.000000, bitrate: N/A
+[info]   Stream #0:0: Video: wrapped_avframe, rgb24, 320x240 [SAR 1:1 DAR 4:3], 25 fps, 25 tbr, 25 tbn
+[debug] Successfully opened the file.
+[info] Stream mapping:
+[trace] detected 8 logical cores
+";
+    let mut parser = FfmpegLogParser::new(Cursor::new(stderr));
+    let mut unknown_logs = 0;
+    let mut streams = Vec::new();
+    loop {
+      match parser.parse_next_event().unwrap() {
+        FfmpegEvent::LogEOF => break,
+        FfmpegEvent::Log(LogLevel::Unknown, _) => unknown_logs += 1,
+        FfmpegEvent::ParsedInputStream(stream) => streams.push(stream),
+        _ => {}
+      }
+    }
+
+    assert!(unknown_logs == 4);
+    assert!(streams.len() == 1);
+    assert!(streams[0].video_data().unwrap().width == 320);
+  }
+
+  #[test]
+  fn test_parse_outputs() {
+    let stderr = "[info] Input #0, lavfi, from 'testsrc':
+[info]   Duration: N/A, start: 0.000000, bitrate: N/A
+[info]   Stream #0:0: Video: wrapped_avframe, rgb24, 320x240 [SAR 1:1 DAR 4:3], 25 fps, 25 tbr, 25 tbn
+[info] Stream mapping:
+[info]   Stream #0:0 -> #0:0 (wrapped_avframe (native) -> hevc (libx265))
+[info]   Stream #0:0 -> #1:0 (wrapped_avframe (native) -> rawvideo (native))
+[info] Output #0, mp4, to 'out.mp4':
+[info]   Stream #0:0: Video: hevc (hev1 / 0x31766568), yuv444p(tv, progressive), 320x240 [SAR 1:1 DAR 4:3], q=2-31, 25 fps, 12800 tbn
+[info] Output #1, rawvideo, to 'pipe:':
+[info]   Stream #1:0: Video: rawvideo (RGB[24] / 0x18424752), rgb24(progressive), 320x240 [SAR 1:1 DAR 4:3], q=2-31, 46080 kb/s, 25 fps, 25 tbn
+";
+    let mut parser = FfmpegLogParser::new(Cursor::new(stderr));
+    let mut metadata = crate::metadata::FfmpegMetadata::new();
+    while !metadata.is_completed() {
+      let event = parser.parse_next_event().unwrap();
+      assert!(event != FfmpegEvent::LogEOF);
+      metadata.handle_event(&Some(event)).unwrap();
+    }
+
+    let outputs: Vec<_> = metadata
+      .outputs
+      .iter()
+      .map(|o| (o.index, o.format.as_str(), o.to.as_str()))
+      .collect();
+    assert!(outputs == [(0, "mp4", "out.mp4"), (1, "rawvideo", "pipe:")]);
+    assert!(metadata.input_streams.len() == 1);
+    let formats = |index| {
+      metadata
+        .streams_of_output(index)
+        .map(|s| s.format.clone())
+        .collect::<Vec<_>>()
+    };
+    assert!(formats(0) == ["hevc"]);
+    assert!(formats(1) == ["rawvideo"]);
+  }
+
+  #[test]
+  fn test_parse_processing_started() {
+    let stderr = "[info] Stream mapping:
+[info]   Stream #0:0 -> #0:0 (wrapped_avframe (native) -> rawvideo (native))
+[info] Press [q] to stop, [?] for help
+";
+    let mut parser = FfmpegLogParser::new(Cursor::new(stderr));
+    let mut events = Vec::new();
+    loop {
+      match parser.parse_next_event().unwrap() {
+        FfmpegEvent::LogEOF => break,
+        event => events.push(event),
+      }
+    }
+
+    let FfmpegEvent::ParsedStreamMapping(mapping) = &events[1] else {
+      panic!("expected a stream mapping, got {:?}", events[1]);
+    };
+    assert!(mapping.source == "0:0" && mapping.dest == "0:0");
+    assert!(mapping.decoder.as_deref() == Some("wrapped_avframe"));
+    assert!(mapping.encoder.as_deref() == Some("rawvideo"));
+    assert!(
+      events[2] == FfmpegEvent::ProcessingStarted("[info] Press [q] to stop, [?] for help".into())
+    );
+  }
+
+  #[test]
+  fn test_parse_input_duration_line() {
+    let stderr = "[info] Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'input.mp4':
+[info]   Duration: 00:01:23.45, start: 0.021333, bitrate: 1234 kb/s
+[info] Input #1, lavfi, from 'testsrc':
+[info]   Duration: N/A, start: 0.000000, bitrate: N/A
+";
+    let mut parser = FfmpegLogParser::new(Cursor::new(stderr));
+    let mut metadata = crate::metadata::FfmpegMetadata::new();
+    loop {
+      match parser.parse_next_event().unwrap() {
+        FfmpegEvent::LogEOF => break,
+        event => metadata.handle_event(&Some(event)).unwrap(),
+      }
+    }
+
+    assert!(metadata.duration == Some(std::time::Duration::from_millis(83_450)));
+    assert!(metadata.start_time == Some(0.021333));
+    assert!(metadata.bitrate_kbps == Some(1234));
+    assert!(metadata.inputs[0].duration == Some(83.45));
+    assert!(metadata.inputs[1].duration.is_none());
+  }
+
+  #[test]
+  fn test_parse_log_levels() {
+    let stderr = "[mp4 @ 0x7f8] [info] Retrying after error 5
+[mp4 @ 0x7f8] [warning] [info] isn't the level of this line
+[panic] Assertion failed
+Error opening output file out.mp4.
+Some unprefixed message
+";
+    let mut parser = FfmpegLogParser::new(Cursor::new(stderr));
+    let levels: Vec<_> = std::iter::from_fn(|| match parser.parse_next_event().unwrap() {
+      FfmpegEvent::Log(level, _) => Some(level),
+      event => panic!("expected a log message, got {event:?}"),
+    })
+    .take(5)
+    .collect();
+    assert!(
+      levels
+        == [
+          LogLevel::Info,
+          LogLevel::Warning,
+          LogLevel::Fatal,
+          LogLevel::Error,
+          LogLevel::Unknown
+        ]
+    );
+  }
+
+  #[test]
+  fn test_parse_repeated_warnings() {
+    let stderr = "[mp4 @ 0x7f8] [warning] Non-monotonic DTS in output stream 0:1; previous: 10, current: 9; changing to 11.
+[mp4 @ 0x7f8] [warning]     Last message repeated 5 times
+[mp4 @ 0x7f8] [warning] Non-monotonic DTS in output stream 0:1; previous: 11, current: 9; changing to 12.
+";
+    let mut parser = FfmpegLogParser::new(Cursor::new(stderr));
+    let events: Vec<_> = std::iter::from_fn(|| match parser.parse_next_event().unwrap() {
+      FfmpegEvent::LogEOF => None,
+      event => Some(event),
+    })
+    .collect();
+
+    assert!(events.len() == 3);
+    assert!(matches!(&events[1], FfmpegEvent::Repeated(folded) if folded.count == 5));
+    // The running count includes the folded repeats
+    assert!(matches!(&events[2], FfmpegEvent::ParsedWarning(warning) if warning.count == 7));
+  }
+
+  #[test]
+  fn test_parse_benchmark() {
+    let stderr = "[info] bench: utime=1.234s stime=0.056s rtime=0.789s
+[info] bench: maxrss=45678KiB
+";
+    let mut parser = FfmpegLogParser::new(Cursor::new(stderr));
+    let events: Vec<_> = std::iter::from_fn(|| match parser.parse_next_event().unwrap() {
+      FfmpegEvent::LogEOF => None,
+      event => Some(event),
+    })
+    .collect();
+
+    assert!(events.len() == 3);
+    assert!(
+      matches!(&events[0], FfmpegEvent::Log(LogLevel::Info, line) if line.contains("utime="))
+    );
+    assert!(
+      matches!(&events[1], FfmpegEvent::Log(LogLevel::Info, line) if line.contains("maxrss="))
+    );
+    assert!(
+      events[2]
+        == FfmpegEvent::Benchmark(BenchmarkStats {
+          utime: Duration::from_millis(1234),
+          stime: Duration::from_millis(56),
+          rtime: Duration::from_millis(789),
+          max_rss_kb: 45678,
+        })
+    );
+
+    // FFmpeg 6.0 and older
+    assert!(try_parse_benchmark_max_rss("[info] bench: maxrss=45678kB") == Some(45678));
+    assert!(try_parse_benchmark_times("[info] bench: utime=1.234s").is_none());
+  }
+
+  #[test]
+  fn test_parse_dropped_frames() {
+    let stderr = "[info] frame=   30 fps=0.0 q=28.0 size=     256KiB time=00:00:01.00 bitrate=2097.2kbits/s speed=1x\r\
+[dshow @ 0x600] [error] real-time buffer [screen-capture-recorder] [video input] too full or near too full (101% of size: 3041280 [rtbufsize parameter])! frame dropped!
+[vost#0:0/libx264 @ 0x600] [warning] 3 frames dropped
+[info] frame=   60 fps= 30 q=28.0 size=     512KiB time=00:00:02.00 bitrate=2097.2kbits/s dup=0 drop=2 speed=1x\r\
+[info] frame=   90 fps= 30 q=-1.0 Lsize=     768KiB time=00:00:03.00 bitrate=2097.2kbits/s dup=0 drop=5 speed=1x
+";
+    let mut parser = FfmpegLogParser::new(Cursor::new(stderr));
+    let events: Vec<_> = std::iter::from_fn(|| match parser.parse_next_event().unwrap() {
+      FfmpegEvent::LogEOF => None,
+      event => Some(event),
+    })
+    .collect();
+
+    let dropped: Vec<_> = events
+      .iter()
+      .filter_map(|event| match event {
+        FfmpegEvent::Progress(progress) => Some(progress.dropped_frames),
+        _ => None,
+      })
+      .collect();
+    // The 3 logged frames overlap with `drop=2`, but not with the capture drop
+    assert!(dropped == [0, 4, 6]);
+    assert!(events
+      .iter()
+      .any(|event| matches!(event, FfmpegEvent::ParsedDupDrop(dup_drop) if dup_drop.drop == 3)));
+    let Some(FfmpegEvent::Summary(summary)) = events.last() else {
+      panic!("expected a summary: {:?}", events.last());
+    };
+    assert!(summary.dropped_frames == 6);
+  }
+
+  #[test]
+  fn test_parse_input_format() {
+    let stderr = "[info] Input #0, matroska,webm, from 'input.mp4':
+[info]   Stream #0:0: Video: h264 (High), yuv420p(progressive), 1920x1080, 25 fps, 25 tbr, 1k tbn
+[info] Input #1, lavfi, from 'testsrc':
+[info]   Stream #1:0: Video: wrapped_avframe, rgb24, 320x240 [SAR 1:1 DAR 4:3], 25 fps, 25 tbr, 25 tbn
+";
+    let mut parser = FfmpegLogParser::new(Cursor::new(stderr));
+    let mut metadata = crate::metadata::FfmpegMetadata::new();
+    loop {
+      match parser.parse_next_event().unwrap() {
+        FfmpegEvent::LogEOF => break,
+        event => metadata.handle_event(&Some(event)).unwrap(),
+      }
+    }
+
+    assert!(metadata.input_format.as_deref() == Some("matroska,webm"));
+    assert!(metadata.inputs[1].format.as_deref() == Some("lavfi"));
+    assert!(metadata.input_streams[0].parent_index == 0);
+    assert!(metadata.input_streams[1].parent_index == 1);
+    assert!(try_parse_input_format("[info] Input #0, from 'x':").is_none());
+  }
+
+  #[test]
+  fn test_parse_hardware_init_errors() {
+    let cases = [
+      (
+        "[AVHWDeviceContext @ 0x5581] [error] Cannot load libcuda.so.1",
+        Some(HardwareBackend::Cuda),
+      ),
+      (
+        "[AVHWDeviceContext @ 0x5581] [error] Failed to initialise VAAPI connection: -1 (unknown libva error).",
+        Some(HardwareBackend::Vaapi),
+      ),
+      (
+        "[h264_qsv @ 0x5581] [error] Error initializing an MFX session: -9.",
+        Some(HardwareBackend::Qsv),
+      ),
+      (
+        "[fatal] Failed to set value 'vulkan' for option 'init_hw_device': Input/output error",
+        Some(HardwareBackend::Vulkan),
+      ),
+      ("[error] Device creation failed: -12.", None),
+    ];
+    for (line, backend) in cases {
+      let event = LogLineParser::new().parse_line(line).unwrap();
+      let FfmpegEvent::ParsedError(error) = event else {
+        panic!("expected a parsed error for {line:?}, got {event:?}");
+      };
+      assert!(error.kind == FfmpegErrorKind::HardwareInit(backend));
+      assert!(error.raw_log_message == line);
+    }
+
+    assert!(try_parse_error("[info] Cannot load libcuda.so.1").is_none());
+  }
+
+  #[test]
+  fn test_parse_hardware_exhausted_errors() {
+    let cases = [
+      "[AVHWDeviceContext @ 0x5581] [error] cu->cuCtxCreate(&hwctx->cuda_ctx, desired_flags, hwctx->internal->cuda_device) failed -> CUDA_ERROR_OUT_OF_MEMORY: out of memory",
+      "[h264_nvenc @ 0x5581] [error] OpenEncodeSessionEx failed: out of memory (10): (no details)",
+      "[hevc_nvenc @ 0x5581] [error] OpenEncodeSessionEx failed: incompatible client key (21): (no details)",
+    ];
+    for line in cases {
+      let event = LogLineParser::new().parse_line(line).unwrap();
+      let FfmpegEvent::ParsedError(error) = event else {
+        panic!("expected a parsed error for {line:?}, got {event:?}");
+      };
+      assert!(error.kind == FfmpegErrorKind::HardwareExhausted(Some(HardwareBackend::Cuda)));
+      assert!(error.raw_log_message == line);
+    }
+
+    // Not a resource problem, so retrying later won't help
+    let line = "[h264_nvenc @ 0x5581] [error] OpenEncodeSessionEx failed: unsupported device (2): (no details)";
+    let error = try_parse_error(line).unwrap();
+    assert!(error.kind == FfmpegErrorKind::HardwareInit(Some(HardwareBackend::Cuda)));
+  }
+
+  #[test]
+  fn test_parse_unprobeable_stream_errors() {
+    let cases = [
+      (
+        "[mov,mp4,m4a,3gp,3g2,mj2 @ 0x5581] [warning] Could not find codec parameters for stream 0 (Video: h264 (avc1 / 0x31637661), none, 1920x1080, 4969 kb/s): unspecified pixel format",
+        LogLevel::Warning,
+        Some(0),
+      ),
+      (
+        "[mpegts @ 0x5581] [warning] Could not find codec parameters for stream 12 (Audio: aac ([15][0][0][0] / 0x000F), 0 channels): unspecified sample format",
+        LogLevel::Warning,
+        Some(12),
+      ),
+      (
+        "[mov,mp4,m4a,3gp,3g2,mj2 @ 0x5581] [error] could not find corresponding trex (id 1)",
+        LogLevel::Error,
+        None,
+      ),
+      (
+        "[mov,mp4,m4a,3gp,3g2,mj2 @ 0x5581] [warning] could not find corresponding track id 2",
+        LogLevel::Warning,
+        None,
+      ),
+    ];
+    for (line, level, index) in cases {
+      let event = LogLineParser::new().parse_line(line).unwrap();
+      let FfmpegEvent::ParsedError(error) = event else {
+        panic!("expected a parsed error for {line:?}, got {event:?}");
+      };
+      assert!(error.kind == FfmpegErrorKind::UnprobeableStream(index));
+      assert!(error.level == level);
+    }
+
+    // Other kinds are still only recognized as errors
+    assert!(try_parse_error("[warning] missing.mp4: No such file or directory").is_none());
+    assert!(try_parse_error("[info] Could not find codec parameters for stream 0").is_none());
+  }
+
+  #[test]
+  fn test_parse_color_info() {
+    let color = parse_color_info("yuvj420p(pc, bt470bg/unknown/unknown, left)");
+    assert!(color.range.as_deref() == Some("pc"));
+    assert!(color.space.as_deref() == Some("bt470bg"));
+    assert!(color.primaries.as_deref() == Some("unknown"));
+    assert!(color.transfer.as_deref() == Some("unknown"));
+
+    // The field order isn't a color
+    let color = parse_color_info("yuv444p(tv, progressive)");
+    assert!(color.range.as_deref() == Some("tv"));
+    assert!(color.space.is_none() && color.primaries.is_none() && color.transfer.is_none());
+
+    let color = parse_color_info("rgb24");
+    assert!(color.range.is_none() && color.space.is_none());
+  }
+
+  #[test]
+  fn test_parse_deprecation() {
+    let line = "[vost#0:0/libx264 @ 0x5581] [warning] -vsync is deprecated. Use -fps_mode";
+    let event = LogLineParser::new().parse_line(line).unwrap();
+    let FfmpegEvent::Deprecation(deprecation) = event else {
+      panic!("expected a deprecation, got {event:?}");
+    };
+    assert!(deprecation.option == "-vsync");
+    assert!(deprecation.suggested.as_deref() == Some("-fps_mode"));
+    assert!(deprecation.raw_log_message == line);
+
+    // Other mentions of deprecation stay plain warnings
+    let line = "[swscaler @ 0x5581] [warning] deprecated pixel format used, make sure you did set range correctly";
+    let event = LogLineParser::new().parse_line(line).unwrap();
+    assert!(matches!(event, FfmpegEvent::Log(LogLevel::Warning, _)));
+  }
+
+  #[test]
+  fn test_parse_progress_out_time_unknown() {
+    let line = "[info] frame=    0 fps=0.0 q=0.0 size=       0kB time=-577014:32:22.77 bitrate=  -0.0kbits/s speed=N/A\n";
+    assert!(try_parse_progress(line).unwrap().out_time.is_none());
+
+    let line = "[info] frame=    0 fps=0.0 q=0.0 size=       0kB time=N/A bitrate=N/A speed=N/A\n";
+    assert!(try_parse_progress(line).unwrap().out_time.is_none());
+
+    let block =
+      "frame=0\nout_time_us=-9223372036854775807\nout_time=-00:00:00.000001\nprogress=continue\n";
+    assert!(try_parse_progress_block(block).unwrap().out_time.is_none());
+  }
+
+  #[test]
+  fn test_parse_progress_fps() {
+    let fps = |line: &str| try_parse_progress(line).unwrap().fps;
+    assert!(
+      fps("frame=  240 fps= 23.98 q=28.0 size=  512KiB time=00:00:10.01 bitrate= 419.0kbits/s speed=   1x")
+        == Some(23.98)
+    );
+    // Startup, before ffmpeg computes a rate
+    assert!(fps("frame=   12 fps=0.0 q=28.0 size=       0KiB time=00:00:00.50 bitrate=N/A speed=N/A elapsed=0:00:00.50").is_none());
+    // Stalled, e.g. waiting on a live input
+    assert!(
+      fps("frame=    0 fps=0.0 q=0.0 size=       0KiB time=N/A bitrate=N/A speed=N/A elapsed=0:00:12.00")
+        == Some(0.0)
+    );
+
+    let block = "frame=0\nfps=0.00\nout_time_us=0\nspeed=N/A\nprogress=continue\n";
+    assert!(try_parse_progress_block(block).unwrap().fps.is_none());
+  }
+
   #[test]
   fn test_parse_progress_empty() {
     let line =
       "[info] frame=    0 fps=0.0 q=-0.0 size=       0kB time=00:00:00.00 bitrate=N/A speed=N/A\n";
     let progress = try_parse_progress(line).unwrap();
     assert!(progress.frame == 0);
-    assert!(progress.fps == 0.0);
+    assert!(progress.fps.is_none());
     assert!(progress.q == -0.0);
     assert!(progress.size_kb == 0);
     assert!(progress.time == "00:00:00.00");
-    assert!(progress.bitrate_kbps == 0.0);
-    assert!(progress.speed == 0.0);
+    assert!(progress.out_time == Some(Duration::ZERO));
+    assert!(progress.bitrate_kbps.is_none());
+    assert!(progress.speed.is_none());
   }
 
   /// Coverage for non-utf-8 bytes: https://github.com/nathanbabcock/ffmpeg-sidecar/issues/67
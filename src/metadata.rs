@@ -1,5 +1,7 @@
 //! Information about an FFmpeg process and its streams.
 
+use std::time::Duration;
+
 use crate::event::{FfmpegEvent, FfmpegInput, FfmpegOutput, Stream};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -10,6 +12,18 @@ pub struct FfmpegMetadata {
   pub inputs: Vec<FfmpegInput>,
   pub input_streams: Vec<Stream>,
 
+  /// The overall duration of the first input, from its `Duration:` line.
+  /// `None` if it's `N/A`, e.g. for live or streamed inputs.
+  pub duration: Option<Duration>,
+  /// The `start:` timestamp of the first input, in seconds. Only parsed along
+  /// with a known duration.
+  pub start_time: Option<f64>,
+  /// The overall bitrate of the first input, in kilobits per second.
+  pub bitrate_kbps: Option<u32>,
+  /// The demuxer(s) which recognized the first input, as logged on its
+  /// `Input #0, <demuxer>, from '...'` line, e.g. `mov,mp4,m4a,3gp,3g2,mj2`.
+  pub input_format: Option<String>,
+
   /// Whether all metadata from the parent process has been gathered into this struct
   completed: bool,
 }
@@ -28,6 +42,10 @@ impl FfmpegMetadata {
       output_streams: Vec::new(),
       inputs: Vec::new(),
       input_streams: Vec::new(),
+      duration: None,
+      start_time: None,
+      bitrate_kbps: None,
+      input_format: None,
       completed: false,
     }
   }
@@ -36,6 +54,42 @@ impl FfmpegMetadata {
     self.completed
   }
 
+  /// The streams of the output with the given index, in order.
+  pub fn streams_of_output(&self, output_index: u32) -> impl Iterator<Item = &Stream> {
+    self
+      .output_streams
+      .iter()
+      .filter(move |stream| stream.parent_index == output_index)
+  }
+
+  /// The first video stream of the inputs, e.g. to read its resolution.
+  /// Cover art is skipped, since it's a single picture.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::{log_parser::try_parse_stream, metadata::FfmpegMetadata};
+  /// let mut metadata = FfmpegMetadata::new();
+  /// metadata.input_streams = [
+  ///   "Stream #0:0: Audio: aac (LC), 48000 Hz, stereo, fltp (default)",
+  ///   "Stream #0:1: Video: h264 (High), yuv420p(progressive), 1920x1080, 25 fps, 25 tbr, 1k tbn",
+  /// ]
+  /// .into_iter()
+  /// .filter_map(try_parse_stream)
+  /// .collect();
+  /// assert!(metadata.first_video().unwrap().stream_index == 1);
+  /// assert!(metadata.first_audio().unwrap().stream_index == 0);
+  /// ```
+  pub fn first_video(&self) -> Option<&Stream> {
+    self
+      .input_streams
+      .iter()
+      .find(|stream| stream.is_video() && !stream.has_disposition("attached pic"))
+  }
+
+  /// The first audio stream of the inputs.
+  pub fn first_audio(&self) -> Option<&Stream> {
+    self.input_streams.iter().find(|stream| stream.is_audio())
+  }
+
   /// A shortcut to obtain the expected duration (in seconds).
   ///
   /// Usually this is the duration of the first input stream. Theoretically
@@ -54,13 +108,36 @@ impl FfmpegMetadata {
       // Every stream mapping corresponds to one output stream
       // We count these to know when we've received all the output streams
       Some(FfmpegEvent::ParsedStreamMapping(_)) => self.expected_output_streams += 1,
-      Some(FfmpegEvent::ParsedInput(input)) => self.inputs.push(input.clone()),
+      Some(FfmpegEvent::ParsedInput(input)) => {
+        if input.index == 0 {
+          self.input_format.clone_from(&input.format);
+        }
+        self.inputs.push(input.clone());
+      }
       Some(FfmpegEvent::ParsedOutput(output)) => self.outputs.push(output.clone()),
       Some(FfmpegEvent::ParsedDuration(duration)) => {
-        self.inputs[duration.input_index as usize].duration = Some(duration.duration)
+        self.inputs[duration.input_index as usize].duration = Some(duration.duration);
+        if duration.input_index == 0 {
+          self.duration = Duration::try_from_secs_f64(duration.duration).ok();
+          self.start_time = duration.start_time;
+          self.bitrate_kbps = duration.bitrate_kbps;
+        }
       }
       Some(FfmpegEvent::ParsedOutputStream(stream)) => self.output_streams.push(stream.clone()),
       Some(FfmpegEvent::ParsedInputStream(stream)) => self.input_streams.push(stream.clone()),
+      Some(FfmpegEvent::ParsedStreamTag(tag)) => {
+        let streams = match tag.is_output {
+          true => &mut self.output_streams,
+          false => &mut self.input_streams,
+        };
+        if let Some(stream) = streams
+          .iter_mut()
+          .rev()
+          .find(|s| s.parent_index == tag.parent_index && s.stream_index == tag.stream_index)
+        {
+          stream.tags.insert(tag.key.clone(), tag.value.clone());
+        }
+      }
       _ => (),
     }
 
@@ -5,7 +5,11 @@
 //! <https://github.com/nathanbabcock/ffmpeg-sidecar/blob/main/examples/named_pipes.rs>
 
 use anyhow::Result;
-use std::io::Read;
+use std::{
+  io::Read,
+  process,
+  sync::atomic::{AtomicUsize, Ordering},
+};
 
 /// On Windows, prepend the pipe name with `\\.\pipe\`.
 /// On Unix, return the name as-is.
@@ -125,6 +129,32 @@ impl Read for NamedPipe {
   }
 }
 
+impl NamedPipe {
+  /// Create a pipe with a name that's unique to this process, like
+  /// `\\.\pipe\ffmpeg_sidecar_1234_0` on Windows, or a FIFO in the system
+  /// temp directory on Unix. Pass [`path()`](NamedPipe::path) to ffmpeg in
+  /// place of an output file.
+  ///
+  /// The FIFO is unlinked when the `NamedPipe` is dropped, like one created
+  /// with [`new`](NamedPipe::new).
+  pub fn new_unique() -> Result<Self> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let name = format!("ffmpeg_sidecar_{}_{count}", process::id());
+    if cfg!(windows) {
+      Self::new(format!(r#"\\.\pipe\{name}"#))
+    } else {
+      Self::new(std::env::temp_dir().join(name).to_string_lossy())
+    }
+  }
+
+  /// The name that the pipe was opened with, for use as an ffmpeg input or
+  /// output argument.
+  pub fn path(&self) -> &str {
+    &self.name
+  }
+}
+
 // The unix implementation is comparatively quite simple...
 
 #[cfg(unix)]
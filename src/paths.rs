@@ -2,17 +2,31 @@
 
 use anyhow::Context;
 use std::{
-  env::current_exe,
+  env::{current_exe, var_os},
+  ffi::OsStr,
+  fmt,
   path::{Path, PathBuf},
+  process::{Command, Stdio},
 };
 
+use crate::command::BackgroundCommand;
+
+/// Environment variable overriding the location of the FFmpeg binary, used by
+/// [`ffmpeg_path`]. Set it to either the binary itself or the directory
+/// containing it.
+pub const FFMPEG_PATH_ENV: &str = "FFMPEG_SIDECAR_PATH";
+
 /// Returns the default path of the FFmpeg executable, to be used as the
-/// argument to `Command::new`. It should first attempt to locate an FFmpeg
-/// binary adjacent to the Rust executable. If that fails, it should invoke
-/// `ffmpeg` expecting it to be in the system path. If that fails, an
-/// informative error message should be printed (not when this function is
-/// called, but when the command is actually run).
+/// argument to `Command::new`. If the `FFMPEG_SIDECAR_PATH` environment
+/// variable is set, it takes precedence. Otherwise, it should first attempt to
+/// locate an FFmpeg binary adjacent to the Rust executable. If that fails, it
+/// should invoke `ffmpeg` expecting it to be in the system path. If that
+/// fails, an informative error message should be printed (not when this
+/// function is called, but when the command is actually run).
 pub fn ffmpeg_path() -> PathBuf {
+  if let Some(path) = path_from_env(FFMPEG_PATH_ENV, "ffmpeg") {
+    return path;
+  }
   let default = Path::new("ffmpeg").to_path_buf();
   match sidecar_path() {
     Ok(sidecar_path) => match sidecar_path.exists() {
@@ -38,6 +52,97 @@ pub fn sidecar_path() -> anyhow::Result<PathBuf> {
   Ok(path)
 }
 
+/// Which program of the FFmpeg suite a binary is, as reported by
+/// [`validate_binary`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryKind {
+  Ffmpeg,
+  Ffprobe,
+  Ffplay,
+}
+
+impl BinaryKind {
+  /// Identify the program from the first token of its `-version` banner.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::paths::BinaryKind;
+  /// let banner = "ffprobe version 6.1.1 Copyright (c) 2007-2023 the FFmpeg developers\n";
+  /// assert!(BinaryKind::from_banner(banner) == Some(BinaryKind::Ffprobe));
+  /// assert!(BinaryKind::from_banner("ffmpeg version n7.0") == Some(BinaryKind::Ffmpeg));
+  /// assert!(BinaryKind::from_banner("Python 3.12.1").is_none());
+  /// ```
+  pub fn from_banner(banner: &str) -> Option<Self> {
+    match banner.split_whitespace().next()? {
+      "ffmpeg" => Some(Self::Ffmpeg),
+      "ffprobe" => Some(Self::Ffprobe),
+      "ffplay" => Some(Self::Ffplay),
+      _ => None,
+    }
+  }
+}
+
+impl fmt::Display for BinaryKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      BinaryKind::Ffmpeg => "ffmpeg",
+      BinaryKind::Ffprobe => "ffprobe",
+      BinaryKind::Ffplay => "ffplay",
+    })
+  }
+}
+
+/// Run `{path} -version` and check which FFmpeg program it is, to catch
+/// misconfigurations like [`ffmpeg_path`] pointing at an ffprobe binary.
+/// Returns an error if the binary can't be run, exits with a non-zero status,
+/// or doesn't print an FFmpeg banner.
+pub fn validate_binary<S: AsRef<OsStr>>(path: S) -> anyhow::Result<BinaryKind> {
+  let path = path.as_ref();
+  let output = Command::new(path)
+    .arg("-version")
+    .create_no_window()
+    .stdin(Stdio::null())
+    .stderr(Stdio::null())
+    .output()
+    .with_context(|| format!("Failed to run {}", path.to_string_lossy()))?;
+  if !output.status.success() {
+    anyhow::bail!(
+      "{} -version exited with {}",
+      path.to_string_lossy(),
+      output.status
+    );
+  }
+  let banner = String::from_utf8_lossy(&output.stdout);
+  BinaryKind::from_banner(&banner).with_context(|| {
+    format!(
+      "{} is not an FFmpeg binary: {}",
+      path.to_string_lossy(),
+      banner.lines().next().unwrap_or_default()
+    )
+  })
+}
+
+/// Read a binary location from the environment variable `var`, which may
+/// point at the binary itself or at the directory containing `binary_name`.
+/// Returns `None` if the variable is unset or empty.
+pub(crate) fn path_from_env(var: &str, binary_name: &str) -> Option<PathBuf> {
+  let value = var_os(var).filter(|value| !value.is_empty())?;
+  let path = PathBuf::from(value);
+  if !path.is_dir() {
+    return Some(path);
+  }
+  Some(binary_in_dir(&path, binary_name))
+}
+
+/// The path of the binary `binary_name` inside `dir`, with the `.exe`
+/// extension on Windows.
+pub(crate) fn binary_in_dir(dir: &Path, binary_name: &str) -> PathBuf {
+  let mut path = dir.join(binary_name);
+  if cfg!(windows) {
+    path.set_extension("exe");
+  }
+  path
+}
+
 /// By default, downloads all temporary files to the same directory as the Rust executable.
 pub fn sidecar_dir() -> anyhow::Result<PathBuf> {
   Ok(
@@ -47,3 +152,37 @@ pub fn sidecar_dir() -> anyhow::Result<PathBuf> {
       .to_path_buf(),
   )
 }
+
+/// Convert `path` to an extended-length path on Windows, prefixed with `\\?\`
+/// (or `\\?\UNC\` for network shares), so it isn't limited to `MAX_PATH` (260)
+/// characters. The path is made absolute and normalized first, since the
+/// prefix disables that in the Windows API.
+///
+/// Paths which already have a `\\?\` or `\\.\` prefix, or aren't valid UTF-8,
+/// are returned unchanged. On other platforms, this returns `path` as is.
+pub fn extended_length_path<P: AsRef<Path>>(path: P) -> PathBuf {
+  let path = path.as_ref();
+  if !cfg!(windows) {
+    return path.to_path_buf();
+  }
+  let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+  match absolute.to_str() {
+    Some(absolute) => PathBuf::from(add_extended_length_prefix(absolute)),
+    None => absolute,
+  }
+}
+
+/// Add the `\\?\` prefix to an absolute, normalized Windows path.
+pub(crate) fn add_extended_length_prefix(path: &str) -> String {
+  if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+    return path.to_string();
+  }
+  let path = path.replace('/', r"\");
+  if let Some(unc) = path.strip_prefix(r"\\") {
+    format!(r"\\?\UNC\{unc}")
+  } else if path.as_bytes().get(1) == Some(&b':') {
+    format!(r"\\?\{path}")
+  } else {
+    path
+  }
+}
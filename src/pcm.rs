@@ -0,0 +1,120 @@
+//! Raw PCM audio output, the audio counterpart of raw video frames.
+
+use std::{
+  fmt,
+  io::{self, ErrorKind, Read},
+};
+
+/// A raw PCM sample format, used as both the muxer (`-f s16le`) and the codec
+/// (`-c:a pcm_s16le`) by [`FfmpegCommand::pcm_output`](crate::command::FfmpegCommand::pcm_output).
+///
+/// ```rust
+/// use ffmpeg_sidecar::pcm::PcmFormat;
+/// assert!(PcmFormat::S16le.to_string() == "s16le");
+/// assert!(PcmFormat::S16le.codec() == "pcm_s16le");
+/// assert!(PcmFormat::F32le.bytes_per_sample() == 4);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcmFormat {
+  /// Unsigned 8-bit
+  U8,
+  /// Signed 16-bit little-endian, the most common format
+  S16le,
+  /// Signed 32-bit little-endian
+  S32le,
+  /// 32-bit float little-endian, in the range `-1.0..=1.0`
+  F32le,
+  /// 64-bit float little-endian, in the range `-1.0..=1.0`
+  F64le,
+}
+
+impl PcmFormat {
+  /// The name of the matching `pcm_*` codec.
+  pub fn codec(&self) -> String {
+    format!("pcm_{self}")
+  }
+
+  pub fn bytes_per_sample(&self) -> usize {
+    match self {
+      PcmFormat::U8 => 1,
+      PcmFormat::S16le => 2,
+      PcmFormat::S32le | PcmFormat::F32le => 4,
+      PcmFormat::F64le => 8,
+    }
+  }
+
+  /// Decode a single sample of `bytes_per_sample()` bytes, scaled to
+  /// `-1.0..=1.0`.
+  fn decode(&self, bytes: &[u8]) -> f32 {
+    match self {
+      PcmFormat::U8 => (bytes[0] as f32 - 128.0) / 128.0,
+      PcmFormat::S16le => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+      PcmFormat::S32le => {
+        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32 / 2_147_483_648.0
+      }
+      PcmFormat::F32le => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+      PcmFormat::F64le => f64::from_le_bytes(bytes[..8].try_into().unwrap()) as f32,
+    }
+  }
+}
+
+impl fmt::Display for PcmFormat {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let name = match self {
+      PcmFormat::U8 => "u8",
+      PcmFormat::S16le => "s16le",
+      PcmFormat::S32le => "s32le",
+      PcmFormat::F32le => "f32le",
+      PcmFormat::F64le => "f64le",
+    };
+    f.write_str(name)
+  }
+}
+
+/// Reads interleaved PCM audio, like the stdout of a command configured with
+/// [`pcm_output`](crate::command::FfmpegCommand::pcm_output), one frame at a
+/// time. Each frame holds one sample per channel, scaled to `-1.0..=1.0`.
+///
+/// A partial frame at the end of the input is discarded.
+///
+/// ```rust
+/// use ffmpeg_sidecar::pcm::{PcmFormat, PcmReader};
+/// let stereo: Vec<u8> = [0i16, 16384, -32768, 0].iter().flat_map(|s| s.to_le_bytes()).collect();
+/// let frames: Vec<Vec<f32>> = PcmReader::new(stereo.as_slice(), PcmFormat::S16le, 2)
+///   .collect::<Result<_, _>>()
+///   .unwrap();
+/// assert!(frames == [vec![0.0, 0.5], vec![-1.0, 0.0]]);
+/// ```
+pub struct PcmReader<R: Read> {
+  reader: R,
+  format: PcmFormat,
+  buffer: Vec<u8>,
+}
+
+impl<R: Read> PcmReader<R> {
+  pub fn new(reader: R, format: PcmFormat, channels: u32) -> Self {
+    Self {
+      reader,
+      format,
+      buffer: vec![0; format.bytes_per_sample() * channels as usize],
+    }
+  }
+}
+
+impl<R: Read> Iterator for PcmReader<R> {
+  type Item = io::Result<Vec<f32>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match self.reader.read_exact(&mut self.buffer) {
+      Ok(()) => Some(Ok(
+        self
+          .buffer
+          .chunks_exact(self.format.bytes_per_sample())
+          .map(|sample| self.format.decode(sample))
+          .collect(),
+      )),
+      Err(e) if e.kind() == ErrorKind::UnexpectedEof => None,
+      Err(e) => Some(Err(e)),
+    }
+  }
+}
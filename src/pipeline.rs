@@ -0,0 +1,170 @@
+//! Chaining two FFmpeg processes through a pipe.
+
+use std::{
+  io::{self, copy},
+  process::ExitStatus,
+  sync::mpsc::{channel, Receiver, Sender},
+  thread::{spawn, JoinHandle},
+};
+
+use anyhow::Context;
+
+use crate::{child::FfmpegChild, command::FfmpegCommand, event::FfmpegEvent, iter::FfmpegIterator};
+
+/// Which process of a [`Pipeline`] emitted an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipelineStage {
+  First,
+  Second,
+}
+
+/// Two running FFmpeg processes, with the stdout of the first connected to the
+/// stdin of the second. Created by [`pipeline`].
+pub struct Pipeline {
+  first: FfmpegChild,
+  second: FfmpegChild,
+  events: Option<Receiver<(PipelineStage, FfmpegEvent)>>,
+  copy_thread: Option<JoinHandle<io::Result<u64>>>,
+}
+
+/// Run the equivalent of `ffmpeg ... - | ffmpeg -i - ...` without an
+/// intermediate file: spawn both commands and copy the stdout of `first` into
+/// the stdin of `second`.
+///
+/// `first` should write a streamable format to stdout, such as
+/// `.format("nut").output("-")`, and `second` should read it with
+/// `.input("-")`.
+///
+/// The stderr of both processes is read continuously on background threads,
+/// so neither can stall on a full pipe, whether or not the merged
+/// [`events()`](Pipeline::events) are consumed. Since the events are buffered
+/// until read, consume them promptly if `second` outputs raw frames.
+///
+/// ```rust,no_run
+/// use ffmpeg_sidecar::{command::FfmpegCommand, event::FfmpegEvent, pipeline::pipeline};
+///
+/// let mut first = FfmpegCommand::new();
+/// first.input("input.mp4").format("nut").output("-");
+/// let mut second = FfmpegCommand::new();
+/// second.input("-").codec_video("libx264").output("output.mp4");
+///
+/// let mut pipeline = pipeline(first, second).unwrap();
+/// for (stage, event) in pipeline.events().unwrap() {
+///   if let FfmpegEvent::Progress(progress) = event {
+///     println!("{stage:?}: {}", progress.time);
+///   }
+/// }
+/// let (first_status, second_status) = pipeline.wait().unwrap();
+/// ```
+pub fn pipeline(mut first: FfmpegCommand, mut second: FfmpegCommand) -> anyhow::Result<Pipeline> {
+  let mut first = first
+    .spawn()
+    .context("Failed to spawn the first ffmpeg process")?;
+  let mut second = match second.spawn() {
+    Ok(second) => second,
+    Err(e) => {
+      first.kill().ok();
+      first.wait().ok();
+      return Err(e).context("Failed to spawn the second ffmpeg process");
+    }
+  };
+
+  match connect(&mut first, &mut second) {
+    Ok((events, copy_thread)) => Ok(Pipeline {
+      first,
+      second,
+      events: Some(events),
+      copy_thread: Some(copy_thread),
+    }),
+    Err(e) => {
+      // Kill both before waiting, since waiting drains stderr until it closes
+      first.kill().ok();
+      second.kill().ok();
+      first.wait().ok();
+      second.wait().ok();
+      Err(e)
+    }
+  }
+}
+
+/// Start forwarding the events of both processes, then copying between them.
+/// Nothing is left running in the background if this fails.
+#[allow(clippy::type_complexity)]
+fn connect(
+  first: &mut FfmpegChild,
+  second: &mut FfmpegChild,
+) -> anyhow::Result<(
+  Receiver<(PipelineStage, FfmpegEvent)>,
+  JoinHandle<io::Result<u64>>,
+)> {
+  // Taken before creating the iterators, which would read stdout otherwise
+  let mut stdout = first
+    .take_stdout()
+    .context("Missing stdout of the first process")?;
+  let mut stdin = second
+    .take_stdin()
+    .context("Missing stdin of the second process")?;
+  let first_events = first.iter()?;
+  let second_events = second.iter()?;
+
+  let (tx, rx) = channel();
+  forward_events(first_events, PipelineStage::First, tx.clone());
+  forward_events(second_events, PipelineStage::Second, tx);
+  // Dropping stdin once the copy finishes signals the end of input; a broken
+  // pipe from the second process exiting early ends it too.
+  let copy_thread = spawn(move || copy(&mut stdout, &mut stdin));
+  Ok((rx, copy_thread))
+}
+
+impl Pipeline {
+  /// Take the merged events of both processes, tagged with the process that
+  /// emitted them. Returns `None` if already taken.
+  pub fn events(&mut self) -> Option<impl Iterator<Item = (PipelineStage, FfmpegEvent)>> {
+    self.events.take().map(|rx| rx.into_iter())
+  }
+
+  /// The upstream process, writing to the pipe.
+  pub fn first(&mut self) -> &mut FfmpegChild {
+    &mut self.first
+  }
+
+  /// The downstream process, reading from the pipe.
+  pub fn second(&mut self) -> &mut FfmpegChild {
+    &mut self.second
+  }
+
+  /// Kill both processes.
+  pub fn kill(&mut self) -> io::Result<()> {
+    let first = self.first.kill();
+    let second = self.second.kill();
+    first.and(second)
+  }
+
+  /// Wait for both processes to exit, returning the exit status of the first
+  /// and second.
+  pub fn wait(&mut self) -> anyhow::Result<(ExitStatus, ExitStatus)> {
+    let second = self.second.wait()?;
+    let first = self.first.wait()?;
+    if let Some(copy_thread) = self.copy_thread.take() {
+      // A broken pipe is expected if the second process stops reading first,
+      // and is already reflected in the exit statuses.
+      copy_thread.join().ok();
+    }
+    Ok((first, second))
+  }
+}
+
+/// Drain `iter` on a background thread, sending each event to `tx`. Keeps
+/// reading after the receiver is dropped, so the process never blocks on
+/// stderr.
+fn forward_events(
+  iter: FfmpegIterator,
+  stage: PipelineStage,
+  tx: Sender<(PipelineStage, FfmpegEvent)>,
+) -> JoinHandle<()> {
+  spawn(move || {
+    for event in iter {
+      tx.send((stage, event)).ok();
+    }
+  })
+}
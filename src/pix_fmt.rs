@@ -242,3 +242,47 @@ pub fn get_bytes_per_frame(video_data: &VideoStream) -> Option<u32> {
     _ => None,
   }
 }
+
+/// Computes the size in bytes of a single raw frame with the given pixel
+/// format and dimensions, as written by `-f rawvideo`. Returns `None` if the
+/// pix_fmt is unsupported/unrecognized.
+///
+/// Unlike [`get_bytes_per_frame`], chroma planes of subsampled formats like
+/// `yuv420p` are sized the way ffmpeg lays them out, rounding odd dimensions
+/// up instead of assuming a fixed number of bits per pixel.
+///
+/// ## Examples
+///
+/// ```rust
+/// use ffmpeg_sidecar::pix_fmt::bytes_per_frame;
+/// assert!(bytes_per_frame("rgb24", 320, 240) == Some(320 * 240 * 3));
+/// assert!(bytes_per_frame("rgba", 320, 240) == Some(320 * 240 * 4));
+/// assert!(bytes_per_frame("gray8", 320, 240) == Some(320 * 240));
+/// assert!(bytes_per_frame("gray16le", 320, 240) == Some(320 * 240 * 2));
+/// assert!(bytes_per_frame("yuv420p", 320, 240) == Some(320 * 240 * 3 / 2));
+/// assert!(bytes_per_frame("yuv420p", 321, 241) == Some(321 * 241 + 2 * 161 * 121));
+/// assert!(bytes_per_frame("nv12", 320, 240) == Some(320 * 240 * 3 / 2));
+/// assert!(bytes_per_frame("yuv444p", 320, 240) == Some(320 * 240 * 3));
+/// assert!(bytes_per_frame("asdf", 320, 240) == None);
+/// ```
+pub fn bytes_per_frame(pix_fmt: &str, width: u32, height: u32) -> Option<usize> {
+  let (width, height) = (width as usize, height as usize);
+  let luma = width.checked_mul(height)?;
+  let (half_width, half_height) = (width.div_ceil(2), height.div_ceil(2));
+
+  match pix_fmt {
+    // A full resolution luma plane, plus two chroma planes (or one
+    // interleaved plane) at half resolution in both dimensions
+    "yuv420p" | "yuvj420p" | "nv12" | "nv21" => Some(luma + 2 * half_width * half_height),
+    // Chroma at half horizontal resolution
+    "yuv422p" | "yuvj422p" => Some(luma + 2 * half_width * height),
+    "yuv444p" | "yuvj444p" => luma.checked_mul(3),
+    // Alias for `gray`
+    "gray8" => Some(luma),
+    _ => {
+      let bits = get_bits_per_pixel(pix_fmt).filter(|&bits| bits > 0)? as usize;
+      let num_bits = luma.checked_mul(bits)?;
+      (num_bits % 8 == 0).then_some(num_bits / 8)
+    }
+  }
+}
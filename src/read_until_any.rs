@@ -19,48 +19,99 @@ pub fn read_until_any<R: BufRead + ?Sized>(
         Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
         Err(e) => return Err(e),
       };
+      scan_until_any(available, delims, read, buf)
+    };
+    r.consume(used);
+    read += used;
 
-      let start_delims = if read == 0 {
-        available
-          .iter()
-          .take_while(|&&b| delims.iter().any(|&d| d == b))
-          .count()
-      } else {
-        0
-      };
+    match finish_read(done, used, read, delims, buf) {
+      Some(read) => return Ok(read),
+      None => continue,
+    }
+  }
+}
 
-      // NB: `memchr` crate would be faster, but it's unstable and not worth the dependency.
-      let first_delim_index = available
-        .iter()
-        .skip(start_delims)
-        .position(|&b| delims.iter().any(|&d| d == b))
-        .map(|i| i + start_delims);
+/// The async equivalent of [`read_until_any`], for the `tokio` event stream.
+#[cfg(feature = "tokio")]
+pub(crate) async fn read_until_any_async<R: tokio::io::AsyncBufRead + Unpin + ?Sized>(
+  r: &mut R,
+  delims: &[u8],
+  buf: &mut Vec<u8>,
+) -> Result<usize> {
+  use tokio::io::AsyncBufReadExt;
 
-      match first_delim_index {
-        Some(i) => {
-          buf.extend_from_slice(&available[..=i]);
-          (true, i + 1)
-        }
-        None => {
-          buf.extend_from_slice(available);
-          (false, available.len())
-        }
-      }
+  let mut read = 0;
+  loop {
+    let (done, used) = {
+      let available = match r.fill_buf().await {
+        Ok(n) => n,
+        Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+        Err(e) => return Err(e),
+      };
+      scan_until_any(available, delims, read, buf)
     };
     r.consume(used);
     read += used;
 
-    if done {
-      return Ok(read);
+    match finish_read(done, used, read, delims, buf) {
+      Some(read) => return Ok(read),
+      None => continue,
     }
+  }
+}
 
-    // Discard final trailing delimiters
-    if used == 0 && buf.iter().all(|&b| delims.iter().any(|&d| d == b)) {
-      return Ok(0);
-    }
+/// Copy bytes from `available` into `buf` up to and including the first
+/// delimiter, skipping leading delimiters at the start of a read. Returns
+/// whether a delimiter was found, and the number of bytes to consume.
+fn scan_until_any(
+  available: &[u8],
+  delims: &[u8],
+  read: usize,
+  buf: &mut Vec<u8>,
+) -> (bool, usize) {
+  let start_delims = if read == 0 {
+    available
+      .iter()
+      .take_while(|&&b| delims.iter().any(|&d| d == b))
+      .count()
+  } else {
+    0
+  };
 
-    if used == 0 {
-      return Ok(read);
+  // NB: `memchr` crate would be faster, but it's unstable and not worth the dependency.
+  let first_delim_index = available
+    .iter()
+    .skip(start_delims)
+    .position(|&b| delims.iter().any(|&d| d == b))
+    .map(|i| i + start_delims);
+
+  match first_delim_index {
+    Some(i) => {
+      buf.extend_from_slice(&available[..=i]);
+      (true, i + 1)
+    }
+    None => {
+      buf.extend_from_slice(available);
+      (false, available.len())
     }
   }
 }
+
+/// Decide whether a read is complete after consuming `used` bytes, returning
+/// the total number of bytes read if so.
+fn finish_read(done: bool, used: usize, read: usize, delims: &[u8], buf: &[u8]) -> Option<usize> {
+  if done {
+    return Some(read);
+  }
+
+  // Discard final trailing delimiters
+  if used == 0 && buf.iter().all(|&b| delims.iter().any(|&d| d == b)) {
+    return Some(0);
+  }
+
+  if used == 0 {
+    return Some(read);
+  }
+
+  None
+}
@@ -0,0 +1,60 @@
+//! Options for splitting output into fixed-length files with the segment
+//! muxer.
+
+use std::time::Duration;
+
+/// Configuration for [`FfmpegCommand::segment`](crate::command::FfmpegCommand::segment).
+/// See <https://ffmpeg.org/ffmpeg-formats.html#segment_002c-stream_005fsegment_002c-ssegment>
+/// for more details.
+///
+/// Unlike [`HlsOptions`](crate::hls::HlsOptions), this writes standalone
+/// files without a playlist, e.g. for archiving a long recording.
+///
+/// ```rust
+/// use ffmpeg_sidecar::{command::FfmpegCommand, segment::SegmentOptions};
+/// use std::time::Duration;
+///
+/// let mut command = FfmpegCommand::new();
+/// command
+///   .input("input.mp4")
+///   .segment(SegmentOptions {
+///     segment_time: Duration::from_secs(600),
+///     reset_timestamps: true,
+///     filename_template: "output/part_%03d.mp4".to_string(),
+///   })
+///   .unwrap();
+/// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+/// assert!(args.ends_with(&[
+///   "-f", "segment", "-segment_time", "600", "-reset_timestamps", "1",
+///   "output/part_%03d.mp4",
+/// ]));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentOptions {
+  /// Target duration of each segment, `-segment_time`. Like HLS, segments are
+  /// cut on the next keyframe after this duration.
+  pub segment_time: Duration,
+  /// `-reset_timestamps 1`: start the timestamps of each segment at zero, so
+  /// every file plays back on its own.
+  pub reset_timestamps: bool,
+  /// The output path, a `printf`-style template with a counter such as
+  /// `part_%03d.mp4`. The extension picks the container of the segments.
+  pub filename_template: String,
+}
+
+/// Whether `template` contains a `printf`-style integer counter like `%d` or
+/// `%03d`, as required by the segment muxer. A literal `%%` doesn't count.
+pub(crate) fn has_counter(template: &str) -> bool {
+  let mut chars = template.chars();
+  while let Some(c) = chars.next() {
+    if c != '%' {
+      continue;
+    }
+    match chars.by_ref().find(|c| !c.is_ascii_digit()) {
+      Some('d') => return true,
+      // `%%` is an escaped percent sign; anything else isn't a counter
+      _ => continue,
+    }
+  }
+  false
+}
@@ -0,0 +1,128 @@
+//! Typed builder for `-map` stream specifiers.
+
+use std::fmt;
+
+/// A stream selection for [`FfmpegCommand::map`](crate::command::FfmpegCommand::map),
+/// following the [stream specifier
+/// syntax](https://ffmpeg.org/ffmpeg.html#Stream-specifiers).
+///
+/// ```rust
+/// use ffmpeg_sidecar::{command::FfmpegCommand, stream_spec::StreamSpec};
+/// assert!(StreamSpec::input(0).video(0).to_string() == "0:v:0");
+/// assert!(StreamSpec::input(1).all_audio().optional().to_string() == "1:a?");
+/// assert!(StreamSpec::input(0).all_subtitles().exclude().to_string() == "-0:s");
+/// assert!(StreamSpec::label("out").to_string() == "[out]");
+///
+/// FfmpegCommand::new()
+///   .input("video.mp4")
+///   .input("audio.m4a")
+///   .map(StreamSpec::input(0).video(0))
+///   .map(StreamSpec::input(1).all_audio())
+///   .map("0:d?") // raw strings work too
+///   .output("output.mp4");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamSpec {
+  spec: String,
+}
+
+impl StreamSpec {
+  /// Every stream of the input file at `index`, counting each `-i` from 0.
+  pub fn input(index: u32) -> Self {
+    Self {
+      spec: index.to_string(),
+    }
+  }
+
+  /// An output pad of a `-filter_complex` graph, e.g. `[out]`.
+  pub fn label<S: AsRef<str>>(label: S) -> Self {
+    let label = label.as_ref().trim_start_matches('[').trim_end_matches(']');
+    Self {
+      spec: format!("[{label}]"),
+    }
+  }
+
+  /// The stream at `index` among all streams of the input, e.g. `0:1`.
+  pub fn stream(self, index: u32) -> Self {
+    self.push(&index.to_string())
+  }
+
+  /// The video stream at `index` among the video streams, e.g. `0:v:0`.
+  pub fn video(self, index: u32) -> Self {
+    self.push(&format!("v:{index}"))
+  }
+
+  /// Every video stream, e.g. `0:v`.
+  pub fn all_video(self) -> Self {
+    self.push("v")
+  }
+
+  /// The audio stream at `index` among the audio streams, e.g. `0:a:1`.
+  pub fn audio(self, index: u32) -> Self {
+    self.push(&format!("a:{index}"))
+  }
+
+  /// Every audio stream, e.g. `0:a`.
+  pub fn all_audio(self) -> Self {
+    self.push("a")
+  }
+
+  /// The subtitle stream at `index` among the subtitle streams, e.g. `0:s:0`.
+  pub fn subtitle(self, index: u32) -> Self {
+    self.push(&format!("s:{index}"))
+  }
+
+  /// Every subtitle stream, e.g. `0:s`.
+  pub fn all_subtitles(self) -> Self {
+    self.push("s")
+  }
+
+  /// Negative mapping, removing the matching streams from those already
+  /// mapped, e.g. `-map 0 -map -0:s` to copy everything except subtitles.
+  pub fn exclude(mut self) -> Self {
+    if !self.spec.starts_with('-') {
+      self.spec.insert(0, '-');
+    }
+    self
+  }
+
+  /// Ignore the mapping instead of failing if no stream matches, e.g. `0:a?`.
+  pub fn optional(mut self) -> Self {
+    if !self.spec.ends_with('?') {
+      self.spec.push('?');
+    }
+    self
+  }
+
+  fn push(mut self, specifier: &str) -> Self {
+    self.spec.push(':');
+    self.spec.push_str(specifier);
+    self
+  }
+}
+
+impl From<&str> for StreamSpec {
+  fn from(spec: &str) -> Self {
+    Self {
+      spec: spec.to_string(),
+    }
+  }
+}
+
+impl From<String> for StreamSpec {
+  fn from(spec: String) -> Self {
+    Self { spec }
+  }
+}
+
+impl AsRef<str> for StreamSpec {
+  fn as_ref(&self) -> &str {
+    &self.spec
+  }
+}
+
+impl fmt::Display for StreamSpec {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.spec)
+  }
+}
@@ -0,0 +1,79 @@
+//! Protocols for live streaming outputs.
+
+/// A live streaming protocol for
+/// [`FfmpegCommand::stream_output`](crate::command::FfmpegCommand::stream_output),
+/// each of which expects a particular muxer.
+///
+/// ```rust
+/// use ffmpeg_sidecar::{command::FfmpegCommand, streaming::StreamProtocol};
+///
+/// let mut command = FfmpegCommand::new();
+/// command
+///   .realtime()
+///   .input("input.mp4")
+///   .codec_video("libx264")
+///   .codec_audio("aac")
+///   .stream_output("rtmp://live.example.com/app/key", StreamProtocol::Rtmp)
+///   .unwrap();
+/// let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+/// assert!(args.ends_with(&[
+///   "-f", "flv", "-flvflags", "no_duration_filesize", "rtmp://live.example.com/app/key",
+/// ]));
+///
+/// // The URL has to match the protocol
+/// let result = command.stream_output("rtsp://localhost/live", StreamProtocol::Srt);
+/// assert!(result.is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamProtocol {
+  /// `rtmp://` and its variants like `rtmps://`, which carry FLV
+  Rtmp,
+  /// Publishing to an RTSP server at `rtsp://` or `rtsps://`, over TCP
+  Rtsp,
+  /// `srt://`, carrying MPEG-TS
+  Srt,
+}
+
+impl StreamProtocol {
+  /// The muxer to pass to `-f`.
+  pub fn format(&self) -> &'static str {
+    match self {
+      StreamProtocol::Rtmp => "flv",
+      StreamProtocol::Rtsp => "rtsp",
+      StreamProtocol::Srt => "mpegts",
+    }
+  }
+
+  /// The URL schemes served by the protocol.
+  pub fn schemes(&self) -> &'static [&'static str] {
+    match self {
+      StreamProtocol::Rtmp => &["rtmp", "rtmps", "rtmpt", "rtmpts", "rtmpe", "rtmpte"],
+      StreamProtocol::Rtsp => &["rtsp", "rtsps"],
+      StreamProtocol::Srt => &["srt"],
+    }
+  }
+
+  /// Output options suited to live streaming with the protocol, following
+  /// the `-f` format.
+  pub(crate) fn output_args(&self) -> &'static [&'static str] {
+    match self {
+      // The duration and size can't be written back into a live stream, and
+      // trying to only logs a warning at the end
+      StreamProtocol::Rtmp => &["-flvflags", "no_duration_filesize"],
+      // UDP drops packets behind NATs and firewalls
+      StreamProtocol::Rtsp => &["-rtsp_transport", "tcp"],
+      StreamProtocol::Srt => &[],
+    }
+  }
+
+  /// Whether `url` uses one of the [`schemes`](Self::schemes) of the
+  /// protocol, ignoring case.
+  pub fn matches_url(&self, url: &str) -> bool {
+    url.split_once("://").is_some_and(|(scheme, _)| {
+      self
+        .schemes()
+        .iter()
+        .any(|s| s.eq_ignore_ascii_case(scheme))
+    })
+  }
+}
@@ -0,0 +1,113 @@
+//! Helpers for burning subtitles into video with the `subtitles` filter.
+
+use std::path::Path;
+
+/// ASS style overrides for burned-in subtitles, passed to the `subtitles`
+/// filter as `force_style`. Fields left as `None` keep the style from the
+/// subtitle file (or libass' defaults for formats like SRT).
+///
+/// ```rust
+/// use ffmpeg_sidecar::subtitles::SubtitleStyle;
+/// let style = SubtitleStyle {
+///   font_name: Some("Noto Sans".to_string()),
+///   font_size: Some(24),
+///   primary_colour: Some([255, 255, 0]),
+///   ..Default::default()
+/// };
+/// assert!(style.to_force_style() == "FontName=Noto Sans,FontSize=24,PrimaryColour=&H0000FFFF");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubtitleStyle {
+  pub font_name: Option<String>,
+  pub font_size: Option<u32>,
+  /// Text colour as `[red, green, blue]`.
+  pub primary_colour: Option<[u8; 3]>,
+  /// Outline colour as `[red, green, blue]`.
+  pub outline_colour: Option<[u8; 3]>,
+  /// Outline width in pixels.
+  pub outline: Option<u32>,
+  pub bold: Option<bool>,
+  /// Vertical margin from the bottom edge, in pixels.
+  pub margin_v: Option<u32>,
+}
+
+impl SubtitleStyle {
+  /// Serialize as the comma-separated `Key=Value` list expected by
+  /// `force_style`, unescaped.
+  pub fn to_force_style(&self) -> String {
+    // ASS colours are written as &HAABBGGRR, with 00 alpha being opaque
+    let colour = |[r, g, b]: [u8; 3]| format!("&H00{b:02X}{g:02X}{r:02X}");
+
+    let mut fields = Vec::new();
+    if let Some(font_name) = &self.font_name {
+      fields.push(format!("FontName={font_name}"));
+    }
+    if let Some(font_size) = self.font_size {
+      fields.push(format!("FontSize={font_size}"));
+    }
+    if let Some(primary_colour) = self.primary_colour {
+      fields.push(format!("PrimaryColour={}", colour(primary_colour)));
+    }
+    if let Some(outline_colour) = self.outline_colour {
+      fields.push(format!("OutlineColour={}", colour(outline_colour)));
+    }
+    if let Some(outline) = self.outline {
+      fields.push(format!("Outline={outline}"));
+    }
+    if let Some(bold) = self.bold {
+      // ASS uses -1 for true
+      fields.push(format!("Bold={}", if bold { -1 } else { 0 }));
+    }
+    if let Some(margin_v) = self.margin_v {
+      fields.push(format!("MarginV={margin_v}"));
+    }
+    fields.join(",")
+  }
+}
+
+/// Build a `subtitles` filter description for burning in the subtitle file at
+/// `path`, escaped for use in a `-vf` filtergraph.
+///
+/// The path goes through both levels of FFmpeg's [filtergraph
+/// escaping](https://ffmpeg.org/ffmpeg-filters.html#Notes-on-filtergraph-escaping):
+/// once as a filter option value, and again for the filtergraph itself. On
+/// Windows, backslashes are converted to forward slashes first, so a drive
+/// letter ends up as `C\\:/`, except in extended-length `\\?\` paths (see
+/// [`FfmpegCommand::long_path_safe`](crate::command::FfmpegCommand::long_path_safe)),
+/// which only accept backslashes.
+///
+/// ```rust
+/// use ffmpeg_sidecar::subtitles::subtitles_filter;
+/// use std::path::Path;
+/// let filter = subtitles_filter(Path::new("subs/it's here.srt"), None);
+/// assert!(filter == r"subtitles=filename=subs/it\\\'s here.srt");
+/// ```
+pub fn subtitles_filter(path: &Path, style: Option<&SubtitleStyle>) -> String {
+  let mut path = path.to_string_lossy().into_owned();
+  if cfg!(windows) && !path.starts_with(r"\\?\") {
+    path = path.replace('\\', "/");
+  }
+
+  let mut filter = format!("subtitles=filename={}", escape_filter_value(&path));
+  if let Some(style) = style {
+    filter.push_str(":force_style=");
+    filter.push_str(&escape_filter_value(&style.to_force_style()));
+  }
+  filter
+}
+
+/// Escape a filter option value, then escape the result again for the
+/// filtergraph level.
+fn escape_filter_value(value: &str) -> String {
+  let escape = |string: &str, special: &str| {
+    let mut escaped = String::with_capacity(string.len());
+    for c in string.chars() {
+      if special.contains(c) {
+        escaped.push('\\');
+      }
+      escaped.push(c);
+    }
+    escaped
+  };
+  escape(&escape(value, "\\':"), "\\'[],;")
+}
@@ -0,0 +1,111 @@
+//! Builder for outputs of the `tee` muxer.
+
+/// One destination of the `tee` muxer, which writes the same encoded streams
+/// to several outputs in a single pass. Passed to
+/// [`FfmpegCommand::tee_outputs`](crate::command::FfmpegCommand::tee_outputs).
+///
+/// See <https://ffmpeg.org/ffmpeg-formats.html#tee-1> for the available
+/// per-output options.
+///
+/// ```rust
+/// use ffmpeg_sidecar::tee::{tee_arg, TeeOutput};
+/// let outputs = [
+///   TeeOutput::new("output/archive.mkv"),
+///   TeeOutput::new("rtmp://live.example.com/app|key")
+///     .format("flv")
+///     .option("onfail", "ignore")
+///     .bsfs("a", "aac_adtstoasc"),
+/// ];
+/// assert!(
+///   tee_arg(&outputs)
+///     == r"output/archive.mkv|[f=flv:onfail=ignore:bsfs/a=aac_adtstoasc]rtmp://live.example.com/app\|key"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeeOutput {
+  url: String,
+  options: Vec<(String, String)>,
+}
+
+impl TeeOutput {
+  pub fn new<S: AsRef<str>>(url: S) -> Self {
+    Self {
+      url: url.as_ref().to_string(),
+      options: Vec::new(),
+    }
+  }
+
+  /// Add an arbitrary per-output option, such as `onfail=ignore` or
+  /// `select=v:0`.
+  pub fn option<K: AsRef<str>, V: AsRef<str>>(mut self, key: K, value: V) -> Self {
+    self
+      .options
+      .push((key.as_ref().to_string(), value.as_ref().to_string()));
+    self
+  }
+
+  /// The container format of this output, like `-f` for a normal output.
+  /// Equivalent to the `f` option.
+  pub fn format<S: AsRef<str>>(self, format: S) -> Self {
+    self.option("f", format)
+  }
+
+  /// Bitstream filters to apply to the streams matching `stream_specifier`
+  /// (e.g. `v` or `a:0`) in this output only. Equivalent to the
+  /// `bsfs/{stream_specifier}` option.
+  pub fn bsfs<S: AsRef<str>, B: AsRef<str>>(
+    self,
+    stream_specifier: S,
+    bitstream_filters: B,
+  ) -> Self {
+    self.option(
+      format!("bsfs/{}", stream_specifier.as_ref()),
+      bitstream_filters,
+    )
+  }
+
+  /// Only write the streams matching `stream_specifier` to this output.
+  /// Equivalent to the `select` option.
+  pub fn select<S: AsRef<str>>(self, stream_specifier: S) -> Self {
+    self.option("select", stream_specifier)
+  }
+}
+
+/// Assemble the `tee` muxer's output argument, joining each output with `|`.
+///
+/// Escaping happens at two levels, matching how the muxer parses it: option
+/// values are escaped within the `[...]` list, and then each output as a whole
+/// is escaped so that a `|` in a URL doesn't split it.
+pub fn tee_arg(outputs: &[TeeOutput]) -> String {
+  outputs
+    .iter()
+    .map(|output| {
+      let mut slave = String::new();
+      if !output.options.is_empty() {
+        let options: Vec<_> = output
+          .options
+          .iter()
+          .map(|(key, value)| format!("{}={}", escape(key, "\\':=]"), escape(value, "\\':]")))
+          .collect();
+        slave.push('[');
+        slave.push_str(&options.join(":"));
+        slave.push(']');
+      }
+      slave.push_str(&output.url);
+      escape(&slave, "\\'|")
+    })
+    .collect::<Vec<_>>()
+    .join("|")
+}
+
+/// Prefix each of `special` characters with a backslash.
+fn escape(string: &str, special: &str) -> String {
+  let mut escaped = String::with_capacity(string.len());
+  for c in string.chars() {
+    if special.contains(c) {
+      escaped.push('\\');
+    }
+    escaped.push(c);
+  }
+  escaped
+}
@@ -0,0 +1,97 @@
+//! Temporary files that must outlive the FFmpeg process reading them, and
+//! outputs written to a temporary file until the process succeeds.
+
+use std::{
+  env::temp_dir,
+  ffi::OsString,
+  fs::{remove_file, rename, write},
+  io,
+  path::{Path, PathBuf},
+  process,
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Makes the names of temporary files unique within this process.
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A file in the system temp directory, deleted when dropped. Shared between
+/// an `FfmpegCommand` and every child spawned from it through an `Arc`, so the
+/// file lives until the last of them is gone.
+#[derive(Debug)]
+pub(crate) struct TempFile(PathBuf);
+
+impl TempFile {
+  /// Write `contents` to a new uniquely named file, e.g.
+  /// `ffmpeg_sidecar_1234_0_concat.txt`.
+  pub(crate) fn create(suffix: &str, contents: &[u8]) -> io::Result<Self> {
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let path = temp_dir().join(format!("ffmpeg_sidecar_{}_{count}_{suffix}", process::id()));
+    write(&path, contents)?;
+    Ok(Self(path))
+  }
+
+  pub(crate) fn path(&self) -> &Path {
+    &self.0
+  }
+}
+
+impl Drop for TempFile {
+  fn drop(&mut self) {
+    remove_file(&self.0).ok();
+  }
+}
+
+/// An output which ffmpeg writes to a sibling of `target`, moved into place
+/// once the process exits successfully. Living in the same directory keeps
+/// the rename on the same filesystem, where it's atomic.
+#[derive(Debug, Clone)]
+pub(crate) struct AtomicOutput {
+  temp: PathBuf,
+  target: PathBuf,
+}
+
+impl AtomicOutput {
+  /// Pick a hidden temporary name next to `target`, keeping its extension so
+  /// ffmpeg still infers the format from it, e.g. `out.mp4` is written as
+  /// `.out.ffmpeg_sidecar_1234_0.mp4`.
+  pub(crate) fn new(target: &Path) -> Self {
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut name = OsString::from(".");
+    name.push(target.file_stem().unwrap_or_default());
+    name.push(format!(".ffmpeg_sidecar_{}_{count}", process::id()));
+    if let Some(extension) = target.extension() {
+      name.push(".");
+      name.push(extension);
+    }
+    Self {
+      temp: target.with_file_name(name),
+      target: target.to_path_buf(),
+    }
+  }
+
+  pub(crate) fn temp_path(&self) -> &Path {
+    &self.temp
+  }
+
+  /// The same output with relative paths resolved against `dir`, the working
+  /// directory ffmpeg runs in, so it's still found after ffmpeg exits.
+  pub(crate) fn relative_to(&self, dir: &Path) -> Self {
+    Self {
+      temp: dir.join(&self.temp),
+      target: dir.join(&self.target),
+    }
+  }
+
+  /// Move the temporary file to the target path if ffmpeg succeeded,
+  /// replacing any existing file, or delete it otherwise.
+  pub(crate) fn finish(&self, success: bool) -> io::Result<()> {
+    if success {
+      rename(&self.temp, &self.target)
+    } else {
+      match remove_file(&self.temp) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+      }
+    }
+  }
+}
@@ -1,8 +1,20 @@
-use std::{sync::mpsc, thread, time::Duration};
+use std::{
+  io::{Read, Write},
+  process::{Command, Stdio},
+  sync::mpsc,
+  thread,
+  time::Duration,
+};
 
 use crate::{
+  audio::{LoudnormMeasurement, LoudnormOptions, Volume},
   command::{ffmpeg_is_installed, FfmpegCommand},
-  event::{FfmpegEvent, LogLevel},
+  event::{FfmpegErrorKind, FfmpegEvent, FfmpegProgress, LogLevel},
+  ffprobe::{
+    ffprobe_audio_duration, ffprobe_audio_samples, ffprobe_duration, ffprobe_frame_count,
+    ffprobe_resolution, FfprobeCommand,
+  },
+  pcm::PcmFormat,
   version::ffmpeg_version,
 };
 
@@ -232,6 +244,23 @@ fn test_error() {
   assert!(errors > 0);
 }
 
+#[test]
+fn test_filter_errors_fatal() {
+  // An unknown encoder is reported at the `fatal` level
+  let errors: Vec<String> = FfmpegCommand::new()
+    .testsrc()
+    .codec_video("not_a_real_encoder")
+    .output("output/test_filter_errors_fatal.mp4")
+    .spawn()
+    .unwrap()
+    .iter()
+    .unwrap()
+    .filter_errors()
+    .collect();
+
+  assert!(errors.iter().any(|e| e.contains("not_a_real_encoder")));
+}
+
 #[test]
 fn test_chunks() {
   let mut chunks = 0;
@@ -285,9 +314,9 @@ fn test_chunks_with_audio_only() -> anyhow::Result<()> {
   let chunks = FfmpegCommand::new()
     .args("-f lavfi -i sine=frequency=1000:duration=10".split(' '))
     .format("s16le")
-    .args(&["-ac", "1"]) // Mono audio
+    .args(["-ac", "1"]) // Mono audio
     .codec_audio("pcm_s16le")
-    .args(&["-ar", "44100"]) // Sample rate 44.1kHz
+    .args(["-ar", "44100"]) // Sample rate 44.1kHz
     .pipe_stdout()
     .spawn()?
     .iter()?
@@ -361,6 +390,128 @@ fn test_metadata_duration() {
   assert!(metadata.duration() == Some(5.0));
 }
 
+#[test]
+fn test_ffprobe_frame_count() -> anyhow::Result<()> {
+  FfmpegCommand::new()
+    .args("-f lavfi -i testsrc=duration=5:rate=1 -y output/test_ffprobe_frame_count.mp4".split(' '))
+    .spawn()?
+    .wait()?;
+
+  assert!(ffprobe_frame_count("output/test_ffprobe_frame_count.mp4")? == 5);
+
+  Ok(())
+}
+
+#[test]
+fn test_ffprobe_audio_samples() -> anyhow::Result<()> {
+  FfmpegCommand::new()
+    .args(
+      "-f lavfi -i sine=duration=1.5:sample_rate=8000 -y output/test_ffprobe_audio_samples.wav"
+        .split(' '),
+    )
+    .spawn()?
+    .wait()?;
+
+  assert!(ffprobe_audio_samples("output/test_ffprobe_audio_samples.wav")? == 12000);
+  let duration = ffprobe_audio_duration("output/test_ffprobe_audio_samples.wav")?;
+  assert!(duration == Duration::from_millis(1500));
+
+  // Video only
+  FfmpegCommand::new()
+    .args("-f lavfi -i testsrc=duration=1 -y output/test_ffprobe_audio_samples.mp4".split(' '))
+    .spawn()?
+    .wait()?;
+  assert!(ffprobe_audio_samples("output/test_ffprobe_audio_samples.mp4").is_err());
+
+  Ok(())
+}
+
+#[test]
+fn test_ffprobe_stdin() -> anyhow::Result<()> {
+  let mut ffmpeg = FfmpegCommand::new()
+    .args("-f lavfi -i testsrc=duration=1:size=320x240 -f nut -".split(' '))
+    .spawn()?;
+  let mut bytes = Vec::new();
+  ffmpeg.take_stdout().unwrap().read_to_end(&mut bytes)?;
+  ffmpeg.wait()?;
+
+  let mut child = FfprobeCommand::new()
+    .print_format("json")
+    .show_entries("stream=width,height")
+    .input_stdin()
+    .spawn()?;
+  let mut stdin = child.take_stdin().unwrap();
+  let writer = thread::spawn(move || stdin.write_all(&bytes));
+
+  let mut stdout = String::new();
+  child.take_stdout().unwrap().read_to_string(&mut stdout)?;
+  writer.join().unwrap().ok();
+  assert!(child.wait()?.success());
+  assert!(stdout.contains("320"));
+
+  Ok(())
+}
+
+#[test]
+fn test_ffprobe_duration_and_resolution() -> anyhow::Result<()> {
+  FfmpegCommand::new()
+    .args(
+      "-f lavfi -i testsrc=duration=5:size=320x240 -y output/test_ffprobe_duration.mp4".split(' '),
+    )
+    .spawn()?
+    .wait()?;
+
+  let duration = ffprobe_duration("output/test_ffprobe_duration.mp4")?;
+  assert!(approx_eq(duration.as_secs_f32(), 5.0, 0.1));
+  assert!(ffprobe_resolution("output/test_ffprobe_duration.mp4")? == (320, 240));
+
+  Ok(())
+}
+
+#[test]
+fn test_ffprobe_frames() -> anyhow::Result<()> {
+  FfmpegCommand::new()
+    .args("-f lavfi -i testsrc=duration=5:rate=1 -g 2 -y output/test_ffprobe_frames.mp4".split(' '))
+    .spawn()?
+    .wait()?;
+
+  let frames: Vec<_> = FfprobeCommand::new()
+    .select_streams("v:0")
+    .input("output/test_ffprobe_frames.mp4")
+    .frames()?
+    .collect();
+  assert!(frames.len() == 5);
+  assert!(frames.iter().all(|frame| frame.media_type == "video"));
+  assert!(frames[0].key_frame);
+  assert!(frames.iter().any(|frame| !frame.key_frame));
+
+  // Stopping early shouldn't hang
+  let first = FfprobeCommand::new()
+    .input("output/test_ffprobe_frames.mp4")
+    .frames()?
+    .next();
+  assert!(first.is_some());
+
+  Ok(())
+}
+
+#[test]
+fn test_ffprobe_spawn() -> anyhow::Result<()> {
+  let mut child = FfprobeCommand::new()
+    .hide_banner()
+    .print_format("json")
+    .show_entries("format=format_name")
+    .args(["-f", "lavfi", "-i", "testsrc"])
+    .spawn()?;
+
+  let mut stdout = String::new();
+  child.take_stdout().unwrap().read_to_string(&mut stdout)?;
+  assert!(child.wait()?.success());
+  assert!(stdout.contains("lavfi"));
+
+  Ok(())
+}
+
 #[test]
 fn test_kill_before_iter() {
   let mut child = FfmpegCommand::new().testsrc().rawvideo().spawn().unwrap();
@@ -386,6 +537,195 @@ fn test_kill_after_iter() {
   assert!(count <= 1);
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_kill_on_drop() {
+  let mut child = FfmpegCommand::new()
+    .kill_on_drop(true)
+    .testsrc()
+    .rawvideo()
+    .spawn()
+    .unwrap();
+  let pid = child.as_inner().id();
+  drop(child);
+  assert!(!std::path::Path::new(&format!("/proc/{pid}")).exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_pause_resume() -> anyhow::Result<()> {
+  let mut child = FfmpegCommand::new()
+    .testsrc()
+    .overwrite()
+    .output("output/test_pause_resume.mp4")
+    .spawn()?;
+  child.pause()?;
+  child.pause()?;
+  thread::sleep(Duration::from_millis(100));
+  assert!(child.as_inner_mut().try_wait()?.is_none());
+  child.resume()?;
+  child.resume()?;
+  assert!(child.wait()?.success());
+  Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_pause_exited() -> anyhow::Result<()> {
+  let mut command = FfmpegCommand::from(Command::new("sh"));
+  command.as_inner_mut().stderr(Stdio::piped());
+  let mut child = command.args(["-c", "exit 0"]).spawn()?;
+  thread::sleep(Duration::from_millis(200));
+  assert!(child.pause().is_err());
+  assert!(child.wait()?.success());
+  assert!(child.resume().is_err());
+  Ok(())
+}
+
+#[test]
+fn test_wait_timeout() -> anyhow::Result<()> {
+  let mut child = FfmpegCommand::new()
+    .testsrc()
+    .overwrite()
+    .output("output/test_wait_timeout.mp4")
+    .spawn()?;
+  assert!(child.wait_timeout(Duration::from_millis(10))?.is_none());
+  child.kill()?;
+  assert!(child.wait_timeout(Duration::from_secs(5))?.is_some());
+  Ok(())
+}
+
+#[test]
+fn test_progress_pipe() -> anyhow::Result<()> {
+  let progress: Vec<_> = FfmpegCommand::new()
+    .with_progress_pipe()
+    .args("-f lavfi -i testsrc=duration=2:rate=10 -y output/test_progress_pipe.mp4".split(' '))
+    .spawn()?
+    .iter()?
+    .filter_progress()
+    .collect();
+  let last = progress.last().unwrap();
+  assert!(last.frame == 20);
+  assert!(last.raw_log_message.contains("progress=end"));
+  assert!(progress
+    .iter()
+    .all(|p| p.raw_log_message.contains("out_time_us=")));
+  Ok(())
+}
+
+#[test]
+fn test_progress_pipe_stderr() -> anyhow::Result<()> {
+  // Progress goes to stdout, unless an output is already there
+  let mut command = FfmpegCommand::new();
+  command.with_progress_pipe().testsrc().output("out.mp4");
+  assert!(command
+    .dry_run()
+    .ends_with(&["-progress".into(), "pipe:1".into(), "-nostats".into()]));
+
+  let script = r#"
+    for frame in 1 2; do
+      echo "frame=$frame" >&2
+      echo "out_time_us=${frame}00000" >&2
+      echo "bitrate=  38.2kbits/s" >&2
+      echo "progress=continue" >&2
+    done
+    echo "[info] frame=    2 fps=0.0 q=-0.0 Lsize=       1kB time=00:00:00.20 bitrate=N/A speed=N/A" >&2
+  "#;
+  let mut command = FfmpegCommand::from(Command::new("sh"));
+  command.as_inner_mut().stderr(Stdio::piped());
+  let events: Vec<_> = command
+    .args(["-c", script, "-progress", "pipe:2"])
+    .spawn()?
+    .iter()?
+    .collect();
+  let progress: Vec<_> = events
+    .iter()
+    .filter_map(|event| match event {
+      FfmpegEvent::Progress(progress) => Some(progress),
+      _ => None,
+    })
+    .collect();
+  // The status line is only a log message, but still ends with a summary
+  assert!(progress.len() == 2);
+  assert!(progress[1].frame == 2 && progress[1].bitrate_kbps == Some(38.2));
+  assert!(progress[1].out_time == Some(Duration::from_millis(200)));
+  assert!(events
+    .iter()
+    .any(|event| matches!(event, FfmpegEvent::Summary(summary) if summary.frames == 2)));
+  Ok(())
+}
+
+#[test]
+fn test_stats_below_info() -> anyhow::Result<()> {
+  let mut command = FfmpegCommand::new();
+  command.stats(false).stats(true);
+  let args: Vec<_> = command.get_args().collect();
+  assert!(args.ends_with(&["-nostats".as_ref(), "-stats".as_ref()]));
+
+  // With `-stats` below `-loglevel info`, the status line has no level prefix
+  let script = r#"
+    printf 'frame=    5 fps=0.0 q=-0.0 size=       0kB time=00:00:00.20 bitrate=N/A speed=N/A    \r' >&2
+    echo "[error] Something went wrong" >&2
+    printf 'frame=   10 fps=0.0 q=-0.0 Lsize=       1kB time=00:00:00.40 bitrate=N/A speed=N/A    \n' >&2
+  "#;
+  let mut command = FfmpegCommand::from(Command::new("sh"));
+  command.as_inner_mut().stderr(Stdio::piped());
+  let events: Vec<_> = command.args(["-c", script]).spawn()?.iter()?.collect();
+  let frames: Vec<_> = events
+    .iter()
+    .filter_map(|event| match event {
+      FfmpegEvent::Progress(progress) => Some(progress.frame),
+      _ => None,
+    })
+    .collect();
+  assert!(frames == [5, 10]);
+  assert!(events
+    .iter()
+    .any(|event| matches!(event, FfmpegEvent::Log(LogLevel::Error, _))));
+  assert!(events
+    .iter()
+    .any(|event| matches!(event, FfmpegEvent::Summary(summary) if summary.frames == 10)));
+  Ok(())
+}
+
+#[test]
+fn test_parsed_error_missing_input() {
+  let errors: Vec<_> = FfmpegCommand::new()
+    .input("output/does_not_exist.mp4")
+    .output("output/test_parsed_error_missing_input.mp4")
+    .spawn()
+    .unwrap()
+    .iter()
+    .unwrap()
+    .filter_map(|event| match event {
+      FfmpegEvent::ParsedError(error) => Some(error.kind),
+      _ => None,
+    })
+    .collect();
+  assert!(errors.contains(&FfmpegErrorKind::NoSuchFile));
+}
+
+#[test]
+fn test_write_frame() -> anyhow::Result<()> {
+  let mut child = FfmpegCommand::new()
+    .rawvideo_input(32, 16, "rgb24", 10.0)
+    .overwrite()
+    .output("output/test_write_frame.mp4")
+    .spawn()?;
+  let frame = vec![128u8; 32 * 16 * 3];
+  assert!(child.write_frame(&frame[1..]).is_err());
+
+  let iter = child.iter()?;
+  let reader = thread::spawn(move || iter.count());
+  for _ in 0..10 {
+    child.write_frame(&frame)?;
+  }
+  child.take_stdin();
+  reader.join().unwrap();
+  assert!(child.wait()?.success());
+  Ok(())
+}
+
 #[test]
 fn test_quit() {
   let mut child = FfmpegCommand::new().testsrc().rawvideo().spawn().unwrap();
@@ -394,6 +734,19 @@ fn test_quit() {
   assert!(count <= 1);
 }
 
+#[test]
+fn test_quit_and_wait() -> anyhow::Result<()> {
+  let child = FfmpegCommand::new()
+    .testsrc()
+    .overwrite()
+    .output("output/test_quit_and_wait.mp4")
+    .spawn()?;
+  thread::sleep(Duration::from_millis(500));
+  let status = child.quit_and_wait(Duration::from_secs(10))?;
+  assert!(status.success());
+  Ok(())
+}
+
 #[test]
 fn test_frame_timestamp() {
   let mut last_timestamp: Option<f32> = None;
@@ -534,6 +887,27 @@ fn test_no_overwrite() -> anyhow::Result<()> {
   Ok(())
 }
 
+#[test]
+#[cfg(feature = "named_pipes")]
+fn test_named_pipe_unique() -> anyhow::Result<()> {
+  use crate::named_pipes::NamedPipe;
+
+  let first = NamedPipe::new_unique()?;
+  let second = NamedPipe::new_unique()?;
+  assert!(first.path() != second.path());
+
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::FileTypeExt;
+    let path = std::path::PathBuf::from(first.path());
+    assert!(path.metadata()?.file_type().is_fifo());
+    drop(first);
+    assert!(!path.exists());
+  }
+
+  Ok(())
+}
+
 #[test]
 #[cfg(feature = "named_pipes")]
 fn test_named_pipe() -> anyhow::Result<()> {
@@ -633,17 +1007,57 @@ fn test_yuv420() -> anyhow::Result<()> {
   Ok(())
 }
 
+/// Odd dimensions round up the size of the subsampled chroma planes, which
+/// must be accounted for to keep frames aligned.
+/// See <https://github.com/nathanbabcock/ffmpeg-sidecar/pull/61>
+#[test]
+fn test_yuv420_odd_size() -> anyhow::Result<()> {
+  let iter = FfmpegCommand::new()
+    .hide_banner()
+    .format("lavfi")
+    .input("testsrc=duration=10:size=321x241")
+    .format("rawvideo")
+    .pix_fmt("yuv420p")
+    .pipe_stdout()
+    .spawn()?
+    .iter()?;
+
+  let mut frames_received = 0;
+
+  for event in iter {
+    match event {
+      FfmpegEvent::OutputFrame(frame) => {
+        frames_received += 1;
+        assert!(frame.pix_fmt == "yuv420p");
+        assert!(frame.data.len() == 321 * 241 + 2 * 161 * 121);
+      }
+      FfmpegEvent::OutputChunk(_) => {
+        panic!("Should not fall back to chunked mode for planar formats");
+      }
+      FfmpegEvent::Log(LogLevel::Error | LogLevel::Fatal, _) => {
+        panic!("Error or fatal log message received");
+      }
+      _ => {}
+    }
+  }
+
+  assert!(frames_received == 10 * 25);
+
+  Ok(())
+}
+
 /// Make sure that the iterator doesn't hang forever if there's an invalid
 /// framebuffer size; instead, it should fall back to chunked mode.
+/// 1 bit per pixel at an odd size doesn't fill a whole number of bytes.
 /// See <https://github.com/nathanbabcock/ffmpeg-sidecar/pull/61>
 #[test]
-fn test_yuv420_invalid_size() -> anyhow::Result<()> {
+fn test_invalid_frame_size() -> anyhow::Result<()> {
   let iter = FfmpegCommand::new()
     .hide_banner()
     .format("lavfi")
     .input("testsrc=duration=10:size=321x241")
     .format("rawvideo")
-    .pix_fmt("yuv420p")
+    .pix_fmt("monob")
     .pipe_stdout()
     .spawn()?
     .iter()?;
@@ -699,6 +1113,49 @@ fn test_stdout_interleaved_frames() -> anyhow::Result<()> {
   Ok(())
 }
 
+/// Frames on stdout are tagged with their output when a file output comes
+/// first, and keep their own size.
+#[test]
+fn test_stdout_frames_after_file_output() -> anyhow::Result<()> {
+  let frames: Vec<_> = FfmpegCommand::new()
+    .testsrc()
+    .format("null")
+    .output(if cfg!(windows) { "NUL" } else { "/dev/null" })
+    .size(32, 24)
+    .rawvideo()
+    .spawn()?
+    .iter()?
+    .filter_frames()
+    .collect();
+
+  assert!(frames.len() == 10 * 25);
+  assert!(frames.iter().all(|frame| frame.parent_index == 1));
+  assert!(frames
+    .iter()
+    .all(|frame| frame.width == 32 && frame.height == 24));
+
+  Ok(())
+}
+
+#[test]
+fn test_frames_matching() -> anyhow::Result<()> {
+  let frames: Vec<_> = FfmpegCommand::new()
+    .testsrc()
+    .rawvideo()
+    .testsrc()
+    .args(["-f", "rawvideo", "-pix_fmt", "gray", "-"])
+    .spawn()?
+    .iter()?
+    .frames_matching("gray")
+    .collect();
+
+  assert!(frames.len() == 10 * 25);
+  assert!(frames.iter().all(|frame| frame.output_index == 1));
+  assert!(frames.windows(2).all(|w| w[0].frame_num < w[1].frame_num));
+
+  Ok(())
+}
+
 /// Multiple interleaved outputs can't be supported with non-uniform framerate.
 #[test]
 fn test_stdout_interleaved_frames_fallback() -> anyhow::Result<()> {
@@ -737,10 +1194,7 @@ fn test_no_empty_events() -> anyhow::Result<()> {
     .rawvideo()
     .spawn()?
     .iter()?
-    .filter(|event| match event {
-      FfmpegEvent::Log(_, msg) if msg.is_empty() => true,
-      _ => false,
-    })
+    .filter(|event| matches!(event, FfmpegEvent::Log(_, msg) if msg.is_empty()))
     .count();
 
   assert!(empty_events == 0);
@@ -774,3 +1228,2149 @@ fn test_wait() -> anyhow::Result<()> {
     .output("-");
   wait_with_timeout(&mut command, 5000)
 }
+
+#[test]
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+fn test_download_url_linux_aarch64() {
+  let url = crate::download::ffmpeg_download_url().unwrap();
+  assert!(url.contains("arm64") || url.contains("aarch64"));
+  assert!(url.ends_with(".tar.xz"));
+}
+
+#[test]
+#[cfg(all(feature = "download_ffmpeg", target_os = "linux"))]
+fn test_unpack_tar_xz() -> anyhow::Result<()> {
+  use crate::download::unpack_ffmpeg;
+  use std::{fs::File, os::unix::fs::PermissionsExt};
+
+  let dir = std::env::temp_dir().join(format!("ffmpeg_sidecar_unpack_{}", std::process::id()));
+  std::fs::create_dir_all(&dir)?;
+
+  // Tiny stand-in for a static build, with binaries that aren't executable
+  let archive_path = dir.join("ffmpeg-release-amd64-static.tar.xz");
+  let encoder = xz2::write::XzEncoder::new(File::create(&archive_path)?, 6);
+  let mut builder = tar::Builder::new(encoder);
+  for name in ["ffmpeg", "ffprobe"] {
+    let contents = b"#!/bin/sh\n";
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(
+      &mut header,
+      format!("ffmpeg-7.0-amd64-static/{name}"),
+      &contents[..],
+    )?;
+  }
+  builder.into_inner()?.finish()?;
+
+  unpack_ffmpeg(&archive_path, &dir)?;
+
+  for name in ["ffmpeg", "ffprobe"] {
+    let mode = std::fs::metadata(dir.join(name))?.permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
+  }
+  assert!(!archive_path.exists());
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+#[cfg(all(feature = "download_ffmpeg", target_os = "linux"))]
+fn test_unpack_reorganized_archive() -> anyhow::Result<()> {
+  use crate::download::unpack_ffmpeg;
+  use std::fs::File;
+
+  let dir = std::env::temp_dir().join(format!(
+    "ffmpeg_sidecar_unpack_layout_{}",
+    std::process::id()
+  ));
+  std::fs::create_dir_all(&dir)?;
+  let archive = |name: &str, entries: &[(&str, &str)]| -> anyhow::Result<std::path::PathBuf> {
+    let archive_path = dir.join(name);
+    let encoder = xz2::write::XzEncoder::new(File::create(&archive_path)?, 6);
+    let mut builder = tar::Builder::new(encoder);
+    for (path, contents) in entries {
+      let mut header = tar::Header::new_gnu();
+      header.set_size(contents.len() as u64);
+      header.set_mode(0o755);
+      header.set_cksum();
+      builder.append_data(&mut header, path, contents.as_bytes())?;
+    }
+    builder.into_inner()?.finish()?;
+    Ok(archive_path)
+  };
+
+  // Nested deeper than before, with a decoy outside of `bin`
+  let archive_path = archive(
+    "renamed.tar.xz",
+    &[
+      ("ffmpeg-8.0-full_build/tools/ffmpeg", "decoy"),
+      ("ffmpeg-8.0-full_build/bin/ffmpeg", "ffmpeg"),
+      ("ffmpeg-8.0-full_build/bin/ffprobe", "ffprobe"),
+    ],
+  )?;
+  unpack_ffmpeg(&archive_path, &dir)?;
+  assert!(std::fs::read_to_string(dir.join("ffmpeg"))? == "ffmpeg");
+  assert!(std::fs::read_to_string(dir.join("ffprobe"))? == "ffprobe");
+
+  let archive_path = archive("empty.tar.xz", &[("ffmpeg-8.0/README.txt", "")])?;
+  let error = unpack_ffmpeg(&archive_path, &dir).unwrap_err().to_string();
+  assert!(error.contains("ffmpeg-8.0/README.txt"), "{error}");
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "download_ffmpeg")]
+#[ignore = "downloads a full FFmpeg release"]
+fn test_auto_download_ffprobe() -> anyhow::Result<()> {
+  crate::download::auto_download()?;
+  assert!(ffmpeg_is_installed());
+  assert!(crate::ffprobe::ffprobe_is_installed());
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "download_ffmpeg")]
+#[ignore = "downloads a full FFmpeg release"]
+fn test_download_ffmpeg_to() -> anyhow::Result<()> {
+  let dir = std::env::temp_dir()
+    .join(format!(
+      "ffmpeg_sidecar_test_download_to_{}",
+      std::process::id()
+    ))
+    .join("nested");
+  let path = crate::download::download_ffmpeg_to(&dir)?;
+  assert!(path.starts_with(&dir));
+  assert!(crate::version::ffmpeg_version_with_path(&path).is_ok());
+
+  // Second call finds the existing binary
+  assert_eq!(crate::download::download_ffmpeg_to(&dir)?, path);
+  std::fs::remove_dir_all(dir.parent().unwrap())?;
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "download_ffmpeg")]
+#[ignore = "downloads a full FFmpeg release"]
+fn test_ensure_ffmpeg() -> anyhow::Result<()> {
+  let path = crate::download::ensure_ffmpeg()?;
+  assert_eq!(path, crate::paths::ffmpeg_path());
+  assert!(ffmpeg_is_installed());
+
+  // Second call is a no-op
+  assert_eq!(crate::download::ensure_ffmpeg()?, path);
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "download_ffmpeg")]
+fn test_install_lock() -> anyhow::Result<()> {
+  use crate::download::InstallLock;
+  use std::time::SystemTime;
+
+  let dir = std::env::temp_dir().join(format!(
+    "ffmpeg_sidecar_install_lock_{}",
+    std::process::id()
+  ));
+  let lock_path = dir.join(InstallLock::FILENAME);
+
+  // A second install waits until the first one is done
+  let lock = InstallLock::acquire(&dir)?;
+  let (tx, rx) = std::sync::mpsc::channel();
+  let waiter = {
+    let dir = dir.clone();
+    std::thread::spawn(move || {
+      let lock = InstallLock::acquire(&dir);
+      tx.send(()).ok();
+      lock.map(drop)
+    })
+  };
+  assert!(rx.recv_timeout(Duration::from_millis(500)).is_err());
+  drop(lock);
+  rx.recv_timeout(Duration::from_secs(5))?;
+  waiter.join().unwrap()?;
+  assert!(!lock_path.exists());
+
+  // A lock which isn't refreshed anymore was left behind by a crash
+  let file = std::fs::File::create(&lock_path)?;
+  file.set_modified(SystemTime::now() - Duration::from_secs(3600))?;
+  drop(InstallLock::acquire(&dir)?);
+  assert!(!lock_path.exists());
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+fn test_ffmpeg_encoders() -> anyhow::Result<()> {
+  use crate::capabilities::{ffmpeg_encoders, has_encoder, MediaKind};
+  let encoders = ffmpeg_encoders()?;
+  assert!(encoders.len() > 10);
+  let rawvideo = encoders.iter().find(|e| e.name == "rawvideo").unwrap();
+  assert_eq!(rawvideo.kind, MediaKind::Video);
+  assert!(has_encoder("rawvideo"));
+  assert!(!has_encoder("not_a_real_encoder"));
+  Ok(())
+}
+
+#[test]
+fn test_ffmpeg_pix_fmts() -> anyhow::Result<()> {
+  let pix_fmts = crate::capabilities::ffmpeg_pix_fmts()?;
+  let yuv420p = pix_fmts.iter().find(|p| p.name == "yuv420p").unwrap();
+  assert_eq!(yuv420p.nb_components, 3);
+  assert_eq!(yuv420p.bits_per_pixel, 12);
+  assert!(yuv420p.input && yuv420p.output && !yuv420p.hardware);
+  Ok(())
+}
+
+#[test]
+fn test_ffmpeg_hwaccels() -> anyhow::Result<()> {
+  let hwaccels = crate::capabilities::ffmpeg_hwaccels()?;
+  assert!(hwaccels
+    .iter()
+    .all(|h| !h.is_empty() && !h.contains(char::is_whitespace)));
+  Ok(())
+}
+
+#[test]
+fn test_filter_graph() -> anyhow::Result<()> {
+  use crate::filter_graph::FilterGraph;
+
+  // Split one input, shrink both halves, and join them back to back
+  let mut graph = FilterGraph::new();
+  graph
+    .input("0:v")
+    .split(2)
+    .output("a")
+    .output("b")
+    .input("a")
+    .scale(160, 120)
+    .output("a_small")
+    .input("b")
+    .scale(160, 120)
+    .output("b_small")
+    .input("a_small")
+    .input("b_small")
+    .concat(2, 1, 0)
+    .output("out");
+
+  let frames: Vec<_> = FfmpegCommand::new()
+    .format("lavfi")
+    .input("testsrc=size=320x240:rate=1:duration=2")
+    .filter_complex(&graph)
+    .map("[out]")
+    .rawvideo()
+    .spawn()?
+    .iter()?
+    .filter_frames()
+    .collect();
+
+  assert_eq!(frames.len(), 4);
+  assert!(frames.iter().all(|f| f.width == 160 && f.height == 120));
+  Ok(())
+}
+
+#[test]
+fn test_two_pass() -> anyhow::Result<()> {
+  use crate::two_pass::run_two_pass;
+
+  let output_path = "output/test_two_pass.mp4";
+  std::fs::create_dir_all("output")?;
+  std::fs::remove_file(output_path).ok();
+
+  let mut passes_with_progress = Vec::new();
+  run_two_pass(
+    "200k",
+    "output/test_two_pass",
+    output_path,
+    |command| {
+      command
+        .format("lavfi")
+        .input("testsrc=size=320x240:rate=10:duration=2")
+        .codec_video("libx264");
+    },
+    |pass, event| {
+      if matches!(event, FfmpegEvent::Progress(_)) && !passes_with_progress.contains(&pass) {
+        passes_with_progress.push(pass);
+      }
+    },
+  )?;
+
+  assert!(std::fs::metadata(output_path)?.len() > 0);
+  assert_eq!(passes_with_progress, vec![1, 2]);
+  let leftover_logs = std::fs::read_dir("output")?
+    .filter_map(|entry| entry.ok())
+    .any(|entry| {
+      let name = entry.file_name();
+      let name = name.to_string_lossy();
+      name.starts_with("test_two_pass-") && name.contains(".log")
+    });
+  assert!(!leftover_logs);
+  Ok(())
+}
+
+#[test]
+fn test_concat_demuxer_list_file() -> anyhow::Result<()> {
+  let mut command = FfmpegCommand::new();
+  command.concat_demuxer(&["it's a clip.mp4", "/tmp/dür/b.mp4"])?;
+
+  let args: Vec<_> = command.get_args().map(|a| a.to_owned()).collect();
+  let list_index = args.iter().position(|a| a == "-i").unwrap() + 1;
+  let list_path = std::path::PathBuf::from(&args[list_index]);
+  let list = std::fs::read_to_string(&list_path)?;
+  let cwd = std::env::current_dir()?;
+  let first = cwd
+    .join("it's a clip.mp4")
+    .to_string_lossy()
+    .replace('\'', r"'\''");
+  assert!(args
+    .windows(2)
+    .any(|pair| pair[0] == "-f" && pair[1] == "concat"));
+  assert_eq!(list, format!("file '{first}'\nfile '/tmp/dür/b.mp4'\n"));
+
+  // The list outlives the command until every child is gone
+  drop(command);
+  assert!(!list_path.exists());
+  Ok(())
+}
+
+#[test]
+fn test_concat_demuxer() -> anyhow::Result<()> {
+  std::fs::create_dir_all("output")?;
+  let clips = ["output/concat 'a'.mp4", "output/concat b.mp4"];
+  for clip in clips {
+    FfmpegCommand::new()
+      .overwrite()
+      .format("lavfi")
+      .input("testsrc=size=160x120:rate=10:duration=1")
+      .output(clip)
+      .spawn()?
+      .wait()?;
+  }
+
+  let frames = FfmpegCommand::new()
+    .concat_demuxer(&clips)?
+    .rawvideo()
+    .spawn()?
+    .iter()?
+    .filter_frames()
+    .count();
+  assert_eq!(frames, 20);
+  Ok(())
+}
+
+#[test]
+fn test_log_level() -> anyhow::Result<()> {
+  use crate::command::FfmpegLogLevel;
+
+  // Extra debug lines don't interfere with parsing
+  let frames = FfmpegCommand::new()
+    .log_level(FfmpegLogLevel::Debug)
+    .testsrc()
+    .frames(5)
+    .rawvideo()
+    .spawn()?
+    .iter()?
+    .filter_frames()
+    .count();
+  assert_eq!(frames, 5);
+
+  // Without `info` there's no metadata, but the iterator still runs to the end
+  let mut child = FfmpegCommand::new()
+    .log_level(FfmpegLogLevel::Quiet)
+    .testsrc()
+    .frames(5)
+    .format("null")
+    .output("-")
+    .spawn()?;
+  let parsed_metadata = child
+    .iter()?
+    .any(|e| matches!(e, FfmpegEvent::ParsedInputStream(_)));
+  assert!(!parsed_metadata);
+  assert!(child.wait()?.success());
+  Ok(())
+}
+
+#[test]
+fn test_map_stream_spec() -> anyhow::Result<()> {
+  use crate::stream_spec::StreamSpec;
+
+  // Keep the video of the first input and the audio of the second
+  let mut child = FfmpegCommand::new()
+    .format("lavfi")
+    .input("testsrc=size=160x120:rate=10:duration=1")
+    .format("lavfi")
+    .input("sine=duration=1")
+    .map(StreamSpec::input(0).video(0))
+    .map(StreamSpec::input(1).all_audio())
+    .map(StreamSpec::input(1).all_subtitles().optional())
+    .format("null")
+    .output("-")
+    .spawn()?;
+  let metadata = child.iter()?.collect_metadata()?;
+  child.wait()?;
+
+  assert_eq!(metadata.output_streams.len(), 2);
+  assert!(metadata.output_streams[0].video_data().is_some());
+  assert!(metadata.output_streams[1].audio_data().is_some());
+  Ok(())
+}
+
+#[test]
+fn test_tee_outputs() -> anyhow::Result<()> {
+  use crate::tee::TeeOutput;
+
+  std::fs::create_dir_all("output")?;
+  let outputs = ["output/test_tee.mkv", "output/test_tee.nut"];
+  for output in outputs {
+    std::fs::remove_file(output).ok();
+  }
+
+  let success = FfmpegCommand::new()
+    .format("lavfi")
+    .input("testsrc=size=160x120:rate=10:duration=1")
+    .map("0:v")
+    .tee_outputs(&[
+      TeeOutput::new(outputs[0]),
+      TeeOutput::new(outputs[1]).format("nut"),
+    ])
+    .spawn()?
+    .wait()?
+    .success();
+
+  assert!(success);
+  for output in outputs {
+    assert!(std::fs::metadata(output)?.len() > 0);
+  }
+  Ok(())
+}
+
+#[test]
+fn test_hls_output() -> anyhow::Result<()> {
+  use crate::hls::{HlsOptions, HlsPlaylistType};
+
+  let dir = "output/test_hls";
+  std::fs::remove_dir_all(dir).ok();
+  std::fs::create_dir_all(dir)?;
+
+  let success = FfmpegCommand::new()
+    .format("lavfi")
+    .input("testsrc=size=160x120:rate=10:duration=4")
+    .args(["-g", "10"])
+    .hls_output(
+      format!("{dir}/stream.m3u8"),
+      HlsOptions {
+        segment_duration: Duration::from_secs(1),
+        playlist_type: Some(HlsPlaylistType::Vod),
+        segment_filename_template: Some(format!("{dir}/segment_%03d.ts")),
+        list_size: Some(0),
+      },
+    )
+    .spawn()?
+    .wait()?
+    .success();
+  assert!(success);
+
+  let playlist = std::fs::read_to_string(format!("{dir}/stream.m3u8"))?;
+  assert!(playlist.contains("#EXT-X-PLAYLIST-TYPE:VOD"));
+  assert!(playlist.contains("segment_000.ts"));
+  assert!(std::path::Path::new(&format!("{dir}/segment_000.ts")).exists());
+  Ok(())
+}
+
+#[test]
+fn test_burn_subtitles() -> anyhow::Result<()> {
+  use crate::subtitles::SubtitleStyle;
+
+  std::fs::create_dir_all("output")?;
+  let subtitles_path = std::path::Path::new("output/test 'burn', [1].srt");
+  std::fs::write(subtitles_path, "1\n00:00:00,000 --> 00:00:01,000\nHello\n")?;
+
+  let style = SubtitleStyle {
+    font_size: Some(12),
+    primary_colour: Some([255, 0, 0]),
+    ..Default::default()
+  };
+  let mut child = FfmpegCommand::new()
+    .format("lavfi")
+    .input("color=size=160x120:rate=10:duration=1")
+    .burn_subtitles(subtitles_path, Some(style))
+    .rawvideo()
+    .spawn()?;
+  let frames: Vec<_> = child.iter()?.filter_frames().collect();
+  assert!(child.wait()?.success());
+
+  // Red text lands somewhere on the otherwise black frame
+  assert_eq!(frames.len(), 10);
+  assert!(frames[0].data.chunks(3).any(|p| p[0] > 128 && p[1] < 64));
+  Ok(())
+}
+
+#[test]
+fn test_x264_options() -> anyhow::Result<()> {
+  use crate::x264::{Preset, Tune};
+
+  std::fs::create_dir_all("output")?;
+  let mut child = FfmpegCommand::new()
+    .overwrite()
+    .format("lavfi")
+    .input("testsrc=size=160x120:rate=10:duration=1")
+    .codec_video("libx264")
+    .preset(Preset::Ultrafast)
+    .tune(Tune::ZeroLatency)
+    .try_crf(30)?
+    .output("output/test_x264_options.mp4")
+    .spawn()?;
+  let errors: Vec<_> = child.iter()?.filter_errors().collect();
+  assert!(errors.is_empty(), "{errors:?}");
+  assert!(child.wait()?.success());
+  Ok(())
+}
+
+#[test]
+fn test_loop_input() -> anyhow::Result<()> {
+  std::fs::create_dir_all("output")?;
+  let clip = "output/test_loop_input.nut";
+  FfmpegCommand::new()
+    .overwrite()
+    .format("lavfi")
+    .input("testsrc=size=160x120:rate=10:duration=1")
+    .output(clip)
+    .spawn()?
+    .wait()?;
+
+  // The original plus two repeats
+  let frames = FfmpegCommand::new()
+    .loop_input(2)
+    .input(clip)
+    .rawvideo()
+    .spawn()?
+    .iter()?
+    .filter_frames()
+    .count();
+  assert_eq!(frames, 30);
+  Ok(())
+}
+
+#[test]
+fn test_capture_log() -> anyhow::Result<()> {
+  // Captured alongside event parsing
+  let mut child = FfmpegCommand::new()
+    .capture_log(None)
+    .testsrc()
+    .frames(5)
+    .rawvideo()
+    .spawn()?;
+  let frames = child.iter()?.filter_frames().count();
+  child.wait()?;
+  let log = child.take_log().unwrap();
+  assert_eq!(frames, 5);
+  assert!(log.contains("Input #0"));
+  assert!(log.lines().count() > 5);
+
+  // Bounded, keeping the end of the log
+  let mut child = FfmpegCommand::new()
+    .capture_log(Some(100))
+    .input("missing.mp4")
+    .output("-")
+    .spawn()?;
+  child.wait()?;
+  let log = child.take_log().unwrap();
+  assert!(log.len() <= 100);
+  assert!(log.contains("missing.mp4") || log.contains("No such file"));
+
+  // Disabled by default
+  let mut child = FfmpegCommand::new()
+    .testsrc()
+    .frames(1)
+    .rawvideo()
+    .spawn()?;
+  child.wait()?;
+  assert!(child.take_log().is_none());
+  Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_events() -> anyhow::Result<()> {
+  use crate::{event::OutputVideoFrame, log_parser::try_parse_progress};
+
+  let progress = try_parse_progress(
+    "frame= 1996 fps=1984 q=-1.0 Lsize=     372kB time=00:01:19.72 bitrate=  38.2kbits/s speed=79.2x",
+  )
+  .unwrap();
+  let events = [
+    FfmpegEvent::Progress(progress),
+    FfmpegEvent::Log(LogLevel::Warning, "deprecated pixel format".to_string()),
+    FfmpegEvent::Done,
+  ];
+  for event in events {
+    let json = serde_json::to_string(&event)?;
+    assert_eq!(serde_json::from_str::<FfmpegEvent>(&json)?, event);
+  }
+
+  let json = serde_json::to_value(FfmpegEvent::LogEOF)?;
+  assert_eq!(json, serde_json::json!({ "type": "LogEOF" }));
+
+  // Frame metadata is kept, but not the pixel data
+  let frame = OutputVideoFrame {
+    width: 2,
+    height: 1,
+    pix_fmt: "gray".to_string(),
+    output_index: 0,
+    parent_index: 0,
+    stream_index: 0,
+    data: vec![0, 255],
+    frame_num: 3,
+    timestamp: 0.1,
+  };
+  let json = serde_json::to_value(FfmpegEvent::OutputFrame(frame))?;
+  assert_eq!(json["type"], "OutputFrame");
+  assert_eq!(json["data"]["frame_num"], 3);
+  assert!(json["data"].get("data").is_none());
+  Ok(())
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_trace() -> anyhow::Result<()> {
+  use std::sync::{Arc, Mutex};
+  use tracing::{
+    field::{Field, Visit},
+    span, Event, Level, Metadata, Subscriber,
+  };
+
+  /// Records the level and target of each event
+  #[derive(Default)]
+  struct Recorder(Arc<Mutex<Vec<(Level, String, String)>>>);
+
+  struct MessageVisitor(String);
+  impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+      if field.name() == "message" {
+        self.0 = format!("{value:?}");
+      }
+    }
+  }
+
+  impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+      true
+    }
+    fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+      span::Id::from_u64(1)
+    }
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+    fn event(&self, event: &Event<'_>) {
+      let mut visitor = MessageVisitor(String::new());
+      event.record(&mut visitor);
+      let metadata = event.metadata();
+      self
+        .0
+        .lock()
+        .unwrap()
+        .push((*metadata.level(), metadata.target().to_string(), visitor.0));
+    }
+    fn enter(&self, _: &span::Id) {}
+    fn exit(&self, _: &span::Id) {}
+  }
+
+  let recorder = Recorder::default();
+  let records = recorder.0.clone();
+  let frames = tracing::subscriber::with_default(recorder, || -> anyhow::Result<usize> {
+    let mut child = FfmpegCommand::new()
+      .testsrc()
+      .frames(3)
+      .rawvideo()
+      .spawn()?;
+    let frames = child
+      .iter()?
+      .trace()
+      .filter(|event| matches!(event, FfmpegEvent::OutputFrame(_)))
+      .count();
+    child.wait()?;
+    Ok(frames)
+  })?;
+  assert_eq!(frames, 3);
+
+  let records = records.lock().unwrap();
+  assert!(!records.is_empty());
+  assert!(records
+    .iter()
+    .all(|(_, target, _)| target == "ffmpeg_sidecar::ffmpeg"));
+  assert!(records
+    .iter()
+    .any(|(level, _, message)| *level == Level::INFO && message.contains("[info]")));
+  Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_spawn_async() -> anyhow::Result<()> {
+  let runtime = tokio::runtime::Builder::new_current_thread()
+    .enable_all()
+    .build()?;
+  runtime.block_on(async {
+    let mut child = FfmpegCommand::new()
+      .testsrc()
+      .frames(5)
+      .rawvideo()
+      .spawn_async()?;
+    let mut events = child.events()?;
+    let mut frames = 0;
+    let mut got_metadata = false;
+    let mut got_done = false;
+    while let Some(event) = events.next().await {
+      match event {
+        FfmpegEvent::ParsedOutputStream(_) => got_metadata = true,
+        FfmpegEvent::OutputFrame(frame) => {
+          assert_eq!(frame.frame_num, frames);
+          assert_eq!(frame.data.len(), (frame.width * frame.height * 3) as usize);
+          frames += 1;
+        }
+        FfmpegEvent::Done => got_done = true,
+        _ => {}
+      }
+    }
+    assert!(child.wait().await?.success());
+    assert!(got_metadata);
+    assert!(got_done);
+    assert_eq!(frames, 5);
+
+    // Progress from `-progress pipe:1`
+    let mut child = FfmpegCommand::new()
+      .testsrc()
+      .frames(5)
+      .with_progress_pipe()
+      .format("null")
+      .output("-")
+      .spawn_async()?;
+    let mut events = child.events()?;
+    let mut progress = 0;
+    while let Some(event) = events.next().await {
+      if let FfmpegEvent::Progress(_) = event {
+        progress += 1;
+      }
+    }
+    assert!(child.wait().await?.success());
+    assert!(progress > 0);
+    anyhow::Ok(())
+  })
+}
+
+#[test]
+fn test_iter_cancellable() -> anyhow::Result<()> {
+  use crate::cancel::CancelToken;
+  use std::time::Instant;
+
+  let token = CancelToken::new();
+  let handle = token.clone();
+  thread::spawn(move || {
+    thread::sleep(Duration::from_millis(500));
+    handle.cancel();
+  });
+
+  // Would run for 60 seconds without cancellation
+  let start = Instant::now();
+  let mut child = FfmpegCommand::new()
+    .realtime()
+    .args(["-f", "lavfi", "-i", "testsrc=duration=60"])
+    .format("null")
+    .output("-")
+    .spawn()?;
+  let mut iter = child.iter_cancellable(&token, Duration::from_secs(5))?;
+  let progress = iter
+    .by_ref()
+    .filter(|event| matches!(event, FfmpegEvent::Progress(_)))
+    .count();
+  assert!(iter.is_cancelled());
+  drop(iter);
+  let status = child.wait()?;
+  assert!(start.elapsed() < Duration::from_secs(5));
+  assert!(status.success()); // stopped gracefully with `q`
+  assert!(progress > 0);
+  Ok(())
+}
+
+#[test]
+fn test_iter_with_timeout() -> anyhow::Result<()> {
+  use crate::cancel::{CancelToken, TimeoutKind};
+  use std::time::Instant;
+
+  // `sh` stands in for a stalled ffmpeg, ignoring `q` until it's killed
+  let spawn = |script: &str| {
+    let mut command = FfmpegCommand::from(Command::new("sh"));
+    command
+      .as_inner_mut()
+      .stdin(Stdio::piped())
+      .stderr(Stdio::piped());
+    command.args(["-c", script]).spawn()
+  };
+
+  let start = Instant::now();
+  let mut child = spawn("echo '[info] connecting' >&2; exec sleep 30")?;
+  let mut iter = child
+    .iter_cancellable(&CancelToken::new(), Duration::from_millis(100))?
+    .with_timeout(Some(Duration::from_millis(300)), None);
+  let errors: Vec<_> = iter
+    .by_ref()
+    .filter_map(|event| match event {
+      FfmpegEvent::Error(e) => Some(e),
+      _ => None,
+    })
+    .collect();
+  assert!(iter.timed_out() == Some(TimeoutKind::Idle));
+  assert!(!iter.is_cancelled());
+  assert!(errors.len() == 1 && errors[0].contains("no events"));
+  drop(iter);
+  assert!(!child.wait()?.success());
+  assert!(start.elapsed() < Duration::from_secs(5));
+
+  // Keeps logging, so only the total timeout applies
+  let mut child = spawn("while true; do echo '[info] tick' >&2; sleep 0.05; done")?;
+  let mut iter = child
+    .iter_cancellable(&CancelToken::new(), Duration::from_millis(100))?
+    .with_timeout(
+      Some(Duration::from_secs(1)),
+      Some(Duration::from_millis(300)),
+    );
+  let logs = iter
+    .by_ref()
+    .filter(|event| matches!(event, FfmpegEvent::Log(..)))
+    .count();
+  assert!(iter.timed_out() == Some(TimeoutKind::Total));
+  assert!(logs > 0);
+  drop(iter);
+  child.wait()?;
+  Ok(())
+}
+
+#[test]
+fn test_pipeline() -> anyhow::Result<()> {
+  use crate::pipeline::{pipeline, PipelineStage};
+
+  let mut first = FfmpegCommand::new();
+  first.testsrc().frames(10).format("nut").output("-");
+  let mut second = FfmpegCommand::new();
+  second.input("-").rawvideo();
+
+  let mut pipeline = pipeline(first, second)?;
+  let mut frames = 0;
+  let mut first_done = false;
+  for (stage, event) in pipeline.events().unwrap() {
+    match (stage, event) {
+      (PipelineStage::Second, FfmpegEvent::OutputFrame(_)) => frames += 1,
+      (PipelineStage::First, FfmpegEvent::LogEOF) => first_done = true,
+      (PipelineStage::First, FfmpegEvent::OutputFrame(_)) => panic!("first output is piped"),
+      _ => {}
+    }
+  }
+  let (first_status, second_status) = pipeline.wait()?;
+  assert!(first_status.success());
+  assert!(second_status.success());
+  assert!(first_done);
+  assert_eq!(frames, 10);
+  Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_pipeline_connect_failure() -> anyhow::Result<()> {
+  use crate::pipeline::pipeline;
+
+  let dir = std::env::temp_dir().join(format!(
+    "ffmpeg_sidecar_pipeline_failure_{}",
+    std::process::id()
+  ));
+  std::fs::create_dir_all(&dir)?;
+  // Each stand-in leaves a file behind unless it's killed
+  let stage = |name: &str| {
+    let mut command = FfmpegCommand::from(Command::new("sh"));
+    command
+      .as_inner_mut()
+      .stdin(Stdio::piped())
+      .stderr(Stdio::piped());
+    let script = format!("sleep 1; touch '{}'", dir.join(name).display());
+    command.args(["-c", &script]);
+    command
+  };
+  let mut first = stage("first");
+  // Nothing to pipe from
+  first.as_inner_mut().stdout(Stdio::null());
+  let second = stage("second");
+
+  assert!(pipeline(first, second).is_err());
+  thread::sleep(Duration::from_millis(1500));
+  assert!(!dir.join("first").exists());
+  assert!(!dir.join("second").exists());
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+fn test_extended_length_paths() {
+  use crate::paths::add_extended_length_prefix;
+
+  assert_eq!(
+    add_extended_length_prefix(r"C:\videos\input.mp4"),
+    r"\\?\C:\videos\input.mp4"
+  );
+  assert_eq!(
+    add_extended_length_prefix("C:/videos/input.mp4"),
+    r"\\?\C:\videos\input.mp4"
+  );
+  assert_eq!(
+    add_extended_length_prefix(r"\\server\share\input.mp4"),
+    r"\\?\UNC\server\share\input.mp4"
+  );
+  assert_eq!(
+    add_extended_length_prefix(r"\\?\C:\input.mp4"),
+    r"\\?\C:\input.mp4"
+  );
+
+  // URLs and pipes are never rewritten, and nothing is on other platforms
+  let long_path = format!("{}/input.mp4", "nested".repeat(50));
+  let args: Vec<String> = FfmpegCommand::new()
+    .long_path_safe()
+    .input("rtmp://localhost/live")
+    .input(&long_path)
+    .output("-")
+    .get_args()
+    .filter_map(|arg| arg.to_str().map(String::from))
+    .collect();
+  assert!(args.contains(&"rtmp://localhost/live".to_string()));
+  assert!(args.ends_with(&["-".to_string()]));
+  if cfg!(windows) {
+    assert!(args.iter().any(|arg| arg.starts_with(r"\\?\")));
+  } else {
+    assert!(args.contains(&long_path));
+  }
+}
+
+#[test]
+fn test_path_from_env() -> anyhow::Result<()> {
+  use crate::paths::path_from_env;
+  use std::path::PathBuf;
+
+  // A dedicated variable, so the real overrides of other tests aren't touched
+  let var = "FFMPEG_SIDECAR_TEST_PATH";
+  assert_eq!(path_from_env(var, "ffmpeg"), None);
+
+  std::env::set_var(var, "");
+  assert_eq!(path_from_env(var, "ffmpeg"), None);
+
+  std::env::set_var(var, "/opt/ffmpeg/bin/ffmpeg-6");
+  assert_eq!(
+    path_from_env(var, "ffmpeg"),
+    Some(PathBuf::from("/opt/ffmpeg/bin/ffmpeg-6"))
+  );
+
+  // A directory resolves to the binary inside it
+  let dir = std::env::temp_dir();
+  std::env::set_var(var, &dir);
+  let expected = match cfg!(windows) {
+    true => dir.join("ffmpeg.exe"),
+    false => dir.join("ffmpeg"),
+  };
+  assert_eq!(path_from_env(var, "ffmpeg"), Some(expected));
+
+  std::env::remove_var(var);
+  Ok(())
+}
+
+#[test]
+fn test_build_config() -> anyhow::Result<()> {
+  use crate::version::{ffmpeg_build_config, supports_library};
+
+  let configuration = ffmpeg_build_config()?;
+  assert!(!configuration.is_empty());
+  assert!(configuration.iter().all(|flag| flag.starts_with("--")));
+  if let Some(library) = configuration
+    .iter()
+    .find_map(|flag| flag.strip_prefix("--enable-lib"))
+  {
+    assert!(supports_library(&format!("lib{library}")));
+  }
+  assert!(!supports_library("libnonexistent"));
+  Ok(())
+}
+
+#[test]
+fn test_wait_and_result() -> anyhow::Result<()> {
+  FfmpegCommand::new()
+    .testsrc()
+    .frames(3)
+    .format("null")
+    .output("-")
+    .spawn()?
+    .wait_and_result()?;
+
+  let error = FfmpegCommand::new()
+    .input("missing.mp4")
+    .output("output/missing.mp4")
+    .spawn()?
+    .wait_and_result()
+    .unwrap_err()
+    .to_string();
+  assert!(error.starts_with("ffmpeg exited with"));
+  assert!(error.contains("missing.mp4"));
+  Ok(())
+}
+
+#[test]
+fn test_keyframe_options() -> anyhow::Result<()> {
+  // Force a keyframe every second in a 3 second clip
+  let output_path = "output/test_keyframes.mp4";
+  std::fs::create_dir_all("output")?;
+  FfmpegCommand::new()
+    .args(["-f", "lavfi", "-i", "testsrc=duration=3:rate=10"])
+    .codec_video("libx264")
+    .gop_size(100)
+    .keyint_min(100)
+    .args(["-sc_threshold", "0"])
+    .force_keyframes_at(&[Duration::from_secs(1), Duration::from_secs(2)])
+    .overwrite()
+    .output(output_path)
+    .spawn()?
+    .wait_and_result()?;
+
+  let keyframes = FfprobeCommand::new()
+    .select_streams("v:0")
+    .input(output_path)
+    .frames()?
+    .filter(|frame| frame.key_frame)
+    .count();
+  assert_eq!(keyframes, 3);
+  Ok(())
+}
+
+#[test]
+fn test_scale_and_crop() {
+  let args: Vec<String> = FfmpegCommand::new()
+    .input("input.mp4")
+    .crop(1280, 720, 0, 180)
+    .scale_fit(640, 640)
+    .output("first.mp4")
+    .scale(-2, 240)
+    .rawvideo()
+    .get_args()
+    .filter_map(|arg| arg.to_str().map(String::from))
+    .collect();
+  assert_eq!(
+    args,
+    [
+      "-loglevel",
+      "level+info",
+      "-i",
+      "input.mp4",
+      "-vf",
+      "crop=1280:720:0:180,scale=640:640:force_original_aspect_ratio=decrease",
+      "first.mp4",
+      "-f",
+      "rawvideo",
+      "-pix_fmt",
+      "rgb24",
+      "-vf",
+      "scale=-2:240",
+      "-",
+    ]
+  );
+}
+
+#[test]
+fn test_compose_filters() {
+  let args: Vec<String> = FfmpegCommand::new()
+    .input("input.mp4")
+    .fps(30.0)
+    .scale(-2, 720)
+    .burn_subtitles("subs.srt", None)
+    .audio_filter("volume=0.5")
+    .video_filter("hflip")
+    .output("output.mp4")
+    .get_args()
+    .filter_map(|arg| arg.to_str().map(String::from))
+    .collect();
+  let vf = args.iter().position(|arg| arg == "-vf").unwrap();
+  assert!(args[vf + 1].starts_with("fps=30,scale=-2:720,subtitles="));
+  assert!(args[vf + 1].ends_with(",hflip"));
+  assert_eq!(args.iter().filter(|arg| *arg == "-vf").count(), 1);
+  assert!(args.ends_with(&["-af".into(), "volume=0.5".into(), "output.mp4".into()]));
+}
+
+#[test]
+fn test_fps_filter() {
+  let vf = |fps: f32| {
+    let mut command = FfmpegCommand::new();
+    command.fps(fps).output("-");
+    let args: Vec<String> = command
+      .get_args()
+      .filter_map(|arg| arg.to_str().map(String::from))
+      .collect();
+    args[args.len() - 2].clone()
+  };
+  assert_eq!(vf(30.0), "fps=30");
+  assert_eq!(vf(12.5), "fps=12.5");
+  assert_eq!(vf(29.9), "fps=29.9");
+  assert_eq!(vf(23.976), "fps=24000/1001");
+  assert_eq!(vf(29.97), "fps=30000/1001");
+  assert_eq!(vf(59.94), "fps=60000/1001");
+}
+
+#[test]
+fn test_threads() {
+  let args: Vec<String> = FfmpegCommand::new()
+    .filter_threads(2)
+    .threads(2)
+    .input("input.mp4")
+    .threads(4)
+    .threads_optimal()
+    .output("output.mp4")
+    .get_args()
+    .filter_map(|arg| arg.to_str().map(String::from))
+    .collect();
+  assert!(args.ends_with(&[
+    "-filter_threads".into(),
+    "2".into(),
+    "-threads".into(),
+    "2".into(),
+    "-i".into(),
+    "input.mp4".into(),
+    "-threads".into(),
+    "4".into(),
+    "-threads".into(),
+    "0".into(),
+    "output.mp4".into(),
+  ]));
+}
+
+#[test]
+fn test_stats_period() -> anyhow::Result<()> {
+  let args: Vec<String> = FfmpegCommand::new()
+    .stats_period(Duration::from_millis(100))?
+    .get_args()
+    .filter_map(|arg| arg.to_str().map(String::from))
+    .collect();
+  assert!(args.ends_with(&["-stats_period".into(), "0.1".into()]));
+
+  let mut command = FfmpegCommand::new();
+  assert!(command.stats_period(Duration::ZERO).is_err());
+  assert!(!command.get_args().any(|arg| arg == "-stats_period"));
+  Ok(())
+}
+
+#[test]
+fn test_probe_metadata() -> anyhow::Result<()> {
+  let metadata = FfmpegCommand::new().testsrc().probe_metadata()?;
+  assert_eq!(metadata.inputs.len(), 1);
+  assert_eq!(metadata.input_streams.len(), 1);
+  assert!(metadata.input_streams[0].is_video());
+  assert!(metadata.outputs.is_empty());
+
+  let missing = FfmpegCommand::new()
+    .input("does_not_exist.mp4")
+    .probe_metadata();
+  assert!(missing.is_err());
+  Ok(())
+}
+
+#[test]
+fn test_segment_template() {
+  use crate::segment::{has_counter, SegmentOptions};
+
+  assert!(has_counter("part_%d.mp4"));
+  assert!(has_counter("part_%03d.mp4"));
+  assert!(has_counter("%%_part_%02d.ts"));
+  assert!(!has_counter("part.mp4"));
+  assert!(!has_counter("part_%s.mp4"));
+  assert!(!has_counter("100%%d.mp4"));
+
+  let result = FfmpegCommand::new()
+    .input("input.mp4")
+    .segment(SegmentOptions {
+      segment_time: Duration::from_millis(1500),
+      reset_timestamps: false,
+      filename_template: "part.mp4".to_string(),
+    })
+    .map(|_| ());
+  assert!(result.is_err());
+}
+
+#[test]
+fn test_image_sequence() -> anyhow::Result<()> {
+  let mut command = FfmpegCommand::new();
+  command
+    .args(["-start_number", "100"])
+    .image_sequence_input("frame_%04d.png", 23.976)?
+    .args(["-start_number", "0"])
+    .image_sequence_output("thumb_%d.jpg")?;
+  let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+  assert!(args.ends_with(&[
+    "-start_number",
+    "100",
+    "-framerate",
+    "23.976",
+    "-i",
+    "frame_%04d.png",
+    "-start_number",
+    "0",
+    "-f",
+    "image2",
+    "thumb_%d.jpg",
+  ]));
+
+  assert!(FfmpegCommand::new()
+    .image_sequence_input("frame.png", 24.0)
+    .is_err());
+  assert!(FfmpegCommand::new()
+    .input("input.mp4")
+    .image_sequence_output("frame_%s.png")
+    .is_err());
+  Ok(())
+}
+
+/// Serve one canned HTTP response per connection on a local port, returning
+/// the base URL and a channel of the received request headers.
+#[cfg(feature = "download_ffmpeg")]
+fn serve_http(responses: Vec<&'static str>) -> (String, std::sync::mpsc::Receiver<String>) {
+  use std::io::{BufRead, BufReader, Write};
+  use std::net::TcpListener;
+
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let url = format!("http://{}", listener.local_addr().unwrap());
+  let (tx, rx) = std::sync::mpsc::channel();
+  std::thread::spawn(move || {
+    for response in responses {
+      let (mut stream, _) = listener.accept().unwrap();
+      let mut reader = BufReader::new(stream.try_clone().unwrap());
+      let mut request = String::new();
+      while reader.read_line(&mut request).is_ok_and(|n| n > 0) && !request.ends_with("\r\n\r\n") {}
+      tx.send(request).ok();
+      stream.write_all(response.as_bytes()).ok();
+    }
+  });
+  (url, rx)
+}
+
+#[test]
+#[cfg(feature = "download_ffmpeg")]
+fn test_download_retry() -> anyhow::Result<()> {
+  use crate::download::{download_ffmpeg_package_with_config, DownloadConfig};
+
+  const UNAVAILABLE: &str =
+    "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+  const NOT_FOUND: &str =
+    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+  const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 3\r\nConnection: close\r\n\r\nabc";
+
+  let dir = std::env::temp_dir().join("ffmpeg_sidecar_test_download_retry");
+  std::fs::create_dir_all(&dir)?;
+  let config = DownloadConfig {
+    max_attempts: 3,
+    base_delay: Duration::from_millis(1),
+  };
+  assert_eq!(config.retry_delay(1), Duration::from_millis(1));
+  assert_eq!(config.retry_delay(3), Duration::from_millis(4));
+
+  // Server errors are retried until a success
+  let (url, _) = serve_http(vec![UNAVAILABLE, UNAVAILABLE, OK]);
+  let path =
+    download_ffmpeg_package_with_config(&format!("{url}/archive.bin"), &dir, &config, |_, _| {})?;
+  assert_eq!(std::fs::read_to_string(path)?, "abc");
+
+  // But not more than `max_attempts` times
+  let (url, _) = serve_http(vec![UNAVAILABLE, UNAVAILABLE, UNAVAILABLE, OK]);
+  let result =
+    download_ffmpeg_package_with_config(&format!("{url}/archive.bin"), &dir, &config, |_, _| {});
+  assert!(result.is_err());
+
+  // A 404 fails immediately, without trying again
+  let (url, _) = serve_http(vec![NOT_FOUND, OK]);
+  let result =
+    download_ffmpeg_package_with_config(&format!("{url}/archive.bin"), &dir, &config, |_, _| {});
+  assert!(result.is_err());
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+#[cfg(feature = "download_ffmpeg")]
+fn test_download_resume() -> anyhow::Result<()> {
+  use crate::download::{download_ffmpeg_package_with_config, DownloadConfig};
+
+  // The connection closes after half of the body
+  const TRUNCATED: &str = "HTTP/1.1 200 OK\r\nContent-Length: 6\r\nConnection: close\r\n\r\nabc";
+  const PARTIAL: &str = "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 3-5/6\r\nContent-Length: 3\r\nConnection: close\r\n\r\ndef";
+  const FULL: &str = "HTTP/1.1 200 OK\r\nContent-Length: 6\r\nConnection: close\r\n\r\nabcdef";
+
+  let dir = std::env::temp_dir().join("ffmpeg_sidecar_test_download_resume");
+  std::fs::create_dir_all(&dir)?;
+  let config = DownloadConfig {
+    max_attempts: 2,
+    base_delay: Duration::from_millis(1),
+  };
+
+  // The rest is requested with a `Range` header and appended
+  let (url, requests) = serve_http(vec![TRUNCATED, PARTIAL]);
+  let path =
+    download_ffmpeg_package_with_config(&format!("{url}/archive.bin"), &dir, &config, |_, _| {})?;
+  assert_eq!(std::fs::read_to_string(path)?, "abcdef");
+  assert!(!requests.recv()?.to_lowercase().contains("range:"));
+  assert!(requests.recv()?.to_lowercase().contains("range: bytes=3-"));
+  assert!(!dir.join("archive.bin.part").exists());
+
+  // A server ignoring the range sends the whole file again
+  let (url, _) = serve_http(vec![TRUNCATED, FULL]);
+  let path =
+    download_ffmpeg_package_with_config(&format!("{url}/archive.bin"), &dir, &config, |_, _| {})?;
+  assert_eq!(std::fs::read_to_string(&path)?, "abcdef");
+
+  // Without retries, a truncated download fails and leaves nothing behind
+  std::fs::remove_file(&path)?;
+  let (url, _) = serve_http(vec![TRUNCATED]);
+  let single = DownloadConfig {
+    max_attempts: 1,
+    ..config
+  };
+  let result =
+    download_ffmpeg_package_with_config(&format!("{url}/archive.bin"), &dir, &single, |_, _| {});
+  assert!(result.is_err());
+  assert!(!path.exists());
+  assert!(!dir.join("archive.bin.part").exists());
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+fn test_filter_complex_via_file() -> anyhow::Result<()> {
+  let graph = (0..100)
+    .map(|i| format!("[v{i}][{}:v]overlay=x={i}[v{}]", i + 1, i + 1))
+    .collect::<Vec<_>>()
+    .join(";");
+  let mut command = FfmpegCommand::new();
+  command.assume_version("7.1".parse()?);
+  command.filter_complex_via_file(&graph)?;
+
+  let args: Vec<_> = command.get_args().map(|a| a.to_owned()).collect();
+  assert!(args[args.len() - 2] == "-/filter_complex");
+  let script_path = std::path::PathBuf::from(&args[args.len() - 1]);
+  assert!(std::fs::read_to_string(&script_path)? == graph);
+
+  drop(command);
+  assert!(!script_path.exists());
+  Ok(())
+}
+
+#[test]
+fn test_frame_sync_mode() {
+  let sync_flag = |version: &str| {
+    let mut command = FfmpegCommand::new();
+    command
+      .assume_version(version.parse().unwrap())
+      .frame_sync_mode("passthrough");
+    let args: Vec<String> = command
+      .get_args()
+      .filter_map(|arg| arg.to_str().map(String::from))
+      .collect();
+    assert!(args.ends_with(&["passthrough".into()]));
+    args[args.len() - 2].clone()
+  };
+  assert_eq!(sync_flag("4.4.2-0ubuntu0.22.04.1"), "-vsync");
+  assert_eq!(sync_flag("5.0.1"), "-vsync");
+  assert_eq!(sync_flag("5.1"), "-fps_mode");
+  assert_eq!(sync_flag("7.0-essentials_build-www.gyan.dev"), "-fps_mode");
+  assert_eq!(sync_flag("N-110000-gabc1234"), "-fps_mode");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_detect_version() -> anyhow::Result<()> {
+  use std::os::unix::fs::PermissionsExt;
+
+  let last_two = |command: &FfmpegCommand| -> Vec<String> {
+    let args: Vec<String> = command
+      .get_args()
+      .filter_map(|arg| arg.to_str().map(String::from))
+      .collect();
+    args[args.len() - 2..].to_vec()
+  };
+
+  // Stands in for an old ffmpeg binary
+  let dir = std::env::temp_dir().join(format!(
+    "ffmpeg_sidecar_detect_version_{}",
+    std::process::id()
+  ));
+  std::fs::create_dir_all(&dir)?;
+  let script = dir.join("ffmpeg");
+  std::fs::write(
+    &script,
+    "#!/bin/sh\necho 'ffmpeg version 4.4.2 Copyright (c) 2000-2021 the FFmpeg developers'\n",
+  )?;
+  std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))?;
+
+  // Nothing is run unless asked for
+  let mut command = FfmpegCommand::new_with_path(&script);
+  command.frame_sync_mode("cfr");
+  assert!(last_two(&command) == ["-fps_mode", "cfr"]);
+  command.detect_version()?.frame_sync_mode("vfr");
+  assert!(last_two(&command) == ["-vsync", "vfr"]);
+
+  // Same for the frame selection helpers
+  let has_flag = |command: &FfmpegCommand, flag: &str| command.get_args().any(|arg| arg == flag);
+  let mut command = FfmpegCommand::new_with_path(&script);
+  command.select_keyframes();
+  assert!(has_flag(&command, "-fps_mode"));
+  let mut command = FfmpegCommand::new_with_path(&script);
+  command.detect_version()?.select_scene_changes(0.4);
+  assert!(has_flag(&command, "-vsync") && !has_flag(&command, "-fps_mode"));
+
+  // A failed detection is reported, and the latest flags are used
+  let mut command = FfmpegCommand::new_with_path(dir.join("missing"));
+  assert!(command.detect_version().is_err());
+  command.frame_sync_mode("cfr");
+  assert!(last_two(&command) == ["-fps_mode", "cfr"]);
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+fn test_take_stdout_fragmented_mp4() -> anyhow::Result<()> {
+  let mut child = FfmpegCommand::new()
+    .testsrc()
+    .args(["-t", "1"])
+    .format("mp4")
+    .frag_mp4()
+    .output("pipe:1")
+    .spawn()?;
+  let mut stdout = child.take_stdout().unwrap();
+  let events = child.iter()?;
+  let events = thread::spawn(move || events.collect::<Vec<_>>());
+
+  let mut mp4 = Vec::new();
+  stdout.read_to_end(&mut mp4)?;
+  let events = events.join().unwrap();
+  assert!(child.wait()?.success());
+  assert!(&mp4[4..8] == b"ftyp");
+  // Encoded output isn't reported as frames or chunks once stdout is taken
+  assert!(!events
+    .iter()
+    .any(|e| matches!(e, FfmpegEvent::OutputChunk(_) | FfmpegEvent::OutputFrame(_))));
+  assert!(events.iter().any(|e| matches!(e, FfmpegEvent::LogEOF)));
+
+  Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_auto_pipes() -> anyhow::Result<()> {
+  // `echo` stands in for ffmpeg, since only the stdio configuration matters.
+  // Stdout is discarded unless it's piped, to keep it out of the test output.
+  let spawn = |output: &str, auto_pipes: bool| {
+    let mut command = FfmpegCommand::from(Command::new("echo"));
+    command
+      .as_inner_mut()
+      .stdout(Stdio::null())
+      .stderr(Stdio::piped());
+    command
+      .input("pipe:0")
+      .output(output)
+      .auto_pipes(auto_pipes)
+      .spawn()
+  };
+
+  let mut child = spawn("-", true)?;
+  assert!(child.take_stdin().is_some());
+  let mut stdout = String::new();
+  child.take_stdout().unwrap().read_to_string(&mut stdout)?;
+  assert!(stdout.starts_with("-i pipe:0 -"));
+  child.wait()?;
+
+  let mut child = spawn("-", false)?;
+  assert!(child.take_stdin().is_none());
+  assert!(child.take_stdout().is_none());
+  child.wait()?;
+
+  // A plain file named `pipe`
+  let mut child = spawn("pipe", true)?;
+  assert!(child.take_stdout().is_none());
+  child.wait()?;
+
+  Ok(())
+}
+
+#[test]
+fn test_check_min_version() {
+  use crate::version::check_min_version;
+
+  let min = "5.0".parse().unwrap();
+  let found = "4.4.2-0ubuntu0.22.04.1".parse().unwrap();
+  let error = check_min_version("ffprobe", found, &min).unwrap_err();
+  assert_eq!(error.to_string(), "ffprobe 4.4.2 found, need 5.0+");
+
+  let found = "5.1.2".parse().unwrap();
+  assert!(check_min_version("ffprobe", found, &min).is_ok());
+  assert!(!crate::version::ffmpeg_is_installed_min(
+    "999.0".parse().unwrap()
+  ));
+}
+
+#[test]
+fn test_ffprobe_selected_entries() -> anyhow::Result<()> {
+  use crate::ffprobe::FfprobeOutput;
+
+  // Output of `-show_entries stream=width,height:format=duration -of json`
+  let json = r#"{
+    "programs": [],
+    "streams": [{ "width": 320, "height": 240 }],
+    "format": { "duration": "1.000000" }
+  }"#;
+  let output: FfprobeOutput = serde_json::from_str(json)?;
+  assert!(output.streams[0].width == Some(320));
+  assert!(output.streams[0].codec_name.is_none());
+  assert!(output.format.unwrap().duration == Some(1.0));
+
+  Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_validate_binary() -> anyhow::Result<()> {
+  use crate::paths::{validate_binary, BinaryKind};
+  use std::os::unix::fs::PermissionsExt;
+
+  // A script printing the banner of ffprobe, as if it were installed as ffmpeg
+  let dir = std::env::temp_dir().join(format!("ffmpeg_sidecar_validate_{}", std::process::id()));
+  std::fs::create_dir_all(&dir)?;
+  let path = dir.join("ffmpeg");
+  std::fs::write(
+    &path,
+    "#!/bin/sh\necho 'ffprobe version 6.1.1 Copyright (c) 2007-2023 the FFmpeg developers'\n",
+  )?;
+  std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+
+  let kind = validate_binary(&path);
+  let not_ffmpeg = validate_binary("true");
+  let missing = validate_binary(dir.join("missing"));
+  std::fs::remove_dir_all(&dir)?;
+
+  assert!(kind? == BinaryKind::Ffprobe);
+  assert!(not_ffmpeg.is_err());
+  assert!(missing.is_err());
+  Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_ffprobe_run_json_value() -> anyhow::Result<()> {
+  // `sh` stands in for ffprobe, ignoring the appended `-print_format json`
+  let json = FfprobeCommand::from(Command::new("sh"))
+    .args(["-c", r#"echo '{"format": {"probe_score": 100}}'"#])
+    .run_json_value()?;
+  assert!(json["format"]["probe_score"] == 100);
+
+  let error = FfprobeCommand::from(Command::new("sh"))
+    .args([
+      "-c",
+      "echo 'input.mp4: No such file or directory' >&2; exit 1",
+    ])
+    .run_json_value()
+    .unwrap_err();
+  assert!(error.to_string().contains("No such file or directory"));
+
+  Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_ffprobe_run_failure() -> anyhow::Result<()> {
+  // Partial JSON on stdout shouldn't hide the reason logged to stderr
+  let error = FfprobeCommand::from(Command::new("sh"))
+    .args([
+      "-c",
+      "printf '{' ; echo 'input.mp4: Invalid data found when processing input' >&2; exit 1",
+    ])
+    .run()
+    .unwrap_err();
+  let message = error.to_string();
+  assert!(message.contains("Invalid data found when processing input"));
+  assert!(!message.contains("JSON"));
+
+  let output = FfprobeCommand::from(Command::new("sh"))
+    .args(["-c", r#"echo '{"format": {"filename": "input.mp4"}}'"#])
+    .run()?;
+  assert!(output.format.unwrap().filename.as_deref() == Some("input.mp4"));
+
+  Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_ffmpeg_error_display() {
+  use crate::error::{FfmpegError, STDERR_TAIL_LINES};
+  use std::os::unix::process::ExitStatusExt;
+  use std::process::ExitStatus;
+
+  let mut errors = vec![("[error] Ignored".to_string(), None); STDERR_TAIL_LINES];
+  errors.push((
+    "[vost#0:0 @ 0x600003bd4000] [fatal] Unknown encoder 'libx265'\n".to_string(),
+    Some(FfmpegErrorKind::UnknownEncoder),
+  ));
+  let error = FfmpegError::new(ExitStatus::from_raw(1 << 8), errors);
+  assert!(error.exit_code == Some(1));
+  assert!(error.kind == Some(FfmpegErrorKind::UnknownEncoder));
+  assert!(error.stderr_tail.lines().count() == STDERR_TAIL_LINES);
+  assert_eq!(
+    error.to_string(),
+    "ffmpeg exited with code 1: Unknown encoder 'libx265'"
+  );
+
+  let error = FfmpegError::new(ExitStatus::from_raw(9), Vec::new());
+  assert!(error.exit_code.is_none());
+  assert_eq!(error.to_string(), "ffmpeg exited with a signal");
+}
+
+#[test]
+fn test_thumbnail_args() {
+  let args = |command: &mut FfmpegCommand| -> Vec<String> {
+    command
+      .get_args()
+      .filter_map(|arg| arg.to_str().map(String::from))
+      .collect()
+  };
+
+  let mut command = FfmpegCommand::new();
+  command.thumbnail("input.mp4", Duration::from_millis(5_500), "thumb.jpg");
+  assert!(args(&mut command).ends_with(
+    &[
+      "-ss",
+      "00:00:05.500",
+      "-i",
+      "input.mp4",
+      "-frames:v",
+      "1",
+      "-q:v",
+      "2",
+      "thumb.jpg"
+    ]
+    .map(String::from)
+  ));
+
+  let mut command = FfmpegCommand::new();
+  command.thumbnail_png("input.mp4", Duration::from_secs(1));
+  assert!(args(&mut command).ends_with(
+    &[
+      "-ss",
+      "00:00:01.000",
+      "-i",
+      "input.mp4",
+      "-frames:v",
+      "1",
+      "-f",
+      "image2pipe",
+      "-c:v",
+      "png",
+      "pipe:1"
+    ]
+    .map(String::from)
+  ));
+}
+
+#[test]
+fn test_pcm_output_args() {
+  let mut command = FfmpegCommand::new();
+  command
+    .input("input.mp3")
+    .pcm_output(48000, 2, PcmFormat::F32le);
+  let args: Vec<String> = command
+    .get_args()
+    .filter_map(|arg| arg.to_str().map(String::from))
+    .collect();
+  assert!(args.ends_with(
+    &[
+      "-f",
+      "f32le",
+      "-c:a",
+      "pcm_f32le",
+      "-ar",
+      "48000",
+      "-ac",
+      "2",
+      "pipe:1"
+    ]
+    .map(String::from)
+  ));
+}
+
+#[test]
+fn test_cut_accurate_args() {
+  let mut command = FfmpegCommand::new();
+  command.cut_accurate(
+    "input.mp4",
+    Duration::from_millis(1_500),
+    Duration::from_secs(4),
+    "clip.mp4",
+  );
+  let args: Vec<String> = command
+    .get_args()
+    .filter_map(|arg| arg.to_str().map(String::from))
+    .collect();
+  assert!(args.ends_with(
+    &[
+      "-ss",
+      "00:00:01.500",
+      "-to",
+      "00:00:04.000",
+      "-i",
+      "input.mp4",
+      "clip.mp4"
+    ]
+    .map(String::from)
+  ));
+}
+
+#[test]
+fn test_shortest_buf_duration_args() {
+  let mut command = FfmpegCommand::new();
+  command
+    .shortest()
+    .shortest_buf_duration(Duration::from_millis(2_500));
+  let args: Vec<String> = command
+    .get_args()
+    .filter_map(|arg| arg.to_str().map(String::from))
+    .collect();
+  assert!(args.ends_with(&["-shortest", "-shortest_buf_duration", "2.5"].map(String::from)));
+}
+
+#[test]
+fn test_audio_filter_helpers_args() {
+  let measured = LoudnormMeasurement {
+    input_i: -27.61,
+    input_lra: 18.06,
+    input_tp: -4.47,
+    input_thresh: -39.2,
+    target_offset: 0.58,
+  };
+  let mut command = FfmpegCommand::new();
+  command
+    .input("input.wav")
+    .volume(Volume::Decibels(-3.0))
+    .loudnorm(LoudnormOptions {
+      integrated: -16.0,
+      measured: Some(measured),
+      ..Default::default()
+    })
+    .volume(Volume::Factor(1.5))
+    .output("output.wav");
+  let args: Vec<String> = command
+    .get_args()
+    .filter_map(|arg| arg.to_str().map(String::from))
+    .collect();
+  let chain = "volume=-3dB,\
+    loudnorm=I=-16:LRA=7:TP=-2:measured_I=-27.61:measured_LRA=18.06:measured_TP=-4.47:measured_thresh=-39.2:offset=0.58:linear=true,\
+    volume=1.5";
+  assert!(args.ends_with(&["-af", chain, "output.wav"].map(String::from)));
+}
+
+#[test]
+fn test_input_with_order() {
+  let mut command = FfmpegCommand::new();
+  command
+    .realtime()
+    .input_with("a.mp4", |i| i.seek(Duration::from_secs(5)))
+    .input_with("b.mp4", |i| i.hwaccel("vaapi").to("00:01:00"))
+    .input("c.mp4")
+    .output("out.mp4");
+  let args: Vec<String> = command
+    .get_args()
+    .filter_map(|arg| arg.to_str().map(String::from))
+    .collect();
+  assert!(args.ends_with(
+    &[
+      "-re",
+      "-ss",
+      "00:00:05.000",
+      "-i",
+      "a.mp4",
+      "-hwaccel",
+      "vaapi",
+      "-to",
+      "00:01:00",
+      "-i",
+      "b.mp4",
+      "-i",
+      "c.mp4",
+      "out.mp4"
+    ]
+    .map(String::from)
+  ));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_atomic_output() -> anyhow::Result<()> {
+  let dir = std::env::temp_dir().join(format!("ffmpeg_sidecar_atomic_{}", std::process::id()));
+  std::fs::create_dir_all(&dir)?;
+  let final_path = dir.join("out.mp4");
+
+  // `true` and `false` stand in for ffmpeg, with the temporary output written
+  // by hand since only the handling of the exit status matters
+  let run = |program: &str| -> anyhow::Result<std::path::PathBuf> {
+    let mut command = FfmpegCommand::from(Command::new(program));
+    command.as_inner_mut().stderr(Stdio::piped());
+    command.atomic_output(final_path.to_str().unwrap());
+    let temp_path = std::path::PathBuf::from(command.get_args().last().unwrap());
+    assert!(temp_path.parent() == Some(dir.as_path()));
+    assert!(temp_path.extension().unwrap() == "mp4");
+    std::fs::write(&temp_path, program)?;
+    command.spawn()?.wait()?;
+    Ok(temp_path)
+  };
+
+  let temp_path = run("false")?;
+  assert!(!temp_path.exists());
+  assert!(!final_path.exists());
+
+  let temp_path = run("true")?;
+  assert!(!temp_path.exists());
+  assert!(std::fs::read_to_string(&final_path)? == "true");
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+fn test_movflags_presets() {
+  let args = |command: &mut FfmpegCommand| -> Vec<String> {
+    command
+      .get_args()
+      .filter_map(|arg| arg.to_str().map(String::from))
+      .collect()
+  };
+
+  let mut command = FfmpegCommand::new();
+  command.faststart();
+  assert!(args(&mut command).ends_with(&["-movflags", "+faststart"].map(String::from)));
+
+  let mut command = FfmpegCommand::new();
+  command.frag_mp4();
+  assert!(
+    args(&mut command).ends_with(&["-movflags", "frag_keyframe+empty_moov"].map(String::from))
+  );
+}
+
+#[test]
+#[cfg(unix)]
+fn test_dedup_warnings() -> anyhow::Result<()> {
+  // A shell script stands in for a remux flooding the log with warnings
+  let script = r#"
+    for i in 1 2 3; do
+      echo "[mp4 @ 0x7f8] [warning] Non-monotonic DTS in output stream 0:1; previous: 10, current: $i; changing to 11." >&2
+    done
+    echo "[mp4 @ 0x7f8] [error] Malformed AAC bitstream detected: use the audio bitstream filter 'aac_adtstoasc' to fix it" >&2
+  "#;
+  let mut command = FfmpegCommand::from(Command::new("sh"));
+  command.as_inner_mut().stderr(Stdio::piped());
+  command.args(["-c", script]);
+  let mut child = command.spawn()?;
+
+  let warnings: Vec<_> = child
+    .iter()?
+    .dedup_warnings(Some(2))
+    .filter_map(|event| match event {
+      FfmpegEvent::ParsedWarning(warning) => Some(format!("{:?} {}", warning.kind, warning.count)),
+      FfmpegEvent::Error(error) if error.contains("warnings logged") => Some(error),
+      _ => None,
+    })
+    .collect();
+  child.wait()?;
+
+  assert!(warnings.len() == 4, "{warnings:?}");
+  assert!(warnings[0] == "NonMonotonicDts 1");
+  assert!(warnings[1].starts_with("2 NonMonotonicDts warnings logged"));
+  assert!(warnings[2] == "MalformedBitstream 1");
+  assert!(warnings[3] == "NonMonotonicDts 3");
+  Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_dedup_folded_warnings() -> anyhow::Result<()> {
+  let script = r#"
+    echo "[mp4 @ 0x7f8] [warning] Non-monotonic DTS in output stream 0:1; previous: 10, current: 9; changing to 11." >&2
+    echo "[mp4 @ 0x7f8] [warning]     Last message repeated 1233 times" >&2
+    echo "[info] Press [q] to stop, [?] for help" >&2
+    echo "[info] Last message repeated 2 times" >&2
+  "#;
+  let mut command = FfmpegCommand::from(Command::new("sh"));
+  command.as_inner_mut().stderr(Stdio::piped());
+  command.args(["-c", script]);
+  let mut child = command.spawn()?;
+
+  let events: Vec<_> = child.iter()?.dedup_warnings(Some(1000)).collect();
+  child.wait()?;
+
+  let warnings: Vec<_> = events
+    .iter()
+    .filter_map(|event| match event {
+      FfmpegEvent::ParsedWarning(warning) => Some(warning.count),
+      _ => None,
+    })
+    .collect();
+  assert!(warnings == [1, 1234], "{warnings:?}");
+  assert!(events
+    .iter()
+    .any(|event| matches!(event, FfmpegEvent::Error(e) if e.starts_with("1234 NonMonotonicDts"))));
+  // A fold following something other than a warning passes through
+  assert!(events
+    .iter()
+    .any(|event| matches!(event, FfmpegEvent::Repeated(folded) if folded.count == 2)));
+  Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_with_eta() -> anyhow::Result<()> {
+  // A shell script stands in for ffmpeg, printing progress halfway through a
+  // 10 second input
+  let progress = |script: &str| -> anyhow::Result<Vec<FfmpegProgress>> {
+    let mut command = FfmpegCommand::from(Command::new("sh"));
+    command.as_inner_mut().stderr(Stdio::piped());
+    command.args(["-c", script]);
+    let mut child = command.spawn()?;
+    let progress = child
+      .iter()?
+      .with_eta()
+      .filter_map(|event| match event {
+        FfmpegEvent::Progress(progress) => Some(progress),
+        _ => None,
+      })
+      .collect();
+    child.wait()?;
+    Ok(progress)
+  };
+  let status_lines = r#"
+    echo "frame=   50 fps=0.0 q=-1.0 size=     256kB time=00:00:02.50 bitrate= 838.9kbits/s speed=2x" >&2
+    echo "frame=  100 fps=0.0 q=-1.0 size=     512kB time=00:00:05.00 bitrate= 838.9kbits/s speed=2x" >&2
+  "#;
+
+  let with_duration = progress(&format!(
+    r#"
+    echo "Input #0, matroska,webm, from 'input.mkv':" >&2
+    echo "  Duration: 00:00:10.00, start: 0.000000, bitrate: 1000 kb/s" >&2
+    {status_lines}"#
+  ))?;
+  assert!(with_duration.len() == 2);
+  assert!(with_duration[0].fraction == Some(0.25));
+  assert!(with_duration[0].eta == Some(Duration::from_secs_f64(3.75)));
+  assert!(with_duration[1].fraction == Some(0.5));
+  assert!(with_duration[1].eta.is_some());
+
+  let unknown_duration = progress(status_lines)?;
+  assert!(unknown_duration.len() == 2);
+  assert!(unknown_duration
+    .iter()
+    .all(|p| p.fraction.is_none() && p.eta.is_none()));
+  Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_stdout_buffer_size() -> anyhow::Result<()> {
+  // A shell script stands in for ffmpeg writing two 2x1 rgb24 frames
+  let script = r#"
+    echo "[info] Stream mapping:" >&2
+    echo "[info]   Stream #0:0 -> #0:0 (wrapped_avframe (native) -> rawvideo (native))" >&2
+    echo "[info] Output #0, rawvideo, to 'pipe:':" >&2
+    echo "[info]   Stream #0:0: Video: rawvideo (RGB[24] / 0x18424752), rgb24(progressive), 2x1, q=2-31, 25 fps, 25 tbn" >&2
+    printf 'abcdefghijkl'
+  "#;
+  let mut command = FfmpegCommand::from(Command::new("sh"));
+  command
+    .as_inner_mut()
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+  // Smaller than a frame, so each one takes several reads
+  command.args(["-c", script]).stdout_buffer_size(4);
+  let mut child = command.spawn()?;
+
+  let frames: Vec<_> = child.iter()?.filter_frames().collect();
+  child.wait()?;
+
+  assert!(frames.len() == 2);
+  assert!(frames[0].data == b"abcdef" && frames[1].data == b"ghijkl");
+  Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_close_stdin() -> anyhow::Result<()> {
+  // `sh` stands in for ffmpeg, echoing stdin to stderr until it's closed
+  let mut command = FfmpegCommand::from(Command::new("sh"));
+  command
+    .as_inner_mut()
+    .stdin(Stdio::piped())
+    .stderr(Stdio::piped());
+  command.args(["-c", "cat >&2; echo 'end of input' >&2"]);
+  let mut child = command.spawn()?;
+
+  child.send_stdin_command(b"frame\n")?;
+  child.close_stdin();
+  assert!(child.quit().is_err());
+
+  let lines: Vec<_> = child.iter()?.into_ffmpeg_stderr().collect();
+  assert!(child.wait()?.success());
+  assert!(lines == ["frame", "end of input"], "{lines:?}");
+  Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_control_from_another_thread() -> anyhow::Result<()> {
+  // `sh` stands in for ffmpeg: echo the first byte of stdin to stderr, then
+  // hang until killed
+  let mut command = FfmpegCommand::from(Command::new("sh"));
+  command
+    .as_inner_mut()
+    .stdin(Stdio::piped())
+    .stderr(Stdio::piped());
+  command.args(["-c", "head -c 1 >&2; sleep 10"]);
+  let mut child = command.spawn()?;
+  let control = child.control();
+  assert!(control.pid() == child.pid());
+
+  let handle = control.clone();
+  thread::spawn(move || handle.quit()).join().unwrap()?;
+  let mut stderr = child.take_stderr().unwrap();
+  let mut byte = [0u8; 1];
+  stderr.read_exact(&mut byte)?;
+  assert!(&byte == b"q");
+
+  thread::spawn(move || control.kill()).join().unwrap()?;
+  assert!(!child.wait()?.success());
+  assert!(child.control().has_exited());
+  Ok(())
+}
+
+#[test]
+fn test_dry_run_matches_spawned_args() -> anyhow::Result<()> {
+  let mut command = FfmpegCommand::from(Command::new("sh"));
+  command.as_inner_mut().stderr(Stdio::piped());
+  command
+    .args(["-c", "exit 0"])
+    .audio_filter("volume=0.5")
+    .output_format("null");
+  let dry_run = command.dry_run();
+  assert!(command.get_args().count() == 2);
+
+  command.spawn()?.wait()?;
+  let spawned: Vec<_> = command.get_args().map(|arg| arg.to_os_string()).collect();
+  assert!(spawned == dry_run);
+  assert!(dry_run.ends_with(&["-af", "volume=0.5", "-f", "null", "-n"].map(Into::into)));
+  Ok(())
+}
+
+#[test]
+fn test_env() -> anyhow::Result<()> {
+  let mut command = FfmpegCommand::from(Command::new("sh"));
+  command.as_inner_mut().stderr(Stdio::piped());
+  let status = command
+    .args([
+      "-c",
+      r#"test "$FFREPORT" = file=job.log:level=32 && test "$A$B" = 12"#,
+    ])
+    .env("FFREPORT", "file=job.log:level=32")
+    .envs([("A", "1"), ("B", "2")])
+    .spawn()?
+    .wait()?;
+  assert!(status.success());
+
+  let json = FfprobeCommand::from(Command::new("sh"))
+    .args(["-c", r#"echo "{\"devices\": \"$CUDA_VISIBLE_DEVICES\"}""#])
+    .env("CUDA_VISIBLE_DEVICES", "1")
+    .run_json_value()?;
+  assert!(json["devices"] == "1");
+  Ok(())
+}
+
+#[test]
+fn test_current_dir() -> anyhow::Result<()> {
+  let dir = std::env::temp_dir().canonicalize()?;
+  let mut command = FfmpegCommand::from(Command::new("sh"));
+  command.as_inner_mut().stderr(Stdio::piped());
+  let status = command
+    .current_dir(&dir)
+    .args(["-c", &format!(r#"test "$(pwd -P)" = '{}'"#, dir.display())])
+    .spawn()?
+    .wait()?;
+  assert!(status.success());
+
+  let json = FfprobeCommand::from(Command::new("sh"))
+    .current_dir(&dir)
+    .args(["-c", r#"echo "{\"cwd\": \"$(pwd -P)\"}""#])
+    .run_json_value()?;
+  assert!(json["cwd"] == dir.to_string_lossy().as_ref());
+
+  // Concat list entries are resolved against the working directory of ffmpeg
+  let mut command = FfmpegCommand::new();
+  command.current_dir(&dir).concat_demuxer(&["a.mp4"])?;
+  let list_path = command.get_args().last().unwrap().to_owned();
+  let list = std::fs::read_to_string(list_path)?;
+  assert!(list == format!("file '{}'\n", dir.join("a.mp4").display()));
+  Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_current_dir_atomic_output() -> anyhow::Result<()> {
+  let dir = std::env::temp_dir().join(format!(
+    "ffmpeg_sidecar_current_dir_atomic_{}",
+    std::process::id()
+  ));
+  std::fs::create_dir_all(&dir)?;
+
+  // `sh` stands in for ffmpeg, writing the relative temporary output it's
+  // given within its working directory
+  let mut command = FfmpegCommand::from(Command::new("sh"));
+  command.as_inner_mut().stderr(Stdio::piped());
+  command
+    .current_dir(&dir)
+    .args(["-c", r#"printf done > "$0""#])
+    .atomic_output("out.mp4");
+  assert!(command.spawn()?.wait()?.success());
+
+  assert!(std::fs::read_to_string(dir.join("out.mp4"))? == "done");
+  assert!(std::fs::read_dir(&dir)?.count() == 1);
+  assert!(!std::path::Path::new("out.mp4").exists());
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
+
+#[test]
+fn test_events_channel() -> anyhow::Result<()> {
+  let mut command = FfmpegCommand::from(Command::new("sh"));
+  command.as_inner_mut().stderr(Stdio::piped());
+  let mut child = command
+    .args(["-c", "for i in 1 2 3; do echo \"[info] line $i\" >&2; done"])
+    .spawn()?;
+  let events = child.events_channel()?;
+  assert!(child.wait()?.success());
+
+  // Everything was sent by the time `wait()` returned, and the channel closed
+  let logs: Vec<_> = events
+    .try_iter()
+    .filter_map(|event| match event {
+      FfmpegEvent::Log(LogLevel::Info, line) => Some(line),
+      _ => None,
+    })
+    .collect();
+  assert!(logs == ["[info] line 1", "[info] line 2", "[info] line 3"]);
+  assert!(events.recv().is_err());
+  Ok(())
+}
@@ -0,0 +1,96 @@
+//! Helper for two-pass encoding to a target bitrate.
+
+use crate::{command::FfmpegCommand, event::FfmpegEvent};
+use std::{
+  fs::{read_dir, remove_file},
+  path::Path,
+};
+
+/// Run a two-pass encode at the given video `bitrate` (e.g. `"2M"`), writing
+/// the final result to `output`.
+///
+/// `configure` is called once per pass to add the shared arguments: inputs,
+/// codec, filters and any other output options. The helper then appends
+/// `-b:v`, `-pass` and `-passlogfile` itself. The first pass discards its
+/// output with [`null_output`](FfmpegCommand::null_output), and skips
+/// audio since only the video statistics are needed.
+///
+/// Every event from both passes is forwarded to `on_event` along with the pass
+/// number (`1` or `2`), so progress can be reported across the whole job. On
+/// success, the `.log` and `.log.mbtree` files written next to
+/// `passlog_prefix` are deleted.
+///
+/// ```rust,no_run
+/// use ffmpeg_sidecar::{event::FfmpegEvent, two_pass::run_two_pass};
+/// run_two_pass(
+///   "1M",
+///   "output/passlog",
+///   "output/two_pass.mp4",
+///   |command| {
+///     command.input("input.mp4").codec_video("libx264");
+///   },
+///   |pass, event| {
+///     if let FfmpegEvent::Progress(progress) = event {
+///       println!("pass {pass}: {}", progress.time);
+///     }
+///   },
+/// )
+/// .unwrap();
+/// ```
+pub fn run_two_pass<P: AsRef<Path>, S: AsRef<str>>(
+  bitrate: &str,
+  passlog_prefix: P,
+  output: S,
+  configure: impl Fn(&mut FfmpegCommand),
+  mut on_event: impl FnMut(u8, FfmpegEvent),
+) -> anyhow::Result<()> {
+  let passlog_prefix = passlog_prefix.as_ref();
+  let passlog_arg = passlog_prefix.to_string_lossy();
+
+  for pass in [1u8, 2] {
+    let mut command = FfmpegCommand::new();
+    configure(&mut command);
+    command.args(["-b:v", bitrate, "-pass", &pass.to_string()]);
+    command.args(["-passlogfile", &passlog_arg]);
+    match pass {
+      1 => command.no_audio().overwrite().null_output(),
+      _ => command.output(output.as_ref()),
+    };
+
+    let mut child = command.spawn()?;
+    for event in child.iter()? {
+      on_event(pass, event);
+    }
+    let status = child.wait()?;
+    if !status.success() {
+      anyhow::bail!("Pass {pass} of two-pass encoding failed with {status}");
+    }
+  }
+
+  remove_passlog_files(passlog_prefix)
+}
+
+/// Delete the statistics files FFmpeg writes for `-passlogfile`, which are
+/// named like `{prefix}-0.log` and `{prefix}-0.log.mbtree`.
+fn remove_passlog_files(passlog_prefix: &Path) -> anyhow::Result<()> {
+  let dir = match passlog_prefix.parent() {
+    Some(parent) if !parent.as_os_str().is_empty() => parent,
+    _ => Path::new("."),
+  };
+  let Some(prefix) = passlog_prefix.file_name().and_then(|p| p.to_str()) else {
+    return Ok(());
+  };
+
+  for entry in read_dir(dir)? {
+    let path = entry?.path();
+    let is_passlog = path
+      .file_name()
+      .and_then(|name| name.to_str())
+      .and_then(|name| name.strip_prefix(prefix))
+      .is_some_and(|rest| rest.starts_with('-') && rest.contains(".log"));
+    if is_passlog {
+      remove_file(path)?;
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,47 @@
+//! Small helpers for examples and tests, outside the core FFmpeg interface.
+
+use std::{io, path::Path, process::Command};
+
+use crate::command::BackgroundCommand;
+
+/// Open a file with the default application for its type, such as the system
+/// video player for an output of an example. Uses `start` on Windows, `open`
+/// on macOS, and `xdg-open` elsewhere.
+///
+/// Returns once the launcher exits, which doesn't wait for the application
+/// itself. Returns `Err` if the launcher can't be spawned or exits
+/// unsuccessfully, e.g. if there's no application for the file type.
+///
+/// ```rust,no_run
+/// use ffmpeg_sidecar::{command::FfmpegCommand, util::open_with_default_app};
+/// use std::path::Path;
+///
+/// FfmpegCommand::new()
+///   .testsrc()
+///   .output("output.mp4")
+///   .spawn()?
+///   .wait()?;
+/// open_with_default_app(Path::new("output.mp4"))?;
+/// # anyhow::Ok(())
+/// ```
+pub fn open_with_default_app(path: &Path) -> io::Result<()> {
+  let mut command = if cfg!(target_os = "windows") {
+    // `start` is a builtin of `cmd`; its first quoted argument is the title
+    let mut command = Command::new("cmd");
+    command.args(["/C", "start", ""]);
+    command
+  } else if cfg!(target_os = "macos") {
+    Command::new("open")
+  } else {
+    Command::new("xdg-open")
+  };
+  let status = command.arg(path).create_no_window().status()?;
+  if status.success() {
+    Ok(())
+  } else {
+    Err(io::Error::other(format!(
+      "Failed to open {} with the default application: {status}",
+      path.display()
+    )))
+  }
+}
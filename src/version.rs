@@ -5,7 +5,9 @@ use anyhow::Context;
 use crate::command::BackgroundCommand;
 use crate::{event::FfmpegEvent, log_parser::FfmpegLogParser, paths::ffmpeg_path};
 use std::ffi::OsStr;
+use std::fmt;
 use std::process::{Command, Stdio};
+use std::str::FromStr;
 
 /// Alias for `ffmpeg -version`, parsing the version number and returning it.
 pub fn ffmpeg_version() -> anyhow::Result<String> {
@@ -15,6 +17,74 @@ pub fn ffmpeg_version() -> anyhow::Result<String> {
 /// Lower level variant of `ffmpeg_version` that exposes a customized path
 /// to the ffmpeg binary.
 pub fn ffmpeg_version_with_path<S: AsRef<OsStr>>(path: S) -> anyhow::Result<String> {
+  let mut version: Option<String> = None;
+  parse_version_output(path, |event| {
+    if let FfmpegEvent::ParsedVersion(v) = event {
+      version = Some(v.version);
+    }
+  })?;
+  version.context("Failed to parse ffmpeg version")
+}
+
+/// Alias for `ffmpeg -version`, returning the flags from its
+/// `configuration:` line, like `--enable-gpl` or `--disable-doc`. Useful to
+/// check for optional libraries before starting a job that needs them.
+pub fn ffmpeg_build_config() -> anyhow::Result<Vec<String>> {
+  ffmpeg_build_config_with_path(ffmpeg_path())
+}
+
+/// Lower level variant of `ffmpeg_build_config` that exposes a customized path
+/// to the ffmpeg binary.
+pub fn ffmpeg_build_config_with_path<S: AsRef<OsStr>>(path: S) -> anyhow::Result<Vec<String>> {
+  let mut configuration: Option<Vec<String>> = None;
+  parse_version_output(path, |event| {
+    if let FfmpegEvent::ParsedConfiguration(c) = event {
+      configuration = Some(c.configuration);
+    }
+  })?;
+  configuration.context("Failed to parse ffmpeg build configuration")
+}
+
+/// Whether the ffmpeg binary was built with `--enable-{name}`, e.g.
+/// `supports_library("libx265")`. Returns `false` if the configuration can't
+/// be read.
+pub fn supports_library(name: &str) -> bool {
+  supports_library_with_path(ffmpeg_path(), name)
+}
+
+/// Lower level variant of `supports_library` that exposes a customized path
+/// to the ffmpeg binary.
+pub fn supports_library_with_path<S: AsRef<OsStr>>(path: S, name: &str) -> bool {
+  ffmpeg_build_config_with_path(path)
+    .map(|configuration| enables_library(&configuration, name))
+    .unwrap_or(false)
+}
+
+/// Whether a list of configuration flags, as returned by
+/// [`ffmpeg_build_config`] or found in
+/// [`FfmpegConfiguration`](crate::event::FfmpegConfiguration), includes
+/// `--enable-{name}`. A leading `--enable-` on `name` is ignored.
+///
+/// ```rust
+/// use ffmpeg_sidecar::version::enables_library;
+/// let configuration = ["--enable-gpl", "--enable-libx264", "--disable-doc"].map(String::from);
+/// assert!(enables_library(&configuration, "libx264"));
+/// assert!(enables_library(&configuration, "--enable-libx264"));
+/// assert!(!enables_library(&configuration, "libx265"));
+/// assert!(!enables_library(&configuration, "doc"));
+/// ```
+pub fn enables_library(configuration: &[String], name: &str) -> bool {
+  let name = name.strip_prefix("--enable-").unwrap_or(name);
+  configuration
+    .iter()
+    .any(|flag| flag.strip_prefix("--enable-") == Some(name))
+}
+
+/// Run `ffmpeg -version`, passing each parsed event to `on_event`.
+fn parse_version_output<S: AsRef<OsStr>>(
+  path: S,
+  mut on_event: impl FnMut(FfmpegEvent),
+) -> anyhow::Result<()> {
   let mut cmd = Command::new(&path)
     .create_no_window()
     .arg("-version")
@@ -23,17 +93,199 @@ pub fn ffmpeg_version_with_path<S: AsRef<OsStr>>(path: S) -> anyhow::Result<Stri
   let stdout = cmd.stdout.take().context("No standard output channel")?;
   let mut parser = FfmpegLogParser::new(stdout);
 
-  let mut version: Option<String> = None;
   while let Ok(event) = parser.parse_next_event() {
     match event {
-      FfmpegEvent::ParsedVersion(v) => version = Some(v.version),
       FfmpegEvent::LogEOF => break,
-      _ => {}
+      event => on_event(event),
     }
   }
   let exit_status = cmd.wait()?;
   if !exit_status.success() {
     anyhow::bail!("ffmpeg -version exited with non-zero status");
   }
-  version.context("Failed to parse ffmpeg version")
+  Ok(())
+}
+
+/// Alias for `ffmpeg -version`, parsing the version number into a
+/// [`FfmpegVersion`] for comparisons.
+pub fn ffmpeg_version_parsed() -> anyhow::Result<FfmpegVersion> {
+  ffmpeg_version_parsed_with_path(ffmpeg_path())
+}
+
+/// Lower level variant of `ffmpeg_version_parsed` that exposes a customized
+/// path to the ffmpeg binary.
+pub fn ffmpeg_version_parsed_with_path<S: AsRef<OsStr>>(path: S) -> anyhow::Result<FfmpegVersion> {
+  ffmpeg_version_with_path(path)?.parse()
+}
+
+/// Like [`ffmpeg_is_installed`](crate::command::ffmpeg_is_installed), but also
+/// requiring version `min` or newer. See [`require_ffmpeg_version`] for an
+/// error describing the mismatch.
+pub fn ffmpeg_is_installed_min(min: FfmpegVersion) -> bool {
+  require_ffmpeg_version(min).is_ok()
+}
+
+/// Check that ffmpeg is installed with version `min` or newer, returning the
+/// installed version. Fails with a message like `ffmpeg 4.4.2 found, need
+/// 5.1+`, suitable for checking requirements at startup.
+pub fn require_ffmpeg_version(min: FfmpegVersion) -> anyhow::Result<FfmpegVersion> {
+  require_ffmpeg_version_with_path(ffmpeg_path(), min)
+}
+
+/// Lower level variant of `require_ffmpeg_version` that exposes a customized
+/// path to the ffmpeg binary.
+pub fn require_ffmpeg_version_with_path<S: AsRef<OsStr>>(
+  path: S,
+  min: FfmpegVersion,
+) -> anyhow::Result<FfmpegVersion> {
+  let version = ffmpeg_version_parsed_with_path(path).context("ffmpeg not found")?;
+  check_min_version("ffmpeg", version, &min)
+}
+
+/// Return `version` if it satisfies `min`, or an error naming `program` and
+/// both versions.
+pub(crate) fn check_min_version(
+  program: &str,
+  version: FfmpegVersion,
+  min: &FfmpegVersion,
+) -> anyhow::Result<FfmpegVersion> {
+  if !version.satisfies(min) {
+    anyhow::bail!("{program} {version} found, need {min}+");
+  }
+  Ok(version)
+}
+
+/// A version number reported by `ffmpeg -version` or `ffprobe -version`.
+///
+/// Builds from the master branch (like `N-110000-gabc1234`) don't carry a
+/// release number, so `major` and `minor` are `0` and only `git` is set.
+///
+/// ## Examples
+///
+/// ```rust
+/// use ffmpeg_sidecar::version::FfmpegVersion;
+///
+/// let release: FfmpegVersion = "6.1.1".parse().unwrap();
+/// assert!(release.major == 6 && release.minor == 1 && release.patch == Some(1));
+/// assert!(release.git.is_none());
+///
+/// let tag: FfmpegVersion = "n6.0".parse().unwrap();
+/// assert!(tag.major == 6 && tag.minor == 0 && tag.patch.is_none());
+///
+/// let distro: FfmpegVersion = "4.4.2-0ubuntu0.22.04.1".parse().unwrap();
+/// assert!(distro.major == 4 && distro.minor == 4 && distro.patch == Some(2));
+///
+/// let gyan: FfmpegVersion = "7.0-essentials_build-www.gyan.dev".parse().unwrap();
+/// assert!(gyan.major == 7 && gyan.minor == 0);
+///
+/// let master: FfmpegVersion = "N-110000-gabc1234".parse().unwrap();
+/// assert!(master.major == 0 && master.git.as_deref() == Some("N-110000-gabc1234"));
+///
+/// let dated: FfmpegVersion = "2023-01-18-git-ba36e6ed52-full_build-www.gyan.dev".parse().unwrap();
+/// assert!(dated.git.as_deref() == Some("2023-01-18-git-ba36e6ed52-full_build-www.gyan.dev"));
+///
+/// assert!("unknown".parse::<FfmpegVersion>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfmpegVersion {
+  pub major: u32,
+  pub minor: u32,
+  /// Omitted by `.0` releases, e.g. `6.0`
+  pub patch: Option<u32>,
+  /// The full version string of builds from git, e.g. `N-110000-gabc1234`
+  pub git: Option<String>,
+}
+
+impl FfmpegVersion {
+  /// Whether this is release `major.minor` or newer. Builds from git are
+  /// assumed to be newer than any release, since their release number is
+  /// unknown.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::version::FfmpegVersion;
+  /// let version: FfmpegVersion = "5.1.2".parse().unwrap();
+  /// assert!(version.at_least(5, 1));
+  /// assert!(version.at_least(4, 4));
+  /// assert!(!version.at_least(6, 0));
+  /// assert!("N-110000-gabc1234".parse::<FfmpegVersion>().unwrap().at_least(7, 0));
+  /// ```
+  pub fn at_least(&self, major: u32, minor: u32) -> bool {
+    if self.git.is_some() && self.major == 0 {
+      return true;
+    }
+    (self.major, self.minor) >= (major, minor)
+  }
+
+  /// Like [`at_least`](FfmpegVersion::at_least), but comparing with another
+  /// version including its patch number, where a missing patch counts as `0`.
+  ///
+  /// ```rust
+  /// use ffmpeg_sidecar::version::FfmpegVersion;
+  /// let min: FfmpegVersion = "5.1.2".parse().unwrap();
+  /// assert!("5.1.2".parse::<FfmpegVersion>().unwrap().satisfies(&min));
+  /// assert!("6.0".parse::<FfmpegVersion>().unwrap().satisfies(&min));
+  /// assert!(!"5.1".parse::<FfmpegVersion>().unwrap().satisfies(&min));
+  /// ```
+  pub fn satisfies(&self, min: &FfmpegVersion) -> bool {
+    if self.git.is_some() && self.major == 0 {
+      return true;
+    }
+    let patch = |version: &FfmpegVersion| version.patch.unwrap_or(0);
+    (self.major, self.minor, patch(self)) >= (min.major, min.minor, patch(min))
+  }
+}
+
+impl FromStr for FfmpegVersion {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> anyhow::Result<Self> {
+    let s = s.trim();
+    let is_git = s.starts_with("N-") || s.contains("-git-") || is_git_describe(s);
+    let git = is_git.then(|| s.to_string());
+
+    // Release numbers may be prefixed with `n` (git tags) and followed by a
+    // distro or build suffix, e.g. `n6.0` or `6.1.1-3ubuntu5`.
+    let release = s.strip_prefix('n').unwrap_or(s);
+    let release = release
+      .split(|c: char| !c.is_ascii_digit() && c != '.')
+      .next()
+      .unwrap_or_default();
+    let mut numbers = release.split('.');
+
+    match (numbers.next(), numbers.next()) {
+      (Some(major), Some(minor)) if !major.is_empty() && !minor.is_empty() => Ok(Self {
+        major: major.parse()?,
+        minor: minor.parse()?,
+        patch: numbers.next().map(str::parse).transpose()?,
+        git,
+      }),
+      _ if git.is_some() => Ok(Self {
+        major: 0,
+        minor: 0,
+        patch: None,
+        git,
+      }),
+      _ => anyhow::bail!("Unrecognized version string: {s}"),
+    }
+  }
+}
+
+impl fmt::Display for FfmpegVersion {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let Some(git) = &self.git {
+      return write!(f, "{git}");
+    }
+    write!(f, "{}.{}", self.major, self.minor)?;
+    if let Some(patch) = self.patch {
+      write!(f, ".{patch}")?;
+    }
+    Ok(())
+  }
+}
+
+/// Matches the `git describe` suffix of untagged builds, e.g. `n6.0-12-gabc1234`.
+fn is_git_describe(s: &str) -> bool {
+  s.rsplit('-').next().is_some_and(|hash| {
+    hash.len() > 1 && hash.starts_with('g') && hash[1..].chars().all(|c| c.is_ascii_hexdigit())
+  })
 }
@@ -0,0 +1,80 @@
+//! Typed options for the libx264 and libx265 encoders.
+//!
+//! See <https://trac.ffmpeg.org/wiki/Encode/H.264> and
+//! <https://trac.ffmpeg.org/wiki/Encode/H.265> for guidance on picking values.
+
+/// Values for [`FfmpegCommand::preset`](crate::command::FfmpegCommand::preset),
+/// from fastest encoding (largest files) to slowest (smallest files).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Preset {
+  Ultrafast,
+  Superfast,
+  Veryfast,
+  Faster,
+  Fast,
+  /// The default preset.
+  Medium,
+  Slow,
+  Slower,
+  Veryslow,
+  /// Rarely worth it; a negligible gain over `Veryslow` for a much longer
+  /// encode.
+  Placebo,
+}
+
+impl AsRef<str> for Preset {
+  fn as_ref(&self) -> &str {
+    match self {
+      Preset::Ultrafast => "ultrafast",
+      Preset::Superfast => "superfast",
+      Preset::Veryfast => "veryfast",
+      Preset::Faster => "faster",
+      Preset::Fast => "fast",
+      Preset::Medium => "medium",
+      Preset::Slow => "slow",
+      Preset::Slower => "slower",
+      Preset::Veryslow => "veryslow",
+      Preset::Placebo => "placebo",
+    }
+  }
+}
+
+/// Values for [`FfmpegCommand::tune`](crate::command::FfmpegCommand::tune),
+/// optimizing the encoder settings for a kind of input or use case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tune {
+  /// High quality movie content; lowers deblocking.
+  Film,
+  /// Cartoons; higher deblocking and more reference frames.
+  Animation,
+  /// Preserves the grain structure in old, grainy film material.
+  Grain,
+  /// Slideshow-like content.
+  StillImage,
+  /// Allows faster decoding by disabling certain filters.
+  FastDecode,
+  /// Fast encoding and low-latency streaming.
+  ZeroLatency,
+  /// Only used for codec development.
+  Psnr,
+  /// Only used for codec development.
+  Ssim,
+}
+
+impl AsRef<str> for Tune {
+  fn as_ref(&self) -> &str {
+    match self {
+      Tune::Film => "film",
+      Tune::Animation => "animation",
+      Tune::Grain => "grain",
+      Tune::StillImage => "stillimage",
+      Tune::FastDecode => "fastdecode",
+      Tune::ZeroLatency => "zerolatency",
+      Tune::Psnr => "psnr",
+      Tune::Ssim => "ssim",
+    }
+  }
+}
+
+/// Highest CRF accepted by libx264 and libx265 for 8-bit encodes.
+pub const MAX_CRF: u8 = 51;